@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use crate::config::EvolutionConfig;
+
+
+/// What is left of `EvolutionConfig::time_budget` and `EvolutionConfig::max_evaluations`,
+/// shared by the evolution cycles and the final optimization.
+pub(crate) struct Budget {
+    start: Instant,
+    time_limit: Option<Duration>,
+    max_evaluations: Option<u64>,
+    evaluations: AtomicU64,
+}
+
+impl Budget {
+    /// A budget starting now, or None when the config sets no limits.
+    pub(crate) fn new(config: &EvolutionConfig) -> Option<Budget> {
+        match (config.time_budget, config.max_evaluations) {
+            (None, None) => None,
+            (time_limit, max_evaluations) => Some(Budget {
+                start: Instant::now(),
+                time_limit,
+                max_evaluations,
+                evaluations: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Count "count" more creature evaluations against the budget.
+    pub(crate) fn record(&self, count: usize) {
+        self.evaluations.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn exhausted(&self) -> bool {
+        !self.allows(1, Duration::ZERO)
+    }
+
+    /// Whether there is room left for "evaluations" more evaluations
+    /// taking "duration" before either limit is reached.
+    pub(crate) fn allows(&self, evaluations: usize, duration: Duration) -> bool {
+        let within_time = self.time_limit.is_none_or(|limit| self.start.elapsed() + duration < limit);
+        let within_evaluations = self.max_evaluations
+            .is_none_or(|max| self.evaluations.load(Ordering::Relaxed) + evaluations as u64 <= max);
+        within_time && within_evaluations
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluation_limit() {
+        assert!(Budget::new(&EvolutionConfig::default()).is_none());
+        let budget = Budget::new(&EvolutionConfig { max_evaluations: Some(100), ..Default::default() }).unwrap();
+        assert!(budget.allows(100, Duration::from_secs(1000)));
+        budget.record(60);
+        assert!(budget.allows(40, Duration::ZERO) && !budget.allows(41, Duration::ZERO));
+        budget.record(40);
+        assert!(budget.exhausted());
+
+        let budget = Budget::new(&EvolutionConfig { time_budget: Some(Duration::from_secs(60)), ..Default::default() }).unwrap();
+        assert!(!budget.exhausted());
+        assert!(!budget.allows(1, Duration::from_secs(61)));
+    }
+}
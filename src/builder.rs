@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::config::{ConfigError, EvolutionConfig, Verbosity};
+use crate::evolution::{Evolution, EvolutionError, MinImprovement, Preprocess, PreprocessFn};
+use crate::events::{event_channel, CycleCallback, EventReceiver, EventSender};
+use crate::features::{DatetimeExpansion, DatetimePart};
+use crate::loss::{CustomLoss, Loss, LossFunction};
+use crate::mutator::Mutator;
+use crate::pin::PinSpec;
+use crate::template::Template;
+
+
+/// Configures and runs an Evolution, for example:
+/// `Evolution::builder("y", &data).num_creatures(5000).num_cycles(20).max_layers(3).build()?`
+/// Anything not set uses the value from `EvolutionConfig::default()`, so
+/// `Evolution::builder("y", &data).build()?` is enough to train a model.
+pub struct EvolutionBuilder<'a> {
+    target: String,
+    data: &'a [HashMap<String, f32>],
+    config: EvolutionConfig,
+    preprocess: Option<Preprocess>,
+    events: Option<EventSender>,
+    on_cycle: Option<Box<CycleCallback>>,
+}
+
+impl<'a> EvolutionBuilder<'a> {
+    pub(crate) fn new(target: &str, data: &'a [HashMap<String, f32>]) -> EvolutionBuilder<'a> {
+        EvolutionBuilder { target: target.to_string(), data, config: EvolutionConfig::default(), preprocess: None, events: None, on_cycle: None }
+    }
+
+    /// Replace the whole config (settings made before this call are discarded).
+    pub fn config(mut self, config: EvolutionConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn num_creatures(mut self, num_creatures: u32) -> Self {
+        self.config.num_creatures = num_creatures;
+        self
+    }
+
+    pub fn num_cycles(mut self, num_cycles: u16) -> Self {
+        self.config.num_cycles = num_cycles;
+        self
+    }
+
+    pub fn max_layers(mut self, max_layers: u8) -> Self {
+        self.config.max_layers = max_layers;
+        self
+    }
+
+    /// Chance of each coefficient changing in a mutation
+    /// (see `MutationOptions::coefficient_probability`).
+    pub fn mutation_probability(mut self, probability: f64) -> Self {
+        self.config.mutation.coefficient_probability = probability;
+        self
+    }
+
+    /// Stop early once the validation R2 reaches "target_r2" (see `EvolutionConfig::target_r2`).
+    /// Requires validation data.
+    pub fn target_r2(mut self, target_r2: f32) -> Self {
+        self.config.target_r2 = Some(target_r2);
+        self
+    }
+
+    /// Replace timestamp column "column" with the calendar "parts"
+    /// (see `EvolutionConfig::datetime_columns`).
+    pub fn datetime(mut self, column: &str, parts: &[DatetimePart]) -> Self {
+        self.config.datetime_columns.push(DatetimeExpansion::new(column, parts));
+        self
+    }
+
+    /// One-hot encode the categorical "columns" (see `EvolutionConfig::one_hot_columns`).
+    pub fn one_hot(mut self, columns: &[&str]) -> Self {
+        self.config.one_hot_columns.extend(columns.iter().map(|column| column.to_string()));
+        self
+    }
+
+    /// Stop early once the best error of a cycle drops below "error"
+    /// (see `EvolutionConfig::stop_at_error`).
+    pub fn stop_at_error(mut self, error: f32) -> Self {
+        self.config.stop_at_error = Some(error);
+        self
+    }
+
+    /// Stop early once the best error goes "patience" cycles without
+    /// improving by more than "improvement" (see `EvolutionConfig::patience`).
+    pub fn patience(mut self, patience: u16, improvement: MinImprovement) -> Self {
+        self.config.patience = Some(patience);
+        self.config.patience_improvement = improvement;
+        self
+    }
+
+    /// Score creatures with "loss" instead of squared error.
+    pub fn loss(mut self, loss: LossFunction) -> Self {
+        self.config.loss = loss;
+        self
+    }
+
+    /// How much training prints (see `Verbosity`).
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.config.verbosity = verbosity;
+        self
+    }
+
+    /// Score creatures with a user defined loss, reported under "name".
+    pub fn custom_loss(mut self, name: &str, loss: Arc<dyn Loss + Send + Sync>) -> Self {
+        self.config.custom_loss = Some(CustomLoss::new(name, loss));
+        self
+    }
+
+    /// Mutate creatures with a custom `Mutator` instead of the built-in Gaussian mutation.
+    pub fn mutator(mut self, mutator: Arc<dyn Mutator + Send + Sync>) -> Self {
+        self.config.mutator = Some(mutator);
+        self
+    }
+
+    /// Drop a random "fraction" of the input parameters from the scored data each cycle
+    /// (see `EvolutionConfig::feature_dropout`).
+    pub fn feature_dropout(mut self, fraction: f32) -> Self {
+        self.config.feature_dropout = fraction;
+        self
+    }
+
+    /// Score creatures with each of their terms zeroed with chance "probability"
+    /// (see `EvolutionConfig::term_dropout`).
+    pub fn term_dropout(mut self, probability: f32) -> Self {
+        self.config.term_dropout = probability;
+        self
+    }
+
+    /// Fail to build if any feature's absolute correlation with the target exceeds
+    /// "threshold" or if one is an affine copy of the target, as that usually means
+    /// the target has leaked into the inputs (see `EvolutionConfig::strict_leakage`).
+    pub fn strict_leakage(mut self, threshold: f32) -> Self {
+        self.config.leakage_threshold = threshold;
+        self.config.strict_leakage = true;
+        self
+    }
+
+    /// Seed the initial population with "instances" creatures of each template
+    /// (see `EvolutionConfig::templates`).
+    pub fn templates(mut self, templates: Vec<Template>, instances: u32) -> Self {
+        self.config.templates = templates;
+        self.config.template_instances = instances;
+        self
+    }
+
+    /// Rounds of mutation polishing the best creature (see `EvolutionConfig::optimize_iterations`).
+    pub fn optimize_iterations(mut self, iterations: u16) -> Self {
+        self.config.optimize_iterations = iterations;
+        self
+    }
+
+    /// Constrain every term of "param" by "spec" (see `EvolutionConfig::pin`).
+    pub fn pin(mut self, param: &str, spec: PinSpec) -> Self {
+        self.config.pin.insert(param.to_string(), spec);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// Transform each data point (for example clipping or winsorizing values)
+    /// before standardization.  It gets applied to the training and validation
+    /// data when fitting and is stored with the model so every prediction
+    /// (including those of a `Predictor`) applies it too.
+    /// The closure must be deterministic: the same input must always produce the same output.
+    /// It is not reflected in `Evolution::formula` and is not saved in bundles.
+    pub fn preprocess(mut self, preprocess: Box<PreprocessFn>) -> Self {
+        self.preprocess = Some(Arc::from(preprocess));
+        self
+    }
+
+    /// Receiver of the `EvolutionEvent`s of the run started by "build", such as
+    /// one for each completed cycle, which can be consumed from another thread.
+    /// Events are sent without ever blocking training (see `EventReceiver`).
+    /// Calling this again replaces the earlier receiver, which then gets no events.
+    pub fn event_receiver(&mut self) -> EventReceiver {
+        let (sender, receiver) = event_channel();
+        self.events = Some(sender);
+        receiver
+    }
+
+    /// Call "on_cycle" with a `CycleReport` after each cycle, for example to send it
+    /// on to a progress display.  It runs on the training thread, so should be quick.
+    /// Installing a callback stops the progress of cycles and of the final
+    /// optimization from being printed, whatever the `Verbosity`.
+    pub fn on_cycle(mut self, on_cycle: Box<CycleCallback>) -> Self {
+        self.on_cycle = Some(on_cycle);
+        self
+    }
+
+    /// Check the config (see `EvolutionConfig::validate`) and run the evolution.
+    /// Every problem with the config is reported at once.
+    pub fn build(self) -> Result<Evolution, ConfigError> {
+        match self.preprocess {
+            // Ranges are checked by "fit" once the rows are preprocessed
+            Some(_) if self.config.strict_ranges => EvolutionConfig { strict_ranges: false, ..self.config.clone() }.validate(&self.target, self.data)?,
+            _ => self.config.validate(&self.target, self.data)?,
+        }
+        Evolution::fit(self.target, self.data, &self.config, self.preprocess, self.events, self.on_cycle)
+    }
+}
+
+
+impl Evolution {
+    pub fn builder<'a>(target: &str, data: &'a [HashMap<String, f32>]) -> EvolutionBuilder<'a> {
+        EvolutionBuilder::new(target, data)
+    }
+}
+
+
+/// Builds the main settings of an EvolutionConfig by name and runs it, for example:
+/// `EvolutionConfig::builder().target("y").num_creatures(5000).num_cycles(20).run(&data)?`
+/// Anything not set uses the value from `EvolutionConfig::default()`.
+/// For every other setting use `Evolution::builder`.
+#[derive(Clone, Default)]
+pub struct ConfigBuilder {
+    target: Option<String>,
+    config: EvolutionConfig,
+}
+
+impl ConfigBuilder {
+    pub fn target(mut self, target: &str) -> Self {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    pub fn num_creatures(mut self, num_creatures: u32) -> Self {
+        self.config.num_creatures = num_creatures;
+        self
+    }
+
+    pub fn num_cycles(mut self, num_cycles: u16) -> Self {
+        self.config.num_cycles = num_cycles;
+        self
+    }
+
+    pub fn max_layers(mut self, max_layers: u8) -> Self {
+        self.config.max_layers = max_layers;
+        self
+    }
+
+    /// Rounds of mutation polishing the best creature (see `EvolutionConfig::optimize_iterations`).
+    pub fn optimize_iterations(mut self, iterations: u16) -> Self {
+        self.config.optimize_iterations = iterations;
+        self
+    }
+
+    /// Score creatures with "loss" instead of squared error.
+    pub fn loss(mut self, loss: LossFunction) -> Self {
+        self.config.loss = loss;
+        self
+    }
+
+    /// How much training prints (see `Verbosity`).
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.config.verbosity = verbosity;
+        self
+    }
+
+    /// The config, for `Evolution::with_config` or `EvolutionBuilder::config`.
+    pub fn build(self) -> EvolutionConfig {
+        self.config
+    }
+
+    /// Train on "data", failing without training when the target is not set or for
+    /// any of the problems reported by `Evolution::new`.
+    pub fn run(self, data: &[HashMap<String, f32>]) -> Result<Evolution, EvolutionError> {
+        let target = self.target.ok_or(EvolutionError::NoTarget)?;
+        Evolution::check_data(&target, data)?;
+        Ok(Evolution::builder(&target, data).config(self.config).build()?)
+    }
+}
+
+impl EvolutionConfig {
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preprocess_clips_at_fit_and_predict() {
+        // One wild x value which the preprocessing clips back into range
+        let data: Vec<HashMap<String, f32>> = (0..20)
+            .map(|i| {
+                let x = if i == 7 { 5000.0 } else { i as f32 * 0.5 };
+                HashMap::from([("x".to_string(), x), ("y".to_string(), (i as f32 * 0.5).min(9.5) * 2.0)])
+            })
+            .collect();
+        let evo = Evolution::builder("y", &data)
+            .num_creatures(500)
+            .num_cycles(3)
+            .preprocess(Box::new(|point| {
+                if let Some(x) = point.get_mut("x") {
+                    *x = x.clamp(0.0, 9.5);
+                }
+            }))
+            .build()
+            .unwrap();
+
+        let x_stats = evo.standardizer.param_standardizer("x").unwrap();
+        assert!(x_stats.mean() < 9.5);
+        let at = |x: f32| HashMap::from([("x".to_string(), x)]);
+        assert_eq!(evo.predict_point(&at(1000.0)).unwrap(), evo.predict_point(&at(9.5)).unwrap());
+
+        let predictor = evo.predictor();
+        assert_eq!(predictor.predict_point(&at(1000.0)), predictor.predict_point(&at(9.5)));
+        let swept = predictor.sweep("x", &[-3.0, 0.0, 20.0], &HashMap::new());
+        assert_eq!(swept, vec![predictor.predict_point(&at(0.0)), predictor.predict_point(&at(0.0)),
+                               predictor.predict_point(&at(9.5))]);
+    }
+
+    #[test]
+    fn config_builder() {
+        let data = crate::data::synthetic::linear(30, 2, 0.1, 2);
+        let builder = EvolutionConfig::builder().num_cycles(2).num_creatures(300).max_layers(2).optimize_iterations(4);
+        let config = builder.clone().build();
+        assert_eq!((config.num_creatures, config.num_cycles, config.max_layers, config.optimize_iterations), (300, 2, 2, 4));
+        assert!(matches!(builder.clone().run(&data), Err(EvolutionError::NoTarget)));
+        assert!(matches!(builder.clone().target("z").run(&data), Err(EvolutionError::MissingTarget(target)) if target == "z"));
+
+        let evo = builder.target("y").run(&data).unwrap();
+        assert_eq!(evo.history().len(), 2);
+        assert_eq!(evo.history()[1].optimize.unwrap().planned, 5);
+        assert!(matches!(EvolutionConfig::builder().target("y").num_creatures(2).run(&data),
+                         Err(EvolutionError::Config(ConfigError::TooSmall { option: "num_creatures", .. }))));
+    }
+
+    #[test]
+    fn all_config_errors_reported() {
+        let data = crate::data::synthetic::linear(10, 2, 0.1, 1);
+        let result = Evolution::builder("z", &data)
+            .config(EvolutionConfig { sample_weights: Some(vec![1.0; 3]), ..Default::default() })
+            .num_creatures(0)
+            .max_layers(0)
+            .feature_dropout(1.5)
+            .build();
+        let Err(error) = result else { panic!("Invalid config was accepted") };
+        let expected = vec![
+            ConfigError::ZeroCount { option: "num_creatures" },
+            ConfigError::ZeroCount { option: "max_layers" },
+            ConfigError::InvalidFraction { option: "feature_dropout", value: 1.5 },
+            ConfigError::UnknownColumn { option: "target", column: "z".to_string() },
+            ConfigError::Conflict("sample_weights has 3 weights for 10 data points".to_string()),
+        ];
+        assert_eq!(error, ConfigError::Multiple(expected));
+        assert_eq!(error.to_string().lines().next(), Some("5 problems with the config:"));
+        assert!(error.to_string().contains("\n  - target column \"z\" is not in the data"));
+    }
+}
@@ -0,0 +1,372 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::config::EvolutionConfig;
+use crate::creature::Creature;
+use crate::encoding::TargetEncoder;
+use crate::features::{self, FeaturePipeline};
+use crate::evolution::Evolution;
+use crate::formula::{ExportError, FormulaFormat};
+use crate::link::Link;
+use crate::standardize::Standardizer;
+
+
+/// Version of the model JSON written into bundles.
+/// Bump whenever the serialized model layout changes.
+const MODEL_FORMAT_VERSION: u32 = 3;
+
+const MODEL_FILE: &str = "model.json";
+const CONFIG_FILE: &str = "config.json";
+const SCHEMA_FILE: &str = "schema.json";
+const HISTORY_FILE: &str = "history.csv";
+const SUMMARY_FILE: &str = "summary.txt";
+const FORMULA_FILES: [(&str, FormulaFormat); 3] = [
+    ("formula.txt", FormulaFormat::Text),
+    ("formula.tex", FormulaFormat::Latex),
+    ("formula.sql", FormulaFormat::Sql),
+];
+
+
+/// The parts of an Evolution needed to make predictions.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ModelFile {
+    format_version: u32,
+    target: String,
+    link: Link,
+    standardizer: Standardizer,
+    best_creature: Creature,
+    #[serde(default)]
+    target_encoder: Option<TargetEncoder>,
+    /// Missing from models saved before version 3, which only had target encoding.
+    #[serde(default)]
+    pipeline: Option<FeaturePipeline>,
+    #[serde(default)]
+    ridge_lambda: Option<f64>,
+    #[serde(default)]
+    train_rmse: Option<f32>,
+}
+
+impl ModelFile {
+    fn new(evolution: &Evolution) -> ModelFile {
+        ModelFile {
+            format_version: MODEL_FORMAT_VERSION,
+            target: evolution.target.clone(),
+            link: evolution.config.link,
+            standardizer: evolution.standardizer.clone(),
+            best_creature: evolution.best_creature.clone(),
+            target_encoder: evolution.target_encoder.clone(),
+            pipeline: Some(evolution.pipeline.clone()),
+            ridge_lambda: evolution.ridge_lambda,
+            train_rmse: evolution.train_rmse,
+        }
+    }
+
+    /// The Evolution predicting with this model, which has no training history.
+    fn into_evolution(self, mut config: EvolutionConfig) -> Evolution {
+        config.link = self.link;
+        let pipeline = match self.pipeline {
+            Some(pipeline) => pipeline.without_preprocess(),
+            None => FeaturePipeline::new(config.column_names, false, Vec::new(), self.target_encoder.clone(), config.missing_params),
+        };
+        Evolution {
+            target: self.target,
+            config,
+            standardizer: self.standardizer,
+            best_creatures: Vec::new(),
+            best_creature: self.best_creature,
+            pareto_front: Vec::new(),
+            hall_of_fame: Vec::new(),
+            history: Vec::new(),
+            cycles_run: 0,
+            metrics: Default::default(),
+            optimize_choice: None,
+            target_r2_reached: None,
+            outcome: None,
+            preprocess: features::chained(None, &pipeline),
+            preprocessing_report: Default::default(),
+            determinism: None,
+            target_encoder: self.target_encoder,
+            pipeline,
+            ridge_lambda: self.ridge_lambda,
+            train_rmse: self.train_rmse,
+        }
+    }
+}
+
+/// A model saved to a single file by `Evolution::save`, with the config
+/// (which decides how points are prepared for prediction) alongside.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedModel {
+    #[serde(flatten)]
+    model: ModelFile,
+    config: EvolutionConfig,
+}
+
+/// The columns a model was trained on along with a fingerprint of them
+/// which can be compared against other datasets.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SchemaFile {
+    target: String,
+    columns: Vec<String>,
+    fingerprint: String,
+}
+
+impl SchemaFile {
+    fn new(target: &str, standardizer: &Standardizer) -> SchemaFile {
+        let columns: Vec<String> = standardizer.parameters().iter().map(|col| col.to_string()).collect();
+        SchemaFile { target: target.to_string(), fingerprint: schema_fingerprint(target, &columns), columns }
+    }
+}
+
+/// Stable (FNV-1a) hash of the target and sorted column names.
+fn schema_fingerprint(target: &str, columns: &[String]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for name in std::iter::once(target).chain(columns.iter().map(|col| col.as_str())) {
+        for byte in name.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+
+#[derive(Debug)]
+pub enum BundleError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+    Export(ExportError),
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BundleError::Io(err) => write!(f, "Bundle I/O error: {}", err),
+            BundleError::Json(err) => write!(f, "Bundle JSON error: {}", err),
+            BundleError::UnsupportedVersion(version) => write!(
+                f, "Bundle model format version {} is newer than supported version {}", version, MODEL_FORMAT_VERSION
+            ),
+            BundleError::Export(err) => write!(f, "Bundle export error: {}", err),
+        }
+    }
+}
+
+impl Error for BundleError {}
+
+impl From<io::Error> for BundleError {
+    fn from(err: io::Error) -> BundleError {
+        BundleError::Io(err)
+    }
+}
+
+impl From<ExportError> for BundleError {
+    fn from(err: ExportError) -> BundleError {
+        BundleError::Export(err)
+    }
+}
+
+impl From<serde_json::Error> for BundleError {
+    fn from(err: serde_json::Error) -> BundleError {
+        BundleError::Json(err)
+    }
+}
+
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "Model I/O error: {}", err),
+            LoadError::Json(err) => write!(f, "Model JSON error: {}", err),
+            LoadError::UnsupportedVersion(version) => write!(
+                f, "Model format version {} is newer than supported version {}", version, MODEL_FORMAT_VERSION
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> LoadError {
+        LoadError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(err: serde_json::Error) -> LoadError {
+        LoadError::Json(err)
+    }
+}
+
+
+impl Evolution {
+    /// Write an experiment bundle directory containing everything needed to
+    /// audit and reproduce this model:
+    ///   model.json      versioned model (target, link, standardizer, best creature, target encoder)
+    ///   config.json     resolved EvolutionConfig
+    ///   schema.json     trained columns and their fingerprint
+    ///   history.csv     per-cycle statistics
+    ///   summary.txt     human-readable summary
+    ///   formula.txt / formula.tex / formula.sql    equation exports
+    /// Output is deterministic for a given model.  Nothing is written
+    /// for a model with a NaN or infinite coefficient.
+    /// A preprocessing closure (see `EvolutionBuilder::preprocess`) cannot be
+    /// saved, so a model trained with one must have it reattached after import.
+    pub fn export_bundle(&self, path: &Path) -> Result<(), BundleError> {
+        self.best_creature.check_finite()?;
+        fs::create_dir_all(path)?;
+        fs::write(path.join(MODEL_FILE), serde_json::to_string_pretty(&ModelFile::new(self))?)?;
+        fs::write(path.join(CONFIG_FILE), serde_json::to_string_pretty(&self.config)?)?;
+        let schema = SchemaFile::new(&self.target, &self.standardizer);
+        fs::write(path.join(SCHEMA_FILE), serde_json::to_string_pretty(&schema)?)?;
+        fs::write(path.join(HISTORY_FILE), self.history_csv())?;
+        fs::write(path.join(SUMMARY_FILE), self.summary())?;
+        for (file, format) in FORMULA_FILES {
+            fs::write(path.join(file), self.formula(format)? + "\n")?;
+        }
+        Ok(())
+    }
+
+    /// Load the model and config from a bundle written by "export_bundle".
+    /// Only model.json is required; any other missing (or inconsistent)
+    /// members are reported in the returned list of warnings.
+    pub fn import_bundle(path: &Path) -> Result<(Evolution, Vec<String>), BundleError> {
+        let model: ModelFile = serde_json::from_str(&fs::read_to_string(path.join(MODEL_FILE))?)?;
+        if model.format_version > MODEL_FORMAT_VERSION {
+            return Err(BundleError::UnsupportedVersion(model.format_version));
+        }
+
+        let mut warnings = Vec::new();
+        let config = match fs::read_to_string(path.join(CONFIG_FILE)) {
+            Ok(text) => serde_json::from_str(&text)?,
+            Err(_) => {
+                warnings.push(format!("Bundle is missing {}; using the default config", CONFIG_FILE));
+                EvolutionConfig::default()
+            },
+        };
+
+        match fs::read_to_string(path.join(SCHEMA_FILE)) {
+            Ok(text) => {
+                let schema: SchemaFile = serde_json::from_str(&text)?;
+                if schema.fingerprint != SchemaFile::new(&model.target, &model.standardizer).fingerprint {
+                    warnings.push(format!("{} does not match the columns of the model", SCHEMA_FILE));
+                }
+            },
+            Err(_) => warnings.push(format!("Bundle is missing {}", SCHEMA_FILE)),
+        }
+        let optional_files = [HISTORY_FILE, SUMMARY_FILE].into_iter().chain(FORMULA_FILES.iter().map(|(file, _)| *file));
+        for file in optional_files {
+            if !path.join(file).exists() {
+                warnings.push(format!("Bundle is missing {}", file));
+            }
+        }
+
+        Ok((model.into_evolution(config), warnings))
+    }
+
+    /// Save the model (target, standardizer, best creature, link, target encoder
+    /// and config) to a single JSON file at "path" which "load" reads back,
+    /// for when the rest of an experiment bundle is not wanted.
+    /// Fails with `io::ErrorKind::InvalidData` for a model with a NaN or infinite coefficient.
+    /// As with bundles, a preprocessing closure is not saved.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        self.best_creature.check_finite().map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let saved = SavedModel { model: ModelFile::new(self), config: self.config.clone() };
+        fs::write(path, serde_json::to_string_pretty(&saved)?)
+    }
+
+    /// Load a model written by "save", which predicts exactly as the saved model did.
+    pub fn load(path: &Path) -> Result<Evolution, LoadError> {
+        let saved: SavedModel = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if saved.model.format_version > MODEL_FORMAT_VERSION {
+            return Err(LoadError::UnsupportedVersion(saved.model.format_version));
+        }
+        Ok(saved.model.into_evolution(saved.config))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn bundle_round_trip() {
+        let data: Vec<HashMap<String, f32>> = (0..20)
+            .map(|i| HashMap::from([
+                ("x1".to_string(), i as f32),
+                ("x2".to_string(), (i % 7) as f32),
+                ("y".to_string(), 2.0 * i as f32 - (i % 7) as f32),
+            ]))
+            .collect();
+        let config = EvolutionConfig { num_creatures: 500, num_cycles: 3, ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+
+        let path = std::env::temp_dir().join(format!("revogression_bundle_{}", std::process::id()));
+        evo.export_bundle(&path).unwrap();
+        let mut members: Vec<String> = fs::read_dir(&path).unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        members.sort();
+        assert_eq!(members, vec![
+            "config.json", "formula.sql", "formula.tex", "formula.txt",
+            "history.csv", "model.json", "schema.json", "summary.txt",
+        ]);
+        let model_json = fs::read_to_string(path.join(MODEL_FILE)).unwrap();
+        evo.export_bundle(&path).unwrap();
+        assert_eq!(model_json, fs::read_to_string(path.join(MODEL_FILE)).unwrap());
+
+        let (loaded, warnings) = Evolution::import_bundle(&path).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(loaded.config.num_creatures, 500);
+        for point in &data {
+            assert_eq!(loaded.predict_point(point).unwrap(), evo.predict_point(point).unwrap());
+        }
+
+        fs::remove_file(path.join(CONFIG_FILE)).unwrap();
+        fs::remove_file(path.join(SUMMARY_FILE)).unwrap();
+        let (loaded, warnings) = Evolution::import_bundle(&path).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(loaded.predict_point(&data[3]).unwrap(), evo.predict_point(&data[3]).unwrap());
+
+        fs::remove_file(path.join(MODEL_FILE)).unwrap();
+        assert!(matches!(Evolution::import_bundle(&path), Err(BundleError::Io(_))));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn save_and_load() {
+        let data = crate::evolution::tests::parabola_data();
+        let mut evo = Evolution::builder("y", &data).num_creatures(500).num_cycles(3).max_layers(3).build().unwrap();
+        evo.ridge_lambda = Some(0.5);
+        let path = std::env::temp_dir().join(format!("revogression_model_{}.json", std::process::id()));
+        evo.save(&path).unwrap();
+        let loaded = Evolution::load(&path).unwrap();
+        assert_eq!(loaded.target, "y");
+        assert_eq!(loaded.ridge_lambda(), Some(0.5));
+        assert_eq!(loaded.config.num_creatures, 500);
+        for point in &data {
+            assert_eq!(loaded.predict_point(point).unwrap(), evo.predict_point(point).unwrap());
+        }
+
+        let mut json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        json["format_version"] = (MODEL_FORMAT_VERSION + 1).into();
+        fs::write(&path, json.to_string()).unwrap();
+        assert!(matches!(Evolution::load(&path), Err(LoadError::UnsupportedVersion(version)) if version == MODEL_FORMAT_VERSION + 1));
+        fs::write(&path, "{}").unwrap();
+        assert!(matches!(Evolution::load(&path), Err(LoadError::Json(_))));
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(Evolution::load(&path), Err(LoadError::Io(_))));
+    }
+}
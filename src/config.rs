@@ -0,0 +1,759 @@
+use crate::creature::{CreationOptions, MutationOptions};
+use crate::data::{ColumnNames, MissingParams, MissingValues};
+use crate::encoding::TargetEncoding;
+use crate::features::{self, DatetimeExpansion};
+use crate::pin::{self, PinSpec};
+use crate::samplers::Samplers;
+use crate::evolution::{MinImprovement, MIN_POPULATION};
+use crate::leakage::{check_target_leakage, LeakageWarning};
+use crate::link::{ErrorScale, Link};
+use crate::loss::{CustomLoss, DatasetAggregation, LossFunction};
+use crate::metrics::Metric;
+use crate::mutator::Mutator;
+use crate::ranges;
+use crate::selection::SelectionCriterion;
+use crate::template::Template;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+
+/// Options controlling a training run of an Evolution.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvolutionConfig {
+    /// Population size of each cycle, which must be at least 4.
+    /// See `population_limit` for more than the data can make use of.
+    pub num_creatures: u32,
+    pub num_cycles: u16,
+    pub max_layers: u8,
+    pub creation: CreationOptions,
+    pub mutation: MutationOptions,
+    /// Custom mutation used instead of the built-in Gaussian mutation
+    /// (see `GaussianMutator`) in every cycle and the final optimization.
+    /// Not included when the config is serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub mutator: Option<Arc<dyn Mutator + Send + Sync>>,
+    /// How column names are matched.  With `ColumnNames::Normalized` the names of
+    /// the training data (including the target and validation data) and of every
+    /// point predicted are normalized, and the model uses the normalized names.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub column_names: ColumnNames,
+    /// How nulls of a DataFrame are handled, in training and prediction
+    /// (see `Evolution::from_dataframe`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub missing_values: MissingValues,
+    /// How input parameters missing from a data point are treated, in training and prediction.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub missing_params: MissingParams,
+    /// Link function applied to the target (see `Link`).
+    pub link: Link,
+    /// Scale creature errors are calculated on when `link` is not `Link::Identity`.
+    pub error_scale: ErrorScale,
+    /// Loss creatures are scored with, throughout selection and optimization.
+    pub loss: LossFunction,
+    /// User defined loss used instead of `loss` when present.
+    /// Not included when the config is serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub custom_loss: Option<CustomLoss>,
+    /// Categorical columns to replace with the smoothed mean of the target
+    /// per category (see `TargetEncoding`), applied after any preprocessing.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target_encoding: Option<TargetEncoding>,
+    /// Timestamp columns to replace with their calendar parts (see `DatetimeExpansion`),
+    /// applied after any preprocessing.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub datetime_columns: Vec<DatetimeExpansion>,
+    /// Categorical columns to replace with an indicator column per category
+    /// (see `OneHotEncoder`), applied after any datetime expansion.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub one_hot_columns: Vec<String>,
+    /// Score each set of exact duplicate rows of the training data as a single row
+    /// with the combined weight of the set, so training matches training on the
+    /// unique rows weighted by their counts while scoring each unique row once.
+    /// The target is standardized and `report_metrics` on the training data are
+    /// calculated over the unique rows.  Cannot be combined with `datasets`.
+    pub deduplicate_rows: bool,
+    /// Optional weight for each data point (in the same order as the data,
+    /// followed by the points of each of `datasets`) scaling that point's
+    /// contribution to each creature's error.
+    pub sample_weights: Option<Vec<f32>>,
+    /// More datasets with the same columns as the training data (such as data from
+    /// related sites) which creatures must also fit.  Each creature's error is then
+    /// its error on each dataset (the training data first) combined by `dataset_aggregation`,
+    /// and each cycle's dataset errors are recorded in the history.
+    /// Not included when the config is serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub datasets: Vec<Vec<HashMap<String, f32>>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dataset_aggregation: DatasetAggregation,
+    /// Improvement in error needed for a mutant to replace the best creature
+    /// during the final optimization.  The default accepts any improvement.
+    pub min_improvement: MinImprovement,
+    /// Stop training early (with `Outcome::Converged`) once the lowest error in the
+    /// population has gone this many cycles in a row without a `patience_improvement`
+    /// on the lowest error of any earlier cycle.  None always runs every cycle.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub patience: Option<u16>,
+    /// Improvement a cycle's lowest error needs to reset the `patience` count.
+    /// The default needs the error to drop by more than 0.1%.
+    #[cfg_attr(feature = "serde", serde(default = "default_patience_improvement"))]
+    pub patience_improvement: MinImprovement,
+    /// Held-out data points (with the same columns as the training data)
+    /// used by `SelectionCriterion::MinValidationError`.  When present, each
+    /// cycle's validation error is also recorded in the history.
+    /// Not included when the config is serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub validation_data: Option<Vec<HashMap<String, f32>>>,
+    pub selection: SelectionCriterion,
+    /// Stop training early once the R2 of a cycle's best creature on
+    /// `validation_data` (in the original units of the target) reaches this.
+    /// Whether it was reached is reported by `Evolution::target_r2_reached`.
+    pub target_r2: Option<f32>,
+    /// Stop training early (with `Outcome::ErrorReached`) once the lowest error in a
+    /// cycle's population (as in `CycleStats::best_error`) drops below this.
+    /// The best creature found so far still gets the final optimization.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stop_at_error: Option<f32>,
+    /// Stop after this many cycles (with `Outcome::NoSignal`) if the best creature's
+    /// error is not yet at least `no_signal_margin` (a fraction) below the error of always
+    /// predicting the mean of the target.  None always runs every cycle.
+    pub no_signal_cycles: Option<u16>,
+    pub no_signal_margin: f32,
+    /// Choose the survivors of each cycle by non-dominated sorting of
+    /// (error, complexity) with crowding distance (as in NSGA-II) instead of
+    /// keeping the creatures below the median error, and keep the Pareto front
+    /// of the creatures found while training (see `Evolution::pareto_front`).
+    pub pareto_front: bool,
+    /// Extra metrics calculated for each cycle's best creature (on the training
+    /// and any validation data) and recorded in the history and summary.
+    /// Selection still only uses the loss.
+    pub report_metrics: Vec<Metric>,
+    /// Features whose absolute correlation with the target exceeds this
+    /// (or which are an affine copy of the target) are reported as possible leakage.
+    pub leakage_threshold: f32,
+    /// Fail validation instead of only reporting when possible target leakage is found:
+    /// `EvolutionConfig::validate` (and so `EvolutionBuilder::build`)
+    /// returns each leaking feature as a `ConfigError::Leakage`.
+    pub strict_leakage: bool,
+    /// Inclusive (min, max) range of valid values for some columns (such as a mass which
+    /// cannot be negative).  Training rows with a value outside of its range (or NaN) are
+    /// dropped, along with their `sample_weights`, and counted in the preprocessing report.
+    /// Validation data is not filtered.  `Evolution::predict_detailed` flags inputs outside of the ranges.
+    /// Bounds must be finite (use f32::MAX for a range without an upper limit) so they serialize.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub valid_ranges: BTreeMap<String, (f32, f32)>,
+    /// Fail instead of dropping rows outside of `valid_ranges`, with a `ConfigError::Conflict`
+    /// for each violated column from `EvolutionConfig::validate`.  With `EvolutionBuilder::preprocess`
+    /// the rows are instead checked once preprocessed, when building.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub strict_ranges: bool,
+    /// Upper limit on how many creatures get scored at once.  The population
+    /// is scored in chunks of this size and the initial population is generated
+    /// and scored a chunk at a time, keeping only the creatures which can
+    /// survive the first cycle.  None scores the whole population at once.
+    pub max_concurrent_evaluations: Option<usize>,
+    /// Time allowed for scoring a single creature against all of the data.
+    /// Creatures which take longer are given the maximum error so they get culled.
+    /// Evaluating the current kinds of Coefficients is cheap enough that this
+    /// should only ever trigger for expensive future activation kinds.
+    pub evaluation_timeout: Option<Duration>,
+    /// Fraction of the input parameters dropped (set to their mean) in the data
+    /// creatures are scored against each cycle, with a new random choice every cycle.
+    /// Every cycle's best creature is re-scored on the full data before the final selection.
+    pub feature_dropout: f32,
+    /// Chance (0.0 up to 1.0) of each term of a creature (the Coefficients of an input,
+    /// the previous layer or a gated input) counting as zero when the creature is
+    /// scored in a cycle, with a new random choice for every creature and cycle.
+    /// This favors creatures which do not depend on exact cancellations between
+    /// their terms.  Every cycle's best creature is re-scored with all of its terms
+    /// before the final selection, and predictions always use all of them.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub term_dropout: f32,
+    /// Screen each cycle's newly created creatures on a copy of the training inputs
+    /// quantized to 16 bits per value, which halves the memory read per data point.
+    /// Only the creatures ranking in the better part of the population are promoted
+    /// and scored on the full precision data, and the rest are culled, so every
+    /// error used to select and report creatures is a full precision error.
+    /// Cannot be combined with `pareto_front`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub quantized_screening: bool,
+    /// Wall clock time allowed for training.  Once it runs out no further cycles
+    /// are started (ending with `Outcome::BudgetExhausted`) and the final optimization
+    /// runs only the iterations which fit in what is left.  A cycle is never cut short.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub time_budget: Option<Duration>,
+    /// Limit on the number of creatures scored while training, applied like `time_budget`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_evaluations: Option<u64>,
+    /// Seed for every random choice of training: creating and mutating creatures
+    /// and options such as `feature_dropout`.  None uses a random seed, which is
+    /// recorded in the trained model's `Evolution::config`.  See
+    /// `Evolution::determinism_report` for whether a run can be repeated exactly.
+    pub seed: Option<u64>,
+    /// Debug option which recomputes a random sample of the cached
+    /// creature errors each cycle and panics if any of them are stale.
+    pub sanity_check: bool,
+    /// Evaluate creatures (while training and predicting) with `Creature::calculate_strict`,
+    /// summing terms in order of parameter name and multiplying out powers from left
+    /// to right, so a seeded run gives bit for bit the same model and predictions on
+    /// every platform.  Rust never fuses a multiply and add or reorders float operations
+    /// on its own, so this fixes the only orders left to the platform and HashMap.
+    /// The guarantee covers the identity link: other links call "exp", whose
+    /// rounding is up to the platform's math library.  Scoring is about 5% slower
+    /// (measured with `Evolution::parallel_efficiency_report`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub strict_fp: bool,
+    /// Functional forms (see `Template`) whose creatures, `template_instances` of each
+    /// with their own random constants, replace part of the initial population.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub templates: Vec<Template>,
+    /// Constraints (see `PinSpec`) on the terms of input parameters, honored by
+    /// every creature generated or mutated and so by the final model.
+    #[cfg_attr(feature = "serde", serde(default, serialize_with = "crate::util::serialize_sorted_map"))]
+    pub pin: HashMap<String, PinSpec>,
+    #[cfg_attr(feature = "serde", serde(default = "default_template_instances"))]
+    pub template_instances: u32,
+    /// Rounds of mutation used to polish the best creature after the last cycle.
+    #[cfg_attr(feature = "serde", serde(default = "default_optimize_iterations"))]
+    pub optimize_iterations: u16,
+    /// What happens when `num_creatures` is more than the training data can make use of.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub population_limit: PopulationLimit,
+    /// How much training prints to stdout.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub verbosity: Verbosity,
+}
+
+impl EvolutionConfig {
+    /// Check this config against the training data, finding every problem
+    /// rather than stopping at the first.  A single problem is returned as is
+    /// and several are returned together as `ConfigError::Multiple`.
+    pub fn validate(&self, target: &str, data: &[HashMap<String, f32>]) -> Result<(), ConfigError> {
+        if self.column_names != ColumnNames::Exact {
+            return self.validate_normalized(target, data);
+        }
+        let mut errors = Vec::new();
+        let counts = [
+            ("num_creatures", self.num_creatures as usize),
+            ("num_cycles", self.num_cycles as usize),
+            ("max_layers", self.max_layers as usize),
+            ("mutation.max_exponent", self.mutation.max_exponent as usize),
+            ("max_concurrent_evaluations", self.max_concurrent_evaluations.unwrap_or(1)),
+            ("no_signal_cycles", self.no_signal_cycles.unwrap_or(1) as usize),
+            ("max_evaluations", self.max_evaluations.unwrap_or(1) as usize),
+            ("template_instances", if self.templates.is_empty() { 1 } else { self.template_instances as usize }),
+            ("patience", self.patience.unwrap_or(1) as usize),
+        ];
+        errors.extend(counts.into_iter().filter(|(_, count)| *count == 0).map(|(option, _)| ConfigError::ZeroCount { option }));
+        if (1..MIN_POPULATION).contains(&self.num_creatures) {
+            errors.push(ConfigError::TooSmall { option: "num_creatures", minimum: MIN_POPULATION as usize });
+        }
+
+        let fractions = [
+            ("feature_dropout", self.feature_dropout as f64, self.feature_dropout < 1.0),
+            ("term_dropout", self.term_dropout as f64, self.term_dropout < 1.0),
+            ("creation.gated_probability", self.creation.gated_probability, self.creation.gated_probability <= 1.0),
+            ("creation.data_informed_strength", self.creation.data_informed_strength, self.creation.data_informed_strength <= 1.0),
+            ("mutation.coefficient_probability", self.mutation.coefficient_probability, self.mutation.coefficient_probability <= 1.0),
+            ("mutation.weight_decay", self.mutation.weight_decay as f64, self.mutation.weight_decay < 1.0),
+            ("leakage_threshold", self.leakage_threshold as f64, self.leakage_threshold <= 1.0),
+            ("no_signal_margin", self.no_signal_margin as f64, self.no_signal_margin < 1.0),
+        ];
+        errors.extend(fractions.into_iter()
+            .filter(|(_, value, below_limit)| !(*value >= 0.0 && *below_limit))
+            .map(|(option, value, _)| ConfigError::InvalidFraction { option, value }));
+        if let Err(error) = Samplers::new(&self.creation.priors) {
+            errors.push(error);
+        }
+
+        // Columns of every row, as the first row need not have them all
+        let columns: HashSet<&String> = data.iter().flat_map(|point| point.keys()).collect();
+        match data.is_empty() {
+            true => errors.push(ConfigError::Conflict("there is no training data".to_string())),
+            false => {
+                let missing_target = data.iter().filter(|point| !point.contains_key(target)).count();
+                if missing_target == data.len() {
+                    errors.push(ConfigError::UnknownColumn { option: "target", column: target.to_string() });
+                } else if missing_target > 0 {
+                    errors.push(ConfigError::Conflict(format!("{} rows are missing the target \"{}\"", missing_target, target)));
+                } else if self.strict_leakage {
+                    errors.extend(check_target_leakage(target, data, self.leakage_threshold).into_iter().map(ConfigError::Leakage));
+                }
+                let parameters = crate::data::input_columns(data, target);
+                let useful_creatures = max_useful_creatures(data.len(), parameters.len());
+                if self.population_limit == PopulationLimit::Strict && self.num_creatures > useful_creatures {
+                    errors.push(ConfigError::TooLarge { option: "num_creatures", maximum: useful_creatures as usize });
+                }
+                for (index, template) in self.templates.iter().enumerate() {
+                    errors.extend(template.problems(index, &parameters, target, self.max_layers, self.mutation.max_exponent));
+                }
+                let mut pinned: Vec<(&String, &PinSpec)> = self.pin.iter().collect();
+                pinned.sort_by_key(|(param, _)| *param);
+                for (param, spec) in pinned {
+                    if param == target {
+                        errors.push(ConfigError::Conflict("pin cannot hold the target".to_string()));
+                    } else if !columns.contains(param) {
+                        errors.push(ConfigError::UnknownColumn { option: "pin", column: param.clone() });
+                    }
+                    errors.extend(spec.problems(param, self.mutation.max_exponent));
+                }
+                if let Some(correlations) = &self.creation.feature_correlations {
+                    let mut unknown: Vec<&String> = correlations.keys().filter(|column| !columns.contains(column)).collect();
+                    unknown.sort();
+                    errors.extend(unknown.into_iter().map(|column| ConfigError::UnknownColumn {
+                        option: "creation.feature_correlations", column: column.clone(),
+                    }));
+                }
+                errors.extend(self.valid_ranges.keys()
+                    .filter(|column| !columns.contains(column))
+                    .map(|column| ConfigError::UnknownColumn { option: "valid_ranges", column: column.clone() }));
+                if self.strict_ranges {
+                    let rows: Vec<HashMap<String, f32>> = data.iter().chain(self.datasets.iter().flatten()).cloned().collect();
+                    let (_, violations) = ranges::check_rows(&rows, &self.valid_ranges);
+                    errors.extend(ranges::violation_errors(&violations, &self.valid_ranges));
+                }
+                let mut engineered: Vec<(&'static str, &String)> = self.datetime_columns.iter()
+                    .map(|expansion| ("datetime_columns", &expansion.column))
+                    .chain(self.one_hot_columns.iter().map(|column| ("one_hot_columns", column)))
+                    .chain(self.target_encoding.iter().flat_map(|encoding| &encoding.columns).map(|column| ("target_encoding.columns", column)))
+                    .collect();
+                engineered.sort_by_key(|(_, column)| *column);
+                for pair in engineered.windows(2).filter(|pair| pair[0].1 == pair[1].1) {
+                    errors.push(ConfigError::Conflict(format!("\"{}\" is in both {} and {}", pair[0].1, pair[0].0, pair[1].0)));
+                }
+                for (option, column) in engineered.into_iter().filter(|(option, _)| !option.starts_with("target_encoding")) {
+                    if column == target {
+                        errors.push(ConfigError::Conflict(format!("{} cannot hold the target", option)));
+                    } else if !columns.contains(column) {
+                        errors.push(ConfigError::UnknownColumn { option, column: column.clone() });
+                    }
+                }
+                if let Some(expansion) = self.datetime_columns.iter().find(|expansion| expansion.parts.is_empty()) {
+                    errors.push(ConfigError::Conflict(format!("datetime_columns has no parts for \"{}\"", expansion.column)));
+                }
+                if let Some(encoding) = &self.target_encoding {
+                    for column in &encoding.columns {
+                        if column == target {
+                            errors.push(ConfigError::Conflict("target_encoding cannot encode the target".to_string()));
+                        } else if !columns.contains(column) {
+                            errors.push(ConfigError::UnknownColumn { option: "target_encoding.columns", column: column.clone() });
+                        }
+                    }
+                }
+                for (index, dataset) in self.datasets.iter().enumerate() {
+                    let dataset_columns: HashSet<&String> = dataset.iter().flat_map(|point| point.keys()).collect();
+                    if dataset.is_empty() || dataset_columns != columns {
+                        errors.push(ConfigError::Conflict(format!("datasets[{}] does not have the columns of the training data", index)));
+                    }
+                }
+                if let Some(validation) = &self.validation_data {
+                    let validation_columns: HashSet<&String> = validation.iter().flat_map(|point| point.keys()).collect();
+                    let mut missing: Vec<&String> = columns.difference(&validation_columns).copied().collect();
+                    missing.sort();
+                    errors.extend(missing.into_iter().map(|column| ConfigError::Conflict(
+                        format!("validation_data is missing the training column \"{}\"", column)
+                    )));
+                }
+            },
+        }
+        if let Some(weights) = &self.sample_weights {
+            let n_points = data.len() + self.datasets.iter().map(|dataset| dataset.len()).sum::<usize>();
+            if weights.len() != n_points {
+                errors.push(ConfigError::Conflict(format!(
+                    "sample_weights has {} weights for {} data points", weights.len(), n_points
+                )));
+            }
+        }
+        errors.extend(self.link.problem().map(ConfigError::Conflict));
+        errors.extend(self.link.target_problem(target, data, "the training data"));
+        for (index, dataset) in self.datasets.iter().enumerate() {
+            errors.extend(self.link.target_problem(target, dataset, &format!("datasets[{}]", index)));
+        }
+        errors.extend(self.validation_data.as_deref().and_then(|validation| self.link.target_problem(target, validation, "validation_data")));
+        match self.missing_params {
+            MissingParams::Constant(value) if !value.is_finite() => errors.push(ConfigError::Conflict(
+                format!("missing_params needs a finite constant but has {}", value)
+            )),
+            MissingParams::Penalty(penalty) if !(penalty >= 0.0 && penalty.is_finite()) => errors.push(ConfigError::Conflict(
+                format!("missing_params needs a finite, non-negative penalty but has {}", penalty)
+            )),
+            MissingParams::Penalty(_) if self.custom_loss.is_some() => errors.push(ConfigError::Conflict(
+                "missing_params cannot penalize rows with a custom_loss".to_string()
+            )),
+            _ => (),
+        }
+        errors.extend(self.valid_ranges.iter()
+            .filter(|(_, (min, max))| !(min.is_finite() && max.is_finite()) || min > max)
+            .map(|(column, (min, max))| ConfigError::Conflict(format!("valid_ranges needs finite bounds with min <= max for \"{}\" but has [{}, {}]", column, min, max))));
+        if let Some(encoding) = &self.target_encoding {
+            if encoding.folds < 2 {
+                errors.push(ConfigError::TooSmall { option: "target_encoding.folds", minimum: 2 });
+            }
+            if !(encoding.smoothing >= 0.0 && encoding.smoothing.is_finite()) {
+                errors.push(ConfigError::Conflict(format!("target_encoding.smoothing of {} must be finite and not negative", encoding.smoothing)));
+            }
+        }
+        if self.templates.len() as u64 * self.template_instances as u64 > self.num_creatures as u64 {
+            errors.push(ConfigError::Conflict(format!(
+                "{} templates with {} template_instances each do not fit in num_creatures", self.templates.len(), self.template_instances
+            )));
+        }
+        if self.quantized_screening && self.pareto_front {
+            errors.push(ConfigError::Conflict("quantized_screening cannot be combined with pareto_front".to_string()));
+        }
+        if self.deduplicate_rows && !self.datasets.is_empty() {
+            errors.push(ConfigError::Conflict("deduplicate_rows cannot be combined with datasets".to_string()));
+        }
+        if let DatasetAggregation::Weighted(weights) = &self.dataset_aggregation {
+            if weights.len() != self.datasets.len() + 1 {
+                errors.push(ConfigError::Conflict(format!(
+                    "dataset_aggregation has {} weights for {} datasets", weights.len(), self.datasets.len() + 1
+                )));
+            }
+            if !(weights.iter().all(|weight| *weight >= 0.0) && weights.iter().sum::<f32>() > 0.0) {
+                errors.push(ConfigError::Conflict("dataset_aggregation weights must not be negative and must not all be 0".to_string()));
+            }
+        }
+        if let Some(target_r2) = self.target_r2 {
+            if target_r2.is_nan() || target_r2 > 1.0 {
+                errors.push(ConfigError::InvalidFraction { option: "target_r2", value: target_r2 as f64 });
+            }
+            if self.validation_data.is_none() {
+                errors.push(ConfigError::Conflict("target_r2 requires validation_data".to_string()));
+            }
+        }
+        if let Some(error) = self.stop_at_error.filter(|error| error.is_nan() || *error < 0.0) {
+            errors.push(ConfigError::Conflict(format!("stop_at_error of {} must not be negative", error)));
+        }
+        if let LossFunction::Huber { delta } = self.loss {
+            if !(delta > 0.0 && delta.is_finite()) {
+                errors.push(ConfigError::Conflict(format!("Huber loss delta of {} must be positive", delta)));
+            }
+        }
+        if self.selection == SelectionCriterion::MinValidationError && self.validation_data.is_none() {
+            errors.push(ConfigError::Conflict("SelectionCriterion::MinValidationError requires validation_data".to_string()));
+        }
+
+        combined(errors)
+    }
+
+    /// Column name collisions of any row of the training, validation and other
+    /// datasets, or else the problems found after normalizing every column name.
+    fn validate_normalized(&self, target: &str, data: &[HashMap<String, f32>]) -> Result<(), ConfigError> {
+        let collisions: BTreeSet<(String, Vec<String>)> = data.iter()
+            .chain(self.validation_data.iter().chain(&self.datasets).flatten())
+            .flat_map(|point| self.column_names.collisions(point.keys()))
+            .collect();
+        if !collisions.is_empty() {
+            return combined(collisions.into_iter()
+                .map(|(normalized, columns)| ConfigError::ColumnCollision { normalized, columns })
+                .collect());
+        }
+        let normalized = EvolutionConfig {
+            column_names: ColumnNames::Exact,
+            validation_data: self.validation_data.as_deref()
+                .map(|validation| self.column_names.normalized_rows(validation).map(Cow::into_owned))
+                .transpose()?,
+            datasets: self.datasets.iter()
+                .map(|dataset| self.column_names.normalized_rows(dataset).map(Cow::into_owned))
+                .collect::<Result<_, _>>()?,
+            target_encoding: self.target_encoding.as_ref().map(|encoding| encoding.normalized(self.column_names)),
+            datetime_columns: features::normalized_datetimes(&self.datetime_columns, self.column_names),
+            one_hot_columns: self.one_hot_columns.iter().map(|column| self.column_names.normalize(column).into_owned()).collect(),
+            valid_ranges: ranges::normalized(&self.valid_ranges, self.column_names),
+            templates: self.templates.iter().map(|template| template.normalized(self.column_names)).collect(),
+            pin: pin::normalized(&self.pin, self.column_names),
+            ..self.clone()
+        };
+        normalized.validate(&self.column_names.normalize(target), &self.column_names.normalized_rows(data)?)
+    }
+
+    /// Name of the loss creatures are scored with.
+    pub fn loss_name(&self) -> &str {
+        match &self.custom_loss {
+            Some(custom) => &custom.name,
+            None => self.loss.name(),
+        }
+    }
+
+    /// Whether the Huber delta gets tuned each cycle (see `LossFunction::HuberAuto`).
+    pub(crate) fn huber_auto(&self) -> bool {
+        self.loss == LossFunction::HuberAuto && self.custom_loss.is_none()
+    }
+}
+
+impl Default for EvolutionConfig {
+    fn default() -> EvolutionConfig {
+        EvolutionConfig {
+            num_creatures: 10000,
+            num_cycles: 10,
+            max_layers: 3,
+            creation: CreationOptions::default(),
+            mutation: MutationOptions::default(),
+            mutator: None,
+            column_names: ColumnNames::Exact,
+            missing_values: MissingValues::DropRow,
+            missing_params: MissingParams::Skip,
+            link: Link::Identity,
+            error_scale: ErrorScale::Linked,
+            loss: LossFunction::SquaredError,
+            custom_loss: None,
+            target_encoding: None,
+            datetime_columns: Vec::new(),
+            one_hot_columns: Vec::new(),
+            deduplicate_rows: false,
+            sample_weights: None,
+            datasets: Vec::new(),
+            dataset_aggregation: DatasetAggregation::Mean,
+            min_improvement: MinImprovement::Absolute(0.0),
+            patience: None,
+            patience_improvement: default_patience_improvement(),
+            validation_data: None,
+            selection: SelectionCriterion::MinTrainError,
+            target_r2: None,
+            stop_at_error: None,
+            no_signal_cycles: Some(3),
+            no_signal_margin: 0.01,
+            pareto_front: false,
+            report_metrics: Vec::new(),
+            leakage_threshold: 0.995,
+            strict_leakage: false,
+            valid_ranges: BTreeMap::new(),
+            strict_ranges: false,
+            max_concurrent_evaluations: None,
+            evaluation_timeout: None,
+            feature_dropout: 0.0,
+            term_dropout: 0.0,
+            quantized_screening: false,
+            time_budget: None,
+            max_evaluations: None,
+            seed: None,
+            sanity_check: false,
+            strict_fp: false,
+            templates: Vec::new(),
+            pin: HashMap::new(),
+            template_instances: default_template_instances(),
+            optimize_iterations: default_optimize_iterations(),
+            population_limit: PopulationLimit::Warn,
+            verbosity: Verbosity::PerCycle,
+        }
+    }
+}
+
+
+fn default_template_instances() -> u32 {
+    20
+}
+
+fn default_optimize_iterations() -> u16 {
+    30
+}
+
+fn default_patience_improvement() -> MinImprovement {
+    MinImprovement::Relative(0.001)
+}
+
+
+/// Creatures per training value (row times input column) which can still be of use.
+const CREATURES_PER_VALUE: u64 = 1000;
+
+/// Populations up to this size (the default `EvolutionConfig::num_creatures`) are always useful.
+const MIN_USEFUL_CREATURES: u32 = 10_000;
+
+/// Largest useful `EvolutionConfig::num_creatures` for "rows" rows of training
+/// data with "parameters" input columns: 1000 creatures for each value, but
+/// never less than 10,000.  Beyond that extra creatures mostly repeat each
+/// other while costing time and memory.
+pub(crate) fn max_useful_creatures(rows: usize, parameters: usize) -> u32 {
+    let useful = CREATURES_PER_VALUE * rows as u64 * parameters.max(1) as u64;
+    useful.clamp(MIN_USEFUL_CREATURES as u64, u32::MAX as u64) as u32
+}
+
+/// What is done when `EvolutionConfig::num_creatures` is more than the training data
+/// can make use of: 1000 creatures for each value (row times input column) of the
+/// training data, or 10,000 if that is more.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PopulationLimit {
+    /// Print a warning and train with every creature.
+    #[default]
+    Warn,
+    /// Print a warning and train with only the useful number of creatures,
+    /// which becomes the `num_creatures` of the model's config.
+    Clamp,
+    /// Fail validation with `ConfigError::TooLarge`.
+    Strict,
+}
+
+/// How much training prints to stdout, each level printing everything
+/// the levels before it do.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Verbosity {
+    /// Nothing at all.
+    Silent,
+    /// Warnings, why training stopped early and the final optimized creature.
+    Summary,
+    /// The standardization of each column and the best creature of every cycle.
+    #[default]
+    PerCycle,
+    /// The `DeterminismReport` of the run.
+    Debug,
+}
+
+
+/// A problem found by `EvolutionConfig::validate`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    /// A count which must be at least 1 is 0.
+    ZeroCount { option: &'static str },
+    /// A count below the smallest value that can work.
+    TooSmall { option: &'static str, minimum: usize },
+    /// A count above the largest useful value (see `PopulationLimit::Strict`).
+    TooLarge { option: &'static str, maximum: usize },
+    /// A fraction or probability outside of its allowed range.
+    InvalidFraction { option: &'static str, value: f64 },
+    /// A column name which is not in the training data.
+    UnknownColumn { option: &'static str, column: String },
+    /// A feature which looks like a copy of the target, found when `EvolutionConfig::strict_leakage` is set.
+    Leakage(LeakageWarning),
+    /// Column names which are the same once normalized (see `ColumnNames::Normalized`).
+    ColumnCollision { normalized: String, columns: Vec<String> },
+    /// A distribution of `CreationOptions::priors` which cannot be sampled.
+    InvalidPrior { prior: &'static str, reason: String },
+    /// Options which do not fit together or with the data.
+    Conflict(String),
+    /// Every problem found when there is more than one.
+    Multiple(Vec<ConfigError>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::ZeroCount { option } => write!(f, "{} must be at least 1", option),
+            ConfigError::TooSmall { option, minimum } => write!(f, "{} must be at least {}", option, minimum),
+            ConfigError::TooLarge { option, maximum } => write!(f, "{} must be at most {}", option, maximum),
+            ConfigError::InvalidFraction { option, value } => write!(f, "{} of {} is outside of its allowed range", option, value),
+            ConfigError::UnknownColumn { option, column } => write!(f, "{} column \"{}\" is not in the data", option, column),
+            ConfigError::Leakage(warning) => write!(f, "possible target leakage: {}", warning),
+            ConfigError::ColumnCollision { normalized, columns } => {
+                let columns: Vec<String> = columns.iter().map(|column| format!("\"{}\"", column)).collect();
+                write!(f, "columns {} are all named \"{}\" once normalized", columns.join(", "), normalized)
+            },
+            ConfigError::InvalidPrior { prior, reason } => write!(f, "creation.priors.{} is invalid: {}", prior, reason),
+            ConfigError::Conflict(message) => write!(f, "{}", message),
+            ConfigError::Multiple(errors) => {
+                write!(f, "{} problems with the config:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  - {}", error)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+
+/// No error, a single error or every error together as `ConfigError::Multiple`.
+pub(crate) fn combined(mut errors: Vec<ConfigError>) -> Result<(), ConfigError> {
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.remove(0)),
+        _ => Err(ConfigError::Multiple(errors)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evolution::Evolution;
+
+    #[test]
+    fn valid_config() {
+        let data = crate::data::synthetic::linear(10, 2, 0.1, 1);
+        assert_eq!(EvolutionConfig::default().validate("y", &data), Ok(()));
+        let config = EvolutionConfig { num_cycles: 0, ..Default::default() };
+        assert_eq!(config.validate("y", &data), Err(ConfigError::ZeroCount { option: "num_cycles" }));
+        let config = EvolutionConfig { num_creatures: 3, ..Default::default() };
+        assert_eq!(config.validate("y", &data).unwrap_err().to_string(), "num_creatures must be at least 4");
+        assert_eq!(EvolutionConfig::default().validate("y", &[]).unwrap_err().to_string(), "there is no training data");
+    }
+
+    #[test]
+    fn dataset_options() {
+        let data = crate::data::synthetic::linear(10, 2, 0.1, 1);
+        let config = EvolutionConfig {
+            datasets: vec![crate::data::synthetic::linear(5, 2, 0.1, 2), crate::data::synthetic::linear(5, 3, 0.1, 2)],
+            dataset_aggregation: DatasetAggregation::Weighted(vec![1.0, 2.0]),
+            sample_weights: Some(vec![1.0; 20]),
+            ..Default::default()
+        };
+        let Err(ConfigError::Multiple(errors)) = config.validate("y", &data) else { panic!("Invalid datasets were accepted") };
+        let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        assert_eq!(messages, vec![
+            "datasets[1] does not have the columns of the training data",
+            "dataset_aggregation has 2 weights for 3 datasets",
+        ]);
+    }
+
+    #[test]
+    fn population_limit() {
+        assert_eq!(max_useful_creatures(3, 2), 10_000);
+        assert_eq!(max_useful_creatures(30, 3), 90_000);
+        assert_eq!(max_useful_creatures(30, 0), 30_000);
+
+        // 30 rows of 2 columns can use up to 60,000 creatures
+        let data = crate::data::synthetic::linear(30, 2, 0.1, 1);
+        let config = EvolutionConfig { num_creatures: 60_000, population_limit: PopulationLimit::Strict, ..Default::default() };
+        assert_eq!(config.validate("y", &data), Ok(()));
+        let config = EvolutionConfig { num_creatures: 60_001, ..config };
+        assert_eq!(config.validate("y", &data), Err(ConfigError::TooLarge { option: "num_creatures", maximum: 60_000 }));
+        assert_eq!(config.validate("y", &data).unwrap_err().to_string(), "num_creatures must be at most 60000");
+        let config = EvolutionConfig { population_limit: PopulationLimit::Warn, ..config };
+        assert_eq!(config.validate("y", &data), Ok(()));
+
+        // Clamping records the useful size in the model's config
+        let data = crate::data::synthetic::linear(4, 1, 0.1, 1);
+        let config = EvolutionConfig {
+            num_creatures: 10_001, num_cycles: 1, population_limit: PopulationLimit::Clamp, ..Default::default()
+        };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        assert_eq!(evo.config.num_creatures, 10_000);
+    }
+
+    #[test]
+    fn column_name_collisions() {
+        let data = vec![HashMap::from([
+            (" Width ".to_string(), 1.0), ("width".to_string(), 2.0), ("Y".to_string(), 3.0),
+        ])];
+        let config = EvolutionConfig { column_names: ColumnNames::Normalized, ..Default::default() };
+        let error = config.validate("y", &data).unwrap_err();
+        assert_eq!(error, ConfigError::ColumnCollision {
+            normalized: "width".to_string(), columns: vec![" Width ".to_string(), "width".to_string()],
+        });
+        assert_eq!(error.to_string(), "columns \" Width \", \"width\" are all named \"width\" once normalized");
+
+        // Every row is checked, not only the first, and each collision is reported once
+        let clean = HashMap::from([("Width".to_string(), 1.0), ("Y".to_string(), 3.0)]);
+        let data = vec![clean.clone(), data[0].clone(), data[0].clone()];
+        assert_eq!(config.validate("y", &data), Err(error.clone()));
+        let validation = vec![clean.clone(), HashMap::from([("Y".to_string(), 1.0), ("y".to_string(), 2.0)])];
+        let config_with_validation = EvolutionConfig { validation_data: Some(validation), ..config.clone() };
+        assert_eq!(config_with_validation.validate("y", &data), Err(ConfigError::Multiple(vec![
+            error,
+            ConfigError::ColumnCollision { normalized: "y".to_string(), columns: vec!["Y".to_string(), "y".to_string()] },
+        ])));
+
+        // Without the collision the target is found by its normalized name
+        let data = vec![HashMap::from([(" Width ".to_string(), 1.0), ("Y".to_string(), 3.0)])];
+        assert_eq!(config.validate(" y", &data), Ok(()));
+        assert!(EvolutionConfig::default().validate(" y", &data).is_err());
+    }
+}
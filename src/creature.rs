@@ -1,22 +1,37 @@
 use rand::prelude::*;
 use rand::Rng;
 use rand::seq::SliceRandom;
-use rand_distr::{Normal, Triangular};
-use std::collections::HashMap;
+use rand::seq::index;
+use rand_distr::Binomial;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
+use itertools::Either;
 use rayon::prelude::*;
+use crate::formula::{ExportError, FormulaFormat, Precision};
+use crate::samplers::{self, CoefficientPriors, Samplers};
+use crate::seeding::Stream;
 
 
-fn num_layers() -> u8 {
+/// Layers with fewer parameter terms than this keep them in a sorted Vec rather than a HashMap.
+pub(crate) const SORTED_MODIFIERS_LIMIT: usize = 16;
+
+/// Chance of a mutation giving a term to a creature's first layer when it has none.
+const EMPTY_LAYER_TERM_PROBABILITY: f64 = 0.5;
+
+
+fn num_layers(rng: &mut dyn RngCore) -> u8 {
     // Generate a random number of Creature modifier layers
-    *[1, 1, 1, 2, 2, 3].choose(&mut rand::thread_rng()).unwrap()
+    *[1, 1, 1, 2, 2, 3].choose(rng).unwrap()
 }
 
 
 /// A "Creature" is essentially a randomly generated function.
 /// The equation of a creature can be one or more Coefficients in one or more
 /// LayerModifiers which function as one or more layers for a simple neural network.
+/// With the "serde" feature a creature can be saved (such as with serde_json)
+/// and loaded again along with its cached error and generation.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Creature {
     equation: Vec<LayerModifiers>,
     pub cached_error_sum: Option<f32>,
@@ -29,49 +44,223 @@ pub enum MutateSpeed {
     Fast,
 }
 
+impl MutateSpeed {
+    /// Standard deviation of the Gaussian noise added to each constant.
+    pub fn sigma(&self) -> f32 {
+        match self {
+            MutateSpeed::Fine => 0.005,
+            MutateSpeed::Fast => 0.05,
+        }
+    }
+}
+
+/// Options controlling how `Creature::mutate_with` modifies a creature's Coefficients.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MutationOptions {
+    /// Upper limit (at least 1) on each Coefficients exponent "x".  Without a limit,
+    /// repeated upward mutations during long runs would eventually overflow f32.
+    pub max_exponent: u8,
+    /// Keep every exponent "x" unchanged so only the constants of a
+    /// creature's equation get mutated and its structure stays fixed.
+    pub freeze_structure: bool,
+    /// Chance (0.0 to 1.0) of each Coefficients field ("c", "b", "z" and "x")
+    /// and gate threshold changing in a mutation.  Lower values make more local
+    /// mutations which explore only a few directions at a time.
+    #[cfg_attr(feature = "serde", serde(default = "always"))]
+    pub coefficient_probability: f64,
+    /// Input parameters a mutation can give a term to a first layer without any
+    /// terms (so a constant creature can start using a feature), unless the
+    /// structure is frozen.  When left empty, `Evolution` fills in the input
+    /// parameters of the training data.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub parameter_options: Vec<String>,
+    /// Fraction (0.0 up to 1.0) by which each mutated "c", "b" and "z" shrinks
+    /// toward zero before its Gaussian change, biasing the population toward
+    /// small coefficients without any hard limit.  0.0 (the default) is off.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub weight_decay: f32,
+}
+
+impl Default for MutationOptions {
+    fn default() -> MutationOptions {
+        MutationOptions {
+            max_exponent: 4,
+            freeze_structure: false,
+            coefficient_probability: always(),
+            parameter_options: Vec::new(),
+            weight_decay: 0.0,
+        }
+    }
+}
+
+fn always() -> f64 {
+    1.0
+}
+
+/// Options controlling how new Creatures get generated.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CreationOptions {
+    /// Chance (0.0 to 1.0) of each layer including a gated term (see "GatedCoefficients")
+    /// which only applies when a parameter is above or below a threshold.
+    /// Gated terms let the population discover breakpoints in piecewise data.
+    pub gated_probability: f64,
+    /// How strongly (0.0 to 1.0) "feature_correlations" bias new creatures:
+    /// parameters more correlated with the target are more likely to get a term,
+    /// and the sign of each term's slope tends to match the correlation.
+    /// Even at 1.0 this only shifts the odds, so any term remains possible.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub data_informed_strength: f64,
+    /// Correlation of each input parameter with the target.  When left as None,
+    /// `Evolution` fills it in from the training data if "data_informed_strength" is above 0.
+    #[cfg_attr(feature = "serde", serde(default, serialize_with = "crate::util::serialize_sorted_option_map"))]
+    pub feature_correlations: Option<HashMap<String, f32>>,
+    /// Distributions the constants of new creatures are drawn from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub priors: CoefficientPriors,
+}
+
+impl CreationOptions {
+    /// Chance of including "param" given the unbiased chance "base".
+    /// Parameters with above average absolute correlations become more likely
+    /// and the others less, so the expected number of terms stays about the same.
+    fn usage_probability(&self, param: &str, base: f64) -> f64 {
+        match (self.correlation(param), &self.feature_correlations) {
+            (Some(correlation), Some(correlations)) => {
+                let mean = correlations.values().map(|correlation| correlation.abs() as f64).sum::<f64>() / correlations.len() as f64;
+                (base * (1.0 + self.data_informed_strength * (correlation.abs() - mean))).clamp(0.0, 1.0)
+            },
+            _ => base,
+        }
+    }
+
+    /// Bias (-1.0 to 1.0) toward a positive slope for a term of "param".
+    fn sign_bias(&self, param: &str) -> f64 {
+        self.correlation(param).map_or(0.0, |correlation| self.data_informed_strength * correlation)
+    }
+
+    fn correlation(&self, param: &str) -> Option<f64> {
+        match self.data_informed_strength > 0.0 {
+            true => self.feature_correlations.as_ref()?.get(param).map(|correlation| *correlation as f64),
+            false => None,
+        }
+    }
+}
+
 impl Creature {
     pub fn new(parameter_options: &Vec<&str>, max_layers: u8) -> Creature {
+        Creature::new_with(parameter_options, max_layers, &CreationOptions::default())
+    }
+
+    /// Panics if `CreationOptions::priors` are invalid.
+    pub fn new_with(parameter_options: &Vec<&str>, max_layers: u8, options: &CreationOptions) -> Creature {
+        Creature::generate(parameter_options, max_layers, options, &Samplers::expect(&options.priors), &mut thread_rng())
+    }
+
+    /// Like "new_with" but drawing every random choice from "rng" and
+    /// every constant from "samplers" (built from `options.priors`).
+    pub(crate) fn generate(parameter_options: &Vec<&str>,
+                           max_layers: u8,
+                           options: &CreationOptions,
+                           samplers: &Samplers,
+                           rng: &mut dyn RngCore) -> Creature {
         let mut equation = Vec::new();
 
-        let mut layer_limit = num_layers();
+        let mut layer_limit = num_layers(rng);
         if layer_limit > max_layers {
             layer_limit = max_layers;
         }
 
         for layer in 0..layer_limit {
             equation.push(LayerModifiers::new(
-                if layer == 0 { true } else {false},
-                &parameter_options,
+                layer == 0,
+                parameter_options,
+                options,
+                samplers,
+                rng,
             ));
         }
-        Creature { equation, cached_error_sum: None, generation: 1 }
+        let mut creature = Creature { equation, cached_error_sum: None, generation: 1 };
+        samplers.pin(&mut creature);
+        creature
     }
 
     pub fn num_layers(&self) -> usize {
         self.equation.len()
     }
 
+    /// Whether this creature's output is the same for every input
+    /// (it has no terms of any input parameter).
+    pub fn is_constant(&self) -> bool {
+        self.equation.iter().all(|layer_modifiers| {
+            layer_modifiers.modifiers.is_empty() && layer_modifiers.gated_modifiers.is_empty()
+        })
+    }
+
+    /// Number of Coefficients terms across all layers.
+    pub fn complexity(&self) -> usize {
+        self.equation.iter().map(|layer_modifiers| {
+            layer_modifiers.modifiers.len()
+                + layer_modifiers.previous_layer_coefficients.iter().count()
+                + layer_modifiers.gated_modifiers.len()
+        }).sum()
+    }
+
+    pub(crate) fn layers(&self) -> &[LayerModifiers] {
+        &self.equation
+    }
+
+    /// The error from the last time this creature was scored, if still valid.
+    pub fn cached_error(&self) -> Option<f32> {
+        self.cached_error_sum
+    }
+
+    /// Record the error from scoring this creature against the current data.
+    pub fn set_cached_error(&mut self, error: f32) {
+        self.cached_error_sum = Some(error);
+    }
+
+    /// Clear the cached error so it gets recalculated
+    /// the next time this creature is scored.
+    /// Any method which modifies a creature's equation in place must call this
+    /// so a stale error is never used for selection.
+    pub fn invalidate_cache(&mut self) {
+        self.cached_error_sum = None;
+    }
+
     /// Calculate the resulting output value for this creature given an input of Key: Value data.
     pub fn calculate(&self, parameters: &HashMap<String, f32>) -> f32 {
         let mut total = 0.0;
         let mut inner_total = 0.0;
 
         for layer_modifiers in &self.equation {
-            // Run through each input parameter and record impact
-            // for each parameter that is used in the curret layer's modifiers.
-            for (param, param_value) in parameters {
-                match layer_modifiers.modifiers.get(param) {
-                    Some(coefficients) => { inner_total += coefficients.calculate(&param_value); },
-                    None => (),
+            // Record the impact of each input parameter used in the current layer's
+            // modifiers, looking up whichever of the two is smaller in the other.
+            let modifiers = &layer_modifiers.modifiers;
+            if modifiers.len() <= parameters.len() {
+                for (param, coefficients) in modifiers.iter() {
+                    if let Some(param_value) = parameters.get(param) {
+                        inner_total += coefficients.calculate(param_value);
+                    }
+                }
+            } else {
+                for (param, param_value) in parameters {
+                    if let Some(coefficients) = modifiers.get(param) {
+                        inner_total += coefficients.calculate(param_value);
+                    }
                 }
             }
 
             // Check if current layer applies coefficients to the total after previous layer
             // Since "total" is updated at the end of each full layer, that same "total"
             // is the resulf of the prevous layer used as an input parameter.
-            match &layer_modifiers.previous_layer_coefficients {
-                Some(t_coefficients) => { inner_total += t_coefficients.calculate(&total); },
-                _ => (),
+            if let Some(t_coefficients) = &layer_modifiers.previous_layer_coefficients {
+                inner_total += t_coefficients.calculate(&total);
+            }
+
+            for gated in &layer_modifiers.gated_modifiers {
+                inner_total += gated.calculate(parameters);
             }
 
             // Add in the bias "layer_bias" to the current layer's calculation.
@@ -80,76 +269,377 @@ impl Creature {
         total
     }
 
+    /// Like "calculate", but in a fixed order of operations which gives the same
+    /// f32 on every platform (see `EvolutionConfig::strict_fp`): terms are summed
+    /// in order of parameter name and powers are multiplied out from left to right.
+    pub fn calculate_strict(&self, parameters: &HashMap<String, f32>) -> f32 {
+        let mut total = 0.0;
+        let mut inner_total = 0.0;
+        for layer_modifiers in &self.equation {
+            let mut add_term = |param: &String, coefficients: &Coefficients| if let Some(param_value) = parameters.get(param) {
+                inner_total += coefficients.calculate_strict(param_value);
+            };
+            match &layer_modifiers.modifiers {
+                Modifiers::Sorted(terms) => terms.iter().for_each(|(param, coefficients)| add_term(param, coefficients)),
+                modifiers => modifiers.sorted().into_iter().for_each(|(param, coefficients)| add_term(param, coefficients)),
+            }
+            if let Some(t_coefficients) = &layer_modifiers.previous_layer_coefficients {
+                inner_total += t_coefficients.calculate_strict(&total);
+            }
+            for gated in &layer_modifiers.gated_modifiers {
+                inner_total += gated.calculate_strict(parameters);
+            }
+            total = inner_total + layer_modifiers.layer_bias;
+        }
+        total
+    }
+
+    /// "calculate_strict" when "strict" and otherwise "calculate".
+    pub(crate) fn calculate_with(&self, parameters: &HashMap<String, f32>, strict: bool) -> f32 {
+        match strict {
+            true => self.calculate_strict(parameters),
+            false => self.calculate(parameters),
+        }
+    }
+
+    /// Whether any term uses an input parameter missing from "parameters".
+    pub(crate) fn uses_missing(&self, parameters: &HashMap<String, f32>) -> bool {
+        self.equation.iter().any(|layer| {
+            layer.modifiers.iter().any(|(param, _)| !parameters.contains_key(param))
+                || layer.gated_modifiers.iter().any(|gated| !parameters.contains_key(&gated.param) || !parameters.contains_key(&gated.gate_param))
+        })
+    }
+
+    /// Every input parameter of this creature's terms (including gates), in sorted order.
+    pub(crate) fn parameters(&self) -> Vec<&str> {
+        let parameters: BTreeSet<&str> = self.equation.iter()
+            .flat_map(|layer| {
+                layer.modifiers.iter().map(|(param, _)| param.as_str())
+                    .chain(layer.gated_modifiers.iter().flat_map(|gated| [gated.param.as_str(), gated.gate_param.as_str()]))
+            })
+            .collect();
+        parameters.into_iter().collect()
+    }
+
+    /// Render this creature's equation in the given format.
+    /// "param_expr" supplies the expression used for each input parameter.
+    pub(crate) fn render(&self, format: FormulaFormat, precision: Precision, param_expr: &dyn Fn(&str) -> String) -> String {
+        // Like "calculate", terms carry over from one layer into the next
+        // with each layer's total also feeding the next layer's Coefficients.
+        let mut terms = Vec::new();
+        let mut total = String::from("0");
+        for layer_modifiers in &self.equation {
+            for (param, coefficients) in layer_modifiers.modifiers.sorted() {
+                terms.push(coefficients.render(format, precision, &param_expr(param)));
+            }
+            if let Some(t_coefficients) = &layer_modifiers.previous_layer_coefficients {
+                terms.push(t_coefficients.render(format, precision, &total));
+            }
+            for gated in &layer_modifiers.gated_modifiers {
+                let term = gated.coefficients.render(format, precision, &param_expr(&gated.param));
+                let above = matches!(gated.gate, Gate::Above);
+                terms.push(format.gated(&param_expr(&gated.gate_param), above, gated.threshold, &term, precision));
+            }
+            let mut layer_terms = terms.clone();
+            layer_terms.push(format.number(layer_modifiers.layer_bias, precision));
+            total = format.sum(&layer_terms);
+        }
+        total
+    }
+
+    /// Fails with the location of the first NaN or infinite value
+    /// (in the order of `Creature::constants`), if there is one.
+    pub fn check_finite(&self) -> Result<(), ExportError> {
+        let non_finite = |coefficients: &Coefficients| [coefficients.c, coefficients.b, coefficients.z].iter().any(|value| !value.is_finite());
+        for (l, layer) in self.equation.iter().enumerate() {
+            let located = |param: &str| Err(ExportError::NonFiniteCoefficient { layer: l + 1, param: param.to_string() });
+            if let Some((param, _)) = layer.modifiers.sorted().into_iter().find(|(_, coefficients)| non_finite(coefficients)) {
+                return located(param);
+            }
+            if layer.previous_layer_coefficients.as_ref().is_some_and(non_finite) {
+                return located("previous_layer");
+            }
+            if let Some(gated) = layer.gated_modifiers.iter().find(|gated| non_finite(&gated.coefficients) || !gated.threshold.is_finite()) {
+                return located(&gated.param);
+            }
+            if !layer.layer_bias.is_finite() {
+                return located("bias");
+            }
+        }
+        Ok(())
+    }
+
+    /// Graphviz DOT diagram of this creature's structure: input parameters on the left,
+    /// each layer's terms (annotated with their "c, b, z, x") feeding the layer's total
+    /// (with its bias), each layer's total feeding the next layer's previous layer term,
+    /// and the last layer's total as the output.  Nodes are in a deterministic order.
+    /// Fails rather than writing a NaN or infinite value.
+    pub fn to_dot(&self) -> Result<String, ExportError> {
+        self.check_finite()?;
+        let mut inputs: Vec<&str> = self.equation.iter().flat_map(|layer| {
+            layer.modifiers.iter().map(|(param, _)| param.as_str())
+                .chain(layer.gated_modifiers.iter().flat_map(|gated| [gated.param.as_str(), gated.gate_param.as_str()]))
+        }).collect();
+        inputs.sort_unstable();
+        inputs.dedup();
+        let input_id = |param: &str| format!("input_{}", inputs.binary_search(&param).unwrap());
+
+        let mut dot = String::from("digraph creature {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (i, param) in inputs.iter().enumerate() {
+            dot += &format!("    input_{} [label=\"{}\", shape=ellipse];\n", i, dot_escape(param));
+        }
+        for (l, layer) in self.equation.iter().enumerate() {
+            let total = format!("layer_{}_total", l + 1);
+            dot += &format!("    {} [label=\"Layer {}\\nbias = {:.4}\", shape=circle];\n", total, l + 1, layer.layer_bias);
+            for (t, (param, coefficients)) in layer.modifiers.sorted().into_iter().enumerate() {
+                let term = format!("layer_{}_term_{}", l + 1, t + 1);
+                dot += &format!("    {} [label=\"{}\"];\n", term, coefficients.dot_label());
+                dot += &format!("    {} -> {};\n    {} -> {};\n", input_id(param), term, term, total);
+            }
+            if let Some(t_coefficients) = &layer.previous_layer_coefficients {
+                let term = format!("layer_{}_previous", l + 1);
+                dot += &format!("    {} [label=\"{}\"];\n", term, t_coefficients.dot_label());
+                dot += &format!("    layer_{}_total -> {};\n    {} -> {};\n", l, term, term, total);
+            }
+            for (g, gated) in layer.gated_modifiers.iter().enumerate() {
+                let term = format!("layer_{}_gated_{}", l + 1, g + 1);
+                let comparison = match gated.gate {
+                    Gate::Above => ">=",
+                    Gate::Below => "<",
+                };
+                dot += &format!("    {} [label=\"{}\\nif {} {} {:.4}\"];\n",
+                                term, gated.coefficients.dot_label(), dot_escape(&gated.gate_param), comparison, gated.threshold);
+                dot += &format!("    {} -> {};\n    {} -> {} [style=dashed];\n    {} -> {};\n",
+                                input_id(&gated.param), term, input_id(&gated.gate_param), term, term, total);
+            }
+        }
+        dot += "    output [label=\"output\", shape=doublecircle];\n";
+        if !self.equation.is_empty() {
+            dot += &format!("    layer_{}_total -> output;\n", self.equation.len());
+        }
+        dot += "}\n";
+        Ok(dot)
+    }
+
     pub fn create_many(num_creatures: u32, parameter_options: &Vec<&str>, max_layers: u8) -> Vec<Creature> {
         let creatures: Vec<Creature> = (0..num_creatures)
-            .map(|_| Creature::new(&parameter_options, max_layers))
+            .map(|_| Creature::new(parameter_options, max_layers))
             .collect();
         creatures
     }
 
     pub fn create_many_parallel(num_creatures: u32, parameter_options: &Vec<&str>, max_layers: u8) -> Vec<Creature> {
+        Creature::create_many_parallel_with(num_creatures, parameter_options, max_layers, &CreationOptions::default())
+    }
+
+    /// Panics if `CreationOptions::priors` are invalid.
+    pub fn create_many_parallel_with(num_creatures: u32,
+                                     parameter_options: &Vec<&str>,
+                                     max_layers: u8,
+                                     options: &CreationOptions) -> Vec<Creature> {
+        let samplers = Samplers::expect(&options.priors);
         let creatures: Vec<Creature> = (0..num_creatures)
             .into_par_iter()
-            .map(|_| Creature::new(&parameter_options, max_layers))
+            .map(|_| Creature::generate(parameter_options, max_layers, options, &samplers, &mut thread_rng()))
             .collect();
         creatures
     }
 
+    /// Like "create_many_parallel_with" but with creature "i" drawn from
+    /// `stream.rng(first_index + i)`, so the creatures are reproducible.
+    pub(crate) fn create_many_seeded(num_creatures: u32,
+                                     parameter_options: &Vec<&str>,
+                                     max_layers: u8,
+                                     options: &CreationOptions,
+                                     samplers: &Samplers,
+                                     stream: Stream,
+                                     first_index: usize) -> Vec<Creature> {
+        (0..num_creatures as usize)
+            .into_par_iter()
+            .map(|index| Creature::generate(parameter_options, max_layers, options, samplers, &mut stream.rng(first_index + index)))
+            .collect()
+    }
+
     pub fn mutate(&self, mutate_speed: MutateSpeed) -> Creature {
-        let modify_value = match mutate_speed {
-                MutateSpeed::Fine => 0.005,
-                MutateSpeed::Fast => 0.05,
-        };
+        self.mutate_with(mutate_speed, &MutationOptions::default())
+    }
 
-        let mut rng = thread_rng();
-        let norm = Normal::new(0.0, modify_value).unwrap();
+    pub fn mutate_with(&self, mutate_speed: MutateSpeed, options: &MutationOptions) -> Creature {
+        self.perturbed(mutate_speed.sigma(), options, Samplers::standard(), &mut thread_rng())
+    }
+
+    /// Mutant with Gaussian noise of standard deviation "sigma" added to each
+    /// constant (after any weight decay) and (unless the structure is frozen)
+    /// randomly stepped exponents.  A term given to an empty layer draws its
+    /// constants from "samplers".
+    pub(crate) fn perturbed(&self, sigma: f32, options: &MutationOptions, samplers: &Samplers, rng: &mut dyn RngCore) -> Creature {
 
         let mut new_equation: Vec<LayerModifiers> = Vec::new();
         for layer_mods in &self.equation {
             let layer_bias = match rng.gen::<f64>() {
-                x if x < 0.5 => layer_mods.layer_bias + rng.sample(norm),
-                _ => layer_mods.layer_bias.clone(),
+                x if x < 0.5 => layer_mods.layer_bias + samplers::gaussian(sigma, rng),
+                _ => layer_mods.layer_bias,
             };
 
             let mut modified_coefficients = |coeff: &Coefficients| {
+                let mutated = |value: f32, rng: &mut dyn RngCore| match rng.gen::<f64>() < options.coefficient_probability {
+                    true => value * (1.0 - options.weight_decay) + samplers::gaussian(sigma, rng),
+                    false => value,
+                };
                 Coefficients {
-                    c: &coeff.c + rng.sample(norm),
-                    b: &coeff.b + rng.sample(norm),
-                    z: &coeff.z + rng.sample(norm),
+                    c: mutated(coeff.c, rng),
+                    b: mutated(coeff.b, rng),
+                    z: mutated(coeff.z, rng),
                     x: match rng.gen::<f64>() {
-                        num if num < 0.2 => &coeff.x + 1,
-                        num if num < 0.4 && &coeff.x > &1 => &coeff.x - 1,
-                        _ => coeff.x,
-                    }
+                        _ if options.freeze_structure => coeff.x,
+                        _ if rng.gen::<f64>() >= options.coefficient_probability => coeff.x.min(options.max_exponent),
+                        num if num < 0.2 => (coeff.x + 1).min(options.max_exponent),
+                        num if num < 0.4 && coeff.x > 1 => coeff.x - 1,
+                        _ => coeff.x.min(options.max_exponent),
+                    },
                 }
             };
 
-            let previous_layer_coefficients = match &layer_mods.previous_layer_coefficients {
-                Some(coeff) => Some(modified_coefficients(&coeff)),
-                None => None,
-            };
+            let previous_layer_coefficients = layer_mods.previous_layer_coefficients.as_ref().map(&mut modified_coefficients);
+
+            let mut modifiers = layer_mods.modifiers.map(&mut modified_coefficients);
 
-            let mut modifiers = HashMap::new();
-            for (param, coeff) in &layer_mods.modifiers {
-                modifiers.insert(param.to_owned(), modified_coefficients(coeff));
+            let mut gated_modifiers: Vec<GatedCoefficients> = layer_mods.gated_modifiers.iter()
+                .map(|gated| GatedCoefficients {
+                    coefficients: modified_coefficients(&gated.coefficients),
+                    ..gated.clone()
+                })
+                .collect();
+            for gated in gated_modifiers.iter_mut() {
+                if rng.gen::<f64>() < options.coefficient_probability {
+                    gated.threshold += samplers::gaussian(sigma, rng);
+                }
+            }
+            if layer_mods.is_empty() && !options.freeze_structure && !options.parameter_options.is_empty()
+                && rng.gen::<f64>() < EMPTY_LAYER_TERM_PROBABILITY {
+                let param = &options.parameter_options[rng.gen_range(0..options.parameter_options.len())];
+                modifiers = Modifiers::from_terms(vec![(param.clone(), Coefficients::new(samplers, rng))]);
             }
 
             let new_layer_mods = LayerModifiers {
-                modifiers: modifiers,
-                previous_layer_coefficients: previous_layer_coefficients,
-                layer_bias: layer_bias,
+                modifiers,
+                previous_layer_coefficients,
+                gated_modifiers,
+                layer_bias,
             };
 
             new_equation.push(new_layer_mods);
         }
-        Creature { equation: new_equation, cached_error_sum: None , generation: &self.generation + 1 }
+        let mut creature = Creature { equation: new_equation, cached_error_sum: None , generation: self.generation.saturating_add(1) };
+        samplers.pin(&mut creature);
+        creature
+    }
+
+    /// Creature summing the (parameter, Coefficients) terms of every layer plus
+    /// "bias", as each layer's terms carry on into the total of the layers after it.
+    pub(crate) fn stacked(layers: Vec<Vec<(String, Coefficients)>>, bias: f32) -> Creature {
+        let last = layers.len() - 1;
+        let equation = layers.into_iter().enumerate().map(|(index, terms)| LayerModifiers {
+            modifiers: Modifiers::from_terms(terms),
+            previous_layer_coefficients: None,
+            gated_modifiers: Vec::new(),
+            layer_bias: if index == last { bias } else { 0.0 },
+        }).collect();
+        Creature { equation, cached_error_sum: None, generation: 1 }
+    }
+
+    /// Copy with the last layer's bias set to "bias" and the multipliers "c" of its
+    /// terms (its parameter terms in sorted order, then the previous layer term and
+    /// then the gated terms), which enter the output linearly, set to "multipliers".
+    pub(crate) fn with_linear_coefficients(&self, bias: f32, multipliers: &[f32]) -> Creature {
+        let mut creature = self.clone();
+        if let Some(layer) = creature.equation.last_mut() {
+            let mut multipliers = multipliers.iter();
+            let mut set = |coefficients: &mut Coefficients| coefficients.c = *multipliers.next().expect("A multiplier for every linear term");
+            layer.modifiers.for_each_sorted(&mut set);
+            layer.previous_layer_coefficients.iter_mut().for_each(&mut set);
+            layer.gated_modifiers.iter_mut().for_each(|gated| set(&mut gated.coefficients));
+            layer.layer_bias = bias;
+        }
+        creature.invalidate_cache();
+        creature
+    }
+
+    /// Copy with each term zeroed with chance "probability" (see `EvolutionConfig::term_dropout`).
+    pub(crate) fn masked(&self, probability: f64, rng: &mut dyn RngCore) -> Creature {
+        let mut creature = self.clone();
+        creature.for_each_coefficients(|coefficients| {
+            if rng.gen::<f64>() < probability {
+                coefficients.c = 0.0;
+            }
+        });
+        creature.invalidate_cache();
+        creature
+    }
+
+    /// Call "f" with the parameter and Coefficients of every parameter term (including gated terms).
+    pub(crate) fn for_each_term(&mut self, mut f: impl FnMut(&str, &mut Coefficients)) {
+        for layer in self.equation.iter_mut() {
+            layer.modifiers.for_each_term(&mut f);
+            layer.gated_modifiers.iter_mut().for_each(|gated| f(&gated.param, &mut gated.coefficients));
+        }
+    }
+
+    fn for_each_coefficients(&mut self, mut f: impl FnMut(&mut Coefficients)) {
+        for layer in self.equation.iter_mut() {
+            layer.modifiers.for_each_sorted(&mut f);
+            layer.previous_layer_coefficients.iter_mut().for_each(&mut f);
+            layer.gated_modifiers.iter_mut().for_each(|gated| f(&mut gated.coefficients));
+        }
+    }
+
+    /// Every continuous constant of the equation (the "c", "b" and "z" of each
+    /// term, gate thresholds and layer biases) in a fixed order, for custom
+    /// `Mutator`s which perturb all of them at once.
+    pub fn constants(&self) -> Vec<f32> {
+        let mut constants = Vec::new();
+        self.clone().visit_constants(|value| constants.push(*value));
+        constants
+    }
+
+    /// Copy of this creature with its constants replaced by "constants"
+    /// (in the order of `Creature::constants`) counting as a new generation.
+    pub fn with_constants(&self, constants: &[f32]) -> Creature {
+        let mut creature = self.clone();
+        let mut values = constants.iter();
+        creature.visit_constants(|value| *value = *values.next().expect("Too few constants for creature"));
+        assert!(values.next().is_none(), "Too many constants for creature");
+        creature.invalidate_cache();
+        creature.generation = self.generation.saturating_add(1);
+        creature
+    }
+
+    fn visit_constants(&mut self, mut visit: impl FnMut(&mut f32)) {
+        fn visit_coefficients(coefficients: &mut Coefficients, visit: &mut impl FnMut(&mut f32)) {
+            visit(&mut coefficients.c);
+            visit(&mut coefficients.b);
+            visit(&mut coefficients.z);
+        }
+        for layer in self.equation.iter_mut() {
+            layer.modifiers.for_each_sorted(&mut |coefficients| visit_coefficients(coefficients, &mut visit));
+            if let Some(coefficients) = layer.previous_layer_coefficients.as_mut() {
+                visit_coefficients(coefficients, &mut visit);
+            }
+            for gated in layer.gated_modifiers.iter_mut() {
+                visit_coefficients(&mut gated.coefficients, &mut visit);
+                visit(&mut gated.threshold);
+            }
+            visit(&mut layer.layer_bias);
+        }
     }
 }
 
 impl fmt::Display for Creature {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, " {}\n", "Creature")?;
-        //write!(f, "Creature:\n({}, {})", self.num_layers(), self.equation)
+        writeln!(f, " Creature")?;
+        if let Err(err) = self.check_finite() {
+            return writeln!(f, "  Invalid: {}", err);
+        }
         for (i, layer_mod) in self.equation.iter().enumerate() {
             write!(f, "  Layer {}\n{}", i+1, layer_mod)?;
         }
@@ -157,88 +647,354 @@ impl fmt::Display for Creature {
     }
 }
 
+/// "text" escaped for use inside a double quoted DOT string.
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 /// Each "LayerModifiers" represents a full neural network layer.
 /// "modifiers" is a collection of Coefficents applied to certain input parameters.
 /// The "previous_layer_coefficients" field is Coefficients applied to a previous layer's output, if applicable.
+/// The "gated_modifiers" field is Coefficients which only apply past a threshold (see "GatedCoefficients").
 /// The "layer_bias" field is a bias added to the layer's calculation.
 #[derive(Clone)]
 #[derive(Debug)]
-struct LayerModifiers {
-    modifiers: HashMap<String, Coefficients>,
-    previous_layer_coefficients: Option<Coefficients>,
-    layer_bias: f32,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct LayerModifiers {
+    pub(crate) modifiers: Modifiers,
+    pub(crate) previous_layer_coefficients: Option<Coefficients>,
+    pub(crate) gated_modifiers: Vec<GatedCoefficients>,
+    pub(crate) layer_bias: f32,
 }
 
 impl LayerModifiers {
-    fn new(first_layer: bool, parameter_options: &Vec<&str>) -> LayerModifiers {
-        let mut rng = thread_rng();
+    fn new(first_layer: bool,
+           parameter_options: &Vec<&str>,
+           options: &CreationOptions,
+           samplers: &Samplers,
+           rng: &mut dyn RngCore) -> LayerModifiers {
 
-        let mut modifiers = HashMap::new();
         let param_usage_scalar = 2.5 / (parameter_options.len() as f64 + 1.0);
-        for &param in parameter_options {
-            if rng.gen::<f64>() < param_usage_scalar {
-                modifiers.insert(param.to_string(), Coefficients::new());
-            }
+        let mut params = included_params(parameter_options, param_usage_scalar, options, rng);
+        // The first layer always gets a term so a new creature is never a constant
+        if first_layer && params.is_empty() && !parameter_options.is_empty() {
+            params.push(parameter_options[rng.gen_range(0..parameter_options.len())]);
         }
+        let modifiers = Modifiers::from_terms(
+            params.into_iter()
+                .map(|param| (param.to_string(), Coefficients::with_sign_bias(options.sign_bias(param), samplers, rng)))
+                .collect()
+        );
 
         let previous_layer_coefficients = match first_layer {
-            false => Some(Coefficients::new()),
+            false => Some(Coefficients::new(samplers, rng)),
             true => None,
         };
 
-        let norm = Normal::new(0.0, 0.1).unwrap();
+        let mut gated_modifiers = Vec::new();
+        if !parameter_options.is_empty() && rng.gen::<f64>() < options.gated_probability {
+            gated_modifiers.push(GatedCoefficients::new(parameter_options, samplers, rng));
+        }
+
         let layer_bias = match rng.gen::<f64>() {
-            x if x >= 0.0 && x <= 0.2 => 0.0,
-            _ => rng.sample(norm),
+            x if (0.0..=0.2).contains(&x) => 0.0,
+            _ => samplers.layer_bias(rng),
         };
-        LayerModifiers { modifiers, previous_layer_coefficients, layer_bias }
+        LayerModifiers { modifiers, previous_layer_coefficients, gated_modifiers, layer_bias }
+    }
+
+    /// Whether this layer has no terms at all (only a bias).
+    fn is_empty(&self) -> bool {
+        self.modifiers.is_empty() && self.previous_layer_coefficients.is_none() && self.gated_modifiers.is_empty()
+    }
+}
+/// The parameters which get a term in a new layer, each included with chance "base"
+/// (adjusted by any data-informed prior).  Without a prior, the number of terms is
+/// drawn first and then that many distinct parameters, so wide data does not need
+/// a random draw for every parameter.
+fn included_params<'a>(parameter_options: &[&'a str], base: f64, options: &CreationOptions, rng: &mut dyn RngCore) -> Vec<&'a str> {
+    if options.data_informed_strength > 0.0 && options.feature_correlations.is_some() {
+        return parameter_options.iter()
+            .copied()
+            .filter(|param| rng.gen::<f64>() < options.usage_probability(param, base))
+            .collect();
+    }
+    let count = Binomial::new(parameter_options.len() as u64, base.min(1.0)).unwrap().sample(rng) as usize;
+    index::sample(rng, parameter_options.len(), count).into_iter().map(|index| parameter_options[index]).collect()
+}
+
+/// The Coefficients of a layer applied to input parameters.  Layers usually
+/// use only a few parameters (even with hundreds of columns), which are kept
+/// in a Vec sorted by parameter name; larger layers use a HashMap instead.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "BTreeMap<String, Coefficients>", into = "BTreeMap<String, Coefficients>"))]
+pub(crate) enum Modifiers {
+    Sorted(Vec<(String, Coefficients)>),
+    Map(HashMap<String, Coefficients>),
+}
+
+impl Modifiers {
+    /// Terms for distinct parameters in the layout suiting their number.
+    pub(crate) fn from_terms(mut terms: Vec<(String, Coefficients)>) -> Modifiers {
+        match terms.len() < SORTED_MODIFIERS_LIMIT {
+            true => {
+                terms.sort_by(|a, b| a.0.cmp(&b.0));
+                Modifiers::Sorted(terms)
+            },
+            false => Modifiers::Map(terms.into_iter().collect()),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Modifiers::Sorted(terms) => terms.len(),
+            Modifiers::Map(terms) => terms.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn get(&self, param: &str) -> Option<&Coefficients> {
+        match self {
+            Modifiers::Sorted(terms) => terms.binary_search_by(|(name, _)| name.as_str().cmp(param))
+                .ok()
+                .map(|index| &terms[index].1),
+            Modifiers::Map(terms) => terms.get(param),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&String, &Coefficients)> {
+        match self {
+            Modifiers::Sorted(terms) => Either::Left(terms.iter().map(|(param, coefficients)| (param, coefficients))),
+            Modifiers::Map(terms) => Either::Right(terms.iter()),
+        }
+    }
+
+    /// The terms in order of parameter name.
+    pub(crate) fn sorted(&self) -> Vec<(&String, &Coefficients)> {
+        let mut terms: Vec<(&String, &Coefficients)> = self.iter().collect();
+        if let Modifiers::Map(_) = self {
+            terms.sort_by(|a, b| a.0.cmp(b.0));
+        }
+        terms
+    }
+
+    /// Call "f" with every term's Coefficients in order of parameter name.
+    fn for_each_sorted(&mut self, f: &mut impl FnMut(&mut Coefficients)) {
+        match self {
+            Modifiers::Sorted(terms) => terms.iter_mut().for_each(|(_, coefficients)| f(coefficients)),
+            Modifiers::Map(terms) => {
+                let mut terms: Vec<(&String, &mut Coefficients)> = terms.iter_mut().collect();
+                terms.sort_by(|a, b| a.0.cmp(b.0));
+                terms.into_iter().for_each(|(_, coefficients)| f(coefficients));
+            },
+        }
+    }
+
+    fn for_each_term(&mut self, f: &mut impl FnMut(&str, &mut Coefficients)) {
+        match self {
+            Modifiers::Sorted(terms) => terms.iter_mut().for_each(|(param, coefficients)| f(param, coefficients)),
+            Modifiers::Map(terms) => terms.iter_mut().for_each(|(param, coefficients)| f(param, coefficients)),
+        }
+    }
+
+    /// Apply "f" to every term's Coefficients, keeping the layout.
+    fn map(&self, mut f: impl FnMut(&Coefficients) -> Coefficients) -> Modifiers {
+        match self {
+            Modifiers::Sorted(terms) => Modifiers::Sorted(terms.iter().map(|(param, coefficients)| (param.clone(), f(coefficients))).collect()),
+            Modifiers::Map(terms) => Modifiers::Map(terms.iter().map(|(param, coefficients)| (param.clone(), f(coefficients))).collect()),
+        }
+    }
+}
+
+impl Default for Modifiers {
+    fn default() -> Modifiers {
+        Modifiers::Sorted(Vec::new())
     }
 }
+
+impl From<BTreeMap<String, Coefficients>> for Modifiers {
+    fn from(terms: BTreeMap<String, Coefficients>) -> Modifiers {
+        Modifiers::from_terms(terms.into_iter().collect())
+    }
+}
+
+impl From<Modifiers> for BTreeMap<String, Coefficients> {
+    fn from(modifiers: Modifiers) -> BTreeMap<String, Coefficients> {
+        match modifiers {
+            Modifiers::Sorted(terms) => terms.into_iter().collect(),
+            Modifiers::Map(terms) => terms.into_iter().collect(),
+        }
+    }
+}
+
 impl fmt::Display for LayerModifiers {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "    Bias:  {:.4}\n", self.layer_bias)?;
-        match &self.previous_layer_coefficients {
-            Some(coeff) => write!(f, "    Previous Layer:   ->  {}\n", coeff)?,
-            _ => (),
+        writeln!(f, "    Bias:  {:.4}", self.layer_bias)?;
+        if let Some(coeff) = &self.previous_layer_coefficients {
+            writeln!(f, "    Previous Layer:   ->  {}", coeff)?;
         }
-        for (key, coeff) in &self.modifiers {
-            write!(f, "    Param \"{}\"   ->   {}\n", key, coeff)?;
+        for (key, coeff) in self.modifiers.sorted() {
+            writeln!(f, "    Param \"{}\"   ->   {}", key, coeff)?;
+        }
+        for gated in &self.gated_modifiers {
+            writeln!(f, "    {}", gated)?;
         }
         Ok(())
     }
 }
 
+/// A "GatedCoefficients" applies its Coefficients to "param" only while the
+/// value of "gate_param" (often the same parameter) is past "threshold":
+///   Gate::Above  ->  applies when gate value >= threshold
+///   Gate::Below  ->  applies when gate value <  threshold
+/// Otherwise, or if either parameter is missing (or the gate value is NaN),
+/// the term contributes nothing.  Thresholds are on the standardized scale
+/// and get mutated along with the Coefficients.
+#[derive(Clone)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct GatedCoefficients {
+    pub(crate) param: String,
+    pub(crate) gate_param: String,
+    pub(crate) gate: Gate,
+    pub(crate) threshold: f32,
+    pub(crate) coefficients: Coefficients,
+}
+
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum Gate {
+    Above,
+    Below,
+}
+
+impl GatedCoefficients {
+    fn new(parameter_options: &[&str], samplers: &Samplers, rng: &mut dyn RngCore) -> GatedCoefficients {
+        let param = *parameter_options.choose(rng).unwrap();
+        let gate_param = if rng.gen::<f64>() < 0.7 { param } else { *parameter_options.choose(rng).unwrap() };
+        let gate = if rng.gen::<f64>() < 0.5 { Gate::Above } else { Gate::Below };
+        let threshold = samplers.threshold(rng);
+        GatedCoefficients {
+            param: param.to_string(),
+            gate_param: gate_param.to_string(),
+            gate,
+            threshold,
+            coefficients: Coefficients::new(samplers, rng),
+        }
+    }
+
+    pub(crate) fn is_open(&self, gate_value: f32) -> bool {
+        match self.gate {
+            Gate::Above => gate_value >= self.threshold,
+            Gate::Below => gate_value < self.threshold,
+        }
+    }
+
+    pub(crate) fn calculate(&self, parameters: &HashMap<String, f32>) -> f32 {
+        self.open_value(parameters).map_or(0.0, |param_value| self.coefficients.calculate(param_value))
+    }
+
+    pub(crate) fn calculate_strict(&self, parameters: &HashMap<String, f32>) -> f32 {
+        self.open_value(parameters).map_or(0.0, |param_value| self.coefficients.calculate_strict(param_value))
+    }
+
+    /// Value of "param" when it and the gate are present and the gate is open.
+    fn open_value<'a>(&self, parameters: &'a HashMap<String, f32>) -> Option<&'a f32> {
+        match (parameters.get(&self.param), parameters.get(&self.gate_param)) {
+            (Some(param_value), Some(&gate_value)) if self.is_open(gate_value) => Some(param_value),
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for GatedCoefficients {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let comparison = match self.gate {
+            Gate::Above => ">=",
+            Gate::Below => "<",
+        };
+        write!(f, "Param \"{}\" if \"{}\" {} {:.4}   ->   {}",
+               self.param, self.gate_param, comparison, self.threshold, self.coefficients)
+    }
+}
+
+/// "base" to the power "exponent", multiplied out from left to right.
+pub(crate) fn strict_powi(base: f32, exponent: u8) -> f32 {
+    let mut power = 1.0;
+    for _ in 0..exponent {
+        power *= base;
+    }
+    power
+}
+
 /// A "Coefficients" struct contains 4 values which
 /// are used to form the following equation given input "param":
 /// Value = C * (B * param + Z) ^ X
-#[derive(Clone)]
+/// There is no separate additive offset for each term as the terms of a layer
+/// are summed, so any offsets would add up to (and only duplicate) the layer bias.
+#[derive(Clone, PartialEq)]
 #[derive(Debug)]
-struct Coefficients { c: f32, b: f32, z: f32, x: u8 }
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Coefficients { pub(crate) c: f32, pub(crate) b: f32, pub(crate) z: f32, pub(crate) x: u8 }
 
 impl Coefficients {
-    fn calculate(&self, &param_value: &f32) -> f32 {
-        &self.c * (&self.b * &param_value + &self.z).powi(self.x as i32)
+    pub(crate) fn calculate(&self, &param_value: &f32) -> f32 {
+        self.c * (self.b * param_value + self.z).powi(self.x as i32)
     }
-    fn new() -> Coefficients {
-        let mut rng = thread_rng();
-        let tri_a = Triangular::new(0.0, 2.0, 1.0).unwrap();
-        let tri_b = Triangular::new(-2.0, 2.0, 0.0).unwrap();
-        // let norm = Normal::new(0.0, 0.1).unwrap();
 
-        let mut c = if rng.gen::<f64>() < 0.4 { 1.0 } else { rng.sample(tri_a) };
-        let mut b = if rng.gen::<f64>() < 0.3 { 1.0 } else { rng.sample(tri_a) };
-        let z = if rng.gen::<f64>() < 0.4 { 0.0 } else { rng.sample(tri_b) };
+    /// Like "calculate" but with the power multiplied out from left to right
+    /// rather than by "powi", whose order of multiplications may vary by platform.
+    pub(crate) fn calculate_strict(&self, &param_value: &f32) -> f32 {
+        self.c * strict_powi(self.b * param_value + self.z, self.x)
+    }
+
+    /// "calculate_strict" when "strict" and otherwise "calculate".
+    pub(crate) fn calculate_with(&self, param_value: &f32, strict: bool) -> f32 {
+        match strict {
+            true => self.calculate_strict(param_value),
+            false => self.calculate(param_value),
+        }
+    }
+    fn dot_label(&self) -> String {
+        format!("c = {:.4}\\nb = {:.4}\\nz = {:.4}\\nx = {}", self.c, self.b, self.z, self.x)
+    }
+    fn render(&self, format: FormulaFormat, precision: Precision, param_expr: &str) -> String {
+        let number = |value| format.number(value, precision);
+        let inner = format!("{} + {}", format.product(&number(self.b), param_expr), number(self.z));
+        format.product(&number(self.c), &format.power(&inner, self.x))
+    }
+    fn new(samplers: &Samplers, rng: &mut dyn RngCore) -> Coefficients {
+        let mut c = if rng.gen::<f64>() < 0.4 { 1.0 } else { samplers.magnitude(rng) };
+        let mut b = if rng.gen::<f64>() < 0.3 { 1.0 } else { samplers.magnitude(rng) };
+        let z = if rng.gen::<f64>() < 0.4 { 0.0 } else { samplers.offset(rng) };
 
         if rng.gen::<f64>() < 0.5 { c = -c; }
         if rng.gen::<f64>() < 0.5 { b = -b; }
 
         let x = match rng.gen::<f64>() {
-            x if x >= 0.0 && x <= 0.4 => 1,
-            x if x >= 0.4 && x <= 0.75 => 2,
+            x if (0.0..=0.4).contains(&x) => 1,
+            x if (0.4..=0.75).contains(&x) => 2,
             _ => 3,
         };
         Coefficients { c, b, z, x }
     }
+
+    /// New Coefficients where "c * b" (the sign of the slope for odd exponents)
+    /// is positive with probability 0.5 + 0.5 * "sign_bias".
+    fn with_sign_bias(sign_bias: f64, samplers: &Samplers, rng: &mut dyn RngCore) -> Coefficients {
+        let mut coefficients = Coefficients::new(samplers, rng);
+        if sign_bias != 0.0 {
+            let positive = rng.gen::<f64>() < 0.5 + 0.5 * sign_bias;
+            if (coefficients.c * coefficients.b > 0.0) != positive {
+                coefficients.c = -coefficients.c;
+            }
+        }
+        coefficients
+    }
 }
 impl fmt::Display for Coefficients {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -248,6 +1004,47 @@ impl fmt::Display for Coefficients {
 
 
 
+#[cfg(test)]
+impl Creature {
+    /// Single layer creature "c * param^x" for tests in other modules.
+    pub(crate) fn single_term(param: &str, c: f32, x: u8) -> Creature {
+        Creature {
+            equation: vec![LayerModifiers {
+                modifiers: Modifiers::from_terms(vec![(param.to_string(), Coefficients { c, b: 1.0, z: 0.0, x })]),
+                previous_layer_coefficients: None,
+                gated_modifiers: Vec::new(),
+                layer_bias: 0.0,
+            }],
+            cached_error_sum: None,
+            generation: 1,
+        }
+    }
+
+    /// Creature made of the given layers for tests in other modules.
+    pub(crate) fn from_layers(equation: Vec<LayerModifiers>) -> Creature {
+        Creature { equation, cached_error_sum: None, generation: 1 }
+    }
+
+    /// Mutant with "delta" added to the "c" of every Coefficients.
+    pub(crate) fn with_c_shifted(&self, delta: f32) -> Creature {
+        let mut creature = self.clone();
+        creature.for_each_coefficients(|coefficients| coefficients.c += delta);
+        creature.invalidate_cache();
+        creature.generation = self.generation.saturating_add(1);
+        creature
+    }
+
+    /// The (c, b, z, x) of every Coefficients.
+    pub(crate) fn coefficient_values(&self) -> Vec<(f32, f32, f32, u8)> {
+        let mut values = Vec::new();
+        self.clone().for_each_coefficients(|coefficients| {
+            values.push((coefficients.c, coefficients.b, coefficients.z, coefficients.x))
+        });
+        values
+    }
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -259,13 +1056,12 @@ mod tests {
         let creature = Creature::new(&param_options, 3);
         println!("\n\n{}\n", creature);
 
-        assert_eq!(creature.num_layers() >= 1 && creature.num_layers() <= 3, true);
+        assert!(creature.num_layers() >= 1 && creature.num_layers() <= 3);
 
-        let test_coeff = creature.equation[0].modifiers.values().next()
-            .expect("\n--> OKAY if this fails occasionally as it is possible to \
-                     \ngenerate a creature with no modifiers for the first layer.");
+        let test_coeff = creature.equation[0].modifiers.iter().next().map(|(_, coefficients)| coefficients)
+            .expect("The first layer always has a modifier");
         println!("{}", test_coeff);
-        assert_eq!((test_coeff.c.abs() + test_coeff.b.abs()) > 0.0, true);
+        assert!((test_coeff.c.abs() + test_coeff.b.abs()) > 0.0);
 
         let input_data = HashMap::from([("width".to_string(), 2.1245), ("height".to_string(), 0.52412)]);
 
@@ -284,13 +1080,12 @@ mod tests {
             println!("{}", result);
             total += result;
         }
-        assert_eq!(total != 0.0, true);
+        assert!(total != 0.0);
     }
 
     #[test]
     fn generate_many_creatures() {
         let param_options = vec!["width", "height", "weight"];
-        //let mut creatures = Vec::new();
 
         let t0 = Instant::now();
         Creature::create_many(100000, &param_options, 3);
@@ -310,16 +1105,427 @@ mod tests {
         let param_options = vec!["width", "height", "weight"];
         let creature = Creature::new(&param_options, 3);
 
-        let mutant1 = creature.mutate(MutateSpeed::Fast);
-        let mutant2 = creature.mutate(MutateSpeed::Fine);
-        let mut_bias = mutant1.equation[0].layer_bias + mutant2.equation[0].layer_bias;
-        assert_eq!(mut_bias != (creature.equation[0].layer_bias * 2.0), true);
+        // Each mutation only adjusts a layer's bias half of the time,
+        // so check across several pairs of mutants rather than just one.
+        let bias_mutated = (0..20).any(|_| {
+            let mutant1 = creature.mutate(MutateSpeed::Fast);
+            let mutant2 = creature.mutate(MutateSpeed::Fine);
+            let mut_bias = mutant1.equation[0].layer_bias + mutant2.equation[0].layer_bias;
+            mut_bias != (creature.equation[0].layer_bias * 2.0)
+        });
+        assert!(bias_mutated);
+    }
+
+    #[test]
+    fn mutation_exponent_bounds() {
+        let param_options = vec!["width", "height", "weight"];
+        let exponents = |creature: &Creature| -> Vec<u8> {
+            creature.equation.iter()
+                .flat_map(|layer| layer.modifiers.iter().map(|(_, coefficients)| coefficients).chain(layer.previous_layer_coefficients.iter()))
+                .map(|coeff| coeff.x)
+                .collect()
+        };
+
+        let mut creature = Creature::new(&param_options, 3);
+        for _ in 0..5000 {
+            creature = creature.mutate(MutateSpeed::Fast);
+            assert!(exponents(&creature).iter().all(|&x| (1..=4).contains(&x)));
+        }
+
+        let options = MutationOptions { max_exponent: 2, ..Default::default() };
+        let mut creature = Creature::new(&param_options, 3);
+        for _ in 0..5000 {
+            creature = creature.mutate_with(MutateSpeed::Fast, &options);
+            assert!(exponents(&creature).iter().all(|&x| (1..=2).contains(&x)));
+        }
+        assert_eq!(creature.generation, u8::MAX);
+
+        let frozen = MutationOptions { freeze_structure: true, ..Default::default() };
+        let original = Creature::new(&param_options, 3);
+        let mut creature = original.clone();
+        for _ in 0..200 {
+            creature = creature.mutate_with(MutateSpeed::Fast, &frozen);
+        }
+        let sorted_exponents = |creature: &Creature| {
+            let mut exponents = exponents(creature);
+            exponents.sort();
+            exponents
+        };
+        assert_eq!(sorted_exponents(&creature), sorted_exponents(&original));
+    }
+
+    #[test]
+    fn data_informed_options() {
+        let correlations = HashMap::from([("a".to_string(), 0.9), ("b".to_string(), -0.1)]);
+        let options = CreationOptions {
+            data_informed_strength: 1.0, feature_correlations: Some(correlations), ..Default::default()
+        };
+        assert!((options.usage_probability("a", 0.5) - 0.7).abs() < 1e-6);
+        assert!((options.usage_probability("b", 0.5) - 0.3).abs() < 1e-6);
+        assert!((options.sign_bias("a") - 0.9).abs() < 1e-6);
+        assert!((options.sign_bias("b") + 0.1).abs() < 1e-6);
+        let off = CreationOptions { data_informed_strength: 0.0, ..options.clone() };
+        assert_eq!((off.usage_probability("a", 0.5), off.sign_bias("a")), (0.5, 0.0));
+
+        let positive = (0..200).filter(|_| {
+            let coefficients = Coefficients::with_sign_bias(1.0, Samplers::standard(), &mut thread_rng());
+            coefficients.c * coefficients.b > 0.0
+        }).count();
+        assert_eq!(positive, 200);
+    }
+
+    #[test]
+    fn wide_data() {
+        let names: Vec<String> = (0..500).map(|i| format!("p{}", i)).collect();
+        let param_options: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+        let base = 2.5 / (param_options.len() as f64 + 1.0);
+        let options = CreationOptions::default();
+        let mut rng = rand::thread_rng();
+        let bernoulli = |rng: &mut rand::rngs::ThreadRng| param_options.iter().filter(|_| rng.gen::<f64>() < base).count();
+
+        // Drawing the number of terms first gives the same distribution as a draw per parameter
+        let n = 5_000;
+        let stats = |counts: Vec<usize>| {
+            let mean = counts.iter().sum::<usize>() as f64 / n as f64;
+            let variance = counts.iter().map(|count| (*count as f64 - mean).powi(2)).sum::<f64>() / n as f64;
+            (mean, variance)
+        };
+        let (sampled_mean, sampled_variance) = stats((0..n).map(|_| included_params(&param_options, base, &options, &mut rng).len()).collect());
+        let (bernoulli_mean, bernoulli_variance) = stats((0..n).map(|_| bernoulli(&mut rng)).collect());
+        assert!((sampled_mean - bernoulli_mean).abs() < 0.1, "{} vs {}", sampled_mean, bernoulli_mean);
+        assert!((sampled_variance - bernoulli_variance).abs() < 0.3, "{} vs {}", sampled_variance, bernoulli_variance);
+
+        // ...while needing far fewer random draws
+        let start = Instant::now();
+        for _ in 0..2000 {
+            included_params(&param_options, base, &options, &mut rng);
+        }
+        let sampled_time = start.elapsed();
+        let start = Instant::now();
+        for _ in 0..2000 {
+            bernoulli(&mut rng);
+        }
+        assert!(sampled_time < start.elapsed());
+
+        // Both layouts calculate the same values
+        let terms: Vec<(String, Coefficients)> = names.iter().take(40)
+            .map(|name| (name.clone(), Coefficients::new(Samplers::standard(), &mut thread_rng())))
+            .collect();
+        let sorted = Modifiers::Sorted({
+            let mut terms = terms.clone();
+            terms.sort_by(|a, b| a.0.cmp(&b.0));
+            terms
+        });
+        let mapped = Modifiers::from_terms(terms);
+        assert!(matches!(mapped, Modifiers::Map(_)));
+        let creature = |modifiers| Creature {
+            equation: vec![LayerModifiers { modifiers, previous_layer_coefficients: None, gated_modifiers: Vec::new(), layer_bias: 0.0 }],
+            cached_error_sum: None,
+            generation: 1,
+        };
+        let (sorted, mapped) = (creature(sorted), creature(mapped));
+        let few = HashMap::from([("p3".to_string(), 0.4), ("p17".to_string(), -1.2)]);
+        let many: HashMap<String, f32> = names.iter().enumerate().map(|(i, name)| (name.clone(), i as f32 / 250.0 - 1.0)).collect();
+        for input in [few, many] {
+            let (a, b) = (sorted.calculate(&input), mapped.calculate(&input));
+            assert!((a - b).abs() <= 1e-4 * a.abs().max(1.0), "{} vs {}", a, b);
+        }
+        assert_eq!(sorted.render(FormulaFormat::Text, Precision::Full, &|param| param.to_string()),
+                   mapped.render(FormulaFormat::Text, Precision::Full, &|param| param.to_string()));
+
+        let creature = Creature::new(&param_options, 3);
+        assert!(creature.equation.iter().all(|layer| layer.modifiers.len() < 20));
+    }
+
+    #[test]
+    fn sparse_mutation() {
+        let terms = (0..100).map(|i| (format!("p{}", i), Coefficients::new(Samplers::standard(), &mut thread_rng()))).collect();
+        let creature = Creature {
+            equation: vec![LayerModifiers {
+                modifiers: Modifiers::from_terms(terms),
+                previous_layer_coefficients: None,
+                gated_modifiers: Vec::new(),
+                layer_bias: 0.0,
+            }],
+            cached_error_sum: None,
+            generation: 1,
+        };
+        let changed_fraction = |options: &MutationOptions| {
+            let mutant = creature.mutate_with(MutateSpeed::Fast, options);
+            let changed = mutant.coefficient_values().iter().zip(creature.coefficient_values())
+                .map(|(new, old)| [new.0 != old.0, new.1 != old.1, new.2 != old.2].iter().filter(|changed| **changed).count())
+                .sum::<usize>();
+            changed as f64 / 300.0
+        };
+        assert_eq!(changed_fraction(&MutationOptions::default()), 1.0);
+        let sparse = MutationOptions { coefficient_probability: 0.2, ..Default::default() };
+        let fraction = changed_fraction(&sparse);
+        assert!(fraction > 0.1 && fraction < 0.3, "{}", fraction);
+        let none = MutationOptions { coefficient_probability: 0.0, ..Default::default() };
+        let mutant = creature.mutate_with(MutateSpeed::Fast, &none);
+        assert_eq!(mutant.coefficient_values(), creature.coefficient_values());
+    }
+
+    #[test]
+    fn constants_round_trip() {
+        let options = CreationOptions { gated_probability: 1.0, ..Default::default() };
+        let creature = Creature::new_with(&vec!["a", "b", "c"], 3, &options);
+        let constants = creature.constants();
+        assert_eq!(creature.with_constants(&constants).coefficient_values(), creature.coefficient_values());
+
+        let shifted: Vec<f32> = constants.iter().map(|value| value + 1.0).collect();
+        let changed = creature.with_constants(&shifted);
+        assert_eq!(changed.constants(), shifted);
+        assert_eq!((changed.cached_error(), changed.generation), (None, creature.generation + 1));
+        assert!(std::panic::catch_unwind(|| creature.with_constants(&constants[1..])).is_err());
+    }
+
+    #[test]
+    fn gated_coefficients() {
+        let identity = Coefficients { c: 1.0, b: 1.0, z: 0.0, x: 1 };
+        let negated = Coefficients { c: -1.0, ..identity.clone() };
+        let gated = |gate, coefficients| GatedCoefficients {
+            param: "x".to_string(), gate_param: "x".to_string(), gate, threshold: 0.0, coefficients,
+        };
+        // |x| as two gated pieces meeting at the threshold
+        let creature = Creature {
+            equation: vec![LayerModifiers {
+                modifiers: Modifiers::default(),
+                previous_layer_coefficients: None,
+                gated_modifiers: vec![gated(Gate::Above, identity), gated(Gate::Below, negated)],
+                layer_bias: 0.0,
+            }],
+            cached_error_sum: None,
+            generation: 1,
+        };
+        for (x, expected) in [(-2.0, 2.0), (-0.5, 0.5), (0.0, 0.0), (0.5, 0.5), (3.0, 3.0)] {
+            let input = HashMap::from([("x".to_string(), x)]);
+            for _ in 0..3 {
+                assert_eq!(creature.calculate(&input), expected);
+            }
+        }
+        assert_eq!(creature.calculate(&HashMap::from([("y".to_string(), 1.0)])), 0.0);
+        assert_eq!(creature.calculate(&HashMap::from([("x".to_string(), f32::NAN)])), 0.0);
+
+        let param_options = vec!["width", "height", "weight"];
+        let options = CreationOptions { gated_probability: 1.0, ..Default::default() };
+        let creature = Creature::new_with(&param_options, 3, &options);
+        assert!(creature.equation.iter().all(|layer| layer.gated_modifiers.len() == 1));
+        let mutant = creature.mutate(MutateSpeed::Fast);
+        assert!(mutant.equation.iter().all(|layer| layer.gated_modifiers.len() == 1));
+        println!("{}", mutant);
+    }
+
+    #[test]
+    fn render_equation() {
+        let creature = Creature {
+            equation: vec![
+                LayerModifiers {
+                    modifiers: Modifiers::from_terms(vec![
+                        ("b".to_string(), Coefficients { c: 2.0, b: 1.0, z: 0.5, x: 1 }),
+                        ("a".to_string(), Coefficients { c: -1.0, b: 3.0, z: 0.0, x: 2 }),
+                    ]),
+                    previous_layer_coefficients: None,
+                    gated_modifiers: Vec::new(),
+                    layer_bias: 0.25,
+                },
+                LayerModifiers {
+                    modifiers: Modifiers::default(),
+                    previous_layer_coefficients: Some(Coefficients { c: 1.0, b: 1.0, z: 0.0, x: 1 }),
+                    gated_modifiers: Vec::new(),
+                    layer_bias: 0.0,
+                },
+            ],
+            cached_error_sum: None,
+            generation: 1,
+        };
+        let layer_1 = "((-1) * (3 * a + 0)^2 + 2 * (1 * b + 0.5)^1 + 0.25)";
+        let expected = format!("((-1) * (3 * a + 0)^2 + 2 * (1 * b + 0.5)^1 + 1 * (1 * {} + 0)^1 + 0)", layer_1);
+        assert_eq!(creature.render(FormulaFormat::Text, Precision::Full, &|param| param.to_string()), expected);
+        let rounded = creature.render(FormulaFormat::Text, Precision::Decimals(1), &|param| param.to_string());
+        assert!(rounded.starts_with("((-1.0) * (3.0 * a + 0.0)^2 + 2.0 * (1.0 * b + 0.5)^1"));
+    }
+
+    #[test]
+    fn render_full_precision() {
+        let coefficients = Coefficients { c: 1.0 / 3.0, b: 0.1, z: -2.0 / 7.0, x: 1 };
+        let text = coefficients.render(FormulaFormat::Sql, Precision::Full, "\"x\"");
+        let numbers: Vec<f32> = text.split(|c: char| "()*+, ".contains(c))
+            .filter_map(|piece| piece.parse().ok())
+            .collect();
+        let bits = |values: &[f32]| values.iter().map(|value| value.to_bits()).collect::<Vec<u32>>();
+        // The trailing 1 is the exponent
+        assert_eq!(bits(&numbers), bits(&[coefficients.c, coefficients.b, coefficients.z, 1.0]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn term_serialization() {
+        let coefficients = Coefficients { c: 2.0, b: -0.5, z: 1.0, x: 3 };
+        let json = serde_json::to_value(&coefficients).unwrap();
+        let mut keys: Vec<&String> = json.as_object().unwrap().keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["b", "c", "x", "z"]);
+        let loaded: Coefficients = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.calculate(&2.0), coefficients.calculate(&2.0));
+        assert_eq!(loaded.calculate(&2.0), 2.0 * (-0.5f32 * 2.0 + 1.0).powi(3));
+    }
+
+    #[test]
+    fn formula_of_hand_built_term() {
+        use crate::config::{EvolutionConfig, Verbosity};
+        use crate::evolution::Evolution;
+        // "x" and "y" both have a mean of 0, so only their standard deviation shows in the formula
+        let data: Vec<HashMap<String, f32>> = (0..20).map(|i| {
+            let value = if i % 2 == 0 { 1.0 } else { -1.0 };
+            HashMap::from([("x".to_string(), value), ("y".to_string(), value)])
+        }).collect();
+        let config = EvolutionConfig { num_creatures: 100, num_cycles: 1, verbosity: Verbosity::Silent, ..Default::default() };
+        let mut evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        evo.best_creature = Creature::stacked(vec![vec![("x".to_string(), Coefficients { c: 2.0, b: -0.5, z: 1.5, x: 3 })]], 0.0);
+        let stdev = evo.standardizer.param_standardizer("x").unwrap().stdev();
+
+        // Each term is c*(b*x+z)^x with nothing added to it outside of the layer bias
+        assert_eq!(evo.formula(FormulaFormat::Text).unwrap(),
+                   format!("y = ((2 * ((-0.5) * ((x - 0) / {0}) + 1.5)^3 + 0) * {0} + 0)", stdev));
+        assert_eq!(evo.formula(FormulaFormat::Latex).unwrap(), format!(
+            "\\mathrm{{y}} = ((2 \\cdot \\left((-0.5) \\cdot \\frac{{\\mathrm{{x}} - 0}}{{{0}}} + 1.5\\right)^{{3}} + 0) \\cdot {0} + 0)",
+            stdev));
+        assert_eq!(evo.formula(FormulaFormat::Sql).unwrap(),
+                   format!("((2 * POWER((-0.5) * ((\"x\" - 0) / {0}) + 1.5, 3) + 0) * {0} + 0) AS \"y\"", stdev));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn creature_serialization() {
+        let terms = |count: usize| -> Vec<(String, Coefficients)> {
+            (0..count).map(|i| (format!("p{}", i), Coefficients { c: 0.1 * i as f32, b: 1.5, z: -0.25, x: (i % 3 + 1) as u8 })).collect()
+        };
+        // The first layer is wide enough to keep its terms in a HashMap
+        let wide = Creature::stacked(vec![terms(SORTED_MODIFIERS_LIMIT + 4), terms(3)], 0.75);
+        assert!(matches!(wide.equation[0].modifiers, Modifiers::Map(_)));
+        let options = CreationOptions { gated_probability: 1.0, ..Default::default() };
+        let gated = Creature::generate(&vec!["p0", "p1", "p2"], 3, &options, Samplers::standard(), &mut StdRng::seed_from_u64(2));
+        let point: HashMap<String, f32> = (0..SORTED_MODIFIERS_LIMIT + 4).map(|i| (format!("p{}", i), 0.2 * i as f32 - 1.0)).collect();
+
+        for mut creature in [wide, gated] {
+            creature.set_cached_error(0.125);
+            creature.generation = 7;
+            let json = serde_json::to_string(&creature).unwrap();
+            let loaded: Creature = serde_json::from_str(&json).unwrap();
+            assert_eq!((loaded.cached_error_sum, loaded.generation), (Some(0.125), 7));
+            assert_eq!(loaded.coefficient_values(), creature.coefficient_values());
+            assert_eq!(loaded.constants(), creature.constants());
+            assert_eq!(loaded.calculate_strict(&point), creature.calculate_strict(&point));
+            assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn first_layer_never_empty() {
+        let param_options = vec!["width", "height", "weight", "depth", "age", "volume"];
+        let creatures = Creature::create_many_parallel(100_000, &param_options, 3);
+        assert!(creatures.iter().all(|creature| !creature.equation[0].modifiers.is_empty()));
+        assert!(creatures.iter().all(|creature| !creature.is_constant()));
+        assert!(Creature::new(&vec![], 1).is_constant());
+    }
+
+    #[test]
+    fn constant_creature_gains_term() {
+        let constant = Creature {
+            equation: vec![LayerModifiers {
+                modifiers: Modifiers::default(),
+                previous_layer_coefficients: None,
+                gated_modifiers: Vec::new(),
+                layer_bias: 1.5,
+            }],
+            cached_error_sum: None,
+            generation: 1,
+        };
+        assert!(constant.is_constant());
+
+        // Without parameter options (or with a frozen structure) it stays a constant
+        let options = MutationOptions::default();
+        assert!((0..100).all(|_| constant.mutate_with(MutateSpeed::Fast, &options).is_constant()));
+        let options = MutationOptions { parameter_options: vec!["x".to_string(), "y".to_string()], ..Default::default() };
+        let frozen = MutationOptions { freeze_structure: true, ..options.clone() };
+        assert!((0..100).all(|_| constant.mutate_with(MutateSpeed::Fast, &frozen).is_constant()));
+
+        let mut creature = constant.clone();
+        for _ in 0..100 {
+            creature = creature.mutate_with(MutateSpeed::Fast, &options);
+            if !creature.is_constant() {
+                break;
+            }
+        }
+        assert_eq!(creature.complexity(), 1);
+        let (param, _) = creature.equation[0].modifiers.iter().next().unwrap();
+        assert!(options.parameter_options.contains(param));
+    }
+
+    #[test]
+    fn dot_export() {
+        let coefficients = |c: f32| Coefficients { c, b: 1.0, z: 0.0, x: 1 };
+        let creature = Creature {
+            equation: vec![
+                LayerModifiers {
+                    modifiers: Modifiers::from_terms(vec![("z".to_string(), coefficients(2.0)), ("x".to_string(), coefficients(0.5))]),
+                    previous_layer_coefficients: None,
+                    gated_modifiers: Vec::new(),
+                    layer_bias: 0.25,
+                },
+                LayerModifiers {
+                    modifiers: Modifiers::from_terms(vec![("x".to_string(), coefficients(-1.0))]),
+                    previous_layer_coefficients: Some(coefficients(3.0)),
+                    gated_modifiers: vec![GatedCoefficients {
+                        param: "w\"q".to_string(),
+                        gate_param: "x".to_string(),
+                        gate: Gate::Above,
+                        threshold: 0.5,
+                        coefficients: coefficients(1.5),
+                    }],
+                    layer_bias: 0.0,
+                },
+            ],
+            cached_error_sum: None,
+            generation: 1,
+        };
+        let dot = creature.to_dot().unwrap();
+        assert!(dot.starts_with("digraph creature {"));
+        assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+        assert_eq!(dot.trim_end().chars().last(), Some('}'));
+
+        // 3 inputs, 2 + 3 terms, 2 layer totals and the output
+        let nodes: Vec<&str> = dot.lines().filter(|line| line.contains("[label=") && !line.contains("->")).collect();
+        assert_eq!(nodes.len(), 11);
+        assert!(nodes[0].contains("input_0 [label=\"w\\\"q\""));
+        assert!(dot.contains("input_1 -> layer_1_term_1;"));
+        assert!(dot.contains("layer_1_total -> layer_2_previous;"));
+        assert!(dot.contains("input_1 -> layer_2_gated_1 [style=dashed];"));
+        assert!(dot.contains("layer_2_total -> output;"));
+        assert!(dot.contains("bias = 0.2500"));
+        assert_eq!(dot, creature.to_dot().unwrap());
+        assert_eq!(dot, creature.clone().to_dot().unwrap());
+    }
+
+    #[test]
+    fn cache_invalidation() {
+        let param_options = vec!["width", "height", "weight"];
+        let mut creature = Creature::new(&param_options, 3);
+        creature.set_cached_error(1.5);
+        assert_eq!(creature.cached_error(), Some(1.5));
+        creature.invalidate_cache();
+        assert_eq!(creature.cached_error(), None);
+
+        // Mutants are new creatures which always need scoring
+        creature.set_cached_error(1.5);
+        assert_eq!(creature.mutate(MutateSpeed::Fine).cached_error(), None);
     }
 
     #[test]
     fn num_layer_bounds() {
-        let layers: Vec<u8> = (0..10000).map(|_| num_layers()).collect();
-        assert_eq!(*layers.iter().min().unwrap(), 1 as u8);
-        assert_eq!(*layers.iter().max().unwrap(), 3 as u8);
+        let layers: Vec<u8> = (0..10000).map(|_| num_layers(&mut thread_rng())).collect();
+        assert_eq!(*layers.iter().min().unwrap(), 1_u8);
+        assert_eq!(*layers.iter().max().unwrap(), 3_u8);
     }
 }
@@ -0,0 +1,354 @@
+//! Helpers for building and working with datasets
+//! (each data point is a HashMap of column name to value).
+//! Like `EvolutionConfig::seed`, every random helper seeds a `StdRng`
+//! with "seed", so the same seed always gives the same result.
+
+pub mod synthetic;
+
+use crate::config::ConfigError;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+type Rows = Vec<HashMap<String, f32>>;
+
+
+/// How the column names of the training data and of points to predict
+/// are matched (see `EvolutionConfig::column_names`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColumnNames {
+    /// Names are used as is.
+    #[default]
+    Exact,
+    /// Names are trimmed and lowercased with each run of internal whitespace
+    /// replaced by a single "_", so " Width " and "width" are the same column.
+    Normalized,
+}
+
+impl ColumnNames {
+    /// The name the model uses for column "name".
+    pub fn normalize<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match self {
+            ColumnNames::Exact => Cow::Borrowed(name),
+            ColumnNames::Normalized => Cow::Owned(name.split_whitespace().collect::<Vec<&str>>().join("_").to_lowercase()),
+        }
+    }
+
+    /// Each normalized name shared by more than one of "columns" (in sorted
+    /// order) along with the sorted original names sharing it.
+    pub fn collisions<'a>(&self, columns: impl IntoIterator<Item = &'a String>) -> Vec<(String, Vec<String>)> {
+        let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+        for column in columns {
+            groups.entry(self.normalize(column).into_owned()).or_default().push(column.clone());
+        }
+        let mut collisions: Vec<(String, Vec<String>)> = groups.into_iter()
+            .filter(|(_, columns)| columns.len() > 1)
+            .map(|(normalized, mut columns)| {
+                columns.sort();
+                (normalized, columns)
+            })
+            .collect();
+        collisions.sort();
+        collisions
+    }
+
+    /// "point" with every column name normalized, or a `ConfigError::ColumnCollision`
+    /// for the first (in sorted order) normalized name two of its names share.
+    pub fn normalized_point<'a>(&self, point: &'a HashMap<String, f32>) -> Result<Cow<'a, HashMap<String, f32>>, ConfigError> {
+        if *self == ColumnNames::Exact {
+            return Ok(Cow::Borrowed(point));
+        }
+        let normalized: HashMap<String, f32> = point.iter()
+            .map(|(name, value)| (self.normalize(name).into_owned(), *value))
+            .collect();
+        if normalized.len() < point.len() {
+            let (normalized, columns) = self.collisions(point.keys()).remove(0);
+            return Err(ConfigError::ColumnCollision { normalized, columns });
+        }
+        Ok(Cow::Owned(normalized))
+    }
+
+    /// Every row of "data" with its column names normalized, or
+    /// the collision of the first row whose names collide.
+    pub fn normalized_rows<'a>(&self, data: &'a [HashMap<String, f32>]) -> Result<Cow<'a, [HashMap<String, f32>]>, ConfigError> {
+        match self {
+            ColumnNames::Exact => Ok(Cow::Borrowed(data)),
+            ColumnNames::Normalized => data.iter()
+                .map(|point| self.normalized_point(point).map(Cow::into_owned))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Cow::Owned),
+        }
+    }
+}
+
+
+/// How missing (null) values are turned into data points
+/// (see `EvolutionConfig::missing_values`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MissingValues {
+    /// Rows with a missing value are left out.
+    #[default]
+    DropRow,
+    /// Missing values are replaced by the given value.
+    Fill(f32),
+}
+
+
+/// How an input parameter missing from a data point is treated, the same
+/// way while training and predicting (see `EvolutionConfig::missing_params`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MissingParams {
+    /// Terms of the parameter are left out, contributing nothing.  This flatters
+    /// creatures using a sparsely populated column, as the rows missing it add
+    /// no error from those terms.  `Evolution::predict_point` fails for points
+    /// missing a parameter the model uses.
+    #[default]
+    Skip,
+    /// The parameter's training mean (0 once standardized).
+    Mean,
+    /// The given value, in the original units of the parameter.
+    Constant(f32),
+    /// While training, each row missing a parameter a creature uses adds the
+    /// given loss (on the standardized target) instead of its actual loss.
+    /// `Evolution::predict_point` fails for points missing a parameter the model
+    /// uses, and other predictions of such points are NaN.
+    Penalty(f32),
+}
+
+
+/// Every column in any point of "data" other than "target", in sorted order.
+pub(crate) fn input_columns<'a>(data: &'a [HashMap<String, f32>], target: &str) -> Vec<&'a str> {
+    let mut columns: Vec<&str> = data.iter()
+        .flat_map(|point| point.keys())
+        .map(|column| column.as_str())
+        .filter(|column| *column != target)
+        .collect();
+    columns.sort();
+    columns.dedup();
+    columns
+}
+
+/// Paired values of "column" and "target" from the points having both.
+pub(crate) fn paired_values(data: &[HashMap<String, f32>], column: &str, target: &str) -> (Vec<f64>, Vec<f64>) {
+    data.iter()
+        .filter_map(|point| Some((*point.get(column)? as f64, *point.get(target)? as f64)))
+        .unzip()
+}
+
+
+/// Shuffle "data" in place.
+pub fn shuffle<T>(data: &mut [T], seed: u64) {
+    data.shuffle(&mut StdRng::seed_from_u64(seed));
+}
+
+/// Randomly split "data" into (train, test) rows with "fraction" (0.0 to 1.0)
+/// of the rows (rounded) in the test set.  Both sets are in shuffled order.
+pub fn train_test_split<T: Clone>(data: &[T], fraction: f32, seed: u64) -> (Vec<T>, Vec<T>) {
+    let mut shuffled = data.to_vec();
+    shuffle(&mut shuffled, seed);
+    let test = shuffled.split_off(data.len() - test_count(data.len(), fraction));
+    (shuffled, test)
+}
+
+/// Like "train_test_split", but the rows are first sorted into "bins" groups of
+/// (nearly) equal size by the value of "target" and each group is split on its own,
+/// so the train and test sets cover the same range of target values.
+pub fn stratified_split(data: &[HashMap<String, f32>],
+                        target: &str,
+                        bins: usize,
+                        fraction: f32,
+                        seed: u64) -> (Rows, Rows) {
+    assert!(bins > 0, "stratified_split needs at least one bin");
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    order.sort_by(|&a, &b| data[a][target].total_cmp(&data[b][target]));
+    let groups: Vec<&[usize]> = (0..bins)
+        .map(|bin| &order[bin * data.len() / bins..(bin + 1) * data.len() / bins])
+        .collect();
+
+    // Give each group its share of the test rows, with the leftover rows
+    // going to the groups with the largest remainders
+    let total = test_count(data.len(), fraction);
+    let shares: Vec<f32> = groups.iter().map(|group| group.len() as f32 * total as f32 / data.len().max(1) as f32).collect();
+    let mut counts: Vec<usize> = shares.iter().map(|share| share.floor() as usize).collect();
+    let mut by_remainder: Vec<usize> = (0..groups.len()).collect();
+    by_remainder.sort_by(|&a, &b| (shares[b] - shares[b].floor()).total_cmp(&(shares[a] - shares[a].floor())));
+    for group in by_remainder.into_iter().take(total - counts.iter().sum::<usize>()) {
+        counts[group] += 1;
+    }
+
+    let (mut train, mut test) = (Vec::new(), Vec::new());
+    for (group, count) in groups.into_iter().zip(counts) {
+        let mut group = group.to_vec();
+        group.shuffle(&mut rng);
+        test.extend(group[..count].iter().map(|&index| data[index].clone()));
+        train.extend(group[count..].iter().map(|&index| data[index].clone()));
+    }
+    train.shuffle(&mut rng);
+    test.shuffle(&mut rng);
+    (train, test)
+}
+
+/// Indices of the rows of "data" grouped into sets of exact duplicates
+/// (the same columns with bit-for-bit equal values), with the groups
+/// in order of their first row and each group's indices in order.
+pub(crate) fn duplicate_groups(data: &[HashMap<String, f32>]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of_row: HashMap<Vec<(&str, u32)>, usize> = HashMap::with_capacity(data.len());
+    for (index, row) in data.iter().enumerate() {
+        let mut key: Vec<(&str, u32)> = row.iter().map(|(name, value)| (name.as_str(), value.to_bits())).collect();
+        key.sort_unstable();
+        match group_of_row.get(&key) {
+            Some(&group) => groups[group].push(index),
+            None => {
+                group_of_row.insert(key, groups.len());
+                groups.push(vec![index]);
+            },
+        }
+    }
+    groups
+}
+
+/// Rows stored against one copy of each column name ("parameters") rather than a
+/// HashMap of owned names per row, which takes much less memory for large datasets.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RevoData {
+    parameters: Vec<String>,
+    /// (index into "parameters", value) of each column present, in parameter order.
+    rows: Vec<Vec<(usize, f32)>>,
+}
+
+impl RevoData {
+    pub fn new(data: &[HashMap<String, f32>]) -> RevoData {
+        let mut parameters: Vec<String> = data.iter().flat_map(|row| row.keys()).cloned().collect();
+        parameters.sort_unstable();
+        parameters.dedup();
+        let rows = data.iter().map(|row| {
+            let mut values: Vec<(usize, f32)> = row.iter()
+                .map(|(name, value)| (parameters.binary_search(name).unwrap(), *value))
+                .collect();
+            values.sort_unstable_by_key(|(index, _)| *index);
+            values
+        }).collect();
+        RevoData { parameters, rows }
+    }
+
+    /// Every column name, sorted.
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The (column, value) pairs of each row, in column order.
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = (&str, f32)>> {
+        self.rows.iter().map(|row| row.iter().map(|(index, value)| (self.parameters[*index].as_str(), *value)))
+    }
+
+    /// The rows as the HashMaps the rest of the crate takes.
+    pub fn to_rows(&self) -> Vec<HashMap<String, f32>> {
+        self.rows().map(|row| row.map(|(name, value)| (name.to_string(), value)).collect()).collect()
+    }
+}
+
+/// Number of the "n_rows" rows which go to the test set.
+fn test_count(n_rows: usize, fraction: f32) -> usize {
+    assert!((0.0..=1.0).contains(&fraction), "The test fraction must be from 0.0 to 1.0");
+    (fraction * n_rows as f32).round() as usize
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reproducible_splits() {
+        let data = synthetic::linear(50, 2, 0.1, 3);
+        let mut shuffled = data.clone();
+        shuffle(&mut shuffled, 5);
+        let mut again = data.clone();
+        shuffle(&mut again, 5);
+        assert_eq!(shuffled, again);
+        assert_ne!(shuffled, data);
+
+        let (train, test) = train_test_split(&data, 0.2, 9);
+        assert_eq!((train.len(), test.len()), (40, 10));
+        assert_eq!((train.clone(), test.clone()), train_test_split(&data, 0.2, 9));
+        assert!(data.iter().all(|row| train.contains(row) != test.contains(row)));
+        assert_eq!(train_test_split(&data, 0.0, 9).1.len(), 0);
+        assert_eq!(train_test_split(&data, 1.0, 9).0.len(), 0);
+
+        let (train, test) = stratified_split(&data, "y", 4, 0.3, 9);
+        assert_eq!((train.len(), test.len()), (35, 15));
+        assert_eq!((train.clone(), test.clone()), stratified_split(&data, "y", 4, 0.3, 9));
+        assert!(data.iter().all(|row| train.contains(row) != test.contains(row)));
+        // Each quarter of the target values is represented in the test set
+        let mut targets: Vec<f32> = data.iter().map(|row| row["y"]).collect();
+        targets.sort_by(|a, b| a.total_cmp(b));
+        for bin in 0..4 {
+            let quarter = &targets[bin * 50 / 4..(bin + 1) * 50 / 4];
+            assert!(test.iter().filter(|row| quarter.contains(&row["y"])).count() >= 3);
+        }
+        assert_eq!(stratified_split(&data, "y", 3, 0.0, 1).1.len(), 0);
+        assert_eq!(stratified_split(&data, "y", 3, 1.0, 1).0.len(), 0);
+        assert_eq!(stratified_split(&data[..0], "y", 3, 0.5, 1), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn generate_from_data() {
+        let mut data = synthetic::linear(30, 2, 0.1, 4);
+        data[3].remove("x2");
+        let revo_data = RevoData::new(&data);
+        assert_eq!(revo_data.parameters(), ["x1", "x2", "y"]);
+        assert_eq!(revo_data.len(), 30);
+        let third: Vec<(&str, f32)> = revo_data.rows().nth(3).unwrap().collect();
+        assert_eq!(third, vec![("x1", data[3]["x1"]), ("y", data[3]["y"])]);
+        assert_eq!(revo_data.to_rows(), data);
+        assert!(RevoData::new(&[]).is_empty());
+    }
+
+    #[test]
+    fn duplicate_rows() {
+        let row = |x: f32, y: f32| HashMap::from([("x".to_string(), x), ("y".to_string(), y)]);
+        let data = vec![row(1.0, 2.0), row(3.0, 4.0), row(1.0, 2.0), row(1.0, -2.0), row(3.0, 4.0), row(1.0, 2.0)];
+        assert_eq!(duplicate_groups(&data), vec![vec![0, 2, 5], vec![1, 4], vec![3]]);
+        assert_eq!(duplicate_groups(&[row(0.0, 1.0), row(-0.0, 1.0)]).len(), 2);
+        assert!(duplicate_groups(&[]).is_empty());
+    }
+
+    #[test]
+    fn normalized_column_names() {
+        let names = ColumnNames::Normalized;
+        assert_eq!(names.normalize(" Width "), "width");
+        assert_eq!(names.normalize("Box  Height\t(cm)"), "box_height_(cm)");
+        assert_eq!(ColumnNames::Exact.normalize(" Width "), " Width ");
+
+        let point = HashMap::from([(" Width ".to_string(), 2.0), ("Box Height".to_string(), 3.0)]);
+        let expected = HashMap::from([("width".to_string(), 2.0), ("box_height".to_string(), 3.0)]);
+        assert_eq!(names.normalized_point(&point).unwrap().into_owned(), expected);
+        assert_eq!(names.normalized_point(&expected).unwrap().into_owned(), expected);
+
+        let columns = ["width", " Width ", "WIDTH", "height"].map(String::from);
+        assert_eq!(names.collisions(&columns), vec![
+            ("width".to_string(), vec![" Width ".to_string(), "WIDTH".to_string(), "width".to_string()]),
+        ]);
+        assert!(ColumnNames::Exact.collisions(&columns).is_empty());
+
+        let colliding = HashMap::from([("Width".to_string(), 1.0), ("width".to_string(), 2.0)]);
+        let collision = ConfigError::ColumnCollision { normalized: "width".to_string(), columns: vec!["Width".to_string(), "width".to_string()] };
+        assert_eq!(names.normalized_point(&colliding), Err(collision.clone()));
+        assert_eq!(names.normalized_rows(&[point, colliding]), Err(collision));
+    }
+}
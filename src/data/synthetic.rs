@@ -0,0 +1,171 @@
+//! Reproducible synthetic datasets with a known relationship between
+//! the features "x1", "x2", ... and the target "y" (see `TARGET`).
+//! Feature values are drawn uniformly from [-3, 3] (except for `friedman1`)
+//! and normally distributed noise with a standard deviation of "noise_sigma"
+//! is added to the target.  The same "seed" always produces the same data.
+//! Also available as `revogression::synthetic`.
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rand_distr::{Normal, Uniform};
+use std::collections::HashMap;
+
+
+/// Name of the target column in every synthetic dataset.
+pub const TARGET: &str = "y";
+
+/// Name of the feature at (zero-based) "index": "x1", "x2", ...
+pub fn feature_name(index: usize) -> String {
+    format!("x{}", index + 1)
+}
+
+/// y = 1 + 1 * x1 + 2 * x2 + ... + n * xn + noise
+pub fn linear(n_rows: usize, n_features: usize, noise_sigma: f32, seed: u64) -> Vec<HashMap<String, f32>> {
+    generate_sum(n_rows, n_features, noise_sigma, seed, |index, x| (index + 1) as f32 * x, 1.0)
+}
+
+/// y = 1 + (x1^2 - x1) + 2 * (x2^2 - x2) + ... + n * (xn^2 - xn) + noise
+pub fn polynomial(n_rows: usize, n_features: usize, noise_sigma: f32, seed: u64) -> Vec<HashMap<String, f32>> {
+    generate_sum(n_rows, n_features, noise_sigma, seed, |index, x| (index + 1) as f32 * (x * x - x), 1.0)
+}
+
+/// y = sin(x1) + sin(2 * x2) + ... + sin(n * xn) + noise
+pub fn sinusoidal(n_rows: usize, n_features: usize, noise_sigma: f32, seed: u64) -> Vec<HashMap<String, f32>> {
+    generate_sum(n_rows, n_features, noise_sigma, seed, |index, x| ((index + 1) as f32 * x).sin(), 0.0)
+}
+
+/// y = intercept + slope * x1 + noise
+pub fn linear_1d(n_rows: usize, slope: f32, intercept: f32, noise_sigma: f32, seed: u64) -> Vec<HashMap<String, f32>> {
+    generate_sum(n_rows, 1, noise_sigma, seed, |_, x| slope * x, intercept)
+}
+
+/// y = coefficients[0] + coefficients[1] * x1 + coefficients[2] * x1^2 + ... + noise
+pub fn polynomial_1d(n_rows: usize, coefficients: &[f32], noise_sigma: f32, seed: u64) -> Vec<HashMap<String, f32>> {
+    generate_sum(n_rows, 1, noise_sigma, seed, |_, x| coefficients.iter().rev().fold(0.0, |total, c| total * x + c), 0.0)
+}
+
+/// y = amplitude * sin(2 * pi * x1 / period) + noise
+pub fn sine(n_rows: usize, amplitude: f32, period: f32, noise_sigma: f32, seed: u64) -> Vec<HashMap<String, f32>> {
+    generate_sum(n_rows, 1, noise_sigma, seed, |_, x| amplitude * (std::f32::consts::TAU * x / period).sin(), 0.0)
+}
+
+/// The Friedman #1 regression benchmark, with features "x1" to "x10" drawn
+/// uniformly from [0, 1] of which only the first five affect the target:
+/// y = 10 * sin(pi * x1 * x2) + 20 * (x3 - 0.5)^2 + 10 * x4 + 5 * x5 + noise
+pub fn friedman1(n_rows: usize, noise_sigma: f32, seed: u64) -> Vec<HashMap<String, f32>> {
+    generate(n_rows, 10, (0.0, 1.0), noise_sigma, seed, |x| {
+        10.0 * (std::f32::consts::PI * x[0] * x[1]).sin() + 20.0 * (x[2] - 0.5).powi(2) + 10.0 * x[3] + 5.0 * x[4]
+    })
+}
+
+/// Build rows where the target is "intercept" plus the sum of "term"
+/// (given each feature's index and value) over all features, plus noise.
+fn generate_sum(n_rows: usize,
+                n_features: usize,
+                noise_sigma: f32,
+                seed: u64,
+                term: impl Fn(usize, f32) -> f32,
+                intercept: f32) -> Vec<HashMap<String, f32>> {
+    generate(n_rows, n_features, (-3.0, 3.0), noise_sigma, seed, |values| {
+        intercept + values.iter().enumerate().map(|(index, x)| term(index, *x)).sum::<f32>()
+    })
+}
+
+/// Build rows of "n_features" features drawn uniformly from the inclusive "range"
+/// with a target of "target" (given the row's feature values in order) plus noise.
+fn generate(n_rows: usize,
+            n_features: usize,
+            range: (f32, f32),
+            noise_sigma: f32,
+            seed: u64,
+            target: impl Fn(&[f32]) -> f32) -> Vec<HashMap<String, f32>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let feature_values = Uniform::new_inclusive(range.0, range.1);
+    let noise = Normal::new(0.0, noise_sigma).expect("noise_sigma must be finite and non-negative");
+    let names: Vec<String> = (0..n_features).map(feature_name).collect();
+
+    (0..n_rows).map(|_| {
+        let values: Vec<f32> = names.iter().map(|_| rng.sample(feature_values)).collect();
+        let mut row: HashMap<String, f32> = names.iter().cloned().zip(values.iter().copied()).collect();
+        row.insert(TARGET.to_string(), target(&values) + rng.sample(noise));
+        row
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EvolutionConfig;
+    use crate::evolution::Evolution;
+
+    #[test]
+    fn reproducible_generators() {
+        for generator in [linear, polynomial, sinusoidal] {
+            let data = generator(50, 3, 0.1, 7);
+            assert_eq!(data.len(), 50);
+            assert!(data.iter().all(|row| row.len() == 4 && row.contains_key("x3") && row.contains_key(TARGET)));
+            assert_eq!(data, generator(50, 3, 0.1, 7));
+            assert_ne!(data, generator(50, 3, 0.1, 8));
+        }
+
+        for row in polynomial(20, 2, 0.0, 1) {
+            let (x1, x2) = (row["x1"], row["x2"]);
+            assert!((row[TARGET] - (1.0 + x1 * x1 - x1 + 2.0 * (x2 * x2 - x2))).abs() < 1e-5);
+            assert!((-3.0..=3.0).contains(&x1));
+        }
+        for row in sinusoidal(20, 2, 0.0, 1) {
+            assert!((row[TARGET] - (row["x1"].sin() + (2.0 * row["x2"]).sin())).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn single_feature_and_friedman_generators() {
+        type Generator = fn(u64) -> Vec<HashMap<String, f32>>;
+        let generators: [Generator; 4] = [
+            |seed| linear_1d(30, 2.0, -1.0, 0.1, seed),
+            |seed| polynomial_1d(30, &[1.0, 0.0, -0.5], 0.1, seed),
+            |seed| sine(30, 3.0, 4.0, 0.1, seed),
+            |seed| friedman1(30, 0.1, seed),
+        ];
+        for generator in generators {
+            let data = generator(11);
+            assert_eq!(data.len(), 30);
+            assert_eq!(data, generator(11));
+            assert_ne!(data, generator(12));
+        }
+
+        for row in linear_1d(20, 2.0, -1.0, 0.0, 1) {
+            assert_eq!(row.len(), 2);
+            assert!((row[TARGET] - (2.0 * row["x1"] - 1.0)).abs() < 1e-5);
+        }
+        for row in polynomial_1d(20, &[1.0, 0.0, -0.5], 0.0, 1) {
+            assert!((row[TARGET] - (1.0 - 0.5 * row["x1"] * row["x1"])).abs() < 1e-5);
+        }
+        for row in sine(20, 3.0, 4.0, 0.0, 1) {
+            assert!((row[TARGET] - 3.0 * (std::f32::consts::PI * row["x1"] / 2.0).sin()).abs() < 1e-5);
+        }
+        for row in friedman1(20, 0.0, 1) {
+            assert_eq!(row.len(), 11);
+            assert!((1..=10).all(|index| (0.0..=1.0).contains(&row[&feature_name(index - 1)])));
+            assert!((0.0..=30.0).contains(&row[TARGET]));
+        }
+    }
+
+    #[test]
+    fn recovers_linear_relationship() {
+        let data = linear(100, 2, 0.05, 3);
+        let config = EvolutionConfig { num_creatures: 3000, num_cycles: 6, ..Default::default() };
+        let evo = Evolution::with_config(TARGET.into(), &data, &config).unwrap();
+
+        // Slopes measured from the model should match the generating coefficients
+        let at = |x1: f32, x2: f32| evo.predict_point(&HashMap::from([
+            ("x1".to_string(), x1), ("x2".to_string(), x2),
+        ])).unwrap();
+        let slope_x1 = (at(1.0, 0.0) - at(-1.0, 0.0)) / 2.0;
+        let slope_x2 = (at(0.0, 1.0) - at(0.0, -1.0)) / 2.0;
+        println!("Slopes: {} {}", slope_x1, slope_x2);
+        assert!((slope_x1 - 1.0).abs() < 0.2);
+        assert!((slope_x2 - 2.0).abs() < 0.2);
+    }
+}
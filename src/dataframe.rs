@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use polars::prelude::{Column, DataFrame, DataType, NamedFrom, PolarsError, Series};
+use crate::config::{ConfigError, EvolutionConfig};
+use crate::data::MissingValues;
+use crate::evolution::Evolution;
+
+
+/// A problem converting a Polars DataFrame to or from data points.
+#[derive(Debug)]
+pub enum DataFrameError {
+    /// Columns (in DataFrame order) which are not integers or floats.
+    NonNumeric(Vec<String>),
+    /// A value too large in magnitude for an f32.
+    Overflow { column: String, row: usize, value: f64 },
+    /// The target column is not in the DataFrame.
+    MissingTarget(String),
+    Config(ConfigError),
+    Polars(PolarsError),
+}
+
+impl fmt::Display for DataFrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataFrameError::NonNumeric(columns) => {
+                let columns: Vec<String> = columns.iter().map(|column| format!("\"{}\"", column)).collect();
+                write!(f, "DataFrame columns {} are not numeric", columns.join(", "))
+            },
+            DataFrameError::Overflow { column, row, value } => write!(
+                f, "DataFrame value {} of column \"{}\" in row {} does not fit in an f32", value, column, row
+            ),
+            DataFrameError::MissingTarget(target) => write!(f, "target column \"{}\" is not in the DataFrame", target),
+            DataFrameError::Config(err) => write!(f, "{}", err),
+            DataFrameError::Polars(err) => write!(f, "Polars error: {}", err),
+        }
+    }
+}
+
+impl Error for DataFrameError {}
+
+impl From<ConfigError> for DataFrameError {
+    fn from(err: ConfigError) -> DataFrameError {
+        DataFrameError::Config(err)
+    }
+}
+
+impl From<PolarsError> for DataFrameError {
+    fn from(err: PolarsError) -> DataFrameError {
+        DataFrameError::Polars(err)
+    }
+}
+
+
+/// The values of "column" as f32, with None for nulls.
+fn column_values(column: &Column) -> Result<Vec<Option<f32>>, DataFrameError> {
+    let values = column.cast(&DataType::Float64)?;
+    values.f64()?.into_iter().enumerate().map(|(row, value)| match value {
+        Some(value) if value.is_finite() && value.abs() > f32::MAX as f64 => Err(DataFrameError::Overflow {
+            column: column.name().to_string(), row, value,
+        }),
+        value => Ok(value.map(|value| value as f32)),
+    }).collect()
+}
+
+/// Every row of "df" as a data point, or None for rows dropped by `MissingValues::DropRow`.
+/// Nulls in the "target" column (if any) always drop the row.
+fn data_points(df: &DataFrame, target: Option<&str>, missing_values: MissingValues) -> Result<Vec<Option<HashMap<String, f32>>>, DataFrameError> {
+    let non_numeric: Vec<String> = df.get_columns().iter()
+        .filter(|column| !column.dtype().is_primitive_numeric())
+        .map(|column| column.name().to_string())
+        .collect();
+    if !non_numeric.is_empty() {
+        return Err(DataFrameError::NonNumeric(non_numeric));
+    }
+
+    let mut points: Vec<Option<HashMap<String, f32>>> = vec![Some(HashMap::new()); df.height()];
+    for column in df.get_columns() {
+        let name = column.name().to_string();
+        for (point, value) in points.iter_mut().zip(column_values(column)?) {
+            let value = match (value, missing_values) {
+                (Some(value), _) => value,
+                (None, MissingValues::Fill(fill)) if target != Some(name.as_str()) => fill,
+                (None, _) => {
+                    *point = None;
+                    continue;
+                },
+            };
+            if let Some(point) = point {
+                point.insert(name.clone(), value);
+            }
+        }
+    }
+    Ok(points)
+}
+
+
+impl Evolution {
+    /// Train on the rows of a Polars DataFrame, every column of which must
+    /// be integers or floats (cast to f32, failing on values too large for one).
+    /// Nulls are handled by `EvolutionConfig::missing_values`, except that rows
+    /// with a null "target" are always dropped.
+    pub fn from_dataframe(target: &str, df: &DataFrame, config: &EvolutionConfig) -> Result<Evolution, DataFrameError> {
+        if df.column(target).is_err() {
+            return Err(DataFrameError::MissingTarget(target.to_string()));
+        }
+        let data: Vec<HashMap<String, f32>> = data_points(df, Some(target), config.missing_values)?.into_iter().flatten().collect();
+        Ok(Evolution::builder(target, &data).config(config.clone()).build()?)
+    }
+
+    /// Predict every row of "df" (which may hold other columns, including the
+    /// target) as a Series named "name".  Rows dropped by `MissingValues::DropRow`
+    /// get a null prediction.
+    pub fn predict_dataframe(&self, df: &DataFrame, name: &str) -> Result<Series, DataFrameError> {
+        let predictions: Vec<Option<f32>> = data_points(df, None, self.config.missing_values)?.into_iter()
+            .map(|point| point.map(|point| self.predict_unchecked(&point)))
+            .collect();
+        Ok(Series::new(name.into(), predictions))
+    }
+
+    /// Append the predictions of `Evolution::predict_dataframe` to "df" as column "name".
+    pub fn append_predictions(&self, df: &mut DataFrame, name: &str) -> Result<(), DataFrameError> {
+        let predictions = self.predict_dataframe(df, name)?;
+        df.with_column(predictions)?;
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::FormulaFormat;
+
+    fn frame() -> DataFrame {
+        let x: Vec<i64> = (0..40).collect();
+        let z: Vec<f64> = (0..40).map(|i| (i % 7) as f64 * 0.5).collect();
+        let y: Vec<f64> = (0..40).map(|i| 2.0 * i as f64 + (i % 7) as f64).collect();
+        DataFrame::new(vec![
+            Series::new("x".into(), x).into(),
+            Series::new("z".into(), z).into(),
+            Series::new("y".into(), y).into(),
+        ]).unwrap()
+    }
+
+    #[test]
+    fn matches_hashmap_path() {
+        let df = frame();
+        let data: Vec<HashMap<String, f32>> = (0..40)
+            .map(|i| HashMap::from([
+                ("x".to_string(), i as f32),
+                ("z".to_string(), (i % 7) as f32 * 0.5),
+                ("y".to_string(), 2.0 * i as f32 + (i % 7) as f32),
+            ]))
+            .collect();
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 2, seed: Some(5), ..Default::default() };
+        let from_df = Evolution::from_dataframe("y", &df, &config).unwrap();
+        let from_rows = Evolution::with_config("y".into(), &data, &config).unwrap();
+        assert_eq!(from_df.formula(FormulaFormat::Text).unwrap(), from_rows.formula(FormulaFormat::Text).unwrap());
+
+        let predictions = from_df.predict_dataframe(&df, "prediction").unwrap();
+        let expected: Vec<Option<f32>> = data.iter().map(|point| Some(from_rows.predict_point(point).unwrap())).collect();
+        assert_eq!(predictions.f32().unwrap().into_iter().collect::<Vec<Option<f32>>>(), expected);
+
+        let mut appended = df.clone();
+        from_df.append_predictions(&mut appended, "prediction").unwrap();
+        assert_eq!(appended.width(), 4);
+        assert!(appended.column("prediction").unwrap().as_materialized_series().equals(&predictions));
+    }
+
+    #[test]
+    fn nulls_and_bad_columns() {
+        let mut df = frame();
+        df.with_column(Series::new("z".into(), (0..40).map(|i| (i % 5 != 0).then_some(1.0)).collect::<Vec<Option<f64>>>())).unwrap();
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 1, ..Default::default() };
+        let dropped = data_points(&df, Some("y"), MissingValues::DropRow).unwrap();
+        assert_eq!(dropped.iter().flatten().count(), 32);
+        let filled = data_points(&df, Some("y"), MissingValues::Fill(-1.0)).unwrap();
+        assert_eq!(filled[5].as_ref().unwrap()["z"], -1.0);
+
+        let evo = Evolution::from_dataframe("y", &df, &config).unwrap();
+        let predictions = evo.predict_dataframe(&df, "prediction").unwrap();
+        assert_eq!(predictions.null_count(), 8);
+
+        df.with_column(Series::new("label".into(), vec!["a"; 40])).unwrap();
+        df.with_column(Series::new("big".into(), vec![1.0e300; 40])).unwrap();
+        assert!(matches!(Evolution::from_dataframe("y", &df, &config), Err(DataFrameError::NonNumeric(columns)) if columns == vec!["label"]));
+        let _ = df.drop_in_place("label").unwrap();
+        assert!(matches!(Evolution::from_dataframe("y", &df, &config), Err(DataFrameError::Overflow { row: 0, .. })));
+        assert!(matches!(Evolution::from_dataframe("w", &df, &config), Err(DataFrameError::MissingTarget(_))));
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use crate::data::{shuffle, ColumnNames};
+
+
+/// Target encoding of categorical columns, an alternative to one-hot encoding
+/// for categories with many levels.  Each listed column holds category codes
+/// (any f32 values, compared exactly) which get replaced by the smoothed mean
+/// of the target for their category:
+///   (sum of the category's targets + smoothing * prior) / (category count + smoothing)
+/// where the prior is the mean of every target.  While training, each row is
+/// encoded from the rows of the other "folds" only, so a row's encoding never
+/// uses its own target.  The model keeps an encoding fit on every row
+/// (see `Evolution::target_encoder`) which is applied to predicted points.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TargetEncoding {
+    pub columns: Vec<String>,
+    /// Weight of the prior, in rows.  Larger values pull rare categories further towards the prior.
+    pub smoothing: f32,
+    /// Number of folds used to encode the training data, which must be at least 2.
+    pub folds: usize,
+}
+
+impl Default for TargetEncoding {
+    fn default() -> TargetEncoding {
+        TargetEncoding { columns: Vec::new(), smoothing: 10.0, folds: 5 }
+    }
+}
+
+impl TargetEncoding {
+    /// Copy with the column names as the model uses them.
+    pub(crate) fn normalized(&self, column_names: ColumnNames) -> TargetEncoding {
+        TargetEncoding {
+            columns: self.columns.iter().map(|column| column_names.normalize(column).into_owned()).collect(),
+            ..self.clone()
+        }
+    }
+}
+
+
+/// Target encodings of the categories of each column, fit on the training data.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TargetEncoder {
+    /// Mean of the target, used for categories not seen while training.
+    global_mean: f32,
+    columns: Vec<ColumnEncoder>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ColumnEncoder {
+    column: String,
+    /// Encoded value of each category, as sorted (category, value) pairs.
+    categories: Vec<(f32, f32)>,
+}
+
+impl ColumnEncoder {
+    fn fit(column: &str, rows: &[(f32, f32)], prior: f32, smoothing: f32) -> ColumnEncoder {
+        let mut stats: HashMap<u32, (f32, f32, usize)> = HashMap::new();
+        for (category, target) in rows {
+            let entry = stats.entry(category_key(*category)).or_insert((*category + 0.0, 0.0, 0));
+            entry.1 += target;
+            entry.2 += 1;
+        }
+        let mut categories: Vec<(f32, f32)> = stats.into_values()
+            .map(|(category, sum, count)| (category, (sum + smoothing * prior) / (count as f32 + smoothing)))
+            .collect();
+        categories.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ColumnEncoder { column: column.to_string(), categories }
+    }
+
+    fn encoded(&self, category: f32, fallback: f32) -> f32 {
+        let category = category + 0.0;
+        match self.categories.binary_search_by(|(known, _)| known.total_cmp(&category)) {
+            Ok(index) => self.categories[index].1,
+            Err(_) => fallback,
+        }
+    }
+}
+
+/// Key treating 0.0 and -0.0 as the same category.
+fn category_key(category: f32) -> u32 {
+    (category + 0.0).to_bits()
+}
+
+impl TargetEncoder {
+    /// Fit the encoding of "options.columns" on every point of "data".
+    pub(crate) fn fit(target: &str, data: &[HashMap<String, f32>], options: &TargetEncoding) -> TargetEncoder {
+        let rows: Vec<&HashMap<String, f32>> = data.iter().collect();
+        TargetEncoder::fit_rows(target, &rows, options)
+    }
+
+    fn fit_rows(target: &str, rows: &[&HashMap<String, f32>], options: &TargetEncoding) -> TargetEncoder {
+        let global_mean = rows.iter().map(|point| point[target]).sum::<f32>() / rows.len().max(1) as f32;
+        let columns = options.columns.iter().map(|column| {
+            let pairs: Vec<(f32, f32)> = rows.iter()
+                .filter_map(|point| point.get(column).map(|category| (*category, point[target])))
+                .collect();
+            ColumnEncoder::fit(column, &pairs, global_mean, options.smoothing)
+        }).collect();
+        TargetEncoder { global_mean, columns }
+    }
+
+    /// Copy of "data" with each row's categories encoded by an encoder fit on
+    /// the rows of the other folds (assigned at random using "seed").
+    pub(crate) fn out_of_fold(target: &str,
+        data: &[HashMap<String, f32>],
+        options: &TargetEncoding,
+        seed: u64) -> Vec<HashMap<String, f32>> {
+        let mut order: Vec<usize> = (0..data.len()).collect();
+        shuffle(&mut order, seed);
+        let folds = options.folds.clamp(1, data.len().max(1));
+        let mut fold_of = vec![0; data.len()];
+        for (position, index) in order.into_iter().enumerate() {
+            fold_of[index] = position % folds;
+        }
+
+        let mut encoded = data.to_vec();
+        for fold in 0..folds {
+            let others: Vec<&HashMap<String, f32>> = data.iter().zip(&fold_of)
+                .filter(|(_, row_fold)| **row_fold != fold)
+                .map(|(point, _)| point)
+                .collect();
+            let encoder = TargetEncoder::fit_rows(target, &others, options);
+            for (point, _) in encoded.iter_mut().zip(&fold_of).filter(|(_, row_fold)| **row_fold == fold) {
+                encoder.encode(point);
+            }
+        }
+        encoded
+    }
+
+    /// Replace the categories of "point" with their encodings.
+    /// Categories not seen while training get the global mean of the target.
+    pub fn encode(&self, point: &mut HashMap<String, f32>) {
+        for column in &self.columns {
+            if let Some(category) = point.get_mut(&column.column) {
+                *category = column.encoded(*category, self.global_mean);
+            }
+        }
+    }
+
+    /// The encoded columns.
+    pub fn columns(&self) -> Vec<&str> {
+        self.columns.iter().map(|encoder| encoder.column.as_str()).collect()
+    }
+
+    /// Mean of the target over the training data.
+    pub fn global_mean(&self) -> f32 {
+        self.global_mean
+    }
+
+    /// Encoded value of "category" in "column", if it was seen while training.
+    pub fn category_encoding(&self, column: &str, category: f32) -> Option<f32> {
+        let column = self.columns.iter().find(|encoder| encoder.column == column)?;
+        let value = column.encoded(category, f32::NAN);
+        (!value.is_nan()).then_some(value)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EvolutionConfig;
+    use crate::evolution::Evolution;
+
+    fn categorical_data() -> Vec<HashMap<String, f32>> {
+        (0..200).map(|i| {
+            let category = (i % 50) as f32;
+            HashMap::from([("c".to_string(), category), ("y".to_string(), category * 2.0 + (i % 7) as f32)])
+        }).collect()
+    }
+
+    #[test]
+    fn out_of_fold_never_uses_own_target() {
+        let data = categorical_data();
+        let options = TargetEncoding { columns: vec!["c".to_string()], smoothing: 0.0, folds: 4 };
+        let encoded = TargetEncoder::out_of_fold("y", &data, &options, 3);
+
+        // Changing one row's target must not change that row's own encoding
+        for index in [0, 17, 123] {
+            let mut changed = data.clone();
+            changed[index].insert("y".to_string(), 1.0e6);
+            let encoded_changed = TargetEncoder::out_of_fold("y", &changed, &options, 3);
+            assert_eq!(encoded_changed[index]["c"], encoded[index]["c"]);
+        }
+        assert_eq!(encoded, TargetEncoder::out_of_fold("y", &data, &options, 3));
+    }
+
+    #[test]
+    fn unseen_category_falls_back_to_global_mean() {
+        let data = categorical_data();
+        let options = TargetEncoding { columns: vec!["c".to_string()], smoothing: 2.0, folds: 5 };
+        let encoder = TargetEncoder::fit("y", &data, &options);
+
+        let mut unseen = HashMap::from([("c".to_string(), 500.0), ("x".to_string(), 1.0)]);
+        encoder.encode(&mut unseen);
+        assert_eq!(unseen["c"], encoder.global_mean());
+        assert_eq!(unseen["x"], 1.0);
+        assert_eq!(encoder.category_encoding("c", 500.0), None);
+
+        // Category 0 has targets 0..=6 (in some order) over 4 rows, pulled towards the prior
+        let targets: Vec<f32> = data.iter().filter(|point| point["c"] == 0.0).map(|point| point["y"]).collect();
+        let expected = (targets.iter().sum::<f32>() + 2.0 * encoder.global_mean()) / (targets.len() as f32 + 2.0);
+        let mut seen = HashMap::from([("c".to_string(), -0.0)]);
+        encoder.encode(&mut seen);
+        assert!((seen["c"] - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn encoded_model() {
+        let data: Vec<HashMap<String, f32>> = (0..600).map(|i| {
+            let (category, x) = ((i * 7 % 300) as f32, (i % 10) as f32);
+            let y = (category % 5.0) * 10.0 + x;
+            HashMap::from([("c".to_string(), category), ("x".to_string(), x), ("y".to_string(), y)])
+        }).collect();
+        let config = EvolutionConfig {
+            num_creatures: 500,
+            num_cycles: 3,
+            target_encoding: Some(TargetEncoding { columns: vec!["c".to_string()], ..Default::default() }),
+            ..Default::default()
+        };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let encoder = evo.target_encoder().expect("The fitted encoder is kept");
+
+        // Points are encoded before they are predicted, with unseen categories at the global mean
+        let point = HashMap::from([("c".to_string(), 12.0), ("x".to_string(), 3.0)]);
+        let encoded = HashMap::from([("c".to_string(), encoder.category_encoding("c", 12.0).unwrap()), ("x".to_string(), 3.0)]);
+        let unseen = HashMap::from([("c".to_string(), 1000.0), ("x".to_string(), 3.0)]);
+        let at_mean = HashMap::from([("c".to_string(), encoder.global_mean()), ("x".to_string(), 3.0)]);
+        let mut plain = Evolution::with_config("y".into(), &data, &EvolutionConfig { target_encoding: None, ..config.clone() }).unwrap();
+        plain.standardizer = evo.standardizer.clone();
+        plain.best_creature = evo.best_creature.clone();
+        assert_eq!(evo.predict_point(&point).unwrap(), plain.predict_point(&encoded).unwrap());
+        assert_eq!(evo.predict_point(&unseen).unwrap(), plain.predict_point(&at_mean).unwrap());
+
+        let bad = EvolutionConfig { target_encoding: Some(TargetEncoding { columns: vec!["d".to_string()], folds: 1, ..Default::default() }), ..config };
+        assert!(matches!(bad.validate("y", &data), Err(crate::config::ConfigError::Multiple(errors)) if errors.len() == 2));
+    }
+}
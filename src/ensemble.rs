@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use crate::bundle::BundleError;
+use crate::evolution::{Evolution, PredictError};
+
+
+/// How member predictions of an EnsembleModel get combined.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregation {
+    Mean,
+    Median,
+    /// Mean weighted by the inverse of each member's squared training RMSE
+    /// (see `Evolution::train_rmse`), or the plain mean when a member has none.
+    ErrorWeighted,
+}
+
+/// Several trained models (loaded from bundles written by "Evolution::export_bundle")
+/// which predict together.  Each member standardizes inputs with its own Standardizer.
+pub struct EnsembleModel {
+    members: Vec<Evolution>,
+}
+
+#[derive(Debug)]
+pub enum EnsembleError {
+    Empty,
+    Bundle { path: PathBuf, error: BundleError },
+    /// A member's target or feature set differs from the first member's.
+    FeatureMismatch { member: usize },
+}
+
+impl fmt::Display for EnsembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnsembleError::Empty => write!(f, "An ensemble needs at least one model"),
+            EnsembleError::Bundle { path, error } => write!(f, "Unable to load {}: {}", path.display(), error),
+            EnsembleError::FeatureMismatch { member } => write!(
+                f, "Ensemble member {} does not have the same target and features as member 0", member
+            ),
+        }
+    }
+}
+
+impl Error for EnsembleError {}
+
+
+impl EnsembleModel {
+    pub fn new(members: Vec<Evolution>) -> Result<EnsembleModel, EnsembleError> {
+        let first = members.first().ok_or(EnsembleError::Empty)?;
+        let features = first.standardizer.parameters();
+        for (index, member) in members.iter().enumerate().skip(1) {
+            if member.target != first.target || member.standardizer.parameters() != features {
+                return Err(EnsembleError::FeatureMismatch { member: index });
+            }
+        }
+        Ok(EnsembleModel { members })
+    }
+
+    /// Load each model from its bundle directory.
+    pub fn load<P: AsRef<Path>>(paths: &[P]) -> Result<EnsembleModel, EnsembleError> {
+        let mut members = Vec::new();
+        for path in paths {
+            let path = path.as_ref();
+            match Evolution::import_bundle(path) {
+                Ok((evolution, _warnings)) => members.push(evolution),
+                Err(error) => return Err(EnsembleError::Bundle { path: path.to_path_buf(), error }),
+            }
+        }
+        EnsembleModel::new(members)
+    }
+
+    pub fn members(&self) -> &[Evolution] {
+        &self.members
+    }
+
+    /// Combined prediction of the members for "data_point", which fails like
+    /// `Evolution::predict_point` with the first failing member's error.
+    pub fn predict_point(&self, data_point: &HashMap<String, f32>, aggregation: Aggregation) -> Result<f32, PredictError> {
+        let mut predictions: Vec<f32> = self.members.iter()
+            .map(|member| member.predict_point(data_point))
+            .collect::<Result<_, _>>()?;
+        let prediction = match aggregation {
+            Aggregation::Mean => predictions.iter().sum::<f32>() / predictions.len() as f32,
+            Aggregation::Median => {
+                predictions.sort_by(|a, b| a.total_cmp(b));
+                let middle = predictions.len() / 2;
+                match predictions.len() % 2 {
+                    0 => (predictions[middle - 1] + predictions[middle]) / 2.0,
+                    _ => predictions[middle],
+                }
+            },
+            Aggregation::ErrorWeighted => {
+                let weights: Vec<f32> = self.members.iter()
+                    .map(|member| member.train_rmse.map(|rmse| 1.0 / rmse.powi(2).max(f32::EPSILON)))
+                    .collect::<Option<_>>()
+                    .unwrap_or_else(|| vec![1.0; self.members.len()]);
+                let weighted_sum: f32 = predictions.iter().zip(&weights).map(|(prediction, weight)| prediction * weight).sum();
+                weighted_sum / weights.iter().sum::<f32>()
+            },
+        };
+        Ok(prediction)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EvolutionConfig;
+
+    fn line_data(slope: f32) -> Vec<HashMap<String, f32>> {
+        (0..15).map(|i| HashMap::from([
+            ("x".to_string(), i as f32),
+            ("y".to_string(), slope * i as f32 + 3.0),
+        ])).collect()
+    }
+
+    #[test]
+    fn ensemble_from_bundles() {
+        let config = EvolutionConfig { num_creatures: 500, num_cycles: 3, ..Default::default() };
+        let root = std::env::temp_dir().join(format!("revogression_ensemble_{}", std::process::id()));
+        let mut paths = Vec::new();
+        let mut train_rmses = Vec::new();
+        for (i, slope) in [1.0, 2.0, 4.0].iter().enumerate() {
+            let evo = Evolution::with_config("y".into(), &line_data(*slope), &config).unwrap();
+            let rmse = evo.stats(&line_data(*slope)).root_mean_squared_error;
+            assert!((evo.train_rmse().unwrap() - rmse).abs() <= 1e-4 * rmse.max(1.0));
+            train_rmses.push(evo.train_rmse());
+            let path = root.join(format!("model_{}", i));
+            evo.export_bundle(&path).unwrap();
+            paths.push(path);
+        }
+
+        let ensemble = EnsembleModel::load(&paths).unwrap();
+        assert_eq!(ensemble.members().len(), 3);
+        assert_eq!(ensemble.members().iter().map(|member| member.train_rmse()).collect::<Vec<_>>(), train_rmses);
+        let point = HashMap::from([("x".to_string(), 5.0)]);
+        let mut predictions: Vec<f32> = ensemble.members().iter().map(|m| m.predict_point(&point).unwrap()).collect();
+        predictions.sort_by(|a, b| a.total_cmp(b));
+
+        let mean = ensemble.predict_point(&point, Aggregation::Mean).unwrap();
+        assert!((mean - predictions.iter().sum::<f32>() / 3.0).abs() < 1e-4);
+        assert_eq!(ensemble.predict_point(&point, Aggregation::Median), Ok(predictions[1]));
+        let weighted = ensemble.predict_point(&point, Aggregation::ErrorWeighted).unwrap();
+        assert!(weighted >= predictions[0] - 1e-4 && weighted <= predictions[2] + 1e-4);
+        assert!(matches!(ensemble.predict_point(&HashMap::new(), Aggregation::Mean), Err(PredictError::MissingParameter(_))));
+
+        // Members are weighted by the inverse of their squared RMSE in the target's units
+        let mut members = ensemble.members;
+        members[0].train_rmse = Some(1.0);
+        members[1].train_rmse = Some(2.0);
+        members[2].train_rmse = Some(2.0);
+        let member_predictions: Vec<f32> = members.iter().map(|member| member.predict_point(&point).unwrap()).collect();
+        let expected = (4.0 * member_predictions[0] + member_predictions[1] + member_predictions[2]) / 6.0;
+        let ensemble = EnsembleModel::new(members).unwrap();
+        assert!((ensemble.predict_point(&point, Aggregation::ErrorWeighted).unwrap() - expected).abs() < 1e-4);
+
+        let other_data: Vec<HashMap<String, f32>> = line_data(1.0).into_iter()
+            .map(|mut point| { point.insert("x2".to_string(), point["x"] * 0.5); point })
+            .collect();
+        let other = Evolution::with_config("y".into(), &other_data, &config).unwrap();
+        let other_path = root.join("other");
+        other.export_bundle(&other_path).unwrap();
+        paths.push(other_path);
+        assert!(matches!(EnsembleModel::load(&paths), Err(EnsembleError::FeatureMismatch { member: 3 })));
+        assert!(matches!(EnsembleModel::load::<PathBuf>(&[]), Err(EnsembleError::Empty)));
+        assert!(matches!(EnsembleModel::load(&[root.join("missing")]), Err(EnsembleError::Bundle { .. })));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{RecvError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use crate::creature::Creature;
+use crate::evolution::CycleStats;
+
+
+/// Events queued for an `EventReceiver` before the oldest get dropped.
+pub const EVENT_CAPACITY: usize = 1024;
+
+/// Progress of a training run (see `EvolutionBuilder::event_receiver`).
+#[derive(Clone, Debug)]
+pub enum EvolutionEvent {
+    CycleCompleted(CycleStats),
+    /// Lowest error among the mutants of an iteration of the final optimization.
+    OptimizeIteration { iteration: u16, error: f32 },
+    /// Training ended, with the model's `Evolution::summary`.  Always the last event.
+    Finished(String),
+}
+
+/// Progress of one cycle, passed to the callback given to `EvolutionBuilder::on_cycle`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CycleReport {
+    pub cycle: u16,
+    /// Lowest error in the population.
+    pub min_error: f32,
+    pub median_error: f32,
+    pub population_size: usize,
+    /// The creature selected as best this cycle (see `EvolutionConfig::selection`).
+    pub best_creature: Creature,
+}
+
+/// Callback run after each cycle (see `EvolutionBuilder::on_cycle`).
+pub type CycleCallback = dyn Fn(&CycleReport) + Send + Sync;
+
+struct Queue {
+    events: VecDeque<EvolutionEvent>,
+    dropped: usize,
+    closed: bool,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    ready: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Sending side of an event channel, held by the training run.
+/// Sending never blocks: when "EVENT_CAPACITY" events are already waiting
+/// the oldest is dropped.  The channel closes when the sender is dropped.
+pub(crate) struct EventSender {
+    shared: Arc<Shared>,
+}
+
+/// Receiving side of an event channel, which may be moved to another thread.
+/// Works like a `std::sync::mpsc::Receiver`, except that a consumer which
+/// falls behind loses the oldest events (counted by "dropped_events")
+/// rather than stalling training.
+pub struct EventReceiver {
+    shared: Arc<Shared>,
+}
+
+pub(crate) fn event_channel() -> (EventSender, EventReceiver) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(Queue { events: VecDeque::new(), dropped: 0, closed: false }),
+        ready: Condvar::new(),
+    });
+    (EventSender { shared: shared.clone() }, EventReceiver { shared })
+}
+
+impl EventSender {
+    pub(crate) fn send(&self, event: EvolutionEvent) {
+        let mut queue = self.shared.lock();
+        if queue.events.len() >= EVENT_CAPACITY {
+            queue.events.pop_front();
+            queue.dropped += 1;
+        }
+        queue.events.push_back(event);
+        self.shared.ready.notify_one();
+    }
+}
+
+impl Drop for EventSender {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.ready.notify_all();
+    }
+}
+
+impl EventReceiver {
+    /// Wait for the next event, failing once training has ended and every event was received.
+    pub fn recv(&self) -> Result<EvolutionEvent, RecvError> {
+        let mut queue = self.shared.lock();
+        loop {
+            if let Some(event) = queue.events.pop_front() {
+                return Ok(event);
+            }
+            if queue.closed {
+                return Err(RecvError);
+            }
+            queue = self.shared.ready.wait(queue).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// The next event if one is waiting.
+    pub fn try_recv(&self) -> Result<EvolutionEvent, TryRecvError> {
+        let mut queue = self.shared.lock();
+        match queue.events.pop_front() {
+            Some(event) => Ok(event),
+            None if queue.closed => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Every remaining event, waiting for each until training has ended.
+    pub fn iter(&self) -> impl Iterator<Item = EvolutionEvent> + '_ {
+        std::iter::from_fn(|| self.recv().ok())
+    }
+
+    /// Number of events dropped so far because the consumer fell behind.
+    pub fn dropped_events(&self) -> usize {
+        self.shared.lock().dropped
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::thread;
+    use crate::evolution::Evolution;
+
+    #[test]
+    fn events_from_another_thread() {
+        let data: Vec<HashMap<String, f32>> = (0..40)
+            .map(|i| HashMap::from([("x".to_string(), i as f32), ("y".to_string(), (3 * i) as f32)]))
+            .collect();
+        let mut builder = Evolution::builder("y", &data).num_creatures(300).num_cycles(4);
+        let receiver = builder.event_receiver();
+        let consumer = thread::spawn(move || (receiver.iter().collect::<Vec<EvolutionEvent>>(), receiver.dropped_events()));
+        let evo = builder.build().unwrap();
+        let (events, dropped) = consumer.join().unwrap();
+
+        assert_eq!(dropped, 0);
+        let cycles: Vec<u16> = events.iter()
+            .filter_map(|event| match event {
+                EvolutionEvent::CycleCompleted(stats) => Some(stats.cycle),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(cycles, evo.history().iter().map(|stats| stats.cycle).collect::<Vec<u16>>());
+        assert!(events.iter().any(|event| matches!(event, EvolutionEvent::OptimizeIteration { .. })));
+        match events.last() {
+            Some(EvolutionEvent::Finished(summary)) => assert_eq!(*summary, evo.summary()),
+            other => panic!("Events should end with Finished but ended with {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cycle_reports_over_a_channel() {
+        let data = crate::data::synthetic::linear(40, 1, 0.1, 3);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let evo = Evolution::builder(crate::data::synthetic::TARGET, &data)
+            .num_creatures(300)
+            .num_cycles(3)
+            .on_cycle(Box::new(move |report: &CycleReport| sender.send(report.clone()).unwrap()))
+            .build()
+            .unwrap();
+        let reports: Vec<CycleReport> = receiver.iter().collect();
+
+        assert_eq!(reports.len(), evo.history().len());
+        for (report, stats) in reports.iter().zip(evo.history()) {
+            assert_eq!(report.cycle, stats.cycle);
+            assert_eq!(report.median_error, stats.median_error);
+            assert_eq!(report.best_creature.cached_error_sum, Some(stats.best_error));
+            assert!(report.min_error <= report.median_error);
+            assert!(report.population_size > 0);
+        }
+    }
+
+    #[test]
+    fn slow_consumer_drops_oldest() {
+        let (sender, receiver) = event_channel();
+        for iteration in 0..EVENT_CAPACITY as u16 + 5 {
+            sender.send(EvolutionEvent::OptimizeIteration { iteration, error: 0.0 });
+        }
+        assert_eq!(receiver.dropped_events(), 5);
+        assert!(matches!(receiver.try_recv(), Ok(EvolutionEvent::OptimizeIteration { iteration: 5, .. })));
+        drop(sender);
+        assert_eq!(receiver.iter().count(), EVENT_CAPACITY - 1);
+        assert_eq!(receiver.try_recv().unwrap_err(), TryRecvError::Disconnected);
+    }
+}
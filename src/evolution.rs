@@ -1,275 +1,3667 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
-use crate::standardize::Standardizer;
-use crate::creature::{Creature, MutateSpeed};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::standardize::{ParamStandardizer, Standardizer};
+use crate::creature::{CreationOptions, Creature, MutateSpeed, MutationOptions, SORTED_MODIFIERS_LIMIT};
+use crate::config::{self, ConfigError, EvolutionConfig, PopulationLimit, Verbosity};
+use crate::data::{self, ColumnNames, MissingParams};
+use crate::formula::{ExportError, FormulaFormat, Precision};
+use crate::link::{ErrorScale, Link};
+use crate::loss::{self, CustomLoss, DatasetAggregation, LossFunction};
+use crate::metrics::{Metric, MetricReport, ModelStats};
+use crate::mutator::Mutator;
+use crate::selection::{self, SelectionCriterion};
+use crate::leakage::{self, PreprocessingReport};
+use crate::ranges;
+use crate::pin;
+use crate::samplers::Samplers;
+use crate::seeding::{SeedStreams, Stream};
+use crate::quantize::QuantizedData;
+use crate::encoding::TargetEncoder;
+use crate::features::{self, FeaturePipeline};
+use crate::events::{CycleCallback, CycleReport, EventSender, EvolutionEvent};
+use crate::budget::Budget;
+use crate::template;
+use crate::insights::SUMMARY_FEATURES;
+use rand::rngs::StdRng;
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::{Rng, RngCore, SeedableRng};
+use itertools::Itertools;
 use rayon::prelude::*;
 
 
+/// Fraction of the population re-scored each cycle when the sanity check is enabled.
+const SANITY_CHECK_FRACTION: f32 = 0.01;
+
+/// Relative difference allowed between a cached and a recomputed error.
+const SANITY_CHECK_TOLERANCE: f32 = 1e-4;
+
+/// Relative change in the HuberAuto delta needed before it gets updated.
+/// Updating the delta invalidates every cached error, so small changes are ignored.
+const HUBER_AUTO_UPDATE_THRESHOLD: f32 = 0.05;
+
+/// Number of creatures with the lowest training errors considered each cycle
+/// by selection criteria other than SelectionCriterion::MinTrainError.
+const SELECTION_CANDIDATES: usize = 20;
+
+/// Fraction of the population (ranked by full precision errors where known
+/// and by screening errors otherwise) promoted past quantized screening.
+/// Above one half so that every creature which can survive the median cull is promoted
+/// even when the screening ranking is slightly off.
+const SCREENING_PROMOTION_FRACTION: f32 = 0.6;
+
+/// Mutants scored in each iteration of the final optimization.
+const OPTIMIZE_MUTANTS: usize = 500;
+
+/// Number of data points scored between checks of the evaluation timeout.
+const TIMEOUT_CHECK_INTERVAL: usize = 32;
+
+/// Smallest supported `EvolutionConfig::num_creatures`.
+pub(crate) const MIN_POPULATION: u32 = 4;
+
+/// Populations smaller than this give every survivor of a cycle an offspring,
+/// since culling leaves too few creatures below the usual mutation cutoff.
+const SMALL_POPULATION: u32 = 100;
+
+/// Fewest creatures kept from each cycle, even when culling would leave fewer.
+const MIN_SURVIVORS: usize = 2;
+
+/// Largest fraction of the population refilled with new random creatures each cycle.
+/// Any further space goes to extra offspring of the survivors.
+const MAX_FRESH_FRACTION: f32 = 0.5;
+
+/// Fraction of the last scored population (at least one creature)
+/// kept as the hall of fame for `Evolution::population_insights`.
+const HALL_OF_FAME_FRACTION: f32 = 0.01;
+
+
 pub struct Evolution {
-    target: String,
-    num_creatures: u32,
-    num_cycles: u16,
-    standardizer: Standardizer,
-    best_creatures: Vec<Creature>,
-    best_creature: Creature,
+    pub(crate) target: String,
+    pub(crate) config: EvolutionConfig,
+    pub(crate) standardizer: Standardizer,
+    pub(crate) best_creatures: Vec<Creature>,
+    pub(crate) best_creature: Creature,
+    pub(crate) pareto_front: Vec<Creature>,
+    pub(crate) hall_of_fame: Vec<Creature>,
+    pub(crate) history: Vec<CycleStats>,
+    pub(crate) cycles_run: u16,
+    pub(crate) metrics: MetricReport,
+    pub(crate) optimize_choice: Option<OptimizeChoice>,
+    pub(crate) target_r2_reached: Option<bool>,
+    pub(crate) outcome: Option<Outcome>,
+    pub(crate) preprocess: Option<Preprocess>,
+    pub(crate) preprocessing_report: PreprocessingReport,
+    pub(crate) determinism: Option<DeterminismReport>,
+    pub(crate) target_encoder: Option<TargetEncoder>,
+    pub(crate) pipeline: FeaturePipeline,
+    /// Penalty of the last `Evolution::ridge_refit` of the best creature.
+    pub(crate) ridge_lambda: Option<f64>,
+    /// See `Evolution::train_rmse`.
+    pub(crate) train_rmse: Option<f32>,
+}
+
+/// A user supplied transformation applied to each data point before standardization.
+pub type PreprocessFn = dyn Fn(&mut HashMap<String, f32>) + Send + Sync;
+pub(crate) type Preprocess = Arc<PreprocessFn>;
+
+/// Which creature was kept after the final optimization phase.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OptimizeChoice {
+    /// The optimized creature improved on the training error
+    /// (and did not worsen the validation error, if any).
+    Optimized,
+    /// Optimizing did not help, so the selected creature was kept as is.
+    Original,
+}
+
+/// How many iterations the final optimization ran out of those planned,
+/// which is fewer when `EvolutionConfig::time_budget` or
+/// `EvolutionConfig::max_evaluations` would not allow them all.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptimizeProgress {
+    pub iterations: u16,
+    pub planned: u16,
+}
+
+impl OptimizeProgress {
+    pub fn truncated(&self) -> bool {
+        self.iterations < self.planned
+    }
+}
+
+/// A problem with the data given to `Evolution::new` (or `ConfigBuilder::run`).
+#[derive(Debug)]
+pub enum EvolutionError {
+    /// `ConfigBuilder::run` was called without setting the target.
+    NoTarget,
+    EmptyData,
+    /// The target column is not in any data point.
+    MissingTarget(String),
+    /// The first data point (by index) missing columns which other points have.
+    InconsistentKeys { row: usize, missing: Vec<String> },
+    Config(ConfigError),
+}
+
+impl fmt::Display for EvolutionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvolutionError::NoTarget => write!(f, "no target column was given"),
+            EvolutionError::EmptyData => write!(f, "there is no training data"),
+            EvolutionError::MissingTarget(target) => write!(f, "target column \"{}\" is not in the data", target),
+            EvolutionError::InconsistentKeys { row, missing } => {
+                let missing: Vec<String> = missing.iter().map(|column| format!("\"{}\"", column)).collect();
+                write!(f, "data point {} is missing columns {} which other points have", row, missing.join(", "))
+            },
+            EvolutionError::Config(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for EvolutionError {}
+
+impl From<ConfigError> for EvolutionError {
+    fn from(err: ConfigError) -> EvolutionError {
+        EvolutionError::Config(err)
+    }
+}
+
+/// Why `Evolution::predict_point` could not predict a data point.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PredictError {
+    /// A parameter the model uses is not in the point (and
+    /// `EvolutionConfig::missing_params` does not fill it in).
+    MissingParameter(String),
+    /// A parameter the model uses is NaN or infinite.
+    NonFinite { parameter: String, value: f32 },
+    /// Column names of the point which are the same once normalized (see `ColumnNames::Normalized`).
+    ColumnCollision { normalized: String, columns: Vec<String> },
+}
+
+impl fmt::Display for PredictError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PredictError::MissingParameter(parameter) => write!(f, "the data point is missing parameter \"{}\"", parameter),
+            PredictError::NonFinite { parameter, value } => write!(f, "parameter \"{}\" has the non-finite value {}", parameter, value),
+            PredictError::ColumnCollision { normalized, columns } => {
+                let columns: Vec<String> = columns.iter().map(|column| format!("\"{}\"", column)).collect();
+                write!(f, "the data point's columns {} are all named \"{}\" once normalized", columns.join(", "), normalized)
+            },
+        }
+    }
+}
+
+impl Error for PredictError {}
+
+impl PredictError {
+    /// The `PredictError::ColumnCollision` of a `ConfigError::ColumnCollision` from normalizing a point.
+    pub(crate) fn from_collision(error: ConfigError) -> PredictError {
+        let ConfigError::ColumnCollision { normalized, columns } = error else {
+            unreachable!("normalizing a point only fails on a column collision");
+        };
+        PredictError::ColumnCollision { normalized, columns }
+    }
+}
+
+
+/// What batch prediction looks up once per batch: each parameter the model uses
+/// with its standardization and standardized fill value (if missing values are
+/// filled in), and the target's standardization.
+struct BatchInputs<'a> {
+    parameters: Vec<(&'a str, &'a ParamStandardizer, Option<f32>)>,
+    output: &'a ParamStandardizer,
+}
+
+impl BatchInputs<'_> {
+    /// A standardized point with an entry for each parameter, to be overwritten per point.
+    fn point(&self) -> HashMap<String, f32> {
+        self.parameters.iter().map(|(parameter, _, _)| (parameter.to_string(), 0.0)).collect()
+    }
+}
+
+/// How training ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    /// Training ran every cycle or stopped once `EvolutionConfig::target_r2` was reached.
+    Completed,
+    /// Training stopped after `EvolutionConfig::no_signal_cycles` cycles as the best
+    /// creature's error was still not `EvolutionConfig::no_signal_margin` below the
+    /// "baseline_error" of always predicting the mean of the target, so the model
+    /// is unlikely to be more than noise.
+    NoSignal { baseline_error: f32, best_error: f32, cycles: u16 },
+    /// Training stopped after "cycles" cycles as `EvolutionConfig::time_budget`
+    /// or `EvolutionConfig::max_evaluations` was used up.
+    BudgetExhausted { cycles: u16 },
+    /// Training stopped after "cycles" cycles as the lowest "error" of the
+    /// population fell below `EvolutionConfig::stop_at_error`.
+    ErrorReached { error: f32, cycles: u16 },
+    /// Training stopped after "cycles" cycles as the lowest error of the population
+    /// stopped improving for `EvolutionConfig::patience` cycles, at "best_error".
+    Converged { best_error: f32, cycles: u16 },
+}
+
+/// Whether a fit repeats exactly when run again with its seed (see `Evolution::determinism_report`).
+/// Every random choice is drawn from generators keyed by the seed and the index of
+/// the task, so the results never depend on the number of threads, but the flagged
+/// code paths can still make runs differ.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeterminismReport {
+    /// Rayon threads available to the fit.
+    pub threads: usize,
+    /// No `EvolutionConfig::seed` was given, so the seed was drawn from the thread-local generator.
+    pub seed_drawn: bool,
+    /// `EvolutionConfig::evaluation_timeout` was set, so which creatures time out depends on the clock.
+    pub wall_clock_timeouts: bool,
+    /// `EvolutionConfig::time_budget` was set, so the cycle (or optimization iteration)
+    /// training stops after depends on the clock.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub wall_clock_budget: bool,
+    /// Some layers may sum their terms in HashMap iteration order, which changes between runs:
+    /// layers with many terms (possible with SORTED_MODIFIERS_LIMIT or more input parameters)
+    /// and data points missing some of the parameters (such as with `feature_dropout`),
+    /// unless `EvolutionConfig::strict_fp` fixes the order.
+    pub hash_order_sums: bool,
+}
+
+impl DeterminismReport {
+    fn new(config: &EvolutionConfig, n_params: usize) -> DeterminismReport {
+        DeterminismReport {
+            threads: rayon::current_num_threads(),
+            seed_drawn: config.seed.is_none(),
+            wall_clock_timeouts: config.evaluation_timeout.is_some(),
+            wall_clock_budget: config.time_budget.is_some(),
+            hash_order_sums: !config.strict_fp && (n_params >= SORTED_MODIFIERS_LIMIT || config.feature_dropout > 0.0),
+        }
+    }
+
+    /// Whether none of the nondeterministic code paths were taken, so the same seed gives the same model.
+    pub fn deterministic(&self) -> bool {
+        !(self.seed_drawn || self.wall_clock_timeouts || self.wall_clock_budget || self.hash_order_sums)
+    }
+}
+
+impl fmt::Display for DeterminismReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "deterministic: {} ({} threads", self.deterministic(), self.threads)?;
+        let reasons = [
+            (self.seed_drawn, "no seed was given"),
+            (self.wall_clock_timeouts, "evaluation timeouts"),
+            (self.wall_clock_budget, "a time budget"),
+            (self.hash_order_sums, "HashMap order sums"),
+        ];
+        for (_, reason) in reasons.iter().filter(|(flagged, _)| *flagged) {
+            write!(f, "; {}", reason)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// How much lower an error must be to count as an improvement, for a mutant to
+/// replace the current best creature during the final optimization
+/// (`EvolutionConfig::min_improvement`) or for a cycle to reset the
+/// early stopping count (`EvolutionConfig::patience_improvement`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MinImprovement {
+    /// The error must drop by more than this amount.
+    Absolute(f32),
+    /// The error must drop by more than this fraction of the current best error.
+    Relative(f32),
+}
+
+impl MinImprovement {
+    pub(crate) fn accepts(&self, best_error: f32, new_error: f32) -> bool {
+        let threshold = match self {
+            MinImprovement::Absolute(amount) => *amount,
+            MinImprovement::Relative(fraction) => fraction * best_error.abs(),
+        };
+        new_error < best_error - threshold
+    }
+}
+
+/// A prediction along with checks of the point it was made for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetailedPrediction {
+    pub prediction: f32,
+    /// Inputs (in sorted order) outside of their `EvolutionConfig::valid_ranges`,
+    /// for which the prediction is likely meaningless.
+    pub out_of_range: Vec<String>,
+}
+
+/// Summary of a single evolution cycle.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CycleStats {
+    pub cycle: u16,
+    pub best_error: f32,
+    pub median_error: f32,
+    pub best_generation: u8,
+    /// Name of the loss the errors are calculated with (see `EvolutionConfig::loss_name`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub loss_name: String,
+    /// Huber delta in effect for the cycle when using LossFunction::HuberAuto.
+    pub huber_delta: Option<f32>,
+    /// Error of the cycle's best creature on the validation data, if any.
+    pub validation_error: Option<f32>,
+    /// The cycle's best creature's `EvolutionConfig::report_metrics`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metrics: MetricReport,
+    /// Error of the cycle's best creature on each dataset (the training data
+    /// followed by each of `EvolutionConfig::datasets`) when training on several.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dataset_errors: Vec<f32>,
+    /// Iterations of the final optimization, recorded on the last cycle.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub optimize: Option<OptimizeProgress>,
+    /// Whether this cycle triggered a stop before `EvolutionConfig::num_cycles`
+    /// (see `Evolution::outcome` for the reason).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stopped_early: bool,
 }
 
 impl Evolution {
-    fn new(
+    /// Train on "data" with the default config apart from the given sizes,
+    /// failing (rather than panicking while training) when the data is empty,
+    /// lacks the target, or has points missing columns which others have
+    /// (for such sparse data see `EvolutionConfig::missing_params`), or when
+    /// `EvolutionConfig::validate` finds any other problem.
+    /// Prefer `EvolutionConfig::builder`, whose named setters cannot be mixed up
+    /// like these positional sizes; this constructor is due to be deprecated.
+    pub fn new(
         target: String,
-        data: &Vec<HashMap<String, f32>>,
+        data: &[HashMap<String, f32>],
         num_creatures: u32,
         num_cycles: u16,
         max_layers: u8,
-    ) -> Evolution {
+    ) -> Result<Evolution, EvolutionError> {
+        EvolutionConfig::builder()
+            .target(&target)
+            .num_creatures(num_creatures)
+            .num_cycles(num_cycles)
+            .max_layers(max_layers)
+            .run(data)
+    }
+
+    /// The problems with "data" reported by "new" before validating the config.
+    pub(crate) fn check_data(target: &str, data: &[HashMap<String, f32>]) -> Result<(), EvolutionError> {
+        if data.is_empty() {
+            return Err(EvolutionError::EmptyData);
+        }
+        if !data.iter().any(|point| point.contains_key(target)) {
+            return Err(EvolutionError::MissingTarget(target.to_string()));
+        }
+        let mut columns: Vec<&String> = data.iter().flat_map(|point| point.keys()).collect();
+        columns.sort();
+        columns.dedup();
+        if let Some((row, point)) = data.iter().enumerate().find(|(_, point)| point.len() < columns.len()) {
+            let missing = columns.into_iter().filter(|column| !point.contains_key(*column)).cloned().collect();
+            return Err(EvolutionError::InconsistentKeys { row, missing });
+        }
+        Ok(())
+    }
+
+    /// Train on "data" with "config", failing without training for any of the
+    /// problems reported by `EvolutionConfig::validate`.  Equivalent to
+    /// `Evolution::builder(&target, data).config(config.clone()).build()`.
+    pub fn with_config(target: String, data: &[HashMap<String, f32>], config: &EvolutionConfig) -> Result<Evolution, EvolutionError> {
+        config.validate(&target, data)?;
+        Ok(Evolution::fit(target, data, config, None, None, None)?)
+    }
+
+    /// Run the evolution, first normalizing column names (with `ColumnNames::Normalized`)
+    /// and applying "preprocess" (if any) to each data point (including validation data)
+    /// before anything else.  Progress is sent to "events" (if any), which is
+    /// dropped (closing the channel) once training ends.  With an "on_cycle"
+    /// callback, it gets each cycle's report in place of the printed progress.
+    /// The config is expected to be validated already, but options which cannot
+    /// work are still reported as a `ConfigError` rather than training.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn fit(target: String,
+        data: &[HashMap<String, f32>],
+        config: &EvolutionConfig,
+        preprocess: Option<Preprocess>,
+        events: Option<EventSender>,
+        on_cycle: Option<Box<CycleCallback>>) -> Result<Evolution, ConfigError> {
+        let verbosity = config.verbosity;
+        let summary = verbosity >= Verbosity::Summary;
+        let print_cycles = on_cycle.is_none() && verbosity >= Verbosity::PerCycle;
+        let useful_creatures = config::max_useful_creatures(data.len(), data::input_columns(data, &target).len());
+        let clamped_config;
+        let config = match (config.num_creatures > useful_creatures, config.population_limit) {
+            (false, _) => config,
+            (true, PopulationLimit::Clamp) => {
+                if summary {
+                    println!("Clamping num_creatures from {} to {}, the most that {} rows can make use of",
+                             config.num_creatures, useful_creatures, data.len());
+                }
+                clamped_config = EvolutionConfig { num_creatures: useful_creatures, ..config.clone() };
+                &clamped_config
+            },
+            (true, _) => {
+                if summary {
+                    println!("Warning: num_creatures of {} is more than the {} that {} rows can make use of",
+                             config.num_creatures, useful_creatures, data.len());
+                }
+                config
+            },
+        };
+        let EvolutionConfig { num_creatures, num_cycles, max_layers, .. } = *config;
+
+        // Other datasets are scored as ranges of the combined data
+        let dataset_sizes: Vec<usize> = std::iter::once(data).chain(config.datasets.iter().map(|dataset| &dataset[..]))
+            .map(|dataset| dataset.len())
+            .collect();
+        let combined_data: Vec<HashMap<String, f32>>;
+        let data = match config.datasets.is_empty() {
+            true => data,
+            false => {
+                combined_data = data.iter().chain(config.datasets.iter().flatten()).cloned().collect();
+                &combined_data[..]
+            },
+        };
+
+        let normalized_data;
+        let normalized_config;
+        let (target, data, config) = match config.column_names {
+            ColumnNames::Exact => (target, data, config),
+            column_names => {
+                normalized_data = column_names.normalized_rows(data)?.into_owned();
+                normalized_config = EvolutionConfig {
+                    validation_data: config.validation_data.as_deref()
+                        .map(|validation| column_names.normalized_rows(validation).map(Cow::into_owned))
+                        .transpose()?,
+                    target_encoding: config.target_encoding.as_ref().map(|encoding| encoding.normalized(column_names)),
+                    datetime_columns: features::normalized_datetimes(&config.datetime_columns, column_names),
+                    one_hot_columns: config.one_hot_columns.iter().map(|column| column_names.normalize(column).into_owned()).collect(),
+                    valid_ranges: ranges::normalized(&config.valid_ranges, column_names),
+                    templates: config.templates.iter().map(|template| template.normalized(column_names)).collect(),
+                    pin: pin::normalized(&config.pin, column_names),
+                    ..config.clone()
+                };
+                (column_names.normalize(&target).into_owned(), &normalized_data[..], &normalized_config)
+            },
+        };
+
+        let preprocessed_data;
+        let data = match &preprocess {
+            Some(preprocess) => {
+                preprocessed_data = preprocessed(preprocess, data);
+                &preprocessed_data
+            },
+            None => data,
+        };
+
+        // Rows outside of the valid ranges are dropped along with their weights
+        let (keep, range_violations) = ranges::check_rows(data, &config.valid_ranges);
+        let rows_dropped = keep.iter().filter(|kept| !**kept).count();
+        if config.strict_ranges {
+            config::combined(ranges::violation_errors(&range_violations, &config.valid_ranges))?;
+        }
+        let in_range_data;
+        let in_range_config;
+        let (data, config, dataset_sizes) = match rows_dropped {
+            0 => (data, config, dataset_sizes),
+            _ => {
+                if summary {
+                    println!("Dropped {} rows with values outside of the valid ranges", rows_dropped);
+                }
+                in_range_data = ranges::kept(data, &keep);
+                in_range_config = EvolutionConfig {
+                    sample_weights: config.sample_weights.as_ref().map(|weights| ranges::kept(weights, &keep)),
+                    ..config.clone()
+                };
+                let mut start = 0;
+                let dataset_sizes = dataset_sizes.iter().map(|size| {
+                    start += size;
+                    keep[start - size..start].iter().filter(|kept| **kept).count()
+                }).collect();
+                (&in_range_data[..], &in_range_config, dataset_sizes)
+            },
+        };
+
+        // Expansions and one-hot encodings replace their raw columns
+        let engineering = features::engineering(data, &config.datetime_columns, &config.one_hot_columns);
+        let engineered_data;
+        let data = match engineering.is_empty() {
+            true => data,
+            false => {
+                engineered_data = features::engineered(&engineering, data);
+                &engineered_data[..]
+            },
+        };
+
+        let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let samplers = Samplers::new(&config.creation.priors)?.with_pins(&config.pin);
+        // Training rows are encoded out of fold while validation data and points
+        // to predict get the encoding fit on every row
+        let target_encoder = config.target_encoding.as_ref().map(|encoding| TargetEncoder::fit(&target, data, encoding));
+        let encoded_data;
+        let data = match &config.target_encoding {
+            Some(encoding) => {
+                encoded_data = TargetEncoder::out_of_fold(&target, data, encoding, seed);
+                &encoded_data[..]
+            },
+            None => data,
+        };
+        let pipeline = FeaturePipeline::new(config.column_names, preprocess.is_some(), engineering, target_encoder.clone(), config.missing_params);
+        let preprocess = features::chained(preprocess, &pipeline);
+
+        if config.deduplicate_rows && !config.datasets.is_empty() {
+            return Err(ConfigError::Conflict("deduplicate_rows cannot be combined with datasets".to_string()));
+        }
+        let duplicate_groups = config.deduplicate_rows.then(|| data::duplicate_groups(data));
+        let preprocessing_report = PreprocessingReport {
+            leakage: leakage::check_target_leakage(&target, data, config.leakage_threshold),
+            rows_folded: duplicate_groups.as_ref().map_or(0, |groups| data.len() - groups.len()),
+            rows_dropped,
+            range_violations,
+        };
+        if summary && preprocessing_report.rows_folded > 0 {
+            println!("Folded {} duplicate rows into the weights of identical rows", preprocessing_report.rows_folded);
+        }
+        for warning in preprocessing_report.leakage.iter().filter(|_| summary) {
+            println!("Possible target leakage: {}", warning);
+        }
 
-        let standardizer = Standardizer::new(&data[..]);
-        standardizer.print_standardization();
-        let standardized_data = standardizer.standardized_values(data);
+        let informed_config;
+        let config = match config.creation.data_informed_strength > 0.0 && config.creation.feature_correlations.is_none() {
+            true => {
+                let correlations = feature_correlations(&target, data, config.link);
+                informed_config = EvolutionConfig {
+                    creation: CreationOptions { feature_correlations: Some(correlations), ..config.creation.clone() },
+                    ..config.clone()
+                };
+                &informed_config
+            },
+            false => config,
+        };
+        let mutation_config;
+        let config = match config.mutation.parameter_options.is_empty() {
+            true => {
+                let parameter_options: Vec<String> = data::input_columns(data, &target).into_iter().map(String::from).collect();
+                mutation_config = EvolutionConfig {
+                    mutation: MutationOptions { parameter_options, ..config.mutation.clone() },
+                    ..config.clone()
+                };
+                &mutation_config
+            },
+            false => config,
+        };
 
-        let param_options = data[0].keys()
-                                   .map(|s| s.as_str())
-                                   .filter(|s| s != &target.as_str())
-                                   .collect();
+        let validation_points = config.validation_data.as_deref().map(|validation| match &preprocess {
+            Some(preprocess) => Cow::Owned(preprocessed(preprocess, validation)),
+            None => Cow::Borrowed(validation),
+        });
+        // Checked again as preprocessing may have changed the target
+        config::combined(config.link.target_problem(&target, data, "the training data").into_iter()
+            .chain(validation_points.as_deref().and_then(|validation| config.link.target_problem(&target, validation, "validation_data")))
+            .collect())?;
+
+        let (standardizer, mut training_data) = prepare_grouped_training_data(&target, data, duplicate_groups.as_deref(), config);
+        if !config.datasets.is_empty() {
+            training_data.datasets = Some(Datasets { sizes: dataset_sizes, aggregation: config.dataset_aggregation.clone() });
+        }
+        if verbosity >= Verbosity::PerCycle {
+            standardizer.print_standardization();
+        }
+        let mut validation_data = validation_points.as_deref()
+            .map(|validation| scoring_data(&standardizer, &target, validation, config));
+        let train_actuals = match &duplicate_groups {
+            Some(groups) => groups.iter().map(|group| data[group[0]][&target]).collect(),
+            None => target_values(&target, data),
+        };
+        let validation_actuals = validation_points.as_deref().map(|validation| target_values(&target, validation));
+        let metrics = |creature: &Creature, training_data: &TrainingData, validation_data: Option<&TrainingData>| creature_metrics(
+            creature, config, &standardizer,
+            (training_data, &train_actuals),
+            validation_data.zip(validation_actuals.as_deref()),
+        );
+        if config.selection == SelectionCriterion::MinValidationError && validation_data.is_none() {
+            return Err(ConfigError::Conflict("SelectionCriterion::MinValidationError requires validation_data".to_string()));
+        }
+        if config.target_r2.is_some() && validation_data.is_none() {
+            return Err(ConfigError::Conflict("target_r2 requires validation_data".to_string()));
+        }
+        if num_creatures < MIN_POPULATION {
+            return Err(ConfigError::TooSmall { option: "num_creatures", minimum: MIN_POPULATION as usize });
+        }
+        if !(0.0..1.0).contains(&config.feature_dropout) {
+            return Err(ConfigError::InvalidFraction { option: "feature_dropout", value: config.feature_dropout as f64 });
+        }
 
-        let mut creatures = Creature::create_many_parallel(num_creatures, &param_options, max_layers);
+        // Sorted so that seeded random choices of parameters repeat from run to run
+        let param_options: Vec<&str> = data::input_columns(data, &target);
+
+        let mut streams = SeedStreams::new(seed);
+        let determinism = DeterminismReport::new(config, param_options.len());
+        if verbosity >= Verbosity::Debug {
+            println!("Determinism report: {}", determinism);
+        }
+
+        // Errors of the whole initial population when it was culled while being generated
+        let (mut creatures, mut initial_errors) = match config.max_concurrent_evaluations {
+            Some(chunk_size) => {
+                let (creatures, errors) = stream_initial_population(
+                    num_creatures, &param_options, max_layers, config, &samplers, &training_data, chunk_size, streams.next()
+                );
+                (creatures, Some(errors))
+            },
+            None => (Creature::create_many_seeded(num_creatures, &param_options, max_layers, &config.creation, &samplers, streams.next(), 0), None),
+        };
+        // Creatures of any templates replace the last of the initial population
+        if !config.templates.is_empty() {
+            let seeded = template::expanded(&config.templates, config.template_instances, &param_options, &samplers, streams.next());
+            creatures.truncate((num_creatures as usize).saturating_sub(seeded.len()));
+            creatures.extend(seeded);
+            initial_errors = None;
+        }
         let mut best_creatures = Vec::new();
+        let mut pareto_front = Vec::new();
+        let mut hall_of_fame = Vec::new();
+        let mut history = Vec::new();
+        let mut target_r2_reached = config.target_r2.map(|_| false);
+        let mut outcome = Outcome::Completed;
+        let mut lowest_error: Option<f32> = None;
+        let mut stalled_cycles = 0;
+        let budget = Budget::new(config);
+        if let (Some(budget), Some(errors)) = (&budget, &initial_errors) {
+            budget.record(errors.len());
+        }
+
+        let mut dropout_rng = StdRng::seed_from_u64(seed);
+        let quantized = config.quantized_screening.then(|| training_data.quantized());
 
         for cycle in 1..=num_cycles {
-            creatures.par_iter_mut().for_each(|creature| {
-                if creature.cached_error_sum == None {
-                    let err = calc_error_sum(&creature, &standardized_data, &target);
-                    creature.cached_error_sum = Some(err);
+            if let (true, Some(previous_best)) = (config.huber_auto(), best_creatures.last()) {
+                update_huber_delta(&mut training_data, previous_best, &mut creatures);
+            }
+
+            // Errors from a cycle with other features dropped or terms masked are not comparable
+            let term_mask = (config.term_dropout > 0.0).then(|| TermMask { probability: config.term_dropout as f64, stream: streams.next() });
+            let dropped = match config.feature_dropout > 0.0 {
+                true => dropped_features(&param_options, config.feature_dropout, &mut dropout_rng),
+                false => Vec::new(),
+            };
+            let dropout_data = match (config.feature_dropout > 0.0, term_mask) {
+                (false, None) => None,
+                (feature_dropout, term_mask) => {
+                    creatures.iter_mut().for_each(|creature| creature.invalidate_cache());
+                    initial_errors = None;
+                    let mut dropout_data = match feature_dropout {
+                        true => training_data.with_dropped_features(&dropped),
+                        false => training_data.clone(),
+                    };
+                    dropout_data.term_mask = term_mask;
+                    Some(dropout_data)
+                },
+            };
+            let cycle_data = dropout_data.as_ref().unwrap_or(&training_data);
+
+            if let Some(quantized) = &quantized {
+                screen_creatures(&mut creatures, cycle_data, quantized, &dropped);
+            }
+            if let Some(budget) = &budget {
+                budget.record(creatures.iter().filter(|creature| creature.cached_error_sum.is_none()).count());
+            }
+            score_creatures(&mut creatures, cycle_data, config.max_concurrent_evaluations, config.evaluation_timeout);
+
+            if config.sanity_check {
+                let mut rng = streams.checks().rng(cycle as usize);
+                if let Err(mismatch) = verify_cached_errors(&creatures, cycle_data, SANITY_CHECK_FRACTION, &mut rng) {
+                    panic!("Sanity check failed in cycle {}: {}", cycle, mismatch);
                 }
-            });
+            }
+
+            hall_of_fame = top_creatures(&creatures, HALL_OF_FAME_FRACTION);
 
-            let (min_error, median_error) = error_results(&creatures);
+            let (min_error, median_error) = match initial_errors.take() {
+                Some(errors) => error_summary(errors),
+                None => error_results(&creatures),
+            };
 
-            let best_creature = creatures
-                .iter()
-                .find(|creature| creature.cached_error_sum == Some(min_error))
-                .expect("Error matching min_error to a creature!");
+            if let Some(validation) = validation_data.as_mut() {
+                validation.loss = training_data.loss;
+            }
+            let (best_creature, validation_error) = select_best(
+                &creatures, config.selection, validation_data.as_ref(), SELECTION_CANDIDATES
+            );
             best_creatures.push(best_creature.clone());
-            print_cycle_data(cycle, median_error, best_creature);
+            history.push(CycleStats {
+                cycle,
+                best_error: best_creature.cached_error_sum.unwrap(),
+                median_error,
+                best_generation: best_creature.generation,
+                loss_name: config.loss_name().to_string(),
+                huber_delta: match (config.huber_auto(), training_data.loss) {
+                    (true, LossFunction::Huber { delta }) => Some(delta),
+                    _ => None,
+                },
+                validation_error,
+                metrics: metrics(best_creature, &training_data, validation_data.as_ref()),
+                dataset_errors: dataset_errors(best_creature, &training_data),
+                optimize: None,
+                stopped_early: false,
+            });
+            match &on_cycle {
+                Some(on_cycle) => on_cycle(&CycleReport {
+                    cycle,
+                    min_error,
+                    median_error,
+                    population_size: creatures.len(),
+                    best_creature: best_creature.clone(),
+                }),
+                None if print_cycles => print_cycle_data(cycle, median_error, best_creature),
+                None => {},
+            }
+            if let Some(events) = &events {
+                events.send(EvolutionEvent::CycleCompleted(history.last().unwrap().clone()));
+            }
+
+            if let (Some(target_r2), Some(validation), Some(actuals)) = (config.target_r2, &validation_data, &validation_actuals) {
+                let output = standardizer.param_standardizer(&target).expect("Target missing from standardizer");
+                let r2 = Metric::RSquared.compute(&original_predictions(best_creature, config.link, output, validation), actuals);
+                if r2 >= target_r2 {
+                    if summary {
+                        println!("Stopping after cycle {}: validation R2 of {} reached the target of {}", cycle, r2, target_r2);
+                    }
+                    target_r2_reached = Some(true);
+                    break;
+                }
+            }
+
+            if let Some(stop_at_error) = config.stop_at_error.filter(|stop_at_error| min_error < *stop_at_error) {
+                if summary {
+                    println!("Stopping after cycle {}: the best error of {} is below {}", cycle, min_error, stop_at_error);
+                }
+                outcome = Outcome::ErrorReached { error: min_error, cycles: cycle };
+                break;
+            }
+
+            if let Some(patience) = config.patience {
+                if lowest_error.is_none_or(|lowest| config.patience_improvement.accepts(lowest, min_error)) {
+                    stalled_cycles = 0;
+                } else {
+                    stalled_cycles += 1;
+                }
+                let lowest = *lowest_error.insert(lowest_error.map_or(min_error, |lowest| lowest.min(min_error)));
+                if stalled_cycles >= patience {
+                    if summary {
+                        println!("Stopping after cycle {}: the best error of {} has not improved for {} cycles", cycle, lowest, patience);
+                    }
+                    outcome = Outcome::Converged { best_error: lowest, cycles: cycle };
+                    break;
+                }
+            }
+
+            if config.no_signal_cycles == Some(cycle) {
+                let baseline_error = baseline_error(cycle_data);
+                let best_error = best_creature.cached_error_sum.unwrap();
+                if best_error.is_nan() || best_error > baseline_error * (1.0 - config.no_signal_margin) {
+                    if summary {
+                        println!("Stopping after cycle {}: the best error of {} does not beat the mean baseline of {}",
+                                 cycle, best_error, baseline_error);
+                    }
+                    outcome = Outcome::NoSignal { baseline_error, best_error, cycles: cycle };
+                    break;
+                }
+            }
+
+            if cycle < num_cycles && budget.as_ref().is_some_and(|budget| budget.exhausted()) {
+                if summary {
+                    println!("Stopping after cycle {}: the training budget is used up", cycle);
+                }
+                outcome = Outcome::BudgetExhausted { cycles: cycle };
+                break;
+            }
 
-            creatures = kill_weak_creatures(creatures, &median_error);
-            creatures.append(&mut mutated_top_creatures(&creatures, &min_error, &median_error));
+            let small_population = num_creatures < SMALL_POPULATION;
+            let survivor_count = if config.pareto_front {
+                pareto_front = updated_pareto_front(pareto_front, &creatures);
+                let survivor_count = (creatures.len() / 2).max(MIN_SURVIVORS);
+                creatures = pareto_survivors(creatures, survivor_count);
+                let parents = if small_population { creatures.len() } else { creatures.len() / 2 };
+                let stream = streams.next();
+                let mut mutants = creatures[..parents].par_iter().enumerate()
+                    .map(|(index, creature)| mutant(creature, MutateSpeed::Fast, &config.mutation, config.mutator.as_ref(), &samplers, &mut stream.rng(index)))
+                    .collect();
+                creatures.append(&mut mutants);
+                survivor_count
+            } else {
+                creatures = kill_weak_creatures(creatures, &median_error);
+                let error_cutoff = match small_population {
+                    true => f32::INFINITY,
+                    false => (min_error + median_error) / 2.0,
+                };
+                let survivor_count = creatures.len();
+                creatures.append(&mut mutated_top_creatures(&creatures, error_cutoff, &config.mutation, config.mutator.as_ref(), &samplers, streams.next()));
+                survivor_count
+            };
 
             // Now ensure creatures is correct length by cutting off extras
             // or adding newly generated Creatures to fill to num_creatures length.
             creatures.truncate(num_creatures as usize);
             if creatures.len() < num_creatures as usize {
-                creatures.append(&mut Creature::create_many_parallel(
-                    num_creatures - creatures.len() as u32, &param_options, max_layers
+                let max_fresh = (MAX_FRESH_FRACTION * num_creatures as f32) as usize;
+                let fresh = (num_creatures as usize - creatures.len()).min(max_fresh);
+                creatures.append(&mut Creature::create_many_seeded(
+                    fresh as u32, &param_options, max_layers, &config.creation, &samplers, streams.next(), 0
                 ));
+                let stream = streams.next();
+                let extra_offspring: Vec<Creature> = (0..num_creatures as usize - creatures.len())
+                    .map(|index| mutant(&creatures[index % survivor_count], MutateSpeed::Fast, &config.mutation, config.mutator.as_ref(), &samplers,
+                                        &mut stream.rng(index)))
+                    .collect();
+                creatures.extend(extra_offspring);
             }
         }
 
-        let mut min_error = 100_000_000_000.0;  // arbitrarily large starting number
-        for creature in &best_creatures {
-            match creature.cached_error_sum {
-                Some(error) => {
-                    if error < min_error {
-                        min_error = error;
-                    }
-                },
-                _ => (),
+        if history.len() < num_cycles as usize {
+            if let Some(last) = history.last_mut() {
+                last.stopped_early = true;
             }
         }
 
-        let best_creature = best_creatures
-            .iter()
-            .find(|creature| creature.cached_error_sum == Some(min_error))
-            .expect("Error matching min_error to a creature!");
-        let optimized_creature = optimize_creature(&best_creature, &standardized_data, &target, 30);
+        // Errors from cycles scored with an earlier HuberAuto delta,
+        // dropped features or masked terms are not comparable
+        if config.huber_auto() || config.feature_dropout > 0.0 || config.term_dropout > 0.0 {
+            best_creatures.par_iter_mut().for_each(|creature| {
+                creature.set_cached_error(calc_error_sum(creature, &training_data));
+            });
+        }
+
+        // The front may mix creatures scored in different cycles
+        pareto_front.par_iter_mut().for_each(|creature| {
+            creature.set_cached_error(calc_error_sum(creature, &training_data));
+        });
+        let pareto_front = updated_pareto_front(Vec::new(), &pareto_front);
+
+        if let Some(validation) = validation_data.as_mut() {
+            validation.loss = training_data.loss;
+        }
+        let (best_creature, _) = select_best(
+            &best_creatures, config.selection, validation_data.as_ref(), best_creatures.len()
+        );
+        let (optimized_creature, optimize_choice, optimize_progress) = optimize_creature(
+            best_creature, &training_data, validation_data.as_ref(), config.optimize_iterations, &config.mutation, config.mutator.as_ref(), &samplers, config.min_improvement,
+            streams.next(), events.as_ref(), budget.as_ref()
+        );
+        if summary && optimize_progress.truncated() {
+            println!("The training budget allowed {} of {} optimization iterations", optimize_progress.iterations, optimize_progress.planned);
+        }
+        if let Some(last) = history.last_mut() {
+            last.optimize = Some(optimize_progress);
+        }
 
-        print_optimize_data(best_creature.cached_error_sum.unwrap(),
-                            optimized_creature.cached_error_sum.unwrap(),
-                            &optimized_creature);
+        if summary && on_cycle.is_none() {
+            print_optimize_data(best_creature.cached_error_sum.unwrap(),
+                                optimized_creature.cached_error_sum.unwrap(),
+                                &optimized_creature);
+        }
+        if summary && optimized_creature.is_constant() {
+            println!("Warning: the best creature uses no input parameters, so every prediction is the same");
+        }
 
-        Evolution {
-            target: target,
-            num_creatures: num_creatures,
-            num_cycles: num_cycles,
-            standardizer: standardizer,
-            best_creatures: best_creatures,
+        let final_metrics = metrics(&optimized_creature, &training_data, validation_data.as_ref());
+        let train_rmse = original_rmse(&optimized_creature, config, &standardizer, &training_data, &train_actuals);
+        let evolution = Evolution {
+            target,
+            config: EvolutionConfig { seed: Some(seed), ..config.clone() },
+            standardizer,
+            best_creatures,
             best_creature: optimized_creature,
+            pareto_front,
+            hall_of_fame,
+            cycles_run: history.len() as u16,
+            history,
+            metrics: final_metrics,
+            optimize_choice: Some(optimize_choice),
+            target_r2_reached,
+            outcome: Some(outcome),
+            preprocess,
+            preprocessing_report,
+            determinism: Some(determinism),
+            target_encoder,
+            pipeline,
+            ridge_lambda: None,
+            train_rmse: Some(train_rmse),
+        };
+        if let Some(events) = &events {
+            events.send(EvolutionEvent::Finished(evolution.summary()));
+        }
+        Ok(evolution)
+    }
+
+    /// Predict the target for "data_point", ignoring any keys which are not
+    /// parameters the model uses (such as the target).  Fails, naming the parameter,
+    /// if one the model uses is NaN or infinite, or is missing and not filled in by
+    /// `EvolutionConfig::missing_params` (`MissingParams::Mean` or `MissingParams::Constant`).
+    pub fn predict_point(&self, data_point: &HashMap<String, f32>) -> Result<f32, PredictError> {
+        let data_point = self.prepared_point(data_point)?;
+        let fills = matches!(self.config.missing_params, MissingParams::Mean | MissingParams::Constant(_));
+        for parameter in self.best_creature.parameters() {
+            match data_point.get(parameter) {
+                Some(value) if !value.is_finite() => {
+                    return Err(PredictError::NonFinite { parameter: parameter.to_string(), value: *value });
+                },
+                None if !fills => return Err(PredictError::MissingParameter(parameter.to_string())),
+                _ => (),
+            }
         }
+        Ok(self.predicted(&data_point, self.config.missing_params))
     }
 
-    fn predict_point(&self, data_point: HashMap<String, f32>) -> f32 {
-        let standardized_point = self.standardizer.standardized_value(&data_point);
-        let result = self.best_creature.calculate(&standardized_point);
-        self.standardizer.unstandardize_value(&self.target, result)
+    /// "predict_point" without its checks, leaving out the terms of missing parameters
+    /// (unless `EvolutionConfig::missing_params` fills them in) and passing on NaNs.
+    pub(crate) fn predict_unchecked(&self, data_point: &HashMap<String, f32>) -> f32 {
+        self.predict_with(data_point, self.config.missing_params)
     }
-}
 
-fn optimize_creature(creature: &Creature,
-    data_points: &Vec<HashMap<String, f32>>,
-    target: &str,
-    iterations: u16) -> Creature {
+    /// Predict every point of "data" (in order) in parallel.  Unlike "predict_point"
+    /// this never fails: parameters missing from a point fall back to their training
+    /// mean when `EvolutionConfig::missing_params` is `MissingParams::Skip`, and
+    /// non-finite inputs give NaN or infinite predictions.
+    pub fn predict(&self, data: &[HashMap<String, f32>]) -> Vec<f32> {
+        let missing = match self.config.missing_params {
+            MissingParams::Skip => MissingParams::Mean,
+            missing => missing,
+        };
+        data.par_iter().map(|point| self.predict_with(point, missing)).collect()
+    }
 
-    let mut errors = Vec::new();
-    let mut best_error = creature.cached_error_sum.unwrap();
-    let mut speed = MutateSpeed::Fast;
-    let mut best_creature = creature.clone();
-    for i in 0..=iterations {
-        let mut creatures = vec![best_creature.clone()];
-        creatures.extend((0..500).map(|_| best_creature.mutate(speed.clone())).collect::<Vec<Creature>>());
+    /// Predict every point of "data" like "predict_point", with the predictions in
+    /// the same order.  Each parameter's standardization is looked up once for the
+    /// whole batch rather than per point.  Fails with the first point's error.
+    pub fn predict_many(&self, data: &[HashMap<String, f32>]) -> Result<Vec<f32>, PredictError> {
+        let inputs = self.batch_inputs();
+        let mut standardized_point = inputs.point();
+        data.iter().map(|data_point| self.batch_predicted(&inputs, data_point, &mut standardized_point)).collect()
+    }
 
-        creatures.par_iter_mut().for_each(|creature| {
-            if creature.cached_error_sum == None {
-                let err = calc_error_sum(&creature, &data_points, &target);
-                creature.cached_error_sum = Some(err);
+    /// "predict_many" with the points split across the rayon pool.
+    pub fn predict_many_parallel(&self, data: &[HashMap<String, f32>]) -> Result<Vec<f32>, PredictError> {
+        let inputs = self.batch_inputs();
+        data.par_iter()
+            .map_init(|| inputs.point(), |standardized_point, data_point| self.batch_predicted(&inputs, data_point, standardized_point))
+            .collect()
+    }
+
+    /// Standardization of every parameter the model uses and of the target.
+    fn batch_inputs(&self) -> BatchInputs<'_> {
+        let parameters = self.best_creature.parameters().into_iter()
+            .map(|parameter| {
+                let stats = self.standardizer.param_standardizer(parameter)
+                    .unwrap_or_else(|| panic!("Unable to find ParamStandardizer for {}", parameter));
+                let fill = match self.config.missing_params {
+                    MissingParams::Mean => Some(0.0),
+                    MissingParams::Constant(value) => Some(stats.standardize(&value)),
+                    MissingParams::Skip | MissingParams::Penalty(_) => None,
+                };
+                (parameter, stats, fill)
+            })
+            .collect();
+        let output = self.standardizer.param_standardizer(&self.target)
+            .unwrap_or_else(|| panic!("Unable to find ParamStandardizer for {}", self.target));
+        BatchInputs { parameters, output }
+    }
+
+    /// Predict "data_point" with "inputs", standardizing it into "standardized_point"
+    /// (from "BatchInputs::point") to avoid allocating per point.
+    fn batch_predicted(&self, inputs: &BatchInputs, data_point: &HashMap<String, f32>,
+                       standardized_point: &mut HashMap<String, f32>) -> Result<f32, PredictError> {
+        let data_point = self.prepared_point(data_point)?;
+        for (parameter, stats, fill) in &inputs.parameters {
+            let value = match (data_point.get(*parameter), fill) {
+                (Some(value), _) if !value.is_finite() => {
+                    return Err(PredictError::NonFinite { parameter: parameter.to_string(), value: *value });
+                },
+                (Some(value), _) => stats.standardize(value),
+                (None, Some(fill)) => *fill,
+                (None, None) => return Err(PredictError::MissingParameter(parameter.to_string())),
+            };
+            *standardized_point.get_mut(*parameter).unwrap() = value;
+        }
+        let result = self.best_creature.calculate_with(standardized_point, self.config.strict_fp);
+        Ok(self.config.link.inverse(inputs.output.unstandardize(&result)))
+    }
+
+    /// Lazily predict each point of "points" like "predict_point", so arbitrarily
+    /// many points can be scored without holding them or their predictions in memory.
+    pub fn predict_stream<'a>(&'a self, points: impl Iterator<Item = HashMap<String, f32>> + 'a)
+        -> impl Iterator<Item = Result<f32, PredictError>> + 'a
+    {
+        points.map(move |point| self.predict_point(&point))
+    }
+
+    /// "predict_stream" which takes "chunk_size" points at a time from "points"
+    /// and predicts them in parallel, yielding each chunk's predictions in order
+    /// before reading the next chunk.
+    pub fn predict_stream_chunked<'a>(&'a self, points: impl Iterator<Item = HashMap<String, f32>> + 'a, chunk_size: usize)
+        -> impl Iterator<Item = Result<f32, PredictError>> + 'a
+    {
+        assert!(chunk_size > 0, "chunk_size must be at least 1");
+        let mut points = points;
+        std::iter::from_fn(move || {
+            let chunk: Vec<HashMap<String, f32>> = points.by_ref().take(chunk_size).collect();
+            match chunk.is_empty() {
+                true => None,
+                false => Some(chunk.par_iter().map(|point| self.predict_point(point)).collect::<Vec<_>>()),
             }
-        });
+        }).flatten()
+    }
 
-        let (min_error, median_error) = error_results(&creatures);
-        errors.push(min_error);
+    /// "predict_unchecked" with parameters missing from "data_point" handled by "missing".
+    /// NaN when column names of "data_point" collide once normalized.
+    fn predict_with(&self, data_point: &HashMap<String, f32>, missing: MissingParams) -> f32 {
+        match self.prepared_point(data_point) {
+            Ok(data_point) => self.predicted(&data_point, missing),
+            Err(_) => f32::NAN,
+        }
+    }
 
-        if min_error < best_error {
-            best_error = min_error;
-            best_creature = creatures
-                .iter()
-                .find(|creature| creature.cached_error_sum == Some(min_error))
-                .expect("Error matching min_error to a creature!").clone();
+    /// "data_point" with the column names the model uses, preprocessed, or a
+    /// `PredictError::ColumnCollision` if two of its names are the same once normalized.
+    pub(crate) fn prepared_point<'a>(&self, data_point: &'a HashMap<String, f32>) -> Result<Cow<'a, HashMap<String, f32>>, PredictError> {
+        let mut data_point = self.config.column_names.normalized_point(data_point).map_err(PredictError::from_collision)?;
+        if let Some(preprocess) = &self.preprocess {
+            preprocess(data_point.to_mut());
         }
+        Ok(data_point)
+    }
 
-        if i > 5 && min_error / errors.get(errors.len() - 4).unwrap() > 0.9999 {
-            speed = MutateSpeed::Fine;
+    /// Prediction for a prepared "data_point" with missing parameters handled by "missing".
+    pub(crate) fn predicted(&self, data_point: &HashMap<String, f32>, missing: MissingParams) -> f32 {
+        let mut standardized_point = self.standardizer.standardized_value(data_point);
+        self.standardizer.fill_missing(&mut standardized_point, &self.target, missing);
+        if matches!(missing, MissingParams::Penalty(_)) && self.best_creature.uses_missing(&standardized_point) {
+            return f32::NAN;
         }
+        let result = self.best_creature.calculate_with(&standardized_point, self.config.strict_fp);
+        self.config.link.inverse(self.standardizer.unstandardize_value(&self.target, result))
     }
-    best_creature
-}
 
-fn print_optimize_data(start_error: f32, end_error: f32, best_creature: &Creature) -> () {
-    println!("\n\n--- FINAL OPTIMIZATION COMPLETE ---");
-    println!("Start: {}    Best: {}", start_error, end_error);
-    println!("  Generation: {}   Error: {}", best_creature.generation, best_creature.cached_error_sum.unwrap());
-    println!("{}", best_creature);
-}
+    /// Like "predict_point" without its checks, but also flagging inputs outside of their valid ranges.
+    /// A point whose column names collide once normalized gives NaN with no flagged inputs.
+    pub fn predict_detailed(&self, data_point: &HashMap<String, f32>) -> DetailedPrediction {
+        let Ok(data_point) = self.config.column_names.normalized_point(data_point) else {
+            return DetailedPrediction { prediction: f32::NAN, out_of_range: Vec::new() };
+        };
+        let out_of_range = ranges::out_of_range(&data_point, &self.config.valid_ranges).into_iter()
+            .map(|column| column.to_string())
+            .collect();
+        DetailedPrediction { prediction: self.predict_unchecked(&data_point), out_of_range }
+    }
 
-fn print_cycle_data(cycle: u16, median_error: f32, best_creature: &Creature) -> () {
-    println!("---------------------------------------");
-    println!("Cycle - {} -", cycle);
-    println!("Median error: {}", median_error);
-    println!("Best Creature:");
-    println!("  Generation: {}   Error: {}", best_creature.generation, best_creature.cached_error_sum.unwrap());
-    println!("{}", best_creature);
-}
+    /// Mean and (population) standard deviation of the predictions of every
+    /// cycle's best creature, as a rough measure of uncertainty which needs no
+    /// extra training.  Models without the cycle bests (such as those loaded
+    /// from a bundle) give their own prediction with a spread of zero.  Both are NaN
+    /// when column names of "data_point" collide once normalized.
+    pub fn cycle_ensemble_prediction(&self, data_point: &HashMap<String, f32>) -> (f32, f32) {
+        let Ok(data_point) = self.prepared_point(data_point) else {
+            return (f32::NAN, f32::NAN);
+        };
+        let mut standardized_point = self.standardizer.standardized_value(&data_point);
+        self.standardizer.fill_missing(&mut standardized_point, &self.target, self.config.missing_params);
+        let creatures = match self.best_creatures.is_empty() {
+            true => std::slice::from_ref(&self.best_creature),
+            false => &self.best_creatures[..],
+        };
+        let predictions: Vec<f32> = creatures.iter().map(|creature| {
+            if matches!(self.config.missing_params, MissingParams::Penalty(_)) && creature.uses_missing(&standardized_point) {
+                return f32::NAN;
+            }
+            let result = creature.calculate_with(&standardized_point, self.config.strict_fp);
+            self.config.link.inverse(self.standardizer.unstandardize_value(&self.target, result))
+        }).collect();
+        let mean = predictions.iter().sum::<f32>() / predictions.len() as f32;
+        let variance = predictions.iter().map(|prediction| (prediction - mean).powi(2)).sum::<f32>() / predictions.len() as f32;
+        (mean, variance.sqrt())
+    }
 
-fn error_results(creatures: &Vec<Creature>) -> (f32, f32) {
-    let mut errors = Vec::new();
-    for creature in creatures.iter() {
-        errors.push(creature.cached_error_sum.unwrap());
+    /// Predictions at a few canonical points summarizing the model's behavior:
+    /// first with every input at its training mean (labeled "mean"), then for
+    /// each input in sorted order, with that input at its training minimum and
+    /// maximum and the others at their means (labeled e.g. "x at min").
+    pub fn reference_predictions(&self) -> Vec<(String, f32)> {
+        let params: Vec<&str> = self.standardizer.parameters().into_iter()
+            .filter(|param| *param != self.target)
+            .collect();
+        let stats = |param: &str| self.standardizer.param_standardizer(param).unwrap();
+        let means: HashMap<String, f32> = params.iter()
+            .map(|param| (param.to_string(), stats(param).mean()))
+            .collect();
+
+        let mut predictions = vec![("mean".to_string(), self.predict_unchecked(&means))];
+        for param in params {
+            for (label, value) in [("min", stats(param).min()), ("max", stats(param).max())] {
+                let mut point = means.clone();
+                point.insert(param.to_string(), value);
+                predictions.push((format!("{} at {}", param, label), self.predict_unchecked(&point)));
+            }
+        }
+        predictions
     }
-    errors.sort_by(|a, b| a.total_cmp(b));
-    let median_error = errors[errors.len() / 2];
-    let min_error = errors[0];
-    (min_error, median_error)
-}
 
-fn kill_weak_creatures(creatures: Vec<Creature>, median_error: &f32) -> Vec<Creature> {
-    creatures.into_par_iter()
-             .filter(|creature| creature.cached_error_sum.unwrap() < *median_error)
-             .collect()
-}
+    /// Predictions over a grid of the "vary" features for response surface plots.
+    /// Each feature is swept from its training minimum to its training maximum
+    /// in "steps" evenly spaced values, with the last feature changing fastest.
+    /// The other inputs are held at their training means when "fixed_at_mean" is
+    /// true, and are otherwise left out of the points (so their terms contribute nothing).
+    /// Each result is the values of the "vary" features (in order) and the prediction.
+    pub fn predict_grid(&self, vary: &[String], steps: usize, fixed_at_mean: bool) -> Vec<(Vec<f32>, f32)> {
+        assert!(!vary.is_empty(), "predict_grid needs at least one feature to vary");
+        assert!(steps >= 2, "predict_grid needs at least 2 steps");
+        let stats = |param: &str| match self.standardizer.param_standardizer(param) {
+            Some(stats) if param != self.target => stats,
+            _ => panic!("{} is not an input parameter of the model", param),
+        };
+        let sweeps: Vec<Vec<f32>> = vary.iter().map(|param| {
+            let (min, max) = (stats(param).min(), stats(param).max());
+            (0..steps).map(|step| min + (max - min) * step as f32 / (steps - 1) as f32).collect()
+        }).collect();
+        let base: HashMap<String, f32> = match fixed_at_mean {
+            true => self.standardizer.parameters().into_iter()
+                .filter(|param| *param != self.target)
+                .map(|param| (param.to_string(), stats(param).mean()))
+                .collect(),
+            false => HashMap::new(),
+        };
 
-fn mutated_top_creatures(creatures: &Vec<Creature>, min_error: &f32, median_error: &f32) -> Vec<Creature> {
-    let error_cutoff = (min_error + median_error) / 2.0;
-    creatures.into_par_iter()
-             .filter(|cr| cr.cached_error_sum.unwrap() < error_cutoff)
-             .map(|cr| cr.mutate(MutateSpeed::Fast))
-             .collect()
-}
+        sweeps.iter()
+            .map(|sweep| sweep.iter().copied())
+            .multi_cartesian_product()
+            .map(|values| {
+                let mut point = base.clone();
+                point.extend(vary.iter().cloned().zip(values.iter().copied()));
+                let prediction = self.predict_unchecked(&point);
+                (values, prediction)
+            })
+            .collect()
+    }
 
-fn calc_error_sum(creature: &Creature,
-                  data_points: &Vec<HashMap<String, f32>>,
-                  target_param: &str) -> f32 {
-    let mut total: f32 = 0.0;
-    for point in data_points {
-        let calc = creature.calculate(&point);
-        let diff = calc - point.get(target_param)
-                               .expect("Data point missing target_param");
-        total += diff.powi(2);
+    /// Name of the column the model predicts.
+    pub fn target(&self) -> &str {
+        &self.target
     }
-    total / (data_points.len() as f32)
-}
 
+    /// The creature making the model's predictions, whose coefficients apply
+    /// to the standardized data (see `Evolution::formula` for the original units).
+    pub fn best_creature(&self) -> &Creature {
+        &self.best_creature
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use itertools::izip;
+    /// The config the model was trained with, including the seed that was
+    /// used when `EvolutionConfig::seed` was None.
+    /// Models loaded from a bundle without a config.json have the default config.
+    pub fn config(&self) -> &EvolutionConfig {
+        &self.config
+    }
 
-    #[test]
-    fn basic_evolution() {
-        let target = "target_param";
-        let data = vec![
-            HashMap::from([("target_param".to_string(), 5.2), ("p2".to_string(), 7.8), ("p3".to_string(), 8.3)]),
-            HashMap::from([("target_param".to_string(), 6.0), ("p2".to_string(), 4.4), ("p3".to_string(), 8.1)]),
-            HashMap::from([("target_param".to_string(), 7.1), ("p2".to_string(), 3.9), ("p3".to_string(), 9.5)]),
-            HashMap::from([("target_param".to_string(), 8.6), ("p2".to_string(), 2.7), ("p3".to_string(), 11.6)]),
-            HashMap::from([("target_param".to_string(), 9.4), ("p2".to_string(), -2.6), ("p3".to_string(), 13.0)]),
-        ];
+    /// Creatures found while training for which no other creature had both a
+    /// lower error and a lower complexity, ordered from least to most complex
+    /// (so from highest to lowest error), with errors on the training data.
+    /// Empty unless `EvolutionConfig::pareto_front` was set.
+    pub fn pareto_front(&self) -> Vec<Creature> {
+        self.pareto_front.clone()
+    }
 
-        let evo = Evolution::new(target.into(), &data, 10000, 10, 3);
-        assert_eq!(evo.num_creatures == 10000, true);
+    /// Results of the checks run on the data before training.
+    pub fn preprocessing_report(&self) -> &PreprocessingReport {
+        &self.preprocessing_report
     }
 
-    #[test]
-    fn parabola() {
-        let parabola_data = vec![
-            HashMap::from([("x".to_string(), -20.0), ("y".to_string(), 195.0967073301952)]),
-            HashMap::from([("x".to_string(), -19.0), ("y".to_string(), 205.88669941695193)]),
-            HashMap::from([("x".to_string(), -18.0), ("y".to_string(), 75.05183418690936)]),
-            HashMap::from([("x".to_string(), -17.0), ("y".to_string(), 153.31304897814132)]),
-            HashMap::from([("x".to_string(), -16.0), ("y".to_string(), 180.72678834266526)]),
-            HashMap::from([("x".to_string(), -15.0), ("y".to_string(), 81.73490536370575)]),
-            HashMap::from([("x".to_string(), -14.0), ("y".to_string(), 76.98269474497451)]),
-            HashMap::from([("x".to_string(), -13.0), ("y".to_string(), 106.65404246488129)]),
-            HashMap::from([("x".to_string(), -12.0), ("y".to_string(), 101.81854634039516)]),
-            HashMap::from([("x".to_string(), -11.0), ("y".to_string(), 32.735790537057994)]),
-            HashMap::from([("x".to_string(), -10.0), ("y".to_string(), 3.5140689599924273)]),
-            HashMap::from([("x".to_string(), -9.0), ("y".to_string(), 21.979234525796137)]),
-            HashMap::from([("x".to_string(), -8.0), ("y".to_string(), 2.101943660864327)]),
-            HashMap::from([("x".to_string(), -7.0), ("y".to_string(), 4.083877304799986)]),
-            HashMap::from([("x".to_string(), -6.0), ("y".to_string(), 0.12110473958116565)]),
-            HashMap::from([("x".to_string(), -5.0), ("y".to_string(), 16.57223235311977)]),
-            HashMap::from([("x".to_string(), -4.0), ("y".to_string(), 0.14511553873582717)]),
-            HashMap::from([("x".to_string(), -3.0), ("y".to_string(), 2.510511396206416)]),
-            HashMap::from([("x".to_string(), -2.0), ("y".to_string(), 56.587670650914006)]),
-            HashMap::from([("x".to_string(), -1.0), ("y".to_string(), 4.880296227847032)]),
-            HashMap::from([("x".to_string(), 0.0), ("y".to_string(), 15.393806879686704)]),
-            HashMap::from([("x".to_string(), 1.0), ("y".to_string(), 19.980723972406757)]),
-            HashMap::from([("x".to_string(), 2.0), ("y".to_string(), 46.44040802736543)]),
-            HashMap::from([("x".to_string(), 3.0), ("y".to_string(), 76.32570640372656)]),
-            HashMap::from([("x".to_string(), 4.0), ("y".to_string(), 28.344936970432833)]),
-            HashMap::from([("x".to_string(), 5.0), ("y".to_string(), 107.80487596755955)]),
-            HashMap::from([("x".to_string(), 6.0), ("y".to_string(), 90.52490037859376)]),
-            HashMap::from([("x".to_string(), 7.0), ("y".to_string(), 157.59858818802704)]),
-            HashMap::from([("x".to_string(), 8.0), ("y".to_string(), 143.33624805335427)]),
-            HashMap::from([("x".to_string(), 9.0), ("y".to_string(), 145.24993288695646)]),
-            HashMap::from([("x".to_string(), 10.0), ("y".to_string(), 260.1807578980633)]),
-            HashMap::from([("x".to_string(), 11.0), ("y".to_string(), 185.66458035427738)]),
-            HashMap::from([("x".to_string(), 12.0), ("y".to_string(), 399.47143038541725)]),
-            HashMap::from([("x".to_string(), 13.0), ("y".to_string(), 461.637154269764)]),
-            HashMap::from([("x".to_string(), 14.0), ("y".to_string(), 224.52939759007862)]),
-            HashMap::from([("x".to_string(), 15.0), ("y".to_string(), 435.1803248133029)]),
-            HashMap::from([("x".to_string(), 16.0), ("y".to_string(), 624.3116876259189)]),
-            HashMap::from([("x".to_string(), 17.0), ("y".to_string(), 453.5298507352485)]),
-            HashMap::from([("x".to_string(), 18.0), ("y".to_string(), 396.33513809585935)]),
-            HashMap::from([("x".to_string(), 19.0), ("y".to_string(), 415.8142609595538)]),
-            HashMap::from([("x".to_string(), 20.0), ("y".to_string(), 758.0144333664495)]),
-        ];
-        let target = String::from("y");
-        let model = Evolution::new(target, &parabola_data, 5000, 7, 3);
+    /// Whether the validation R2 reached `EvolutionConfig::target_r2`, ending
+    /// training early.  None when there was no target (or for models loaded
+    /// from a bundle or refit).
+    pub fn target_r2_reached(&self) -> Option<bool> {
+        self.target_r2_reached
+    }
+
+    /// Whether this model's training can be repeated exactly with its seed
+    /// (see `DeterminismReport`).  None for models loaded from a bundle or refit.
+    pub fn determinism_report(&self) -> Option<&DeterminismReport> {
+        self.determinism.as_ref()
+    }
+
+    /// The encoding of `EvolutionConfig::target_encoding` applied to points
+    /// before they are predicted, fit on every training row.
+    pub fn target_encoder(&self) -> Option<&TargetEncoder> {
+        self.target_encoder.as_ref()
+    }
+
+    /// How training ended.  None for models loaded from a bundle or refit.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Whether the final optimization phase was kept.
+    /// None for models loaded from a bundle.
+    pub fn optimize_choice(&self) -> Option<OptimizeChoice> {
+        self.optimize_choice
+    }
+
+    /// Root mean squared error of the best creature on the training data (in the
+    /// original units of the target) when it was trained, which is kept in saved
+    /// models.  None for models saved before it was recorded.
+    pub fn train_rmse(&self) -> Option<f32> {
+        self.train_rmse
+    }
+
+    /// The L2 penalty the best creature's coefficients were last refit with
+    /// by `Evolution::ridge_refit`, or None if they never were.
+    pub fn ridge_lambda(&self) -> Option<f64> {
+        self.ridge_lambda
+    }
+
+    /// Statistics recorded for each evolution cycle.
+    pub fn history(&self) -> &[CycleStats] {
+        &self.history
+    }
+
+    /// Number of evolution cycles that ran, fewer than `EvolutionConfig::num_cycles`
+    /// when training stopped early (see `outcome`).  0 for models loaded from a bundle or refit.
+    pub fn cycles_run(&self) -> u16 {
+        self.cycles_run
+    }
+
+    /// The final model's `EvolutionConfig::report_metrics` on the training and validation data.
+    /// Empty for models loaded from a bundle.
+    pub fn metrics(&self) -> &MetricReport {
+        &self.metrics
+    }
+
+    /// R2, mean absolute error and root mean squared error of the predictions
+    /// (by `Evolution::predict_point`) of the points of "data" which have a
+    /// value for the target and can be predicted, in its original units.
+    /// The values are NaN when there is no such point.
+    pub fn stats(&self, data: &[HashMap<String, f32>]) -> ModelStats {
+        let (predictions, actuals): (Vec<f32>, Vec<f32>) = data.iter()
+            .filter_map(|point| {
+                let actual = *self.config.column_names.normalized_point(point).ok()?.get(&self.target)?;
+                Some((self.predict_point(point).ok()?, actual))
+            })
+            .unzip();
+        ModelStats::new(&predictions, &actuals)
+    }
+
+    /// The best creature's equation in the original units of the data,
+    /// with numbers at full precision (see `Precision::Full`).
+    /// Fails rather than writing a NaN or infinite coefficient.
+    pub fn formula(&self, format: FormulaFormat) -> Result<String, ExportError> {
+        self.formula_with_precision(format, Precision::Full)
+    }
+
+    /// Graphviz DOT diagram of the best creature's structure (see `Creature::to_dot`).
+    /// Its coefficients apply to the standardized data.
+    pub fn best_dot(&self) -> Result<String, ExportError> {
+        self.best_creature.to_dot()
+    }
+
+    /// Like "formula" but with numbers written to the given precision.
+    pub fn formula_with_precision(&self, format: FormulaFormat, precision: Precision) -> Result<String, ExportError> {
+        self.creature_formula(&self.best_creature, format, precision)
+    }
+
+    /// Text formula (see "formula") of the best creature of each cycle, by cycle,
+    /// leaving out cycles whose best has the same formula as the one before unless
+    /// "include_unchanged".  The final model (after the final optimization, which
+    /// follows the last cycle) ends the history, labelled with the last cycle, when
+    /// it differs from the last entry, so the last entry is always the model's formula.
+    /// Models without the cycle bests (such as those loaded from a bundle) give only their own.
+    pub fn formula_history(&self, include_unchanged: bool) -> Result<Vec<(u16, String)>, ExportError> {
+        Ok(self.formula_steps(include_unchanged)?.into_iter().map(|(cycle, formula, _)| (cycle, formula)).collect())
+    }
+
+    /// Training error of each entry of "formula_history", if known.
+    pub fn formula_history_errors(&self, include_unchanged: bool) -> Result<Vec<(u16, Option<f32>)>, ExportError> {
+        Ok(self.formula_steps(include_unchanged)?.into_iter().map(|(cycle, _, error)| (cycle, error)).collect())
+    }
+
+    /// The entries of "formula_history" along with their errors.
+    fn formula_steps(&self, include_unchanged: bool) -> Result<Vec<(u16, String, Option<f32>)>, ExportError> {
+        let cycle_bests = self.history.iter().zip(&self.best_creatures)
+            .map(|(stats, creature)| (stats.cycle, creature, Some(stats.best_error)));
+        let last_cycle = self.history.last().map_or(0, |stats| stats.cycle);
+        let final_model = (last_cycle, &self.best_creature, self.best_creature.cached_error_sum);
+
+        let mut steps: Vec<(u16, String, Option<f32>)> = Vec::new();
+        for (index, (cycle, creature, error)) in cycle_bests.chain([final_model]).enumerate() {
+            let formula = self.creature_formula(creature, FormulaFormat::Text, Precision::Full)?;
+            let is_final = index == self.history.len().min(self.best_creatures.len());
+            let unchanged = steps.last().is_some_and(|(_, previous, _)| *previous == formula);
+            if !unchanged || (include_unchanged && !is_final) {
+                steps.push((cycle, formula, error));
+            }
+        }
+        Ok(steps)
+    }
+
+    /// The equation of "creature" in the original units of the data.
+    fn creature_formula(&self, creature: &Creature, format: FormulaFormat, precision: Precision) -> Result<String, ExportError> {
+        creature.check_finite()?;
+        let standardized_param = |param: &str| {
+            let stats = self.standardizer.param_standardizer(param)
+                .unwrap_or_else(|| panic!("Unable to find ParamStandardizer for {}", param));
+            format.standardized(&format.param(param), stats.mean(), stats.stdev(), precision)
+        };
+        let output = creature.render(format, precision, &standardized_param);
+
+        let target_stats = self.standardizer.param_standardizer(&self.target)
+            .expect("Target missing from standardizer");
+        let unstandardized = format.unstandardized(&output, target_stats.mean(), target_stats.stdev(), precision);
+        let expression = match self.config.link {
+            Link::Identity => unstandardized,
+            Link::Log => format.exp(&unstandardized),
+            Link::Logit => format.logistic(&unstandardized),
+            Link::BoundedLogit { min, max } => format.sum(&[
+                format.number(min, precision),
+                format.product(&format.number(max - min, precision), &format.logistic(&unstandardized)),
+            ]),
+        };
+        Ok(format.equation(&self.target, &expression))
+    }
+
+    /// Human-readable summary of the trained model.
+    pub fn summary(&self) -> String {
+        let mut summary = String::from("Revogression Model\n");
+        summary += &format!("  Target: {}    Link: {:?}    Loss: {}\n", self.target, self.config.link, self.config.loss_name());
+        summary += &format!("  Creatures: {}    Cycles: {}    Max Layers: {}\n",
+                            self.config.num_creatures, self.history.len(), self.config.max_layers);
+        if let Some(error) = self.best_creature.cached_error_sum {
+            summary += &format!("  Best Error: {}\n", error);
+        }
+        if let (Some(target_r2), Some(reached)) = (self.config.target_r2, self.target_r2_reached) {
+            let outcome = if reached { "reached" } else { "not reached" };
+            summary += &format!("  Target Validation R2: {} ({})\n", target_r2, outcome);
+        }
+        match self.outcome {
+            Some(Outcome::NoSignal { baseline_error, best_error, cycles }) => {
+                summary += &format!("  No Signal: best error of {} after {} cycles does not beat the mean baseline of {}\n",
+                                    best_error, cycles, baseline_error);
+            },
+            Some(Outcome::BudgetExhausted { cycles }) => summary += &format!("  Budget: used up after {} cycles\n", cycles),
+            Some(Outcome::ErrorReached { error, cycles }) => {
+                summary += &format!("  Stopped Early: best error of {} reached after {} cycles\n", error, cycles);
+            },
+            Some(Outcome::Converged { best_error, cycles }) => {
+                summary += &format!("  Converged: best error of {} stopped improving after {} cycles\n", best_error, cycles);
+            },
+            _ => (),
+        }
+        if let Some(progress) = self.history.last().and_then(|stats| stats.optimize).filter(|progress| progress.truncated()) {
+            summary += &format!("  Optimization: budget allowed {} of {} iterations\n", progress.iterations, progress.planned);
+        }
+        if let Some(determinism) = &self.determinism {
+            summary += &format!("  Determinism: {}\n", determinism);
+        }
+        match self.formula(FormulaFormat::Text) {
+            Ok(formula) => summary += &format!("  {}\n", formula),
+            Err(err) => summary += &format!("  Formula: {}\n", err),
+        }
+        if !self.metrics.is_empty() {
+            for line in self.metrics.to_string().lines() {
+                summary += &format!("  {}\n", line);
+            }
+        }
+        if !self.hall_of_fame.is_empty() {
+            for line in self.population_insights().feature_table(SUMMARY_FEATURES).lines() {
+                summary += &format!("  {}\n", line);
+            }
+        }
+        summary
+    }
+
+    /// The per-cycle history as CSV text with a header row.
+    pub fn history_csv(&self) -> String {
+        let mut csv = String::from("cycle,best_error,median_error,best_generation,huber_delta,validation_error");
+        for metric in &self.config.report_metrics {
+            let name = metric.name().to_lowercase();
+            csv += &format!(",train_{},validation_{}", name, name);
+        }
+        if !self.config.datasets.is_empty() {
+            for index in 0..=self.config.datasets.len() {
+                csv += &format!(",dataset_{}_error", index);
+            }
+        }
+        csv += "\n";
+        for stats in &self.history {
+            let huber_delta = stats.huber_delta.map(|delta| delta.to_string()).unwrap_or_default();
+            let validation_error = stats.validation_error.map(|error| error.to_string()).unwrap_or_default();
+            csv += &format!("{},{},{},{},{},{}",
+                            stats.cycle, stats.best_error, stats.median_error, stats.best_generation,
+                            huber_delta, validation_error);
+            for metric in &self.config.report_metrics {
+                let train = stats.metrics.train_value(*metric).map(|value| value.to_string()).unwrap_or_default();
+                let validation = stats.metrics.validation_value(*metric).map(|value| value.to_string()).unwrap_or_default();
+                csv += &format!(",{},{}", train, validation);
+            }
+            for error in &stats.dataset_errors {
+                csv += &format!(",{}", error);
+            }
+            csv += "\n";
+        }
+        csv
+    }
+}
+
+/// The standardized data creatures are scored against,
+/// along with how their output gets compared to the target.
+#[derive(Clone)]
+pub(crate) struct TrainingData {
+    points: Vec<HashMap<String, f32>>,
+    target: String,
+    response: Option<ResponseScale>,
+    loss: LossFunction,
+    custom_loss: Option<CustomLoss>,
+    weights: Option<Vec<f32>>,
+    datasets: Option<Datasets>,
+    /// Loss of each row missing an input a creature uses (see `MissingParams::Penalty`).
+    missing_penalty: Option<f32>,
+    /// Evaluate creatures with `Creature::calculate_strict` (see `EvolutionConfig::strict_fp`).
+    strict_fp: bool,
+    term_mask: Option<TermMask>,
+}
+
+/// Terms zeroed at random while scoring a cycle (see `EvolutionConfig::term_dropout`).
+#[derive(Clone, Copy)]
+struct TermMask {
+    probability: f64,
+    stream: Stream,
+}
+
+impl TermMask {
+    /// "creature" with its masked terms zeroed.  The mask is chosen by the creature's
+    /// constants, so it is the same however the scoring is split between threads.
+    fn applied(&self, creature: &Creature) -> Creature {
+        let mut hasher = DefaultHasher::new();
+        creature.constants().iter().for_each(|constant| constant.to_bits().hash(&mut hasher));
+        creature.masked(self.probability, &mut self.stream.rng(hasher.finish() as usize))
+    }
+}
+
+/// Consecutive ranges of the points which are separate datasets,
+/// each scored on its own before their errors get combined.
+#[derive(Clone)]
+struct Datasets {
+    sizes: Vec<usize>,
+    aggregation: DatasetAggregation,
+}
+
+impl Datasets {
+    fn ranges(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.sizes.iter().scan(0, |start, size| {
+            *start += size;
+            Some(*start - size..*start)
+        })
+    }
+}
+
+/// The original target values and the mapping of creature output onto their
+/// scale, used when creature errors are calculated on the response scale.
+#[derive(Clone)]
+struct ResponseScale {
+    actuals: Vec<f32>,
+    link: Link,
+    target_standardizer: ParamStandardizer,
+}
+
+impl TrainingData {
+    fn new(points: Vec<HashMap<String, f32>>, target: &str) -> TrainingData {
+        TrainingData {
+            points,
+            target: target.to_string(),
+            response: None,
+            loss: LossFunction::SquaredError,
+            custom_loss: None,
+            weights: None,
+            datasets: None,
+            missing_penalty: None,
+            strict_fp: false,
+            term_mask: None,
+        }
+    }
+
+    /// Output of "creature" for "point", or None when the point is missing one
+    /// of its inputs and such rows are penalized.
+    fn creature_output(&self, creature: &Creature, point: &HashMap<String, f32>) -> Option<f32> {
+        #[cfg(test)]
+        tests::FULL_PRECISION_OUTPUTS.with(|count| count.set(count.get() + 1));
+        match self.missing_penalty.is_some() && creature.uses_missing(point) {
+            true => None,
+            false => Some(creature.calculate_with(point, self.strict_fp)),
+        }
+    }
+
+    /// Copy with the standardized values of "features" set to zero (their training mean).
+    fn with_dropped_features(&self, features: &[&str]) -> TrainingData {
+        let mut dropped = self.clone();
+        for point in dropped.points.iter_mut() {
+            for feature in features {
+                if let Some(value) = point.get_mut(*feature) {
+                    *value = 0.0;
+                }
+            }
+        }
+        dropped
+    }
+
+    /// The input columns (of any of the points) quantized for screening.
+    fn quantized(&self) -> QuantizedData {
+        let columns: Vec<&str> = self.points.iter()
+            .flat_map(|point| point.keys())
+            .map(|column| column.as_str())
+            .filter(|column| *column != self.target)
+            .sorted()
+            .dedup()
+            .collect();
+        QuantizedData::new(&self.points, &columns)
+    }
+
+    /// Replace LossFunction::HuberAuto with a Huber delta starting
+    /// from the residuals of a constant (mean) prediction.
+    pub(crate) fn initialize_huber_auto(&mut self) {
+        if self.loss == LossFunction::HuberAuto && self.custom_loss.is_none() {
+            self.loss = LossFunction::Huber { delta: self.auto_huber_delta(&vec![0.0; self.points.len()]) };
+        }
+    }
+
+    /// HuberAuto delta for the residuals of the given creature outputs (one per point).
+    fn auto_huber_delta(&self, outputs: &[f32]) -> f32 {
+        let residuals: Vec<f32> = outputs.iter().enumerate()
+            .map(|(index, output)| self.residual(index, *output))
+            .collect();
+        loss::auto_huber_delta(&residuals, self.weights.as_deref())
+    }
+
+    /// Difference between a creature's output for the point at "index"
+    /// and that point's target, on the scale creature errors are calculated on.
+    fn residual(&self, index: usize, output: f32) -> f32 {
+        let (prediction, actual) = self.prediction_and_actual(index, output);
+        prediction - actual
+    }
+
+    /// A creature's output for the point at "index" and that point's target,
+    /// both on the scale creature errors are calculated on.
+    fn prediction_and_actual(&self, index: usize, output: f32) -> (f32, f32) {
+        match &self.response {
+            Some(response) => {
+                let prediction = response.link.inverse(response.target_standardizer.unstandardize(&output));
+                (prediction, response.actuals[index])
+            },
+            None => (output, *self.points[index].get(&self.target).expect("Data point missing target_param")),
+        }
+    }
+}
+
+/// Fit the Standardizer and build the TrainingData creatures are scored against.
+pub(crate) fn prepare_training_data(target: &str,
+    data: &[HashMap<String, f32>],
+    config: &EvolutionConfig) -> (Standardizer, TrainingData) {
+    prepare_grouped_training_data(target, data, None, config)
+}
+
+/// Like "prepare_training_data", but each of "groups" (of duplicate rows, see
+/// `data::duplicate_groups`) is scored as its first row with the combined weight
+/// of the group, and the Standardizer is fit to the unique rows (each group's
+/// first row) so duplicates do not skew it.
+fn prepare_grouped_training_data(target: &str,
+    data: &[HashMap<String, f32>],
+    groups: Option<&[Vec<usize>]>,
+    config: &EvolutionConfig) -> (Standardizer, TrainingData) {
+    // The standardizer is fit to the target on the linked scale so that
+    // creature output only needs the inverse link after unstandardizing.
+    // With duplicates folded it is fit to the unique rows, matching training
+    // on those rows weighted by how often each appears.
+    let unique_rows: Option<Vec<HashMap<String, f32>>> = groups
+        .map(|groups| groups.iter().map(|group| data[group[0]].clone()).collect());
+    let linked_data = linked_points(target, unique_rows.as_deref().unwrap_or(data), config.link);
+    let standardizer = Standardizer::new(&linked_data);
+    if cfg!(debug_assertions) {
+        let sample: Vec<f32> = linked_data.iter().step_by((linked_data.len() / 100).max(1)).map(|point| point[target]).collect();
+        if let Err(error) = standardizer.check_roundtrip(target, &sample) {
+            panic!("Inconsistent standardization of the target: {}", error);
+        }
+    }
+    if let Some(weights) = &config.sample_weights {
+        assert_eq!(weights.len(), data.len(), "sample_weights must have one weight per data point");
+    }
+    let mut training_data = match groups.zip(unique_rows.as_deref()) {
+        Some((groups, unique_rows)) => {
+            let mut training_data = scoring_data(&standardizer, target, unique_rows, config);
+            training_data.weights = Some(groups.iter().map(|group| match &config.sample_weights {
+                Some(weights) => group.iter().map(|index| weights[*index]).sum(),
+                None => group.len() as f32,
+            }).collect());
+            training_data
+        },
+        None => {
+            let mut training_data = scoring_data(&standardizer, target, data, config);
+            training_data.weights = config.sample_weights.clone();
+            training_data
+        },
+    };
+    training_data.initialize_huber_auto();
+    (standardizer, training_data)
+}
+
+/// Correlation of each input parameter with the (linked) target over the points
+/// having it, with 0 for constant columns.
+fn feature_correlations(target: &str, data: &[HashMap<String, f32>], link: Link) -> HashMap<String, f32> {
+    data::input_columns(data, target).into_iter().map(|param| {
+        let (values, target_values) = data::paired_values(data, param, target);
+        let linked: Vec<f64> = target_values.into_iter().map(|value| link.apply(value as f32) as f64).collect();
+        let correlation = leakage::correlation(&values, &linked).unwrap_or(0.0);
+        (param.to_string(), correlation as f32)
+    }).collect()
+}
+
+/// Copies of the data points after applying "preprocess" to each.
+pub(crate) fn preprocessed(preprocess: &Preprocess, data: &[HashMap<String, f32>]) -> Vec<HashMap<String, f32>> {
+    data.iter().map(|point| {
+        let mut point = point.clone();
+        preprocess(&mut point);
+        point
+    }).collect()
+}
+
+/// Data points with the target mapped onto the linked scale.
+fn linked_points(target: &str, data: &[HashMap<String, f32>], link: Link) -> Vec<HashMap<String, f32>> {
+    data.iter().map(|point| {
+        let mut point = point.clone();
+        if let Some(value) = point.get_mut(target) {
+            *value = link.apply(*value);
+        }
+        point
+    }).collect()
+}
+
+/// Values of "target" in each data point.
+pub(crate) fn target_values(target: &str, data: &[HashMap<String, f32>]) -> Vec<f32> {
+    data.iter().map(|point| point[target]).collect()
+}
+
+/// `EvolutionConfig::report_metrics` of "creature" on the original target scale,
+/// given TrainingData and the matching original target values.
+pub(crate) fn creature_metrics(creature: &Creature,
+    config: &EvolutionConfig,
+    standardizer: &Standardizer,
+    train: (&TrainingData, &[f32]),
+    validation: Option<(&TrainingData, &[f32])>) -> MetricReport {
+    if config.report_metrics.is_empty() {
+        return MetricReport::default();
+    }
+    let output = standardizer.param_standardizer(&train.0.target).expect("Target missing from standardizer");
+    let predictions = |data: &TrainingData| original_predictions(creature, config.link, output, data);
+    let train_predictions = predictions(train.0);
+    let validation_predictions = validation.map(|(data, actuals)| (predictions(data), actuals));
+    MetricReport::new(
+        &config.report_metrics,
+        (&train_predictions, train.1),
+        validation_predictions.as_ref().map(|(predictions, actuals)| (predictions.as_slice(), *actuals)),
+    )
+}
+
+/// Root mean squared error of "creature" on "data" (with targets "actuals")
+/// in the original units of the target.
+pub(crate) fn original_rmse(creature: &Creature, config: &EvolutionConfig, standardizer: &Standardizer, data: &TrainingData, actuals: &[f32]) -> f32 {
+    let output = standardizer.param_standardizer(&data.target).expect("Target missing from standardizer");
+    Metric::RootMeanSquaredError.compute(&original_predictions(creature, config.link, output, data), actuals)
+}
+
+/// Predictions of "creature" for each point of "data" on the original scale of the
+/// target, given the target's ParamStandardizer ("output") and the link.
+fn original_predictions(creature: &Creature, link: Link, output: &ParamStandardizer, data: &TrainingData) -> Vec<f32> {
+    data.points.iter()
+        .map(|point| link.inverse(output.unstandardize(&creature.calculate_with(point, data.strict_fp))))
+        .collect()
+}
+
+/// Unweighted TrainingData for "data" standardized with an already fit Standardizer.
+pub(crate) fn scoring_data(standardizer: &Standardizer,
+    target: &str,
+    data: &[HashMap<String, f32>],
+    config: &EvolutionConfig) -> TrainingData {
+    let linked_data = linked_points(target, data, config.link);
+    let mut points = standardizer.standardized_values(&linked_data);
+    for point in points.iter_mut() {
+        standardizer.fill_missing(point, target, config.missing_params);
+    }
+    let mut training_data = TrainingData::new(points, target);
+    if let MissingParams::Penalty(penalty) = config.missing_params {
+        training_data.missing_penalty = Some(penalty);
+    }
+    training_data.strict_fp = config.strict_fp;
+    if config.link != Link::Identity && config.error_scale == ErrorScale::Response {
+        training_data.response = Some(ResponseScale {
+            actuals: data.iter().map(|point| point[target]).collect(),
+            link: config.link,
+            target_standardizer: standardizer.param_standardizer(target)
+                .expect("Target missing from standardizer")
+                .clone(),
+        });
+    }
+    training_data.loss = config.loss;
+    training_data.custom_loss = config.custom_loss.clone();
+    training_data
+}
+
+/// Refine a creature through repeated rounds of mutation, where the best mutant
+/// only replaces the current best creature when it improves by "min_improvement".
+/// The result never has a higher training error than "creature", and when
+/// there is validation data the original is kept unless the optimized
+/// creature's validation error is at least as low.
+/// The lowest error of each iteration is sent to "events" (if any).
+/// With a "budget", iterations stop once the next one (taking as long as the
+/// average so far) would not fit in what is left, keeping the best creature found.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn optimize_creature(creature: &Creature,
+    training_data: &TrainingData,
+    validation_data: Option<&TrainingData>,
+    iterations: u16,
+    mutation_options: &MutationOptions,
+    mutator: Option<&Arc<dyn Mutator + Send + Sync>>,
+    samplers: &Samplers,
+    min_improvement: MinImprovement,
+    stream: Stream,
+    events: Option<&EventSender>,
+    budget: Option<&Budget>) -> (Creature, OptimizeChoice, OptimizeProgress) {
+
+    // Start from a freshly calculated error rather than trusting the cached one
+    let mut original = creature.clone();
+    original.set_cached_error(calc_error_sum(creature, training_data));
+
+    let mut errors = Vec::new();
+    let mut best_error = original.cached_error_sum.unwrap();
+    let mut speed = MutateSpeed::Fast;
+    let mut best_creature = original.clone();
+    let mut progress = OptimizeProgress { iterations: 0, planned: iterations + 1 };
+    let start = Instant::now();
+    for i in 0..=iterations {
+        if let Some(budget) = budget {
+            if !budget.allows(OPTIMIZE_MUTANTS, start.elapsed() / (i as u32).max(1)) {
+                break;
+            }
+            budget.record(OPTIMIZE_MUTANTS);
+        }
+        progress.iterations += 1;
+        let mut creatures = vec![best_creature.clone()];
+        let mut rng = stream.rng(i as usize);
+        creatures.extend((0..OPTIMIZE_MUTANTS).map(|_| mutant(&best_creature, speed.clone(), mutation_options, mutator, samplers, &mut rng)).collect::<Vec<Creature>>());
+
+        creatures.par_iter_mut().for_each(|creature| {
+            if creature.cached_error_sum.is_none() {
+                let err = calc_error_sum(creature, training_data);
+                creature.set_cached_error(err);
+            }
+        });
+
+        let (min_error, _median_error) = error_results(&creatures);
+        errors.push(min_error);
+        if let Some(events) = events {
+            events.send(EvolutionEvent::OptimizeIteration { iteration: i, error: min_error });
+        }
+
+        if min_improvement.accepts(best_error, min_error) {
+            best_error = min_error;
+            if let Some(creature) = creatures.iter().min_by(|a, b| a.cached_error_sum.unwrap().total_cmp(&b.cached_error_sum.unwrap())) {
+                best_creature = creature.clone();
+            }
+        }
+
+        if i > 5 && min_error / errors.get(errors.len() - 4).unwrap() > 0.9999 {
+            speed = MutateSpeed::Fine;
+        }
+    }
+
+    // Never return a creature with a higher training error than the input
+    let final_error = calc_error_sum(&best_creature, training_data);
+    if final_error >= original.cached_error_sum.unwrap() {
+        return (original, OptimizeChoice::Original, progress);
+    }
+    best_creature.set_cached_error(final_error);
+    match validation_data {
+        Some(validation) if calc_error_sum(&best_creature, validation) > calc_error_sum(&original, validation) => {
+            (original, OptimizeChoice::Original, progress)
+        },
+        _ => (best_creature, OptimizeChoice::Optimized, progress),
+    }
+}
+
+
+fn print_optimize_data(start_error: f32, end_error: f32, best_creature: &Creature) {
+    println!("\n\n--- FINAL OPTIMIZATION COMPLETE ---");
+    println!("Start: {}    Best: {}", start_error, end_error);
+    println!("  Generation: {}   Error: {}", best_creature.generation, best_creature.cached_error_sum.unwrap());
+    println!("{}", best_creature);
+}
+
+fn print_cycle_data(cycle: u16, median_error: f32, best_creature: &Creature) {
+    println!("---------------------------------------");
+    println!("Cycle - {} -", cycle);
+    println!("Median error: {}", median_error);
+    println!("Best Creature:");
+    println!("  Generation: {}   Error: {}", best_creature.generation, best_creature.cached_error_sum.unwrap());
+    println!("{}", best_creature);
+}
+
+/// Score every creature without a cached error, "chunk_size" creatures at a time if given.
+/// Creatures taking longer than "timeout" to score are given an error of f32::MAX.
+fn score_creatures(creatures: &mut [Creature],
+                   training_data: &TrainingData,
+                   chunk_size: Option<usize>,
+                   timeout: Option<Duration>) {
+    let chunk_size = chunk_size.unwrap_or(creatures.len()).max(1);
+    for chunk in creatures.chunks_mut(chunk_size) {
+        chunk.par_iter_mut().for_each(|creature| {
+            if creature.cached_error_sum.is_none() {
+                let err = match timeout {
+                    Some(timeout) => error_sum_within(creature, training_data, Some(Instant::now() + timeout))
+                        .unwrap_or(f32::MAX),
+                    None => calc_error_sum(creature, training_data),
+                };
+                creature.set_cached_error(err);
+            }
+        });
+    }
+}
+
+/// Score the creatures without a cached error on the "quantized" copy of the
+/// inputs of "training_data", with the "dropped" features of this cycle read as 0.
+/// Those ranking in the best SCREENING_PROMOTION_FRACTION of the population are
+/// promoted (left unscored so they get the full precision error) and the rest
+/// are given the maximum error so they get culled.
+fn screen_creatures(creatures: &mut [Creature], training_data: &TrainingData, quantized: &QuantizedData, dropped: &[&str]) {
+    let screening: Vec<Option<f32>> = creatures.par_iter()
+        .map(|creature| match creature.cached_error_sum {
+            Some(_) => None,
+            None => Some(screening_error(creature, training_data, quantized, dropped)),
+        })
+        .collect();
+    if screening.iter().all(Option::is_none) {
+        return;
+    }
+    let mut estimates: Vec<f32> = creatures.iter().zip(&screening)
+        .map(|(creature, screened)| screened.or(creature.cached_error_sum).unwrap())
+        .collect();
+    estimates.sort_by(|a, b| a.total_cmp(b));
+    let promoted_count = ((SCREENING_PROMOTION_FRACTION * estimates.len() as f32).ceil() as usize).max(1);
+    let cutoff = estimates[promoted_count - 1];
+
+    for (creature, screened) in creatures.iter_mut().zip(screening) {
+        if let Some(error) = screened {
+            if error.total_cmp(&cutoff).is_gt() {
+                creature.set_cached_error(f32::MAX);
+            }
+        }
+    }
+}
+
+/// Like "calc_error_sum", but with the inputs taken from "quantized" instead
+/// of "training_data" and the "dropped" features read as 0.
+fn screening_error(creature: &Creature, training_data: &TrainingData, quantized: &QuantizedData, dropped: &[&str]) -> f32 {
+    assert_eq!(quantized.len(), training_data.points.len(), "Quantized data must have a row for each data point");
+    if creature.check_finite().is_err() {
+        return f32::MAX;
+    }
+    let masked;
+    let creature = match &training_data.term_mask {
+        Some(mask) => {
+            masked = mask.applied(creature);
+            &masked
+        },
+        None => creature,
+    };
+    let outputs = quantized.outputs(creature, dropped, training_data.strict_fp, training_data.missing_penalty.is_some());
+    output_error_within(&|index, _| outputs[index], training_data, None)
+        .map(|error| if error.is_nan() { f32::MAX } else { error })
+        .expect("Scoring without a deadline cannot time out")
+}
+
+/// Generate and score the initial population "chunk_size" creatures at a time,
+/// so the whole population never exists at once.  After each chunk only the
+/// best half of the creatures (and at least SELECTION_CANDIDATES) are kept,
+/// which includes every creature that survives the first cycle's cull.
+/// Returns the kept creatures along with the errors of the whole population.
+#[allow(clippy::too_many_arguments)]
+fn stream_initial_population(num_creatures: u32,
+                             param_options: &Vec<&str>,
+                             max_layers: u8,
+                             config: &EvolutionConfig,
+                             samplers: &Samplers,
+                             training_data: &TrainingData,
+                             chunk_size: usize,
+                             stream: Stream) -> (Vec<Creature>, Vec<f32>) {
+    let keep = (num_creatures as usize / 2).max(SELECTION_CANDIDATES).min(num_creatures as usize);
+    let mut kept: Vec<Creature> = Vec::with_capacity(keep + chunk_size);
+    let mut errors = Vec::with_capacity(num_creatures as usize);
+    let mut remaining = num_creatures;
+    while remaining > 0 {
+        let size = remaining.min(chunk_size.max(1) as u32);
+        let generated = (num_creatures - remaining) as usize;
+        let mut chunk = Creature::create_many_seeded(size, param_options, max_layers, &config.creation, samplers, stream, generated);
+        score_creatures(&mut chunk, training_data, None, config.evaluation_timeout);
+        errors.extend(chunk.iter().map(|creature| creature.cached_error_sum.unwrap()));
+        kept.append(&mut chunk);
+        if kept.len() > keep {
+            kept.select_nth_unstable_by(keep, |a, b| a.cached_error_sum.unwrap().total_cmp(&b.cached_error_sum.unwrap()));
+            kept.truncate(keep);
+        }
+        remaining -= size;
+    }
+    (kept, errors)
+}
+
+fn error_results(creatures: &[Creature]) -> (f32, f32) {
+    error_summary(creatures.iter().map(|creature| creature.cached_error_sum.unwrap()).collect())
+}
+
+/// Minimum and median of the given errors.
+fn error_summary(mut errors: Vec<f32>) -> (f32, f32) {
+    errors.sort_by(|a, b| a.total_cmp(b));
+    let median_error = errors[errors.len() / 2];
+    let min_error = errors[0];
+    (min_error, median_error)
+}
+
+/// The best creature according to "selection" among the "candidates" creatures
+/// with the lowest training errors (only the lowest for MinTrainError),
+/// along with its validation error when there is validation data.
+fn select_best<'a>(creatures: &'a [Creature],
+                   selection: SelectionCriterion,
+                   validation_data: Option<&TrainingData>,
+                   candidates: usize) -> (&'a Creature, Option<f32>) {
+    let mut ranked: Vec<&Creature> = creatures.iter().collect();
+    ranked.sort_by(|a, b| a.cached_error_sum.unwrap().total_cmp(&b.cached_error_sum.unwrap()));
+    ranked.truncate(match selection {
+        SelectionCriterion::MinTrainError => 1,
+        _ => candidates.max(1),
+    });
+
+    let scored: Vec<(&Creature, Option<f32>, f32)> = ranked.into_par_iter().map(|creature| {
+        let validation_error = validation_data.map(|validation| calc_error_sum(creature, validation));
+        let score = selection.score(creature.cached_error_sum.unwrap(), validation_error, creature.complexity());
+        (creature, validation_error, score)
+    }).collect();
+    let (best, validation_error, _) = scored.into_iter()
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .expect("Cannot select the best of zero creatures!");
+    (best, validation_error)
+}
+
+/// Clones of the lowest error "fraction" of the scored "creatures" (at least one), best first.
+fn top_creatures(creatures: &[Creature], fraction: f32) -> Vec<Creature> {
+    let count = ((creatures.len() as f32 * fraction).ceil() as usize).max(1);
+    let mut ranked: Vec<&Creature> = creatures.iter().collect();
+    ranked.sort_by(|a, b| a.cached_error_sum.unwrap_or(f32::MAX).total_cmp(&b.cached_error_sum.unwrap_or(f32::MAX)));
+    ranked.into_iter().take(count).cloned().collect()
+}
+
+/// The creatures with errors below the median, or the MIN_SURVIVORS
+/// with the lowest errors when that would leave fewer.
+fn kill_weak_creatures(mut creatures: Vec<Creature>, median_error: &f32) -> Vec<Creature> {
+    if creatures.iter().filter(|creature| creature.cached_error_sum.unwrap() < *median_error).count() < MIN_SURVIVORS {
+        creatures.sort_by(|a, b| a.cached_error_sum.unwrap().total_cmp(&b.cached_error_sum.unwrap()));
+        creatures.truncate(MIN_SURVIVORS);
+        return creatures;
+    }
+    creatures.into_par_iter()
+             .filter(|creature| creature.cached_error_sum.unwrap() < *median_error)
+             .collect()
+}
+
+/// The "count" creatures ranked best by non-dominated sorting of
+/// (error, complexity) and then crowding distance, best first.
+fn pareto_survivors(creatures: Vec<Creature>, count: usize) -> Vec<Creature> {
+    let points: Vec<(f32, usize)> = creatures.iter()
+        .map(|creature| (creature.cached_error_sum.unwrap(), creature.complexity()))
+        .collect();
+    let mut slots: Vec<Option<Creature>> = creatures.into_iter().map(Some).collect();
+    selection::pareto_ranking(&points, count).into_iter()
+        .map(|index| slots[index].take().unwrap())
+        .collect()
+}
+
+/// The Pareto front of (error, complexity) across "front" and "creatures",
+/// keeping one creature for each point and ordered by complexity.
+fn updated_pareto_front(mut front: Vec<Creature>, creatures: &[Creature]) -> Vec<Creature> {
+    let point = |creature: &Creature| (creature.cached_error_sum.unwrap(), creature.complexity());
+    let points: Vec<(f32, usize)> = creatures.iter().map(point).collect();
+    if let Some(first_front) = selection::nondominated_fronts(&points).into_iter().next() {
+        front.extend(first_front.into_iter().map(|index| creatures[index].clone()));
+    }
+    let points: Vec<(f32, usize)> = front.iter().map(point).collect();
+    let mut slots: Vec<Option<Creature>> = front.into_iter().map(Some).collect();
+    let mut updated: Vec<Creature> = selection::nondominated_fronts(&points).into_iter().next().unwrap_or_default()
+        .into_iter()
+        .map(|index| slots[index].take().unwrap())
+        .collect();
+    updated.sort_by(|a, b| point(a).1.cmp(&point(b).1).then(point(a).0.total_cmp(&point(b).0)));
+    updated.dedup_by(|a, b| point(a) == point(b));
+    updated
+}
+
+/// One mutant of each creature with an error below "error_cutoff".
+fn mutated_top_creatures(creatures: &[Creature],
+                         error_cutoff: f32,
+                         mutation_options: &MutationOptions,
+                         mutator: Option<&Arc<dyn Mutator + Send + Sync>>,
+                         samplers: &Samplers,
+                         stream: Stream) -> Vec<Creature> {
+    creatures.into_par_iter()
+             .enumerate()
+             .filter(|(_, cr)| cr.cached_error_sum.unwrap() < error_cutoff)
+             .map(|(index, cr)| mutant(cr, MutateSpeed::Fast, mutation_options, mutator, samplers, &mut stream.rng(index)))
+             .collect()
+}
+
+/// A mutant of "creature" from "mutator" if there is one, or else
+/// from the built-in Gaussian mutation at "speed", drawn from "rng".
+fn mutant(creature: &Creature,
+          speed: MutateSpeed,
+          mutation_options: &MutationOptions,
+          mutator: Option<&Arc<dyn Mutator + Send + Sync>>,
+          samplers: &Samplers,
+          rng: &mut dyn RngCore) -> Creature {
+    match mutator {
+        Some(mutator) => {
+            let mutant = mutator.mutate(creature, rng);
+            let mut mutant = match mutation_options.freeze_structure {
+                true => mutant,
+                false => mutator.mutate_structure(mutant, rng),
+            };
+            samplers.pin(&mut mutant);
+            mutant
+        },
+        None => creature.perturbed(speed.sigma(), mutation_options, samplers, rng),
+    }
+}
+
+/// Recalculate the HuberAuto delta from the residuals of the previous cycle's best creature.
+/// A changed delta changes every creature's error, so all cached errors are invalidated.
+fn update_huber_delta(training_data: &mut TrainingData, best_creature: &Creature, creatures: &mut [Creature]) {
+    let outputs: Vec<f32> = training_data.points.iter().map(|point| best_creature.calculate_with(point, training_data.strict_fp)).collect();
+    let delta = training_data.auto_huber_delta(&outputs);
+    if let LossFunction::Huber { delta: current } = training_data.loss {
+        if ((delta - current) / current).abs() > HUBER_AUTO_UPDATE_THRESHOLD {
+            training_data.loss = LossFunction::Huber { delta };
+            creatures.iter_mut().for_each(|creature| creature.invalidate_cache());
+        }
+    }
+}
+
+/// A random "fraction" (rounded, and always leaving at least one) of "params" to drop for a cycle.
+fn dropped_features<'a>(params: &[&'a str], fraction: f32, rng: &mut StdRng) -> Vec<&'a str> {
+    let count = ((fraction * params.len() as f32).round() as usize).min(params.len().saturating_sub(1));
+    params.choose_multiple(rng, count).copied().collect()
+}
+
+/// Weighted mean of the loss across all data points.
+pub(crate) fn calc_error_sum(creature: &Creature, training_data: &TrainingData) -> f32 {
+    error_sum_within(creature, training_data, None).expect("Scoring without a deadline cannot time out")
+}
+
+/// Like "calc_error_sum" but gives up (returning None) once "deadline" (if any) has passed.
+/// Creatures with a NaN or infinite coefficient (see `Creature::check_finite`) or a NaN
+/// error get an error of f32::MAX so they can never be chosen as the best creature.
+fn error_sum_within(creature: &Creature, training_data: &TrainingData, deadline: Option<Instant>) -> Option<f32> {
+    if creature.check_finite().is_err() {
+        return Some(f32::MAX);
+    }
+    let masked;
+    let creature = match &training_data.term_mask {
+        Some(mask) => {
+            masked = mask.applied(creature);
+            &masked
+        },
+        None => creature,
+    };
+    output_error_within(&|_, point| training_data.creature_output(creature, point), training_data, deadline)
+        .map(|error| if error.is_nan() { f32::MAX } else { error })
+}
+
+/// Error of always predicting the mean of the (linked) target, which is a
+/// standardized output of 0, as a baseline creatures should beat.
+fn baseline_error(training_data: &TrainingData) -> f32 {
+    output_error_within(&|_, _| Some(0.0), training_data, None).expect("Scoring without a deadline cannot time out")
+}
+
+/// Error of the standardized outputs given by "output" for each data point
+/// (and its index), combining the errors of each dataset when there are several.
+/// Points without an output get the `MissingParams::Penalty` loss.
+fn output_error_within(output: &impl Fn(usize, &HashMap<String, f32>) -> Option<f32>,
+                       training_data: &TrainingData,
+                       deadline: Option<Instant>) -> Option<f32> {
+    match &training_data.datasets {
+        Some(datasets) => {
+            let errors = datasets.ranges()
+                .map(|range| range_error_within(output, training_data, range, deadline))
+                .collect::<Option<Vec<f32>>>()?;
+            Some(datasets.aggregation.combine(&errors))
+        },
+        None => range_error_within(output, training_data, 0..training_data.points.len(), deadline),
+    }
+}
+
+/// Error of "creature" on each of the datasets of "training_data"
+/// (empty unless it has several).
+pub(crate) fn dataset_errors(creature: &Creature, training_data: &TrainingData) -> Vec<f32> {
+    training_data.datasets.iter()
+        .flat_map(|datasets| datasets.ranges())
+        .map(|range| range_error_within(&|_, point| training_data.creature_output(creature, point), training_data, range, None)
+            .expect("Scoring without a deadline cannot time out"))
+        .collect()
+}
+
+/// Weighted mean of the loss across the points in "range".
+fn range_error_within(output: &impl Fn(usize, &HashMap<String, f32>) -> Option<f32>,
+                      training_data: &TrainingData,
+                      range: Range<usize>,
+                      deadline: Option<Instant>) -> Option<f32> {
+    let points = training_data.points[range.clone()].iter().zip(range.clone());
+    if let Some(custom) = &training_data.custom_loss {
+        let mut predicted = Vec::with_capacity(range.len());
+        let mut actual = Vec::with_capacity(range.len());
+        for (point, index) in points {
+            if index % TIMEOUT_CHECK_INTERVAL == 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return None;
+            }
+            // Penalized rows cannot be combined with a custom loss (see `EvolutionConfig::validate`)
+            let (prediction, target) = training_data.prediction_and_actual(index, output(index, point).unwrap_or(f32::NAN));
+            predicted.push(prediction);
+            actual.push(target);
+        }
+        let weights = training_data.weights.as_ref().map(|weights| &weights[range]);
+        return Some(custom.loss.mean_loss(&predicted, &actual, weights));
+    }
+
+    let mut total: f32 = 0.0;
+    let mut total_weight: f32 = 0.0;
+    for (point, index) in points {
+        if index % TIMEOUT_CHECK_INTERVAL == 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return None;
+        }
+        let loss = match output(index, point) {
+            Some(output) => training_data.loss.point_loss(training_data.residual(index, output)),
+            None => training_data.missing_penalty.expect("Only penalized points have no output"),
+        };
+        let weight = training_data.weights.as_ref().map_or(1.0, |weights| weights[index]);
+        total += weight * loss;
+        total_weight += weight;
+    }
+    Some(total / total_weight)
+}
+
+/// A cached creature error which no longer matches the error
+/// recalculated from the current evaluation data.
+#[derive(Debug)]
+struct CachedErrorMismatch {
+    cached: f32,
+    recalculated: f32,
+}
+
+impl fmt::Display for CachedErrorMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cached error {} does not match recalculated error {}", self.cached, self.recalculated)
+    }
+}
+
+/// Recalculate the errors of a random sample (at least one, drawn with "rng") of
+/// the creatures with a cached error and check that they still match the cached
+/// values.  A mismatch means selection has been running on stale errors.
+fn verify_cached_errors(creatures: &[Creature],
+                        training_data: &TrainingData,
+                        fraction: f32,
+                        rng: &mut StdRng) -> Result<(), CachedErrorMismatch> {
+    let sample_size = ((creatures.len() as f32 * fraction).ceil() as usize).max(1);
+    let sample = creatures.iter()
+                          // Creatures which ran out of evaluation time were given f32::MAX,
+                          // and non-finite errors cannot be compared
+                          .filter(|creature| creature.cached_error_sum.is_some_and(|error| error.is_finite() && error != f32::MAX))
+                          .choose_multiple(rng, sample_size);
+    for creature in sample {
+        let cached = creature.cached_error_sum.unwrap();
+        let recalculated = calc_error_sum(creature, training_data);
+        let scale = cached.abs().max(recalculated.abs()).max(f32::MIN_POSITIVE);
+        if (cached - recalculated).abs() / scale > SANITY_CHECK_TOLERANCE {
+            return Err(CachedErrorMismatch { cached, recalculated });
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+#[allow(clippy::excessive_precision)]
+pub(crate) mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::fs;
+    use std::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+    use itertools::izip;
+
+    fn basic_data() -> Vec<HashMap<String, f32>> {
+        vec![
+            HashMap::from([("target_param".to_string(), 5.2), ("p2".to_string(), 7.8), ("p3".to_string(), 8.3)]),
+            HashMap::from([("target_param".to_string(), 6.0), ("p2".to_string(), 4.4), ("p3".to_string(), 8.1)]),
+            HashMap::from([("target_param".to_string(), 7.1), ("p2".to_string(), 3.9), ("p3".to_string(), 9.5)]),
+            HashMap::from([("target_param".to_string(), 8.6), ("p2".to_string(), 2.7), ("p3".to_string(), 11.6)]),
+            HashMap::from([("target_param".to_string(), 9.4), ("p2".to_string(), -2.6), ("p3".to_string(), 13.0)]),
+        ]
+    }
+
+    #[test]
+    fn basic_evolution() {
+        let target = "target_param";
+        let data = basic_data();
+
+        let evo = Evolution::new(target.into(), &data, 10000, 10, 3).unwrap();
+        assert!(evo.config.num_creatures == 10000);
+        assert_eq!(evo.history().len(), 10);
+    }
+
+    #[test]
+    fn reference_points() {
+        let config = EvolutionConfig { num_creatures: 500, num_cycles: 2, ..Default::default() };
+        let evo = Evolution::with_config("target_param".into(), &basic_data(), &config).unwrap();
+        let predictions = evo.reference_predictions();
+        let labels: Vec<&str> = predictions.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, vec!["mean", "p2 at min", "p2 at max", "p3 at min", "p3 at max"]);
+
+        let p2_mean = (7.8 + 4.4 + 3.9 + 2.7 - 2.6) / 5.0;
+        let p3_mean = (8.3 + 8.1 + 9.5 + 11.6 + 13.0) / 5.0;
+        let at = |p2: f32, p3: f32| evo.predict_point(&HashMap::from([("p2".to_string(), p2), ("p3".to_string(), p3)])).unwrap();
+        assert!((predictions[0].1 - at(p2_mean, p3_mean)).abs() < 1e-3);
+        assert!((predictions[2].1 - at(7.8, p3_mean)).abs() < 1e-3);
+        assert!((predictions[3].1 - at(p2_mean, 8.1)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn sanity_check_catches_stale_cache() {
+        let target = "target_param";
+        let data = basic_data();
+        let training_data = TrainingData::new(data.clone(), target);
+        let mut creatures = Creature::create_many(200, &vec!["p2", "p3"], 3);
+        for creature in creatures.iter_mut() {
+            creature.set_cached_error(calc_error_sum(creature, &training_data));
+        }
+        // Shifting the target barely changes the relative error of creatures whose errors are huge
+        creatures.retain(|creature| creature.cached_error_sum.unwrap() < 1.0e4);
+        assert!(verify_cached_errors(&creatures, &training_data, 0.01, &mut StdRng::seed_from_u64(1)).is_ok());
+
+        // Changing the evaluation data without invalidating leaves every cached error stale
+        let shifted_data: Vec<HashMap<String, f32>> = data.iter().map(|point| {
+            let mut point = point.clone();
+            *point.get_mut(target).unwrap() += 10.0;
+            point
+        }).collect();
+        let shifted_data = TrainingData::new(shifted_data, target);
+        assert!(verify_cached_errors(&creatures, &shifted_data, 0.01, &mut StdRng::seed_from_u64(1)).is_err());
+
+        for creature in creatures.iter_mut() {
+            creature.invalidate_cache();
+            creature.set_cached_error(calc_error_sum(creature, &shifted_data));
+        }
+        assert!(verify_cached_errors(&creatures, &shifted_data, 0.01, &mut StdRng::seed_from_u64(1)).is_ok());
+    }
+
+    #[test]
+    fn evolution_with_sanity_check() {
+        let config = EvolutionConfig { num_creatures: 500, num_cycles: 3, sanity_check: true, seed: Some(3), ..Default::default() };
+        let evo = Evolution::with_config("target_param".into(), &basic_data(), &config).unwrap();
+        assert_eq!(evo.config.num_creatures, 500);
+
+        // The sampled creatures come from their own stream, so checking changes nothing else
+        let unchecked = Evolution::with_config("target_param".into(), &basic_data(), &EvolutionConfig { sanity_check: false, ..config }).unwrap();
+        assert_eq!(evo.formula(FormulaFormat::Text), unchecked.formula(FormulaFormat::Text));
+    }
+
+    #[test]
+    fn selection_criteria() {
+        let training_data = TrainingData::new(basic_data(), "target_param");
+        let mut creatures = Creature::create_many(30, &vec!["p2", "p3"], 3);
+        for (index, creature) in creatures.iter_mut().enumerate() {
+            creature.set_cached_error(1.0 + index as f32 * 0.01);
+        }
+        let (best, validation_error) = select_best(&creatures, SelectionCriterion::MinTrainError, None, 20);
+        assert_eq!(best.cached_error_sum, Some(1.0));
+        assert!(validation_error.is_none());
+
+        // A large lambda picks the simplest of the 20 lowest-error creatures
+        let tradeoff = SelectionCriterion::BestComplexityTradeoff { lambda: 100.0 };
+        let (best, _) = select_best(&creatures, tradeoff, None, 20);
+        let simplest = creatures[..20].iter().map(|creature| creature.complexity()).min().unwrap();
+        assert_eq!(best.complexity(), simplest);
+        assert!(best.cached_error_sum.unwrap() < 1.2);
+
+        let (best, validation_error) = select_best(
+            &creatures, SelectionCriterion::MinValidationError, Some(&training_data), 20
+        );
+        let min_validation = creatures[..20].iter()
+            .map(|creature| calc_error_sum(creature, &training_data))
+            .fold(f32::INFINITY, f32::min);
+        assert_eq!(validation_error, Some(min_validation));
+        assert_eq!(calc_error_sum(best, &training_data), min_validation);
+    }
+
+    #[test]
+    fn validation_selection() {
+        let data: Vec<HashMap<String, f32>> = (0..40)
+            .map(|i| HashMap::from([("x".to_string(), i as f32), ("y".to_string(), 3.0 * i as f32 - 2.0)]))
+            .collect();
+        let (validation, training): (Vec<_>, Vec<_>) = data.into_iter().enumerate()
+            .partition(|(index, _)| index % 4 == 0);
+        let config = EvolutionConfig {
+            num_creatures: 500,
+            num_cycles: 3,
+            validation_data: Some(validation.into_iter().map(|(_, point)| point).collect()),
+            selection: SelectionCriterion::MinValidationError,
+            ..Default::default()
+        };
+        let training: Vec<HashMap<String, f32>> = training.into_iter().map(|(_, point)| point).collect();
+        let evo = Evolution::with_config("y".into(), &training, &config).unwrap();
+        assert!(evo.history().iter().all(|stats| stats.validation_error.is_some()));
+        assert!(evo.history_csv().lines().all(|line| line.split(',').count() == 6));
+        assert!(evo.optimize_choice().is_some());
+    }
+
+    #[test]
+    fn validation_selection_requires_data() {
+        let config = EvolutionConfig { num_creatures: 100, selection: SelectionCriterion::MinValidationError, ..Default::default() };
+        let Err(EvolutionError::Config(error)) = Evolution::with_config("target_param".into(), &basic_data(), &config) else {
+            panic!("Selection without validation data was accepted")
+        };
+        assert_eq!(error.to_string(), "SelectionCriterion::MinValidationError requires validation_data");
+
+        // Options which cannot work are errors even without validating first
+        assert_eq!(Evolution::fit("target_param".into(), &basic_data(), &config, None, None, None).err(), Some(error));
+        let dropout = EvolutionConfig { num_creatures: 100, feature_dropout: 1.5, ..Default::default() };
+        assert!(matches!(
+            Evolution::fit("target_param".into(), &basic_data(), &dropout, None, None, None),
+            Err(ConfigError::InvalidFraction { option: "feature_dropout", .. })
+        ));
+    }
+
+    #[test]
+    fn asymmetric_custom_loss() {
+        use crate::loss::Loss;
+        struct UnderPredictionPenalty;
+        impl Loss for UnderPredictionPenalty {
+            fn point_loss(&self, predicted: f32, actual: f32) -> f32 {
+                let residual = predicted - actual;
+                if residual < 0.0 { 3.0 * residual.powi(2) } else { residual.powi(2) }
+            }
+        }
+        let data = crate::data::synthetic::linear(200, 1, 1.0, 13);
+        let config = EvolutionConfig { num_creatures: 500, num_cycles: 3, max_layers: 2, ..Default::default() };
+        let symmetric = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let asymmetric = Evolution::builder("y", &data)
+            .config(config)
+            .custom_loss("under_x3", Arc::new(UnderPredictionPenalty))
+            .build()
+            .unwrap();
+
+        let mean_residual = |evo: &Evolution| data.iter()
+            .map(|point| evo.predict_point(point).unwrap() - point["y"])
+            .sum::<f32>() / data.len() as f32;
+        // About 0.43 noise stdevs for this loss with normal noise
+        assert!(mean_residual(&asymmetric) > mean_residual(&symmetric) + 0.25,
+                "{} vs {}", mean_residual(&asymmetric), mean_residual(&symmetric));
+        assert!(asymmetric.history().iter().all(|stats| stats.loss_name == "under_x3"));
+        assert_eq!(symmetric.history()[0].loss_name, "SquaredError");
+        assert!(asymmetric.summary().contains("Loss: under_x3"));
+    }
+
+    #[test]
+    fn pareto_front() {
+        let data = crate::data::synthetic::polynomial(150, 2, 0.2, 5);
+        let config = EvolutionConfig { num_creatures: 800, num_cycles: 4, pareto_front: true, seed: Some(4), ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let front = evo.pareto_front();
+        assert!(front.len() > 1);
+        // Each more complex creature on the front has a strictly lower error
+        for pair in front.windows(2) {
+            assert!(pair[0].complexity() < pair[1].complexity());
+            assert!(pair[0].cached_error().unwrap() > pair[1].cached_error().unwrap());
+        }
+        let (_, training_data) = prepare_training_data("y", &data, &config);
+        for creature in &front {
+            let error = calc_error_sum(creature, &training_data);
+            assert!((creature.cached_error().unwrap() - error).abs() <= 1e-4 * error.abs().max(1.0));
+        }
+        assert!(Evolution::new("y".into(), &data, 200, 1, 2).unwrap().pareto_front().is_empty());
+    }
+
+    #[test]
+    fn custom_mutator() {
+        use crate::mutator::Mutator;
+        use rand::RngCore;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Deterministic mutator adding 1.0 to every "c" and counting its calls.
+        struct AddToC(AtomicUsize);
+        impl Mutator for AddToC {
+            fn mutate(&self, creature: &Creature, _rng: &mut dyn RngCore) -> Creature {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                creature.with_c_shifted(1.0)
+            }
+        }
+
+        let data: Vec<HashMap<String, f32>> = (0..20)
+            .map(|i| HashMap::from([("x".to_string(), i as f32), ("y".to_string(), 3.0 * i as f32)]))
+            .collect();
+        let mutator = Arc::new(AddToC(AtomicUsize::new(0)));
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 1, mutator: Some(mutator.clone()), ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+
+        // Both the cycle and every round of the final optimization used the mutator
+        assert!(mutator.0.load(Ordering::Relaxed) > 31 * 500);
+        let cycle_best = &evo.best_creatures[0];
+        let steps = (evo.best_creature.generation - cycle_best.generation) as f32;
+        for (optimized, original) in evo.best_creature.coefficient_values().into_iter().zip(cycle_best.coefficient_values()) {
+            assert_eq!(optimized.0, original.0 + steps);
+            assert_eq!((optimized.1, optimized.2, optimized.3), (original.1, original.2, original.3));
+        }
+    }
+
+    #[test]
+    fn predict_grid() {
+        let data: Vec<HashMap<String, f32>> = (0..30).map(|i| HashMap::from([
+            ("a".to_string(), i as f32),
+            ("b".to_string(), (i % 5) as f32),
+            ("c".to_string(), (i % 3) as f32),
+            ("y".to_string(), i as f32 + 2.0 * (i % 5) as f32),
+        ])).collect();
+        let evo = Evolution::new("y".into(), &data, 300, 2, 2).unwrap();
+
+        let grid = evo.predict_grid(&["a".to_string(), "b".to_string()], 3, true);
+        assert_eq!(grid.len(), 9);
+        let values: Vec<Vec<f32>> = grid.iter().map(|(values, _)| values.clone()).collect();
+        assert_eq!(values[..4], [vec![0.0, 0.0], vec![0.0, 2.0], vec![0.0, 4.0], vec![14.5, 0.0]]);
+        assert_eq!(values[8], vec![29.0, 4.0]);
+        let c_mean = evo.standardizer.param_standardizer("c").unwrap().mean();
+        let means = HashMap::from([("a".to_string(), 29.0), ("b".to_string(), 2.0), ("c".to_string(), c_mean)]);
+        assert_eq!(grid[7].1, evo.predict_point(&means).unwrap());
+
+        let line = evo.predict_grid(&["a".to_string()], 5, false);
+        assert_eq!(line[1], (vec![7.25], evo.predict_unchecked(&HashMap::from([("a".to_string(), 7.25)]))));
+    }
+
+    #[test]
+    fn small_population() {
+        let data = crate::data::synthetic::linear(60, 2, 0.1, 5);
+        let config = EvolutionConfig {
+            num_creatures: 8, num_cycles: 20, seed: Some(9), no_signal_cycles: None, ..Default::default()
+        };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let history = evo.history();
+        assert_eq!(history.len(), 20);
+        assert!(history[19].best_error < history[0].best_error);
+
+        let config = EvolutionConfig { num_creatures: MIN_POPULATION, num_cycles: 5, pareto_front: true, ..config };
+        assert_eq!(Evolution::with_config("y".into(), &data, &config).unwrap().history().len(), 5);
+    }
+
+    #[test]
+    fn target_r2_stops_early() {
+        let data = crate::data::synthetic::linear(120, 2, 0.3, 21);
+        let (training, validation) = data.split_at(90);
+        let config = EvolutionConfig {
+            num_creatures: 400, num_cycles: 15, validation_data: Some(validation.to_vec()), ..Default::default()
+        };
+        let evo = Evolution::builder("y", training).config(config.clone()).target_r2(0.8).build().unwrap();
+        assert_eq!(evo.target_r2_reached(), Some(true));
+        assert!(evo.history().len() < 15);
+        assert!(evo.summary().contains("Target Validation R2: 0.8 (reached)"));
+
+        // Noise keeps a perfect fit out of reach
+        let evo = Evolution::builder("y", training).config(config).num_cycles(2).target_r2(1.0).build().unwrap();
+        assert_eq!((evo.target_r2_reached(), evo.history().len()), (Some(false), 2));
+        assert_eq!(Evolution::new("y".into(), training, 200, 1, 2).unwrap().target_r2_reached(), None);
+    }
+
+    #[test]
+    fn resolved_config() {
+        let data: Vec<HashMap<String, f32>> = (0..20)
+            .map(|i| HashMap::from([("x".to_string(), i as f32), ("y".to_string(), 3.0 * i as f32)]))
+            .collect();
+        let evo = Evolution::new("y".into(), &data, 300, 2, 2).unwrap();
+        let config = evo.config();
+        assert_eq!((config.num_creatures, config.num_cycles, config.max_layers), (300, 2, 2));
+        let seed = config.seed.expect("The seed used is recorded");
+
+        let seeded = Evolution::builder("y", &data).num_creatures(300).num_cycles(1).seed(seed).build().unwrap();
+        assert_eq!(seeded.config().seed, Some(seed));
+    }
+
+    #[test]
+    fn normalized_column_names() {
+        let data: Vec<HashMap<String, f32>> = (0..30).map(|i| HashMap::from([
+            (" Width ".to_string(), i as f32),
+            ("Box Height".to_string(), (i % 5) as f32),
+            ("Area".to_string(), 2.0 * i as f32 + (i % 5) as f32),
+        ])).collect();
+        let config = EvolutionConfig {
+            num_creatures: 300, num_cycles: 2, column_names: ColumnNames::Normalized, ..Default::default()
+        };
+        let evo = Evolution::with_config("AREA".into(), &data, &config).unwrap();
+        assert_eq!(evo.target, "area");
+        assert_eq!(evo.standardizer.parameters(), vec!["area", "box_height", "width"]);
+
+        let raw = HashMap::from([(" Width ".to_string(), 7.0), ("Box Height".to_string(), 2.0)]);
+        let renamed = HashMap::from([("width".to_string(), 7.0), ("BOX   height ".to_string(), 2.0)]);
+        let prediction = evo.predict_point(&raw).unwrap();
+        assert_eq!(evo.predict_point(&renamed).unwrap(), prediction);
+        let predictor = evo.predictor();
+        assert_eq!(predictor.predict_point(&raw), prediction);
+        assert_eq!(predictor.predict_point(&renamed), prediction);
+        assert_eq!(predictor.sweep(" WIDTH", &[7.0], &renamed), vec![prediction]);
+
+        // Points whose names collide once normalized fail or predict NaN rather than panicking
+        let colliding = HashMap::from([(" Width ".to_string(), 7.0), ("width".to_string(), 8.0), ("Box Height".to_string(), 2.0)]);
+        let collision = PredictError::ColumnCollision {
+            normalized: "width".to_string(), columns: vec![" Width ".to_string(), "width".to_string()],
+        };
+        assert_eq!(evo.predict_point(&colliding), Err(collision.clone()));
+        assert_eq!(evo.predict_many(&[raw, colliding.clone()]), Err(collision.clone()));
+        assert_eq!(evo.trace(&colliding).unwrap_err(), collision);
+        assert!(evo.predict(std::slice::from_ref(&colliding))[0].is_nan());
+        assert!(predictor.predict_point(&colliding).is_nan());
+        assert!(predictor.sweep("width", &[1.0, 2.0], &colliding).iter().all(|prediction| prediction.is_nan()));
+    }
+
+    #[test]
+    fn report_metrics() {
+        use crate::metrics::Metric;
+        let data: Vec<HashMap<String, f32>> = (0..40)
+            .map(|i| HashMap::from([("x".to_string(), i as f32), ("y".to_string(), (i as f32 * 0.3).sin() + 0.1 * i as f32)]))
+            .collect();
+        let (training, validation) = data.split_at(30);
+        let report_metrics = vec![Metric::MeanSquaredError, Metric::MeanAbsoluteError, Metric::RSquared];
+        let config = EvolutionConfig {
+            num_creatures: 500,
+            num_cycles: 3,
+            validation_data: Some(validation.to_vec()),
+            report_metrics: report_metrics.clone(),
+            ..Default::default()
+        };
+        let evo = Evolution::with_config("y".into(), training, &config).unwrap();
+
+        let predict = |creature: &Creature, point: &HashMap<String, f32>| {
+            let output = creature.calculate(&evo.standardizer.standardized_value(point));
+            evo.standardizer.unstandardize_value("y", output)
+        };
+        let check = |values: &[(Metric, f32)], creature: &Creature, points: &[HashMap<String, f32>]| {
+            let predictions: Vec<f32> = points.iter().map(|point| predict(creature, point)).collect();
+            let actuals: Vec<f32> = points.iter().map(|point| point["y"]).collect();
+            assert_eq!(values.iter().map(|(metric, _)| *metric).collect::<Vec<_>>(), report_metrics);
+            for (metric, value) in values {
+                let expected = metric.compute(&predictions, &actuals);
+                assert!((value - expected).abs() <= 1e-4 * expected.abs().max(1.0), "{:?}: {} vs {}", metric, value, expected);
+            }
+        };
+        for (stats, creature) in evo.history().iter().zip(&evo.best_creatures) {
+            check(&stats.metrics.train, creature, training);
+            check(&stats.metrics.validation, creature, validation);
+        }
+        check(&evo.metrics().train, &evo.best_creature, training);
+        check(&evo.metrics().validation, &evo.best_creature, validation);
+
+        // The selection loss is unaffected and the metrics appear in the summary and history
+        assert!(evo.history().iter().all(|stats| stats.best_error.is_finite()));
+        assert!(evo.summary().contains("R2"));
+        assert!(evo.history_csv().starts_with("cycle,best_error,median_error,best_generation,huber_delta,validation_error,train_mse,validation_mse,"));
+        assert!(evo.history_csv().lines().all(|line| line.split(',').count() == 12));
+    }
+
+    #[test]
+    fn cycle_ensemble_prediction() {
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 4, ..Default::default() };
+        let mut evo = Evolution::with_config("target_param".into(), &basic_data(), &config).unwrap();
+        let point = HashMap::from([("p2".to_string(), 3.0), ("p3".to_string(), 9.0)]);
+        let predictions: Vec<f32> = evo.best_creatures.iter().map(|creature| {
+            let output = creature.calculate(&evo.standardizer.standardized_value(&point));
+            evo.standardizer.unstandardize_value("target_param", output)
+        }).collect();
+        let mean = predictions.iter().sum::<f32>() / 4.0;
+        let spread = (predictions.iter().map(|p| (p - mean).powi(2)).sum::<f32>() / 4.0).sqrt();
+        let (ensemble_mean, ensemble_spread) = evo.cycle_ensemble_prediction(&point);
+        assert!((ensemble_mean - mean).abs() <= 1e-4 * mean.abs().max(1.0));
+        assert!((ensemble_spread - spread).abs() <= 1e-4 * spread.max(1.0));
+
+        evo.best_creatures.clear();
+        assert_eq!(evo.cycle_ensemble_prediction(&point), (evo.predict_point(&point).unwrap(), 0.0));
+    }
+
+    #[test]
+    fn data_informed_creation() {
+        let data = crate::data::synthetic::linear(200, 1, 0.1, 5);
+        let correlations = feature_correlations("y", &data, Link::Identity);
+        assert!(correlations.values().all(|correlation| *correlation > 0.0));
+
+        // The median error of the initial population, as recorded for cycle 1
+        let config = EvolutionConfig::default();
+        let (_, training_data) = prepare_training_data("y", &data, &config);
+        let params = vec!["x1"];
+        let median_error = |strength: f64| {
+            let options = CreationOptions {
+                data_informed_strength: strength, feature_correlations: Some(correlations.clone()), ..Default::default()
+            };
+            let mut creatures = Creature::create_many_parallel_with(3000, &params, 3, &options);
+            score_creatures(&mut creatures, &training_data, None, None);
+            error_results(&creatures).1
+        };
+        let (random, informed) = (median_error(0.0), median_error(1.0));
+        println!("Cycle 1 median error: random {} vs data-informed {}", random, informed);
+        assert!(informed < random);
+
+        let config = EvolutionConfig {
+            num_creatures: 200,
+            num_cycles: 1,
+            creation: CreationOptions { data_informed_strength: 0.5, ..Default::default() },
+            ..Default::default()
+        };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        assert_eq!(evo.config.creation.feature_correlations, Some(correlations));
+    }
+
+    #[test]
+    fn feature_dropout() {
+        let params = vec!["a", "b", "c", "d", "e"];
+        let choose = |seed| dropped_features(&params, 0.4, &mut StdRng::seed_from_u64(seed));
+        assert_eq!(choose(3).len(), 2);
+        assert_eq!(choose(3), choose(3));
+        assert_eq!(dropped_features(&params[..1], 0.9, &mut StdRng::seed_from_u64(3)).len(), 0);
+
+        let training_data = TrainingData::new(basic_data(), "target_param");
+        let dropped = training_data.with_dropped_features(&["p2"]);
+        assert!(dropped.points.iter().all(|point| point["p2"] == 0.0));
+        assert_eq!(dropped.points[0]["p3"], training_data.points[0]["p3"]);
+
+        let config = EvolutionConfig {
+            num_creatures: 500, num_cycles: 3, feature_dropout: 0.5, seed: Some(1), sanity_check: true, ..Default::default()
+        };
+        let data = basic_data();
+        let evo = Evolution::with_config("target_param".into(), &data, &config).unwrap();
+        // The cycle bests are re-scored against the full data
+        let (_, full_data) = prepare_training_data("target_param", &data, &config);
+        for creature in &evo.best_creatures {
+            let error = calc_error_sum(creature, &full_data);
+            assert!((creature.cached_error_sum.unwrap() - error).abs() <= 1e-5 * error.abs().max(1.0));
+        }
+    }
+
+    #[test]
+    fn term_dropout() {
+        let creature = Creature::single_term("x1", 2.0, 1);
+        assert_eq!(creature.masked(0.0, &mut StdRng::seed_from_u64(1)).coefficient_values(), creature.coefficient_values());
+        assert_eq!(creature.masked(1.0, &mut StdRng::seed_from_u64(1)).coefficient_values()[0].0, 0.0);
+
+        // Small, noisy data on which unmasked creatures overfit by cancelling terms
+        let data = crate::data::synthetic::linear(40, 3, 3.0, 2);
+        let validation = crate::data::synthetic::linear(400, 3, 3.0, 3);
+        let config = |term_dropout, seed| EvolutionConfig {
+            num_creatures: 300, num_cycles: 6, term_dropout, seed: Some(seed), ..Default::default()
+        };
+        let errors = |term_dropout: f32| -> Vec<f32> {
+            (0..6).map(|seed| Evolution::with_config("y".into(), &data, &config(term_dropout, seed)).unwrap().stats(&validation).root_mean_squared_error)
+                .collect()
+        };
+        let variance = |errors: &[f32]| {
+            let mean = errors.iter().sum::<f32>() / errors.len() as f32;
+            errors.iter().map(|error| (error - mean).powi(2)).sum::<f32>() / errors.len() as f32
+        };
+        assert!(variance(&errors(0.1)) < variance(&errors(0.0)));
+
+        // The cycle bests are re-scored and predictions made with every term
+        let evo = Evolution::with_config("y".into(), &data, &config(0.1, 0)).unwrap();
+        let (_, full_data) = prepare_training_data("y", &data, &config(0.1, 0));
+        for creature in &evo.best_creatures {
+            assert_eq!(creature.cached_error_sum, Some(calc_error_sum(creature, &full_data)));
+        }
+        for point in &validation[..20] {
+            assert_eq!(evo.trace(point).unwrap().prediction(), Some(evo.predict_point(point).unwrap()));
+        }
+    }
+
+    #[test]
+    fn quantized_screening() {
+        let data = crate::data::synthetic::polynomial(300, 3, 0.2, 8);
+        let config = EvolutionConfig { num_creatures: 2000, quantized_screening: true, seed: Some(4), ..Default::default() };
+        let (_, training_data) = prepare_training_data("y", &data, &config);
+        let params = vec!["x1", "x2", "x3"];
+        let creatures = Creature::create_many_seeded(2000, &params, 3, &config.creation, Samplers::standard(), SeedStreams::new(4).next(), 0);
+
+        let quantized = training_data.quantized();
+        let top_decile = |errors: Vec<f32>| -> std::collections::HashSet<usize> {
+            let mut ranked: Vec<usize> = (0..errors.len()).collect();
+            ranked.sort_by(|a, b| errors[*a].total_cmp(&errors[*b]));
+            ranked.into_iter().take(errors.len() / 10).collect()
+        };
+        let exact = top_decile(creatures.iter().map(|creature| calc_error_sum(creature, &training_data)).collect());
+        let screened = top_decile(creatures.iter().map(|creature| screening_error(creature, &training_data, &quantized, &[])).collect());
+        let overlap = exact.intersection(&screened).count() as f32 / exact.len() as f32;
+        assert!(overlap > 0.9, "Top decile overlap of {} between exact and quantized screening", overlap);
+
+        // Promoted creatures get full precision errors and the rest get culled
+        let mut population = creatures.clone();
+        screen_creatures(&mut population, &training_data, &quantized, &[]);
+        let promoted = population.iter().filter(|creature| creature.cached_error_sum.is_none()).count();
+        assert_eq!(promoted, 1200);
+        assert!(population.iter().all(|creature| creature.cached_error_sum.is_none_or(|error| error == f32::MAX)));
+
+        let small = EvolutionConfig { num_creatures: 500, num_cycles: 3, sanity_check: true, ..config.clone() };
+        let evo = Evolution::with_config("y".into(), &data, &small).unwrap();
+        for creature in &evo.best_creatures {
+            let error = calc_error_sum(creature, &training_data);
+            assert!((creature.cached_error_sum.unwrap() - error).abs() <= 1e-5 * error.abs().max(1.0));
+        }
+        let conflicting = EvolutionConfig { pareto_front: true, ..small.clone() };
+        assert!(conflicting.validate("y", &data).is_err());
+
+        // Screening culls most new creatures before they reach the full precision data
+        let full_precision_outputs = |config: &EvolutionConfig| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+            pool.install(|| {
+                FULL_PRECISION_OUTPUTS.with(|count| count.set(0));
+                Evolution::with_config("y".into(), &data, config).unwrap();
+                FULL_PRECISION_OUTPUTS.with(|count| count.get())
+            })
+        };
+        let unscreened = EvolutionConfig { quantized_screening: false, ..small.clone() };
+        let (screened_outputs, unscreened_outputs) = (full_precision_outputs(&small), full_precision_outputs(&unscreened));
+        assert!(screened_outputs < unscreened_outputs, "{} screened vs {} unscreened outputs", screened_outputs, unscreened_outputs);
+
+        // Rows missing inputs are screened as they are scored
+        let sparse: Vec<HashMap<String, f32>> = data.iter().enumerate()
+            .map(|(index, point)| {
+                let mut point = point.clone();
+                if index % 4 == 0 {
+                    point.remove(["x1", "x2", "x3"][index % 3]);
+                }
+                point
+            })
+            .collect();
+        for missing_params in [MissingParams::Skip, MissingParams::Penalty(4.0)] {
+            let config = EvolutionConfig { missing_params, feature_dropout: 0.3, ..small.clone() };
+            let (_, training_data) = prepare_training_data("y", &sparse, &config);
+            let quantized = training_data.quantized();
+            for creature in creatures.iter().take(200) {
+                let (screened, exact) = (screening_error(creature, &training_data, &quantized, &[]), calc_error_sum(creature, &training_data));
+                assert!((screened - exact).abs() <= 1e-2 * exact.abs().max(1.0), "{} screened vs {} exact", screened, exact);
+            }
+            assert!(Evolution::with_config("y".into(), &sparse, &config).is_ok());
+        }
+    }
+
+    #[test]
+    fn optimize_never_increases_error() {
+        // An inflated (stale) cached error makes regressing mutants look like improvements
+        let training_data = TrainingData::new(basic_data(), "target_param");
+        for _ in 0..5 {
+            let mut creature = Creature::new(&vec!["p2", "p3"], 3);
+            let true_error = calc_error_sum(&creature, &training_data);
+            creature.set_cached_error(true_error * 100.0 + 100.0);
+            let (optimized, choice, _) = optimize_creature(
+                &creature, &training_data, None, 3, &MutationOptions::default(), None, Samplers::standard(), MinImprovement::Absolute(0.0),
+                SeedStreams::new(1).next(), None, None
+            );
+            let optimized_error = calc_error_sum(&optimized, &training_data);
+            assert!(optimized_error <= true_error);
+            assert_eq!(optimized.cached_error_sum, Some(optimized_error));
+            if choice == OptimizeChoice::Original {
+                assert_eq!(optimized_error, true_error);
+            }
+        }
+    }
+
+    #[test]
+    fn optimize_compares_on_validation() {
+        // With the target flipped in the validation data, improving on the
+        // training data usually makes the validation error worse
+        let data = basic_data();
+        let training_data = TrainingData::new(data.clone(), "target_param");
+        let flipped: Vec<HashMap<String, f32>> = data.iter().map(|point| {
+            let mut point = point.clone();
+            *point.get_mut("target_param").unwrap() *= -1.0;
+            point
+        }).collect();
+        let validation_data = TrainingData::new(flipped, "target_param");
+        for _ in 0..5 {
+            let creature = Creature::new(&vec!["p2", "p3"], 3);
+            let (optimized, choice, _) = optimize_creature(
+                &creature, &training_data, Some(&validation_data), 3, &MutationOptions::default(), None, Samplers::standard(), MinImprovement::Absolute(0.0),
+                SeedStreams::new(1).next(), None, None
+            );
+            assert!(calc_error_sum(&optimized, &training_data) <= calc_error_sum(&creature, &training_data));
+            assert!(calc_error_sum(&optimized, &validation_data) <= calc_error_sum(&creature, &validation_data));
+            if choice == OptimizeChoice::Original {
+                assert_eq!(calc_error_sum(&optimized, &training_data), calc_error_sum(&creature, &training_data));
+            }
+        }
+    }
+
+    #[test]
+    fn min_improvement_limits_updates() {
+        assert!(MinImprovement::Absolute(0.0).accepts(1.0, 0.999));
+        assert!(!MinImprovement::Absolute(0.0).accepts(1.0, 1.0));
+        assert!(!MinImprovement::Relative(0.01).accepts(2.0, 1.99));
+        assert!(MinImprovement::Relative(0.01).accepts(2.0, 1.97));
+
+        let data = crate::data::synthetic::linear(100, 1, 0.5, 3);
+        let (_, training_data) = prepare_training_data("y", &data, &EvolutionConfig::default());
+        let creature = Creature::single_term("x1", 0.3, 1);
+        // Each accepted mutant is one generation past the creature it replaced
+        let updates = |min_improvement| {
+            let (optimized, _, _) = optimize_creature(
+                &creature, &training_data, None, 30, &MutationOptions::default(), None, Samplers::standard(), min_improvement, SeedStreams::new(1).next(), None, None
+            );
+            optimized.generation - creature.generation
+        };
+        let (every_gain, significant_gains) = (updates(MinImprovement::Absolute(0.0)), updates(MinImprovement::Relative(0.02)));
+        println!("Best creature updates: {} without a threshold, {} with", every_gain, significant_gains);
+        assert!(significant_gains < every_gain);
+    }
+
+    /// Counts bytes allocated by threads flagged with PROBED, so allocations made
+    /// by other tests running at the same time are not included.
+    struct ProbeAllocator;
+
+    static CURRENT_BYTES: AtomicIsize = AtomicIsize::new(0);
+    static PEAK_BYTES: AtomicIsize = AtomicIsize::new(0);
+
+    thread_local! {
+        static PROBED: Cell<bool> = const { Cell::new(false) };
+        /// Creature outputs calculated on the full precision data by this thread.
+        pub(super) static FULL_PRECISION_OUTPUTS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    fn probed() -> bool {
+        PROBED.try_with(|probed| probed.get()).unwrap_or(false)
+    }
+
+    unsafe impl GlobalAlloc for ProbeAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() && probed() {
+                let current = CURRENT_BYTES.fetch_add(layout.size() as isize, Ordering::SeqCst) + layout.size() as isize;
+                PEAK_BYTES.fetch_max(current, Ordering::SeqCst);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            if probed() {
+                CURRENT_BYTES.fetch_sub(layout.size() as isize, Ordering::SeqCst);
+            }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: ProbeAllocator = ProbeAllocator;
+
+    /// Approximate peak heap usage while running "f" in its own pool of "threads" threads.
+    fn peak_allocation(threads: usize, f: impl FnOnce() + Send) -> isize {
+        // The counters are shared, so only one test may probe at a time
+        static PROBE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let _lock = PROBE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .start_handler(|_| PROBED.with(|probed| probed.set(true)))
+            .build()
+            .unwrap();
+        CURRENT_BYTES.store(0, Ordering::SeqCst);
+        PEAK_BYTES.store(0, Ordering::SeqCst);
+        pool.install(f);
+        PEAK_BYTES.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn chunked_initial_population() {
+        let data = basic_data();
+        let config = EvolutionConfig::default();
+        let (_, training_data) = prepare_training_data("target_param", &data, &config);
+        let params = vec!["p2", "p3"];
+
+        let mut full_stats = (0.0, 0.0);
+        let full_peak = peak_allocation(2, || {
+            let mut creatures = Creature::create_many_parallel_with(20_000, &params, 3, &config.creation);
+            score_creatures(&mut creatures, &training_data, None, None);
+            full_stats = error_results(&creatures);
+        });
+        let mut chunked_stats = (0.0, 0.0);
+        let chunked_peak = peak_allocation(2, || {
+            let (kept, errors) = stream_initial_population(20_000, &params, 3, &config, Samplers::standard(), &training_data, 1000, SeedStreams::new(1).next());
+            assert_eq!(errors.len(), 20_000);
+            assert_eq!(kept.len(), 10_000);
+            chunked_stats = error_summary(errors.clone());
+            // Every creature which survives the first cull was kept
+            let survivors = errors.iter().filter(|error| **error < chunked_stats.1).count();
+            assert_eq!(kept.iter().filter(|creature| creature.cached_error_sum.unwrap() < chunked_stats.1).count(), survivors);
+        });
+        println!("Peak bytes: {} whole population vs {} chunked", full_peak, chunked_peak);
+        println!("(min, median) errors: {:?} vs {:?}", full_stats, chunked_stats);
+        assert!(chunked_peak * 4 < full_peak * 3);
+        assert!((chunked_stats.1 - full_stats.1).abs() < 0.2 * full_stats.1);
+
+        let config = EvolutionConfig { num_creatures: 1000, num_cycles: 3, max_concurrent_evaluations: Some(300), ..config };
+        let evo = Evolution::with_config("target_param".into(), &data, &config).unwrap();
+        assert_eq!(evo.history().len(), 3);
+    }
+
+    #[test]
+    fn evaluation_timeout() {
+        let training_data = TrainingData::new(basic_data(), "target_param");
+        let mut creatures = Creature::create_many(50, &vec!["p2", "p3"], 3);
+        score_creatures(&mut creatures, &training_data, None, Some(Duration::ZERO));
+        assert!(creatures.iter().all(|creature| creature.cached_error_sum == Some(f32::MAX)));
+
+        creatures.iter_mut().for_each(|creature| creature.invalidate_cache());
+        score_creatures(&mut creatures, &training_data, Some(7), Some(Duration::from_secs(60)));
+        for creature in &creatures {
+            assert_eq!(creature.cached_error_sum, Some(calc_error_sum(creature, &training_data)));
+        }
+
+        let config = EvolutionConfig {
+            num_creatures: 500, num_cycles: 2, evaluation_timeout: Some(Duration::from_secs(60)), ..Default::default()
+        };
+        let evo = Evolution::with_config("target_param".into(), &basic_data(), &config).unwrap();
+        assert!(evo.history().iter().all(|stats| stats.best_error < f32::MAX));
+    }
+
+    #[test]
+    fn weighted_error_matches_duplicated_points() {
+        let data = basic_data();
+        // Overflowing creatures have infinite errors which cannot be compared
+        let creature = std::iter::repeat_with(|| Creature::new(&vec!["p2", "p3"], 3))
+            .find(|creature| calc_error_sum(creature, &TrainingData::new(data.clone(), "target_param")).is_finite())
+            .unwrap();
+        for loss in [LossFunction::SquaredError, LossFunction::AbsoluteError, LossFunction::Huber { delta: 0.5 }] {
+            let mut duplicated = data.clone();
+            duplicated.push(data[0].clone());
+            let mut duplicated_data = TrainingData::new(duplicated, "target_param");
+            duplicated_data.loss = loss;
+
+            let mut weighted_data = TrainingData::new(data.clone(), "target_param");
+            weighted_data.loss = loss;
+            weighted_data.weights = Some(vec![2.0, 1.0, 1.0, 1.0, 1.0]);
+
+            let duplicated_error = calc_error_sum(&creature, &duplicated_data);
+            let weighted_error = calc_error_sum(&creature, &weighted_data);
+            assert!((duplicated_error - weighted_error).abs() <= 1e-5 * duplicated_error.abs().max(1.0));
+        }
+    }
+
+    #[test]
+    fn huber_auto_on_contaminated_data() {
+        // y = 2x + 1 with small noise, except every 8th point is a large outlier
+        let data: Vec<HashMap<String, f32>> = (0..48).map(|i| {
+            let x = i as f32 / 4.0;
+            let noise = ((i * 7) % 11) as f32 * 0.3 - 1.5;
+            let outlier = if i % 8 == 3 { 40.0 } else { 0.0 };
+            HashMap::from([("x".to_string(), x), ("y".to_string(), 2.0 * x + 1.0 + noise + outlier)])
+        }).collect();
+        let clean_error = |evo: &Evolution| -> f32 {
+            data.iter()
+                .map(|point| (evo.predict_point(point).unwrap() - (2.0 * point["x"] + 1.0)).abs())
+                .sum::<f32>() / data.len() as f32
+        };
+
+        let config = EvolutionConfig { num_creatures: 2000, num_cycles: 8, ..Default::default() };
+        let tuned = Evolution::with_config("y".into(), &data, &EvolutionConfig {
+            loss: LossFunction::Huber { delta: 0.1 }, ..config.clone()
+        }).unwrap();
+        let auto = Evolution::with_config("y".into(), &data, &EvolutionConfig {
+            loss: LossFunction::HuberAuto, ..config
+        }).unwrap();
+        println!("Tuned: {}   Auto: {}", clean_error(&tuned), clean_error(&auto));
+        assert!(clean_error(&auto) < 2.0 * clean_error(&tuned));
+
+        let deltas: Vec<f32> = auto.history().iter().map(|stats| stats.huber_delta.unwrap()).collect();
+        println!("Deltas: {:?}", deltas);
+        let last = deltas[deltas.len() - 1];
+        let previous = deltas[deltas.len() - 3];
+        assert!(((last - previous) / previous).abs() < 0.25);
+        assert!(tuned.history().iter().all(|stats| stats.huber_delta.is_none()));
+    }
+
+    #[test]
+    fn absolute_error_loss() {
+        // y = 2x + 1, except every 8th point is a large outlier
+        let data: Vec<HashMap<String, f32>> = (0..48).map(|i| {
+            let x = i as f32 / 4.0;
+            let outlier = if i % 8 == 3 { 40.0 } else { 0.0 };
+            HashMap::from([("x".to_string(), x), ("y".to_string(), 2.0 * x + 1.0 + outlier)])
+        }).collect();
+        let clean_error = |evo: &Evolution| -> f32 {
+            data.iter()
+                .map(|point| (evo.predict_point(point).unwrap() - (2.0 * point["x"] + 1.0)).abs())
+                .sum::<f32>() / data.len() as f32
+        };
+        let config = EvolutionConfig { num_creatures: 2000, num_cycles: 8, seed: Some(3), ..Default::default() };
+        let squared = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let absolute = Evolution::with_config("y".into(), &data, &EvolutionConfig { loss: LossFunction::AbsoluteError, ..config }).unwrap();
+        println!("Squared: {}   Absolute: {}", clean_error(&squared), clean_error(&absolute));
+        assert!(clean_error(&absolute) < clean_error(&squared));
+        assert!(absolute.history().iter().all(|stats| stats.loss_name == "AbsoluteError"));
+
+        let invalid = EvolutionConfig { loss: LossFunction::Huber { delta: 0.0 }, ..Default::default() };
+        assert!(invalid.validate("y", &data).is_err());
+    }
+
+    #[test]
+    fn silent_training() {
+        // Test output is captured in process, so the training runs in a child
+        // process of this test which prints markers around it
+        const CHILD: &str = "REVOGRESSION_SILENT_CHILD";
+        if std::env::var_os(CHILD).is_some() {
+            let data = crate::data::synthetic::linear(30, 2, 0.1, 4);
+            println!("<<<");
+            Evolution::builder(crate::data::synthetic::TARGET, &data)
+                .num_creatures(300)
+                .num_cycles(4)
+                .patience(1, MinImprovement::Absolute(f32::MAX))
+                .verbosity(Verbosity::Silent)
+                .build()
+                .unwrap();
+            println!(">>>");
+            return;
+        }
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "evolution::tests::silent_training", "--nocapture", "--test-threads=1"])
+            .env(CHILD, "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let (_, rest) = stdout.split_once("<<<\n").expect("The child test did not run");
+        let (training, _) = rest.split_once(">>>").unwrap();
+        assert_eq!(training, "");
+    }
+
+    #[test]
+    fn log_link() {
+        let data: Vec<HashMap<String, f32>> = (-12..=12)
+            .map(|i| {
+                let x = i as f32 * 0.5;
+                HashMap::from([("x".to_string(), x), ("y".to_string(), (0.5 * x).exp())])
+            })
+            .collect();
+        let mean_squared_error = |evo: &Evolution| -> f32 {
+            data.iter()
+                .map(|point| (evo.predict_point(point).unwrap() - point["y"]).powi(2))
+                .sum::<f32>() / data.len() as f32
+        };
+
+        let config = EvolutionConfig { num_creatures: 2000, num_cycles: 5, ..Default::default() };
+        let identity = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let log_config = EvolutionConfig { link: Link::Log, ..config.clone() };
+        let log = Evolution::with_config("y".into(), &data, &log_config).unwrap();
+        let response_config = EvolutionConfig { error_scale: ErrorScale::Response, ..log_config };
+        let log_response = Evolution::with_config("y".into(), &data, &response_config).unwrap();
+
+        for x in -40..=40 {
+            let point = HashMap::from([("x".to_string(), x as f32 * 0.25)]);
+            assert!(log.predict_point(&point).unwrap() > 0.0);
+            assert!(log_response.predict_point(&point).unwrap() > 0.0);
+        }
+        assert!(mean_squared_error(&log) < mean_squared_error(&identity));
+    }
+
+    #[test]
+    fn bounded_logit_link() {
+        // A percentage target that reaches both of its hard limits
+        let data: Vec<HashMap<String, f32>> = (-20..=20)
+            .map(|i| {
+                let x = i as f32 * 0.5;
+                let y = (50.0 + 12.0 * x).clamp(0.0, 100.0);
+                HashMap::from([("x".to_string(), x), ("y".to_string(), y)])
+            })
+            .collect();
+        let link = Link::BoundedLogit { min: 0.0, max: 100.0 };
+        let config = EvolutionConfig { num_creatures: 1000, num_cycles: 3, link, ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        for x in -200..=200 {
+            let point = HashMap::from([("x".to_string(), x as f32 * 5.0)]);
+            assert!((0.0..=100.0).contains(&evo.predict_point(&point).unwrap()));
+        }
+        assert!(evo.formula(FormulaFormat::Text).unwrap().contains("100"));
+
+        let invalid = EvolutionConfig { link: Link::BoundedLogit { min: 1.0, max: 0.0 }, ..config };
+        assert!(matches!(invalid.validate("y", &data), Err(ConfigError::Conflict(_))));
+    }
+
+    /// Noisy y = x1^2 points shared by tests in several modules.
+    pub(crate) fn parabola_data() -> Vec<HashMap<String, f32>> {
+        crate::data::synthetic::polynomial_1d(41, &[0.0, 0.0, 1.0], 0.5, 1)
+    }
+
+    #[test]
+    fn no_signal() {
+        let mut rng = StdRng::seed_from_u64(17);
+        let noise: Vec<HashMap<String, f32>> = (0..500).map(|_| HashMap::from([
+            ("x".to_string(), rng.gen_range(-5.0..5.0)),
+            ("z".to_string(), rng.gen_range(0.0..1.0)),
+            ("y".to_string(), rng.gen_range(-1.0..1.0)),
+        ])).collect();
+        let config = EvolutionConfig { num_creatures: 1000, num_cycles: 10, ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &noise, &config).unwrap();
+        let Some(Outcome::NoSignal { baseline_error, best_error, cycles }) = evo.outcome() else {
+            panic!("Expected no signal, got {:?}", evo.outcome())
+        };
+        assert_eq!((cycles, evo.history().len()), (3, 3));
+        // The mean baseline is the variance of the standardized target
+        assert!((baseline_error - 1.0).abs() < 1e-2);
+        assert!(best_error > 0.99 * baseline_error);
+        assert_eq!(best_error, evo.history()[2].best_error);
+        assert!(evo.summary().contains("No Signal: best error of"));
+
+        // Opting out runs every cycle
+        let config = EvolutionConfig { num_cycles: 4, no_signal_cycles: None, ..config };
+        let evo = Evolution::with_config("y".into(), &noise, &config).unwrap();
+        assert_eq!((evo.outcome(), evo.history().len()), (Some(Outcome::Completed), 4));
+    }
+
+    #[test]
+    fn stop_at_error() {
+        let data = crate::data::synthetic::linear(100, 1, 0.1, 4);
+        let config = EvolutionConfig { num_creatures: 500, num_cycles: 20, stop_at_error: Some(0.5), seed: Some(2), ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let Some(Outcome::ErrorReached { error, cycles }) = evo.outcome() else {
+            panic!("Expected the error to be reached, got {:?}", evo.outcome())
+        };
+        assert!(cycles < 20);
+        assert_eq!(evo.history().len(), cycles as usize);
+        assert!(error < 0.5);
+        assert!(evo.history()[..cycles as usize - 1].iter().all(|stats| stats.best_error >= 0.5));
+        // The final optimization still ran
+        assert!(evo.history().last().unwrap().optimize.is_some());
+        assert!(evo.best_creature.cached_error_sum.unwrap() <= error);
+        assert!(evo.summary().contains(&format!("Stopped Early: best error of {} reached after {} cycles", error, cycles)));
+
+        let config = EvolutionConfig { stop_at_error: Some(-1.0), ..config };
+        assert_eq!(config.validate("y", &data), Err(ConfigError::Conflict("stop_at_error of -1 must not be negative".to_string())));
+    }
+
+    #[test]
+    fn patience() {
+        let data = crate::data::synthetic::linear(60, 1, 0.5, 9);
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 40, patience: Some(2), no_signal_cycles: None, seed: Some(4), ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let Some(Outcome::Converged { best_error, cycles }) = evo.outcome() else {
+            panic!("Expected convergence, got {:?}", evo.outcome())
+        };
+        let history = evo.history();
+        assert!(cycles < 40);
+        assert_eq!(history.len(), cycles as usize);
+        assert_eq!(evo.cycles_run(), cycles);
+        assert_eq!(history.iter().filter(|stats| stats.stopped_early).count(), 1);
+        assert!(history.last().unwrap().stopped_early);
+        // The last two cycles did not improve on the earlier best by more than 0.1%
+        let earlier_best = history[..history.len() - 2].iter().map(|stats| stats.best_error).fold(f32::INFINITY, f32::min);
+        assert!(history[history.len() - 2..].iter().all(|stats| stats.best_error >= earlier_best * 0.999));
+        assert_eq!(best_error, history.iter().map(|stats| stats.best_error).fold(f32::INFINITY, f32::min));
+        assert!(evo.summary().contains("Converged: best error of"));
+
+        // Stopping at the last cycle is not early
+        let config = EvolutionConfig { num_cycles: cycles, ..config };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        assert!(evo.history().iter().all(|stats| !stats.stopped_early));
+    }
+
+    #[test]
+    fn non_finite_coefficient_exports() {
+        let data = parabola_data();
+        let mut evo = Evolution::with_config("y".into(), &data, &EvolutionConfig { num_creatures: 300, num_cycles: 1, ..Default::default() }).unwrap();
+        // Constants of a single term creature are c, b, z and then the layer bias
+        evo.best_creature = Creature::single_term("x1", 1.0, 1).with_constants(&[1.0, 1.0, f32::NAN, 0.0]);
+        let expected = ExportError::NonFiniteCoefficient { layer: 1, param: "x1".to_string() };
+
+        for format in [FormulaFormat::Text, FormulaFormat::Latex, FormulaFormat::Sql] {
+            assert_eq!(evo.formula(format), Err(expected.clone()));
+            assert_eq!(evo.formula_with_precision(format, Precision::Decimals(3)), Err(expected.clone()));
+        }
+        assert_eq!(evo.best_dot(), Err(expected.clone()));
+        assert!(!evo.best_creature.to_string().contains("NaN"));
+        assert!(!evo.summary().contains("NaN"));
+        #[cfg(feature = "serde")]
+        {
+            let path = std::env::temp_dir().join("revogression_non_finite_bundle");
+            let _ = fs::remove_dir_all(&path);
+            assert!(matches!(evo.export_bundle(&path), Err(crate::BundleError::Export(err)) if err == expected));
+            assert!(!path.exists());
+        }
+
+        // Such a creature is never the best of a population
+        let training_data = TrainingData::new(evo.standardizer.standardized_values(&data), "y");
+        assert_eq!(calc_error_sum(&evo.best_creature, &training_data), f32::MAX);
+    }
+
+    #[test]
+    fn training_budget() {
+        let data = parabola_data();
+        // The 300 creatures of the only cycle leave room for 5 optimization iterations of 500 mutants
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 1, max_evaluations: Some(3000), seed: Some(2), ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        assert_eq!(evo.outcome(), Some(Outcome::Completed));
+        assert_eq!(evo.history()[0].optimize, Some(OptimizeProgress { iterations: 5, planned: 31 }));
+        assert!(evo.summary().contains("Optimization: budget allowed 5 of 31 iterations"));
+        assert!(evo.predict_point(&HashMap::from([("x1".to_string(), 3.0)])).unwrap().is_finite());
+
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 500, time_budget: Some(Duration::from_millis(1)), ..Default::default() };
+        let start = Instant::now();
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        assert!(matches!(evo.outcome(), Some(Outcome::BudgetExhausted { cycles }) if cycles < 500));
+        assert_eq!(evo.history().last().and_then(|stats| stats.optimize).map(|progress| progress.iterations), Some(0));
+        assert!(start.elapsed() < Duration::from_secs(30));
+        assert_eq!(EvolutionConfig { max_evaluations: Some(0), ..config }.validate("y", &data),
+                   Err(crate::config::ConfigError::ZeroCount { option: "max_evaluations" }));
+    }
+
+    #[test]
+    fn seeded_runs_repeat() {
+        let data = parabola_data();
+        let config = EvolutionConfig { num_creatures: 500, num_cycles: 3, seed: Some(11), ..Default::default() };
+        let fit_with_threads = |threads: usize, config: &EvolutionConfig| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| Evolution::with_config("y".into(), &data, config).unwrap())
+        };
+        let (single, multi) = (fit_with_threads(1, &config), fit_with_threads(4, &config));
+        for evo in [&single, &multi] {
+            let report = evo.determinism_report().unwrap();
+            assert!(report.deterministic());
+            assert!(evo.summary().contains("Determinism: deterministic: true"));
+        }
+        assert_eq!(single.determinism_report().unwrap().threads, 1);
+        assert_eq!(multi.determinism_report().unwrap().threads, 4);
+        assert_eq!(single.formula(FormulaFormat::Text).unwrap(), multi.formula(FormulaFormat::Text).unwrap());
+        let errors = |evo: &Evolution| evo.history().iter().map(|stats| (stats.best_error, stats.median_error)).collect::<Vec<_>>();
+        assert_eq!(errors(&single), errors(&multi));
+
+        let unseeded = Evolution::with_config("y".into(), &data, &EvolutionConfig { seed: None, ..config.clone() }).unwrap();
+        let report = unseeded.determinism_report().unwrap();
+        assert!(report.seed_drawn && !report.deterministic());
+        assert!(report.to_string().contains("no seed was given"));
+        let timed = EvolutionConfig { evaluation_timeout: Some(Duration::from_secs(60)), ..config.clone() };
+        assert!(!Evolution::with_config("y".into(), &data, &timed).unwrap().determinism_report().unwrap().deterministic());
+        let budgeted = EvolutionConfig { time_budget: Some(Duration::from_secs(60)), ..config };
+        let report = Evolution::with_config("y".into(), &data, &budgeted).unwrap().determinism_report().cloned().unwrap();
+        assert!(report.wall_clock_budget && !report.deterministic());
+        assert!(report.to_string().contains("a time budget"));
+    }
+
+    #[test]
+    fn deduplicated_rows() {
+        let unique = parabola_data();
+        let data: Vec<HashMap<String, f32>> = (0..10).flat_map(|_| unique.iter().cloned()).collect();
+        let config = EvolutionConfig { deduplicate_rows: true, ..Default::default() };
+        let groups = data::duplicate_groups(&data);
+        assert_eq!(groups.len(), unique.len());
+
+        let weighted_config = EvolutionConfig { sample_weights: Some(vec![10.0; unique.len()]), ..Default::default() };
+        let (weighted_standardizer, weighted) = prepare_training_data("y", &unique, &weighted_config);
+        let (standardizer, folded) = prepare_grouped_training_data("y", &data, Some(&groups), &config);
+        assert_eq!(folded.points.len(), unique.len());
+        assert_eq!(folded.weights, weighted.weights);
+        for point in &unique {
+            assert_eq!(standardizer.standardized_value(point), weighted_standardizer.standardized_value(point));
+        }
+
+        // Sample weights of duplicates are added together
+        let weights: Vec<f32> = (0..data.len()).map(|index| (index / unique.len()) as f32).collect();
+        let config = EvolutionConfig { sample_weights: Some(weights), ..config };
+        let (_, folded) = prepare_grouped_training_data("y", &data, Some(&groups), &config);
+        assert_eq!(folded.weights, Some(vec![45.0; unique.len()]));
+
+        // Training on the folded rows repeats training on the weighted unique rows
+        // while scoring a tenth of the rows of the full data
+        use crate::loss::Loss;
+        struct CountedSquaredError(AtomicUsize);
+        impl Loss for CountedSquaredError {
+            fn point_loss(&self, predicted: f32, actual: f32) -> f32 {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                (predicted - actual).powi(2)
+            }
+        }
+        let train = |data: &[HashMap<String, f32>], config: EvolutionConfig| {
+            let loss = Arc::new(CountedSquaredError(AtomicUsize::new(0)));
+            let evo = Evolution::builder("y", data).config(config).custom_loss("counted", loss.clone()).build().unwrap();
+            (evo, loss.0.load(Ordering::Relaxed))
+        };
+        let config = EvolutionConfig { num_creatures: 200, num_cycles: 2, seed: Some(8), ..Default::default() };
+        let (_, full_rows) = train(&data, config.clone());
+        let (evo, folded_rows) = train(&data, EvolutionConfig { deduplicate_rows: true, ..config.clone() });
+        let (weighted, weighted_rows) = train(&unique, EvolutionConfig { sample_weights: Some(vec![10.0; unique.len()]), ..config.clone() });
+        assert_eq!(evo.preprocessing_report().rows_folded, data.len() - unique.len());
+        assert_eq!(evo.formula(FormulaFormat::Text).unwrap(), weighted.formula(FormulaFormat::Text).unwrap());
+        assert_eq!(folded_rows, weighted_rows);
+        assert_eq!(full_rows, 10 * folded_rows);
+        let config = EvolutionConfig { deduplicate_rows: true, ..config };
+        let config = EvolutionConfig { datasets: vec![unique.clone()], ..config };
+        assert!(matches!(config.validate("y", &data), Err(ConfigError::Conflict(_))));
+    }
+
+    #[test]
+    fn multiple_datasets() {
+        let plant_a = parabola_data();
+        let plant_b: Vec<HashMap<String, f32>> = plant_a.iter()
+            .map(|point| HashMap::from([("x1".to_string(), point["x1"]), ("y".to_string(), point["y"] + 300.0)]))
+            .collect();
+        let worst_error = |evo: &Evolution| [&plant_a, &plant_b].into_iter().map(|data| {
+            let predictions: Vec<f32> = data.iter().map(|point| evo.predict_point(point).unwrap()).collect();
+            Metric::MeanSquaredError.compute(&predictions, &target_values("y", data))
+        }).fold(0.0, f32::max);
+
+        let config = EvolutionConfig { num_creatures: 1000, num_cycles: 5, ..Default::default() };
+        let single = Evolution::with_config("y".into(), &plant_a, &config).unwrap();
+        assert!(single.history().iter().all(|stats| stats.dataset_errors.is_empty()));
+
+        let config = EvolutionConfig { datasets: vec![plant_b.clone()], dataset_aggregation: DatasetAggregation::Max, ..config };
+        let multi = Evolution::with_config("y".into(), &plant_a, &config).unwrap();
+        for stats in multi.history() {
+            assert_eq!(stats.dataset_errors.len(), 2);
+            let worst = stats.dataset_errors.iter().cloned().fold(0.0, f32::max);
+            assert!((stats.best_error - worst).abs() <= 1e-4 * worst);
+        }
+        assert!(multi.history_csv().starts_with("cycle,best_error,median_error,best_generation,huber_delta,validation_error,dataset_0_error,dataset_1_error\n"));
+        assert!(worst_error(&multi) < worst_error(&single));
+    }
+
+    #[test]
+    fn missing_params_policies() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let data: Vec<HashMap<String, f32>> = (0..120).map(|i| {
+            let x: f32 = rng.gen_range(-3.0..3.0);
+            let sparse: f32 = rng.gen_range(1.0..4.0);
+            let mut point = HashMap::from([("x".to_string(), x), ("y".to_string(), 2.0 * x + 3.0 * sparse + rng.gen_range(-0.1..0.1))]);
+            if i % 2 == 0 {
+                point.insert("sparse".to_string(), sparse);
+            }
+            point
+        }).collect();
+        let train = |missing_params| Evolution::with_config("y".into(), &data, &EvolutionConfig {
+            num_creatures: 500, num_cycles: 3, seed: Some(6), missing_params, ..Default::default()
+        }).unwrap();
+        let only_x = HashMap::from([("x".to_string(), 1.0)]);
+        let with_sparse = |sparse: f32| HashMap::from([("x".to_string(), 1.0), ("sparse".to_string(), sparse)]);
+
+        let mean = train(MissingParams::Mean);
+        let sparse_mean = mean.standardizer.param_standardizer("sparse").unwrap().unstandardize(&0.0);
+        assert!((mean.predict_point(&only_x).unwrap() - mean.predict_point(&with_sparse(sparse_mean)).unwrap()).abs() < 1e-4);
+        let constant = train(MissingParams::Constant(1.5));
+        assert_eq!(constant.predict_point(&only_x).unwrap(), constant.predict_point(&with_sparse(1.5)).unwrap());
+        assert_eq!(constant.predictor().predict_point(&only_x), constant.predict_point(&only_x).unwrap());
+
+        // A harsh penalty keeps the model off the sparse column, while a light one does not
+        let harsh = train(MissingParams::Penalty(4.0));
+        assert!(!harsh.predict_point(&only_x).unwrap().is_nan());
+        let penalty = train(MissingParams::Penalty(0.05));
+        assert_eq!(penalty.predict_point(&only_x), Err(PredictError::MissingParameter("sparse".to_string())));
+        assert!(penalty.predict_unchecked(&only_x).is_nan());
+        assert!(penalty.predictor().predict_point(&only_x).is_nan());
+        assert!(penalty.predictor().sweep("x", &[0.0, 1.0], &HashMap::new()).iter().all(|value| value.is_nan()));
+        assert!(!penalty.predict_point(&with_sparse(2.0)).unwrap().is_nan());
+
+        let skip = train(MissingParams::Skip);
+        assert_eq!(skip.predict_point(&only_x).is_err(), skip.best_creature.parameters().contains(&"sparse"));
+        let formulas: Vec<String> = [&skip, &mean, &constant, &penalty].iter().map(|evo| evo.formula(FormulaFormat::Text).unwrap()).collect();
+        assert!(formulas.iter().enumerate().all(|(i, formula)| formulas[i + 1..].iter().all(|other| other != formula)));
+        let bad = EvolutionConfig { missing_params: MissingParams::Penalty(f32::NAN), ..Default::default() };
+        assert!(matches!(bad.validate("y", &data), Err(ConfigError::Conflict(_))));
+    }
+
+    #[test]
+    fn formula_history() {
+        let evo = Evolution::with_config("y".into(), &parabola_data(), &EvolutionConfig {
+            num_creatures: 300, num_cycles: 6, seed: Some(12), ..Default::default()
+        }).unwrap();
+        let mut formulas: Vec<String> = evo.best_creatures.iter()
+            .map(|creature| evo.creature_formula(creature, FormulaFormat::Text, Precision::Full).unwrap())
+            .collect();
+        formulas.push(evo.formula(FormulaFormat::Text).unwrap());
+        formulas.dedup();
+
+        let history = evo.formula_history(false).unwrap();
+        assert_eq!(history.len(), formulas.len());
+        assert_eq!(history.last().unwrap().1, evo.formula(FormulaFormat::Text).unwrap());
+        assert!(history.windows(2).all(|pair| pair[0].0 <= pair[1].0 && pair[0].1 != pair[1].1));
+        let errors = evo.formula_history_errors(false).unwrap();
+        assert_eq!(errors.iter().map(|(cycle, _)| *cycle).collect::<Vec<u16>>(), history.iter().map(|(cycle, _)| *cycle).collect::<Vec<u16>>());
+        assert_eq!(errors.last().unwrap().1, evo.best_creature.cached_error_sum);
+
+        let all = evo.formula_history(true).unwrap();
+        assert!(all.len() >= 6 && all.len() <= 7);
+        assert_eq!(all.last(), history.last());
+    }
+
+    #[test]
+    fn weight_decay() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let population: Vec<Creature> = (0..200).map(|_| Creature::generate(&vec!["x", "z"], 2, &Default::default(), Samplers::standard(), &mut rng)).collect();
+        let median_abs_c = |options: &MutationOptions| {
+            let mut rng = StdRng::seed_from_u64(4);
+            let mut values: Vec<f32> = population.iter().flat_map(|creature| {
+                let mut creature = creature.clone();
+                for _ in 0..20 {
+                    creature = creature.perturbed(0.05, options, Samplers::standard(), &mut rng);
+                }
+                creature.coefficient_values().into_iter().map(|(c, _, _, _)| c.abs()).collect::<Vec<f32>>()
+            }).collect();
+            values.sort_by(f32::total_cmp);
+            values[values.len() / 2]
+        };
+        let decayed = MutationOptions { weight_decay: 0.05, ..Default::default() };
+        assert!(median_abs_c(&decayed) < 0.8 * median_abs_c(&MutationOptions::default()));
+
+        let data = crate::data::synthetic::linear(120, 2, 0.05, 9);
+        let r2 = |mutation: MutationOptions| {
+            let evo = Evolution::with_config("y".into(), &data, &EvolutionConfig {
+                num_creatures: 500, num_cycles: 4, seed: Some(2), mutation, ..Default::default()
+            }).unwrap();
+            let predictions: Vec<f32> = data.iter().map(|point| evo.predict_point(point).unwrap()).collect();
+            Metric::RSquared.compute(&predictions, &target_values("y", &data))
+        };
+        assert!(r2(decayed) > 0.98);
+        let invalid = EvolutionConfig { mutation: MutationOptions { weight_decay: 1.0, ..Default::default() }, ..Default::default() };
+        assert!(invalid.validate("y", &data).is_err());
+    }
+
+    #[test]
+    fn public_constructor() {
+        let mut data = crate::data::synthetic::linear(60, 2, 0.1, 4);
+        let evo = Evolution::new("y".into(), &data, 200, 2, 2).unwrap();
+        assert_eq!(evo.target(), "y");
+        assert_eq!(evo.best_creature().calculate(&HashMap::new()), evo.best_creature.calculate(&HashMap::new()));
+        assert!(matches!(Evolution::new("w".into(), &data, 200, 2, 2), Err(EvolutionError::MissingTarget(_))));
+        assert!(matches!(Evolution::new("y".into(), &[], 200, 2, 2), Err(EvolutionError::EmptyData)));
+        assert!(matches!(Evolution::new("y".into(), &data, 0, 2, 2),
+                         Err(EvolutionError::Config(ConfigError::ZeroCount { option: "num_creatures" }))));
+
+        // Sparse data is an error here, while with_config discovers every column
+        data[4].remove("x2");
+        assert!(matches!(Evolution::new("y".into(), &data, 200, 2, 2),
+                         Err(EvolutionError::InconsistentKeys { row: 4, missing }) if missing == vec!["x2".to_string()]));
+        data.swap(0, 4);
+        let config = EvolutionConfig { num_creatures: 200, num_cycles: 2, ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        assert_eq!(evo.config().mutation.parameter_options, vec!["x1".to_string(), "x2".to_string()]);
+        data[3].remove("y");
+        assert!(matches!(config.validate("y", &data), Err(ConfigError::Conflict(_))));
+    }
+
+    #[test]
+    fn strict_fp_golden_values() {
+        // Generated at this seed with strict_fp, which must give the same bits on every platform
+        const GOLDEN: [u32; 4] = [0x4063db30, 0x413de95a, 0x4152592c, 0x41786ad8];
+        let data = crate::data::synthetic::polynomial(80, 2, 0.1, 17);
+        let config = EvolutionConfig { num_creatures: 200, num_cycles: 3, seed: Some(23), strict_fp: true, ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        assert!(!evo.determinism_report().unwrap().hash_order_sums);
+        let predictions: Vec<u32> = data[..4].iter().map(|point| evo.predict_point(point).unwrap().to_bits()).collect();
+        let serialized: Vec<String> = predictions.iter().map(|bits| format!("{:#010x}", bits)).collect();
+        assert_eq!(predictions, GOLDEN, "{:?}", serialized);
+        let predictor = evo.predictor();
+        assert!(data.iter().all(|point| predictor.predict_point(point) == evo.predict_point(point).unwrap()));
+        assert_eq!(evo.trace(&data[0]).unwrap().prediction(), Some(f32::from_bits(GOLDEN[0])));
+    }
+
+    #[test]
+    fn batch_predict() {
+        let data = crate::data::synthetic::linear(100, 2, 0.1, 6);
+        let evo = Evolution::new("y".into(), &data, 300, 2, 2).unwrap();
+        let predictions = evo.predict(&data);
+        assert_eq!(predictions, data.iter().map(|point| evo.predict_point(point).unwrap()).collect::<Vec<f32>>());
+
+        let x2_mean = evo.standardizer.param_standardizer("x2").unwrap().mean();
+        let sparse = vec![HashMap::from([("x1".to_string(), 0.5)]), HashMap::from([("x1".to_string(), 0.5), ("x2".to_string(), x2_mean)])];
+        let predictions = evo.predict(&sparse);
+        assert!((predictions[0] - predictions[1]).abs() <= 1e-5 * predictions[1].abs().max(1.0));
+    }
+
+    #[test]
+    fn predict_errors() {
+        let data = crate::data::synthetic::linear(100, 2, 0.1, 6);
+        let evo = Evolution::builder("y", &data).num_creatures(300).num_cycles(2).max_layers(2).seed(1).build().unwrap();
+        assert_eq!(evo.best_creature.parameters(), vec!["x1", "x2"]);
+        let point = HashMap::from([("x1".to_string(), 0.5), ("x2".to_string(), -1.0)]);
+        let mut extra = point.clone();
+        extra.insert("unknown".to_string(), f32::NAN);
+        assert_eq!(evo.predict_point(&extra), evo.predict_point(&point));
+
+        let missing = HashMap::from([("x1".to_string(), 0.5)]);
+        assert_eq!(evo.predict_point(&missing), Err(PredictError::MissingParameter("x2".to_string())));
+        let infinite = HashMap::from([("x1".to_string(), f32::INFINITY), ("x2".to_string(), -1.0)]);
+        let error = evo.predict_point(&infinite).unwrap_err();
+        assert_eq!(error, PredictError::NonFinite { parameter: "x1".to_string(), value: f32::INFINITY });
+        assert_eq!(error.to_string(), "parameter \"x1\" has the non-finite value inf");
+
+        // Unless missing_params fills it in
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 2, max_layers: 2, missing_params: MissingParams::Mean, ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        assert!(evo.predict_point(&missing).is_ok());
+    }
+
+    #[test]
+    fn predict_many() {
+        let data = parabola_data();
+        let evo = Evolution::builder("y", &data).num_creatures(500).num_cycles(3).max_layers(3).build().unwrap();
+        let each: Vec<f32> = data.iter().map(|point| evo.predict_point(point).unwrap()).collect();
+        assert_eq!(evo.predict_many(&data).unwrap(), each);
+        assert_eq!(evo.predict_many(&[]).unwrap(), Vec::<f32>::new());
+
+        let mut with_missing = data.clone();
+        with_missing[20].remove("x1");
+        assert_eq!(evo.predict_many(&with_missing), Err(PredictError::MissingParameter("x1".to_string())));
+    }
+
+    #[test]
+    fn predict_many_parallel() {
+        fn send_sync<T: Send + Sync>() {}
+        send_sync::<Creature>();
+        send_sync::<Standardizer>();
+
+        let data = crate::data::synthetic::linear(200, 4, 0.1, 3);
+        let evo = Evolution::builder("y", &data).num_creatures(500).num_cycles(3).max_layers(3).build().unwrap();
+        let rows = crate::data::synthetic::linear(100_000, 4, 0.1, 4);
+
+        let start = Instant::now();
+        let sequential = evo.predict_many(&rows).unwrap();
+        let sequential_time = start.elapsed();
+        let start = Instant::now();
+        let parallel = evo.predict_many_parallel(&rows).unwrap();
+        let parallel_time = start.elapsed();
+        println!("100k predictions: {:?} sequential, {:?} parallel on {} threads",
+                 sequential_time, parallel_time, rayon::current_num_threads());
+        assert_eq!(parallel, sequential);
+
+        let mut with_missing = rows[..1000].to_vec();
+        with_missing[500].remove("x3");
+        assert_eq!(evo.predict_many_parallel(&with_missing), evo.predict_many(&with_missing));
+    }
+
+    #[test]
+    fn predict_stream() {
+        let data = parabola_data();
+        let evo = Evolution::builder("y", &data).num_creatures(500).num_cycles(3).max_layers(3).build().unwrap();
+        let point = |index: usize| HashMap::from([("x1".to_string(), (index % 2000) as f32 / 100.0 - 10.0)]);
+        let points = |count: usize| (0..count).map(point);
+
+        let sampled = [0, 1, 777, 1999, 2000, 31_337, 99_999];
+        let expected: Vec<f32> = sampled.iter().map(|index| evo.predict_point(&point(*index)).unwrap()).collect();
+        let at_samples = |predictions: &mut dyn Iterator<Item = Result<f32, PredictError>>| -> Vec<f32> {
+            predictions.enumerate()
+                .filter(|(index, _)| sampled.contains(index))
+                .map(|(_, prediction)| prediction.unwrap())
+                .collect()
+        };
+        assert_eq!(at_samples(&mut evo.predict_stream(points(100_000))), expected);
+        assert_eq!(at_samples(&mut evo.predict_stream_chunked(points(100_000), 1000)), expected);
+        assert_eq!(evo.predict_stream_chunked(points(2500), 1000).count(), 2500);
+
+        // Memory use does not grow with the number of points (the sequential stream
+        // on a single thread, so no idle worker's bookkeeping gets counted)
+        let sequential = |count| peak_allocation(1, || evo.predict_stream(points(count)).for_each(|prediction| { prediction.unwrap(); }));
+        let chunked = |count| peak_allocation(2, || evo.predict_stream_chunked(points(count), 1000).for_each(|prediction| { prediction.unwrap(); }));
+        let (sequential_few, sequential_many) = (sequential(2000), sequential(100_000));
+        let (chunked_few, chunked_many) = (chunked(2000), chunked(100_000));
+        let collected = peak_allocation(1, || assert_eq!(evo.predict_stream(points(100_000)).collect::<Vec<_>>().len(), 100_000));
+        println!("Peak bytes: {} / {} sequential, {} / {} chunked, {} collected",
+                 sequential_few, sequential_many, chunked_few, chunked_many, collected);
+        assert!(sequential_many <= sequential_few + 4096);
+        assert!(chunked_many * 4 <= chunked_few * 5);
+        assert!(chunked_many * 4 < collected);
+
+        let mut missing = points(10).collect::<Vec<_>>();
+        missing[3].clear();
+        let predictions: Vec<_> = evo.predict_stream_chunked(missing.into_iter(), 4).collect();
+        assert_eq!(predictions[3], Err(PredictError::MissingParameter("x1".to_string())));
+        assert!(predictions.iter().enumerate().all(|(index, prediction)| index == 3 || prediction.is_ok()));
+    }
+
+    #[test]
+    fn model_stats() {
+        let data = crate::data::synthetic::linear(100, 2, 0.1, 8);
+        let evo = Evolution::new("y".into(), &data, 300, 3, 2).unwrap();
+        let predictions = evo.predict(&data);
+        let actuals = target_values("y", &data);
+        let mut with_unlabelled = data.clone();
+        with_unlabelled.push(HashMap::from([("x1".to_string(), 0.5), ("x2".to_string(), 0.5)]));
+        let stats = evo.stats(&with_unlabelled);
+        assert_eq!(stats.count, 100);
+        assert_eq!(stats.r_squared, Metric::RSquared.compute(&predictions, &actuals));
+        assert_eq!(stats.mean_absolute_error, Metric::MeanAbsoluteError.compute(&predictions, &actuals));
+        assert_eq!(stats.root_mean_squared_error, Metric::RootMeanSquaredError.compute(&predictions, &actuals));
+        assert!(stats.r_squared > 0.5);
+        assert!(evo.stats(&[]).r_squared.is_nan());
+    }
+
+    #[test]
+    fn parabola() {
+        let parabola_data = parabola_data();
+        let model = Evolution::builder("y", &parabola_data)
+            .num_creatures(5000)
+            .num_cycles(30)
+            .max_layers(3)
+            .patience(3, MinImprovement::Relative(0.001))
+            .seed(1)
+            .build()
+            .unwrap();
+        assert!(model.cycles_run() < 20, "Ran {} cycles", model.cycles_run());
+        assert_eq!(model.cycles_run() as usize, model.history().len());
 
-        let output_data: Vec<f32> = (-20..=20)
-            .map(|x| model.predict_point(HashMap::from([("x".to_string(), x as f32)])))
+        let inputs: Vec<f32> = (-30..=30).map(|step| step as f32 / 10.0).collect();
+        let output_data: Vec<f32> = inputs.iter()
+            .map(|x| model.predict_point(&HashMap::from([("x1".to_string(), *x)])).unwrap())
             .collect();
         let mut output_string = String::from("x,y,\n");
-        for (x, y) in izip!(-20..=20, output_data) {
+        for (x, y) in izip!(inputs, output_data) {
             output_string += &format!("{},{},\n", x, y);
         }
         fs::write("parabola_output.csv", output_string).expect("Unable to write to file");
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use rand::Rng;
+use rayon::prelude::*;
+use crate::config::{ConfigError, EvolutionConfig};
+use crate::data::{input_columns, train_test_split};
+use crate::encoding::TargetEncoding;
+use crate::evolution::{Evolution, EvolutionError, MinImprovement};
+use crate::metrics::Metric;
+
+
+/// Most cycles of the short evolutions scoring each candidate feature.
+const SEARCH_CYCLES: u16 = 3;
+
+/// Creature evaluations allowed per cycle of those short evolutions (see
+/// `EvolutionConfig::max_evaluations`), in populations, leaving the final
+/// optimization only a few iterations.
+const SEARCH_EVALUATIONS_PER_CYCLE: u64 = 3;
+
+/// Fraction of the rows held out to score candidates when there is no validation data.
+const HOLDOUT_FRACTION: f32 = 0.25;
+
+/// How much a feature must lower the held out error to be added.
+const SEARCH_MIN_IMPROVEMENT: MinImprovement = MinImprovement::Relative(0.02);
+
+/// A feature added by a `FeatureSearch`, along with the held out error of the
+/// short evolution using it and every feature added before it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchStep {
+    pub feature: String,
+    pub error: f32,
+}
+
+/// Features chosen by greedy forward selection and the model trained on them.
+pub struct FeatureSearch {
+    steps: Vec<SearchStep>,
+    model: Evolution,
+}
+
+impl FeatureSearch {
+    /// Choose at most "max_features" of the input columns of "data" by greedy forward selection.
+    /// Each round runs a short evolution (at most 3 cycles of "config", with a small
+    /// evaluation budget for the final optimization) for every remaining
+    /// column along with those already chosen, in parallel, and adds the column giving the
+    /// lowest mean squared error on `EvolutionConfig::validation_data` (or on a quarter of
+    /// the rows held out from training when there is none).  The search stops once
+    /// "max_features" are chosen or the best addition lowers the error by less than 2%,
+    /// though the first column is always added.  The final model is then trained with the
+    /// full "config" on every row, using only the chosen columns.
+    pub fn forward(target: &str,
+        data: &[HashMap<String, f32>],
+        max_features: usize,
+        config: &EvolutionConfig) -> Result<FeatureSearch, EvolutionError> {
+        config.validate(target, data)?;
+        if max_features == 0 {
+            return Err(ConfigError::ZeroCount { option: "max_features" }.into());
+        }
+        let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let (train, holdout) = match &config.validation_data {
+            Some(validation) => (data.to_vec(), validation.clone()),
+            None => train_test_split(data, HOLDOUT_FRACTION, seed),
+        };
+        let actuals: Vec<f32> = holdout.iter().map(|point| point[target]).collect();
+        let mut candidates: Vec<String> = input_columns(data, target).into_iter().map(String::from).collect();
+
+        let search_cycles = config.num_cycles.min(SEARCH_CYCLES);
+        let search_evaluations = config.num_creatures as u64 * search_cycles as u64 * SEARCH_EVALUATIONS_PER_CYCLE;
+        let search_config = EvolutionConfig {
+            num_cycles: search_cycles,
+            max_evaluations: Some(config.max_evaluations.map_or(search_evaluations, |max| max.min(search_evaluations))),
+            seed: Some(seed),
+            ..config.clone()
+        };
+        let mut steps: Vec<SearchStep> = Vec::new();
+        while steps.len() < max_features && !candidates.is_empty() {
+            let chosen: Vec<&str> = steps.iter().map(|step| step.feature.as_str()).collect();
+            let errors = candidates.par_iter().map(|candidate| {
+                let mut columns = chosen.clone();
+                columns.push(candidate);
+                let model = Evolution::with_config(
+                    target.to_string(), &subset(&train, target, &columns), &subset_config(&search_config, target, &columns),
+                )?;
+                let predictions: Vec<f32> = subset(&holdout, target, &columns).iter().map(|point| model.predict_unchecked(point)).collect();
+                Ok(Metric::MeanSquaredError.compute(&predictions, &actuals))
+            }).collect::<Result<Vec<f32>, EvolutionError>>()?;
+
+            let (best, error) = errors.iter().enumerate()
+                .min_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(index, error)| (index, *error))
+                .unwrap();
+            if steps.last().is_some_and(|last| !SEARCH_MIN_IMPROVEMENT.accepts(last.error, error)) {
+                break;
+            }
+            steps.push(SearchStep { feature: candidates.remove(best), error });
+        }
+
+        let features: Vec<&str> = steps.iter().map(|step| step.feature.as_str()).collect();
+        let model = Evolution::with_config(
+            target.to_string(), &subset(data, target, &features), &subset_config(config, target, &features),
+        )?;
+        Ok(FeatureSearch { steps, model })
+    }
+
+    /// Chosen features in the order they were added.
+    pub fn features(&self) -> Vec<&str> {
+        self.steps.iter().map(|step| step.feature.as_str()).collect()
+    }
+
+    pub fn steps(&self) -> &[SearchStep] {
+        &self.steps
+    }
+
+    /// The model trained with the full config on the chosen features.
+    pub fn model(&self) -> &Evolution {
+        &self.model
+    }
+
+    pub fn into_model(self) -> Evolution {
+        self.model
+    }
+}
+
+
+/// Rows of "data" keeping only "target" and "columns".
+fn subset(data: &[HashMap<String, f32>], target: &str, columns: &[&str]) -> Vec<HashMap<String, f32>> {
+    data.iter().map(|point| {
+        point.iter()
+            .filter(|(column, _)| *column == target || columns.contains(&column.as_str()))
+            .map(|(column, value)| (column.clone(), *value))
+            .collect()
+    }).collect()
+}
+
+/// "config" with its data and options naming columns restricted to "target" and "columns".
+fn subset_config(config: &EvolutionConfig, target: &str, columns: &[&str]) -> EvolutionConfig {
+    let kept = |column: &String| {
+        let column = config.column_names.normalize(column);
+        column == config.column_names.normalize(target)
+            || columns.iter().any(|kept| config.column_names.normalize(kept) == column)
+    };
+    let mut creation = config.creation.clone();
+    if let Some(correlations) = &mut creation.feature_correlations {
+        correlations.retain(|column, _| kept(column));
+    }
+    let normalized_columns: Vec<String> = columns.iter().map(|column| config.column_names.normalize(column).into_owned()).collect();
+    let normalized_columns: Vec<&str> = normalized_columns.iter().map(String::as_str).collect();
+    let templates = config.templates.iter()
+        .filter(|template| template.normalized(config.column_names)
+            .problems(0, &normalized_columns, &config.column_names.normalize(target), config.max_layers, config.mutation.max_exponent)
+            .is_empty())
+        .cloned()
+        .collect();
+    EvolutionConfig {
+        creation,
+        templates,
+        pin: config.pin.iter().filter(|(column, _)| kept(column)).map(|(column, spec)| (column.clone(), spec.clone())).collect(),
+        datetime_columns: config.datetime_columns.iter().filter(|expansion| kept(&expansion.column)).cloned().collect(),
+        one_hot_columns: config.one_hot_columns.iter().filter(|column| kept(column)).cloned().collect(),
+        validation_data: config.validation_data.as_deref().map(|validation| subset(validation, target, columns)),
+        datasets: config.datasets.iter().map(|dataset| subset(dataset, target, columns)).collect(),
+        valid_ranges: config.valid_ranges.iter().filter(|(column, _)| kept(column)).map(|(column, range)| (column.clone(), *range)).collect(),
+        target_encoding: config.target_encoding.as_ref()
+            .map(|encoding| TargetEncoding { columns: encoding.columns.iter().filter(|column| kept(column)).cloned().collect(), ..encoding.clone() })
+            .filter(|encoding| !encoding.columns.is_empty()),
+        ..config.clone()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn finds_the_two_signal_columns() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let data: Vec<HashMap<String, f32>> = (0..200).map(|_| {
+            let mut point: HashMap<String, f32> = (0..20).map(|i| (format!("x{:02}", i), rng.gen_range(-3.0..3.0))).collect();
+            let y = 4.0 * point["x03"] - 2.5 * point["x11"] * point["x11"] + rng.gen_range(-0.1..0.1);
+            point.insert("y".to_string(), y);
+            point
+        }).collect();
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 2, seed: Some(21), ..Default::default() };
+        let search = FeatureSearch::forward("y", &data, 3, &config).unwrap();
+
+        let mut features = search.features();
+        assert!(features.len() <= 3);
+        assert!(search.steps().windows(2).all(|pair| pair[1].error < pair[0].error));
+        features.truncate(2);
+        features.sort();
+        assert_eq!(features, vec!["x03", "x11"]);
+        let mut inputs: Vec<&str> = search.model().standardizer.parameters().into_iter().filter(|param| *param != "y").collect();
+        inputs.sort();
+        let mut chosen = search.features();
+        chosen.sort();
+        assert_eq!(inputs, chosen);
+        assert!(matches!(FeatureSearch::forward("y", &data, 0, &config), Err(EvolutionError::Config(ConfigError::ZeroCount { .. }))));
+    }
+}
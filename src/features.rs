@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use crate::data::{ColumnNames, MissingParams};
+use crate::encoding::TargetEncoder;
+use crate::evolution::{Evolution, PredictError, Preprocess};
+
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// A calendar part of a timestamp (see `DatetimeExpansion`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DatetimePart {
+    Year,
+    /// 1 to 12.
+    Month,
+    /// Day of the month, 1 to 31.
+    Day,
+    /// 0 (Monday) to 6 (Sunday).
+    Weekday,
+    /// 1 to 366.
+    DayOfYear,
+    /// 0 to 23.
+    Hour,
+}
+
+impl DatetimePart {
+    /// Suffix of the name of the column holding this part.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            DatetimePart::Year => "year",
+            DatetimePart::Month => "month",
+            DatetimePart::Day => "day",
+            DatetimePart::Weekday => "weekday",
+            DatetimePart::DayOfYear => "yearday",
+            DatetimePart::Hour => "hour",
+        }
+    }
+}
+
+/// A timestamp column (seconds since 1970-01-01 UTC) replaced by columns of its
+/// calendar parts, each named "{column}_{suffix}" (see `DatetimePart::suffix`).
+/// As an f32 holds current timestamps to within about a minute, parts smaller
+/// than an hour are not offered.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DatetimeExpansion {
+    pub column: String,
+    pub parts: Vec<DatetimePart>,
+}
+
+impl DatetimeExpansion {
+    pub fn new(column: &str, parts: &[DatetimePart]) -> DatetimeExpansion {
+        DatetimeExpansion { column: column.to_string(), parts: parts.to_vec() }
+    }
+
+    /// Names of the columns this expansion adds.
+    pub fn columns(&self) -> Vec<String> {
+        self.parts.iter().map(|part| format!("{}_{}", self.column, part.suffix())).collect()
+    }
+
+    fn expand(&self, point: &mut HashMap<String, f32>) {
+        let Some(timestamp) = point.remove(&self.column) else { return };
+        if !timestamp.is_finite() {
+            return;
+        }
+        let seconds = timestamp as f64;
+        let days = (seconds / SECONDS_PER_DAY).floor();
+        let (year, month, day) = civil_from_days(days as i64);
+        for (part, column) in self.parts.iter().zip(self.columns()) {
+            let value = match part {
+                DatetimePart::Year => year as f32,
+                DatetimePart::Month => month as f32,
+                DatetimePart::Day => day as f32,
+                DatetimePart::Weekday => (days as i64 + 3).rem_euclid(7) as f32,
+                DatetimePart::DayOfYear => (days as i64 - days_from_civil(year, 1, 1) + 1) as f32,
+                DatetimePart::Hour => ((seconds - days * SECONDS_PER_DAY) / 3600.0).floor() as f32,
+            };
+            point.insert(column, value);
+        }
+    }
+}
+
+/// (year, month, day) of the date "days" after 1970-01-01, from
+/// Howard Hinnant's "chrono-Compatible Low-Level Date Algorithms".
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Days from 1970-01-01 to the given date (the inverse of "civil_from_days").
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+
+/// A categorical column (holding category codes, compared exactly) replaced by
+/// an indicator column per category seen while training, named "{column}_{category}",
+/// which is 1 for the point's category and 0 for the others.  Unseen categories
+/// get 0 in every indicator.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OneHotEncoder {
+    column: String,
+    /// Every category seen while training, sorted.
+    categories: Vec<f32>,
+}
+
+impl OneHotEncoder {
+    pub(crate) fn fit(column: &str, data: &[HashMap<String, f32>]) -> OneHotEncoder {
+        let mut categories: Vec<f32> = data.iter()
+            .filter_map(|point| point.get(column))
+            .filter(|category| !category.is_nan())
+            .map(|category| category + 0.0)
+            .collect();
+        categories.sort_by(|a, b| a.total_cmp(b));
+        categories.dedup();
+        OneHotEncoder { column: column.to_string(), categories }
+    }
+
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    pub fn categories(&self) -> &[f32] {
+        &self.categories
+    }
+
+    /// Name of the indicator column of "category".
+    pub fn indicator(&self, category: f32) -> String {
+        format!("{}_{}", self.column, category)
+    }
+
+    fn encode(&self, point: &mut HashMap<String, f32>) {
+        let Some(value) = point.remove(&self.column) else { return };
+        for category in &self.categories {
+            point.insert(self.indicator(*category), if *category == value { 1.0 } else { 0.0 });
+        }
+    }
+}
+
+
+/// A step of a `FeaturePipeline`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FeatureStep {
+    /// Column names are normalized (see `ColumnNames::Normalized`).
+    NormalizeColumns(ColumnNames),
+    /// The closure given to `EvolutionBuilder::preprocess`.
+    Preprocess,
+    /// See `EvolutionConfig::datetime_columns`.
+    ExpandDatetime(DatetimeExpansion),
+    /// See `EvolutionConfig::one_hot_columns`.
+    OneHot(OneHotEncoder),
+    /// See `EvolutionConfig::target_encoding`.
+    TargetEncode(TargetEncoder),
+    /// Input parameters missing from a point are handled as given once it is standardized.
+    Impute(MissingParams),
+}
+
+impl fmt::Display for FeatureStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FeatureStep::NormalizeColumns(column_names) => write!(f, "Normalize column names ({:?})", column_names),
+            FeatureStep::Preprocess => write!(f, "Preprocess with the user supplied closure"),
+            FeatureStep::ExpandDatetime(expansion) => write!(f, "Expand \"{}\" into {}", expansion.column, expansion.columns().join(", ")),
+            FeatureStep::OneHot(encoder) => {
+                write!(f, "One-hot encode the {} categories of \"{}\"", encoder.categories.len(), encoder.column)
+            },
+            FeatureStep::TargetEncode(encoder) => write!(f, "Target encode {}", encoder.columns().join(", ")),
+            FeatureStep::Impute(missing) => write!(f, "Handle missing parameters with {:?}", missing),
+        }
+    }
+}
+
+/// Every transformation a model applies to a data point before standardizing it,
+/// fit on the training data and recorded with the model so points to predict
+/// can be given with their raw columns.  Every prediction applies it.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeaturePipeline {
+    steps: Vec<FeatureStep>,
+}
+
+impl FeaturePipeline {
+    pub(crate) fn new(column_names: ColumnNames,
+        preprocess: bool,
+        engineering: Vec<FeatureStep>,
+        target_encoder: Option<TargetEncoder>,
+        missing: MissingParams) -> FeaturePipeline {
+        let mut steps = Vec::new();
+        if column_names != ColumnNames::Exact {
+            steps.push(FeatureStep::NormalizeColumns(column_names));
+        }
+        if preprocess {
+            steps.push(FeatureStep::Preprocess);
+        }
+        steps.extend(engineering);
+        steps.extend(target_encoder.map(FeatureStep::TargetEncode));
+        steps.push(FeatureStep::Impute(missing));
+        FeaturePipeline { steps }
+    }
+
+    /// The steps, in the order they are applied.
+    pub fn steps(&self) -> &[FeatureStep] {
+        &self.steps
+    }
+
+    /// Copy without the user supplied preprocessing, for a model loaded without it.
+    #[cfg(feature = "serde")]
+    pub(crate) fn without_preprocess(&self) -> FeaturePipeline {
+        FeaturePipeline { steps: self.steps.iter().filter(|step| **step != FeatureStep::Preprocess).cloned().collect() }
+    }
+
+    /// Whether any of the steps were fit on the training data.
+    fn is_fitted(&self) -> bool {
+        self.steps.iter().any(|step| matches!(step, FeatureStep::ExpandDatetime(_) | FeatureStep::OneHot(_) | FeatureStep::TargetEncode(_)))
+    }
+
+    /// Apply the steps fit on the training data (expansions and encodings) to "point".
+    pub(crate) fn transform(&self, point: &mut HashMap<String, f32>) {
+        for step in &self.steps {
+            match step {
+                FeatureStep::ExpandDatetime(expansion) => expansion.expand(point),
+                FeatureStep::OneHot(encoder) => encoder.encode(point),
+                FeatureStep::TargetEncode(encoder) => encoder.encode(point),
+                FeatureStep::NormalizeColumns(_) | FeatureStep::Preprocess | FeatureStep::Impute(_) => (),
+            }
+        }
+    }
+}
+
+impl fmt::Display for FeaturePipeline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Feature Pipeline:")?;
+        for (index, step) in self.steps.iter().enumerate() {
+            writeln!(f, "  {}. {}", index + 1, step)?;
+        }
+        Ok(())
+    }
+}
+
+/// Copy of "datetimes" with the column names as the model uses them.
+pub(crate) fn normalized_datetimes(datetimes: &[DatetimeExpansion], column_names: ColumnNames) -> Vec<DatetimeExpansion> {
+    datetimes.iter()
+        .map(|expansion| DatetimeExpansion { column: column_names.normalize(&expansion.column).into_owned(), ..expansion.clone() })
+        .collect()
+}
+
+/// Steps expanding "datetimes" and one-hot encoding "one_hot" (fit on "data").
+pub(crate) fn engineering(data: &[HashMap<String, f32>], datetimes: &[DatetimeExpansion], one_hot: &[String]) -> Vec<FeatureStep> {
+    datetimes.iter().cloned().map(FeatureStep::ExpandDatetime)
+        .chain(one_hot.iter().map(|column| FeatureStep::OneHot(OneHotEncoder::fit(column, data))))
+        .collect()
+}
+
+/// Copy of "data" transformed by "steps".
+pub(crate) fn engineered(steps: &[FeatureStep], data: &[HashMap<String, f32>]) -> Vec<HashMap<String, f32>> {
+    let pipeline = FeaturePipeline { steps: steps.to_vec() };
+    data.iter().map(|point| {
+        let mut point = point.clone();
+        pipeline.transform(&mut point);
+        point
+    }).collect()
+}
+
+/// "preprocess" followed by the fitted steps of "pipeline" (when it has any).
+pub(crate) fn chained(preprocess: Option<Preprocess>, pipeline: &FeaturePipeline) -> Option<Preprocess> {
+    if !pipeline.is_fitted() {
+        return preprocess;
+    }
+    let pipeline = pipeline.clone();
+    Some(Arc::new(move |point: &mut HashMap<String, f32>| {
+        if let Some(preprocess) = &preprocess {
+            preprocess(point);
+        }
+        pipeline.transform(point);
+    }))
+}
+
+
+impl Evolution {
+    /// The transformations applied to every point before it is standardized.
+    pub fn pipeline(&self) -> &FeaturePipeline {
+        &self.pipeline
+    }
+
+    /// "data_point" as the model's creatures see it before standardization,
+    /// after every step of the pipeline other than imputation.  Fails with
+    /// `PredictError::ColumnCollision` if column names of "data_point" are the
+    /// same once normalized.
+    pub fn engineered_point(&self, data_point: &HashMap<String, f32>) -> Result<HashMap<String, f32>, PredictError> {
+        Ok(self.prepared_point(data_point)?.into_owned())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EvolutionConfig;
+    use crate::encoding::TargetEncoding;
+
+    /// Unix time of 2024-01-01 00:00 UTC, a Monday.
+    const NEW_YEAR_2024: f32 = 1_704_067_200.0;
+
+    #[test]
+    fn datetime_parts() {
+        let expansion = DatetimeExpansion::new("t", &[
+            DatetimePart::Year, DatetimePart::Month, DatetimePart::Day,
+            DatetimePart::Weekday, DatetimePart::DayOfYear, DatetimePart::Hour,
+        ]);
+        let parts = |timestamp: f32| {
+            let mut point = HashMap::from([("t".to_string(), timestamp), ("x".to_string(), 1.0)]);
+            expansion.expand(&mut point);
+            assert!(!point.contains_key("t"));
+            ["t_year", "t_month", "t_day", "t_weekday", "t_yearday", "t_hour"].map(|column| point[column])
+        };
+        assert_eq!(parts(NEW_YEAR_2024 + 1800.0), [2024.0, 1.0, 1.0, 0.0, 1.0, 0.0]);
+        // 2024-02-29 12:30, a Thursday
+        assert_eq!(parts(1_709_209_800.0), [2024.0, 2.0, 29.0, 3.0, 60.0, 12.0]);
+        // 1969-12-31 23:30, a Wednesday
+        assert_eq!(parts(-1800.0), [1969.0, 12.0, 31.0, 2.0, 365.0, 23.0]);
+        for days in [-800_000, -1, 0, 59, 11_016, 19_723, 2_932_896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn predict_from_raw_columns() {
+        // Hourly timestamps over three weeks with two category codes
+        let data: Vec<HashMap<String, f32>> = (0..500).map(|i| {
+            let hour = (i % 24) as f32;
+            let category = (i % 3) as f32;
+            let other = (i % 5) as f32;
+            HashMap::from([
+                ("t".to_string(), NEW_YEAR_2024 + 1800.0 + 3600.0 * i as f32),
+                ("c".to_string(), category),
+                ("d".to_string(), other),
+                ("y".to_string(), hour / 6.0 + 2.0 * category - other),
+            ])
+        }).collect();
+        let config = EvolutionConfig {
+            num_creatures: 500,
+            num_cycles: 3,
+            datetime_columns: vec![DatetimeExpansion::new("t", &[DatetimePart::Weekday, DatetimePart::Hour])],
+            one_hot_columns: vec!["c".to_string()],
+            target_encoding: Some(TargetEncoding { columns: vec!["d".to_string()], ..Default::default() }),
+            ..Default::default()
+        };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let steps = evo.pipeline().steps();
+        assert_eq!(steps.len(), 4);
+        assert!(matches!(&steps[0], FeatureStep::ExpandDatetime(expansion) if expansion.columns() == vec!["t_weekday", "t_hour"]));
+        let FeatureStep::OneHot(encoder) = &steps[1] else { panic!("Expected one-hot encoding, got {:?}", steps[1]) };
+        assert_eq!((encoder.column(), encoder.categories()), ("c", &[0.0, 1.0, 2.0][..]));
+        assert!(matches!(&steps[2], FeatureStep::TargetEncode(encoder) if encoder.columns() == vec!["d"]));
+        assert_eq!(steps[3], FeatureStep::Impute(MissingParams::Skip));
+        assert!(evo.pipeline().to_string().contains("2. One-hot encode the 3 categories of \"c\""));
+        let mut parameters = evo.standardizer.parameters();
+        parameters.sort();
+        assert_eq!(parameters, vec!["c_0", "c_1", "c_2", "d", "t_hour", "t_weekday", "y"]);
+
+        // 2024-01-10 13:30 (a Wednesday) in categories 2 and 4, engineered by hand
+        let raw = HashMap::from([
+            ("t".to_string(), NEW_YEAR_2024 + 9.0 * 86_400.0 + 13.5 * 3600.0), ("c".to_string(), 2.0), ("d".to_string(), 4.0),
+        ]);
+        let encoder = evo.target_encoder.as_ref().unwrap();
+        let manual = HashMap::from([
+            ("t_weekday".to_string(), 2.0), ("t_hour".to_string(), 13.0),
+            ("c_0".to_string(), 0.0), ("c_1".to_string(), 0.0), ("c_2".to_string(), 1.0),
+            ("d".to_string(), encoder.category_encoding("d", 4.0).unwrap()),
+        ]);
+        assert_eq!(evo.engineered_point(&raw).unwrap(), manual);
+        // The manual point is already engineered, so it skips the pipeline
+        assert_eq!(evo.predict_point(&raw).unwrap(), evo.predicted(&manual, evo.config.missing_params));
+        assert_eq!(evo.predictor().predict_point(&raw), evo.predict_point(&raw).unwrap());
+        assert_eq!(evo.predict(std::slice::from_ref(&raw)), vec![evo.predict_point(&raw).unwrap()]);
+    }
+}
@@ -0,0 +1,170 @@
+use std::error::Error;
+use std::fmt;
+
+
+/// Formats a model's equation can be rendered in (see `Evolution::formula`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FormulaFormat {
+    /// Plain text such as "y = 1.5 * (x)^2"
+    Text,
+    /// LaTeX math such as "\mathrm{y} = 1.5 \cdot \left(\mathrm{x}\right)^{2}"
+    Latex,
+    /// A SQL expression such as "1.5 * POWER("x", 2) AS "y""
+    Sql,
+}
+
+/// How the numbers in a rendered formula are written.
+/// Both options give the same text on every platform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Precision {
+    /// The shortest text which parses back to exactly the same f32.
+    Full,
+    /// Rounded to the given number of decimal places.
+    Decimals(u8),
+}
+
+/// Why a model cannot be exported.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExportError {
+    /// A NaN or infinite value in layer "layer" (numbered from 1, as in the creature's
+    /// Display) on the term of input "param", where "previous_layer" is the term of the
+    /// previous layer's total and "bias" is the layer bias.
+    NonFiniteCoefficient { layer: usize, param: String },
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::NonFiniteCoefficient { layer, param } => write!(
+                f, "non-finite coefficient in layer {} on \"{}\"", layer, param
+            ),
+        }
+    }
+}
+
+impl Error for ExportError {}
+
+/// The pieces of an equation in each FormulaFormat.
+/// Every method returns a self-contained (parenthesized where needed) expression.
+impl FormulaFormat {
+    pub(crate) fn param(&self, name: &str) -> String {
+        match self {
+            FormulaFormat::Text => name.to_string(),
+            FormulaFormat::Latex => format!("\\mathrm{{{}}}", name.replace('_', "\\_")),
+            FormulaFormat::Sql => format!("\"{}\"", name.replace('"', "\"\"")),
+        }
+    }
+
+    pub(crate) fn number(&self, value: f32, precision: Precision) -> String {
+        let text = match precision {
+            Precision::Full => format!("{}", value),
+            Precision::Decimals(decimals) => format!("{:.*}", decimals as usize, value),
+        };
+        if text.starts_with('-') { format!("({})", text) } else { text }
+    }
+
+    pub(crate) fn product(&self, left: &str, right: &str) -> String {
+        match self {
+            FormulaFormat::Latex => format!("{} \\cdot {}", left, right),
+            _ => format!("{} * {}", left, right),
+        }
+    }
+
+    pub(crate) fn sum(&self, terms: &[String]) -> String {
+        match terms.len() {
+            0 => "0".to_string(),
+            _ => format!("({})", terms.join(" + ")),
+        }
+    }
+
+    pub(crate) fn power(&self, base: &str, exponent: u8) -> String {
+        match self {
+            FormulaFormat::Text => format!("({})^{}", base, exponent),
+            FormulaFormat::Latex => format!("\\left({}\\right)^{{{}}}", base, exponent),
+            FormulaFormat::Sql => format!("POWER({}, {})", base, exponent),
+        }
+    }
+
+    /// "term" when "value" compared against "threshold" holds, otherwise 0.
+    pub(crate) fn gated(&self, value: &str, above: bool, threshold: f32, term: &str, precision: Precision) -> String {
+        let threshold = self.number(threshold, precision);
+        match (self, above) {
+            (FormulaFormat::Text, true) => format!("({} >= {} ? {} : 0)", value, threshold, term),
+            (FormulaFormat::Text, false) => format!("({} < {} ? {} : 0)", value, threshold, term),
+            (FormulaFormat::Latex, true) => format!("\\mathbb{{1}}[{} \\geq {}] \\cdot {}", value, threshold, term),
+            (FormulaFormat::Latex, false) => format!("\\mathbb{{1}}[{} < {}] \\cdot {}", value, threshold, term),
+            (FormulaFormat::Sql, true) => format!("CASE WHEN {} >= {} THEN {} ELSE 0 END", value, threshold, term),
+            (FormulaFormat::Sql, false) => format!("CASE WHEN {} < {} THEN {} ELSE 0 END", value, threshold, term),
+        }
+    }
+
+    /// (value - mean) / stdev
+    pub(crate) fn standardized(&self, value: &str, mean: f32, stdev: f32, precision: Precision) -> String {
+        let (mean, stdev) = (self.number(mean, precision), self.number(stdev, precision));
+        match self {
+            FormulaFormat::Latex => format!("\\frac{{{} - {}}}{{{}}}", value, mean, stdev),
+            _ => format!("(({} - {}) / {})", value, mean, stdev),
+        }
+    }
+
+    /// value * stdev + mean
+    pub(crate) fn unstandardized(&self, value: &str, mean: f32, stdev: f32, precision: Precision) -> String {
+        format!("({} + {})", self.product(value, &self.number(stdev, precision)), self.number(mean, precision))
+    }
+
+    pub(crate) fn exp(&self, value: &str) -> String {
+        match self {
+            FormulaFormat::Text => format!("exp({})", value),
+            FormulaFormat::Latex => format!("e^{{{}}}", value),
+            FormulaFormat::Sql => format!("EXP({})", value),
+        }
+    }
+
+    /// 1 / (1 + exp(-value))
+    pub(crate) fn logistic(&self, value: &str) -> String {
+        let denominator = format!("1 + {}", self.exp(&format!("-{}", value)));
+        match self {
+            FormulaFormat::Latex => format!("\\frac{{1}}{{{}}}", denominator),
+            _ => format!("(1 / ({}))", denominator),
+        }
+    }
+
+    /// Assign the rendered expression to the target.
+    pub(crate) fn equation(&self, target: &str, expression: &str) -> String {
+        match self {
+            FormulaFormat::Sql => format!("{} AS {}", expression, self.param(target)),
+            _ => format!("{} = {}", self.param(target), expression),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formula_pieces() {
+        assert_eq!(FormulaFormat::Text.power("x", 2), "(x)^2");
+        assert_eq!(FormulaFormat::Sql.power("\"x\"", 2), "POWER(\"x\", 2)");
+        assert_eq!(FormulaFormat::Latex.param("max_temp"), "\\mathrm{max\\_temp}");
+        assert_eq!(FormulaFormat::Sql.param("a\"b"), "\"a\"\"b\"");
+        assert_eq!(FormulaFormat::Text.number(-1.5, Precision::Full), "(-1.5)");
+        assert_eq!(FormulaFormat::Text.number(1.0 / 3.0, Precision::Decimals(4)), "0.3333");
+        assert_eq!(FormulaFormat::Text.number(-0.00001, Precision::Decimals(2)), "(-0.00)");
+        assert_eq!(FormulaFormat::Text.sum(&[]), "0");
+        assert_eq!(FormulaFormat::Sql.equation("y", "1"), "1 AS \"y\"");
+    }
+
+    #[test]
+    fn full_precision_round_trips() {
+        let values = [0.1, 1.0 / 3.0, -2.0 / 7.0, 1.0e-7, 123456.79, f32::MAX, f32::MIN_POSITIVE, -0.0, 16777217.0];
+        for value in values {
+            for format in [FormulaFormat::Text, FormulaFormat::Latex, FormulaFormat::Sql] {
+                let text = format.number(value, Precision::Full);
+                let parsed: f32 = text.trim_start_matches('(').trim_end_matches(')').parse().unwrap();
+                assert_eq!(parsed.to_bits(), value.to_bits(), "{} -> {}", value, text);
+            }
+        }
+    }
+}
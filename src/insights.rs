@@ -0,0 +1,139 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use crate::creature::Creature;
+use crate::evolution::Evolution;
+
+
+/// Most used features listed in `Evolution::summary`.
+pub(crate) const SUMMARY_FEATURES: usize = 10;
+
+/// How a feature is used by the creatures of a `PopulationInsights`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeatureUsage {
+    pub feature: String,
+    /// Number of creatures with a term of this feature (or gated by it).
+    pub creatures: usize,
+    /// "creatures" as a fraction of all of the creatures.
+    pub fraction: f32,
+    /// Number of terms of this feature with each exponent.
+    pub exponents: BTreeMap<u8, usize>,
+}
+
+/// The structural choices of the best creatures of the final population,
+/// showing what the search settled on (see `Evolution::population_insights`).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PopulationInsights {
+    /// Number of creatures summarized.
+    pub creatures: usize,
+    /// Every feature used by any of the creatures, most used first (ties by name).
+    pub features: Vec<FeatureUsage>,
+    /// Number of creatures with each number of layers.
+    pub layer_counts: BTreeMap<usize, usize>,
+}
+
+impl PopulationInsights {
+    pub(crate) fn new(creatures: &[Creature]) -> PopulationInsights {
+        let mut usage: HashMap<&str, FeatureUsage> = HashMap::new();
+        let mut layer_counts = BTreeMap::new();
+        for creature in creatures {
+            *layer_counts.entry(creature.num_layers()).or_insert(0) += 1;
+            for feature in creature.parameters() {
+                usage.entry(feature).or_insert_with(|| FeatureUsage {
+                    feature: feature.to_string(),
+                    creatures: 0,
+                    fraction: 0.0,
+                    exponents: BTreeMap::new(),
+                }).creatures += 1;
+            }
+            for layer in creature.layers() {
+                let terms = layer.modifiers.iter()
+                    .chain(layer.gated_modifiers.iter().map(|gated| (&gated.param, &gated.coefficients)));
+                for (param, coefficients) in terms {
+                    let exponents = &mut usage.get_mut(param.as_str()).unwrap().exponents;
+                    *exponents.entry(coefficients.x).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut features: Vec<FeatureUsage> = usage.into_values()
+            .map(|feature| FeatureUsage { fraction: feature.creatures as f32 / creatures.len() as f32, ..feature })
+            .collect();
+        features.sort_by(|a, b| b.creatures.cmp(&a.creatures).then_with(|| a.feature.cmp(&b.feature)));
+        PopulationInsights { creatures: creatures.len(), features, layer_counts }
+    }
+
+    /// Table of the "limit" most used features with their usage and exponents.
+    pub fn feature_table(&self, limit: usize) -> String {
+        let mut table = format!("Feature Usage (top {} creatures):\n", self.creatures);
+        let width = self.features.iter().take(limit).map(|usage| usage.feature.len()).max().unwrap_or(0);
+        for usage in self.features.iter().take(limit) {
+            let exponents: Vec<String> = usage.exponents.iter().map(|(x, count)| format!("^{}: {}", x, count)).collect();
+            table += &format!("  {:width$}  {:>6.1}%  ({})\n", usage.feature, 100.0 * usage.fraction, exponents.join(", "), width = width);
+        }
+        if self.features.len() > limit {
+            table += &format!("  ... and {} more\n", self.features.len() - limit);
+        }
+        table
+    }
+}
+
+impl fmt::Display for PopulationInsights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.feature_table(self.features.len()))?;
+        let layers: Vec<String> = self.layer_counts.iter().map(|(layers, count)| format!("{}: {}", layers, count)).collect();
+        writeln!(f, "Layers: {}", layers.join(", "))
+    }
+}
+
+
+impl Evolution {
+    /// Feature usage, exponents per feature and layer counts among the top 1% (at least
+    /// one) of the creatures last scored in training.  Empty for models which were
+    /// not trained here (such as those loaded from a file) or were refit.
+    pub fn population_insights(&self) -> PopulationInsights {
+        PopulationInsights::new(&self.hall_of_fame)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::creature::Coefficients;
+
+    fn term(param: &str, x: u8) -> (String, Coefficients) {
+        (param.to_string(), Coefficients { c: 1.0, b: 1.0, z: 0.0, x })
+    }
+
+    #[test]
+    fn population_insights() {
+        let data = crate::data::synthetic::linear(60, 2, 0.1, 5);
+        let mut evo = Evolution::builder("y", &data).num_creatures(300).num_cycles(2).max_layers(3).build().unwrap();
+        assert_eq!(evo.population_insights().creatures, 3);
+        assert!(evo.summary().contains("Feature Usage (top 3 creatures):"));
+
+        evo.hall_of_fame = vec![
+            Creature::stacked(vec![vec![term("x1", 1), term("x2", 2)]], 0.0),
+            Creature::stacked(vec![vec![term("x1", 2)], vec![term("x1", 2)]], 0.5),
+            Creature::stacked(vec![vec![term("x1", 1)], vec![term("x3", 3)]], 0.0),
+            Creature::stacked(vec![vec![]], 1.0),
+        ];
+        let insights = evo.population_insights();
+        assert_eq!(insights.creatures, 4);
+        assert_eq!(insights.layer_counts, BTreeMap::from([(1, 2), (2, 2)]));
+        assert_eq!(insights.features, vec![
+            FeatureUsage { feature: "x1".to_string(), creatures: 3, fraction: 0.75, exponents: BTreeMap::from([(1, 2), (2, 2)]) },
+            FeatureUsage { feature: "x2".to_string(), creatures: 1, fraction: 0.25, exponents: BTreeMap::from([(2, 1)]) },
+            FeatureUsage { feature: "x3".to_string(), creatures: 1, fraction: 0.25, exponents: BTreeMap::from([(3, 1)]) },
+        ]);
+        assert_eq!(insights.feature_table(2), "Feature Usage (top 4 creatures):\n  x1    75.0%  (^1: 2, ^2: 2)\n  x2    25.0%  (^2: 1)\n  ... and 1 more\n");
+        assert!(insights.to_string().ends_with("Layers: 1: 2, 2: 2\n"));
+        assert!(evo.summary().contains("  x3    25.0%  (^3: 1)\n"));
+
+        evo.hall_of_fame.clear();
+        assert_eq!(evo.population_insights().features, Vec::new());
+        assert!(!evo.summary().contains("Feature Usage"));
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::data::{input_columns, paired_values};
+
+
+/// Largest residual (relative to the target's range) of a least squares line
+/// for which a feature is considered an affine copy of the target.
+const AFFINE_TOLERANCE: f64 = 1e-6;
+
+
+/// A feature which looks like it leaks the target into the inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeakageWarning {
+    pub feature: String,
+    /// Pearson correlation of the feature with the target.
+    pub correlation: f32,
+    /// Whether the target is exactly "a * feature + b" for some a and b.
+    pub affine_copy: bool,
+}
+
+impl fmt::Display for LeakageWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.affine_copy {
+            true => write!(f, "\"{}\" is an affine copy of the target (correlation {:.4})", self.feature, self.correlation),
+            false => write!(f, "\"{}\" is suspiciously correlated with the target ({:.4})", self.feature, self.correlation),
+        }
+    }
+}
+
+/// Checks run on the data before training starts.
+#[derive(Clone, Debug, Default)]
+pub struct PreprocessingReport {
+    pub leakage: Vec<LeakageWarning>,
+    /// Number of duplicate rows folded into the weight of an identical row
+    /// (see `EvolutionConfig::deduplicate_rows`).
+    pub rows_folded: usize,
+    /// Number of training rows dropped for having values outside of `EvolutionConfig::valid_ranges`.
+    pub rows_dropped: usize,
+    /// Number of rows outside of each violated column's valid range, by column
+    /// (a row can be outside of several).
+    pub range_violations: Vec<(String, usize)>,
+}
+
+
+/// Features (in sorted order) whose absolute correlation with "target"
+/// exceeds "threshold" or which equal the target after an affine transformation.
+pub fn check_target_leakage(target: &str, data: &[HashMap<String, f32>], threshold: f32) -> Vec<LeakageWarning> {
+    input_columns(data, target).into_iter().filter_map(|feature| {
+        let (values, target_values) = paired_values(data, feature, target);
+        let correlation = correlation(&values, &target_values)?;
+        let affine_copy = is_affine_copy(&values, &target_values);
+        match affine_copy || correlation.abs() > threshold as f64 {
+            true => Some(LeakageWarning { feature: feature.to_string(), correlation: correlation as f32, affine_copy }),
+            false => None,
+        }
+    }).collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Pearson correlation, or None if either series is constant.
+pub(crate) fn correlation(x: &[f64], y: &[f64]) -> Option<f64> {
+    let (x_mean, y_mean) = (mean(x), mean(y));
+    let mut covariance = 0.0;
+    let mut x_variance = 0.0;
+    let mut y_variance = 0.0;
+    for (x, y) in x.iter().zip(y) {
+        covariance += (x - x_mean) * (y - y_mean);
+        x_variance += (x - x_mean).powi(2);
+        y_variance += (y - y_mean).powi(2);
+    }
+    match x_variance > 0.0 && y_variance > 0.0 {
+        true => Some(covariance / (x_variance * y_variance).sqrt()),
+        false => None,
+    }
+}
+
+/// Whether a least squares line through (x, y) fits every point exactly.
+fn is_affine_copy(x: &[f64], y: &[f64]) -> bool {
+    let (x_mean, y_mean) = (mean(x), mean(y));
+    let x_variance: f64 = x.iter().map(|x| (x - x_mean).powi(2)).sum();
+    if x_variance == 0.0 {
+        return false;
+    }
+    let slope = x.iter().zip(y).map(|(x, y)| (x - x_mean) * (y - y_mean)).sum::<f64>() / x_variance;
+    let intercept = y_mean - slope * x_mean;
+    let y_range = y.iter().cloned().fold(f64::NEG_INFINITY, f64::max) - y.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max_residual = x.iter().zip(y).map(|(x, y)| (slope * x + intercept - y).abs()).fold(0.0, f64::max);
+    max_residual <= AFFINE_TOLERANCE * y_range.max(f64::MIN_POSITIVE)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConfigError, EvolutionConfig};
+    use crate::evolution::Evolution;
+
+    fn leaky_data() -> Vec<HashMap<String, f32>> {
+        (0..40).map(|i| {
+            let y = (i as f32 * 0.37).sin() * 10.0 + i as f32;
+            let noise = ((i * 7) % 5) as f32 - 2.0;
+            HashMap::from([
+                ("y".to_string(), y),
+                ("copy".to_string(), y),
+                ("affine".to_string(), 3.0 * y - 12.5),
+                ("strong".to_string(), y + noise),
+                ("x".to_string(), (i % 6) as f32),
+            ])
+        }).collect()
+    }
+
+    #[test]
+    fn flags_copies_of_the_target() {
+        let data = leaky_data();
+        let warnings = check_target_leakage("y", &data, 0.995);
+        let flagged: Vec<&str> = warnings.iter().map(|warning| warning.feature.as_str()).collect();
+        assert_eq!(flagged, vec!["affine", "copy"]);
+        assert!(warnings.iter().all(|warning| warning.affine_copy && warning.correlation > 0.9999));
+
+        // The strongly correlated feature sits just under the default threshold
+        let strong = check_target_leakage("y", &data, 0.99);
+        let strong = strong.iter().find(|warning| warning.feature == "strong").unwrap();
+        assert!(!strong.affine_copy);
+        assert!(strong.correlation > 0.99 && strong.correlation <= 0.995);
+    }
+
+    #[test]
+    fn leakage_in_preprocessing_report() {
+        let config = EvolutionConfig { num_creatures: 200, num_cycles: 1, ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &leaky_data(), &config).unwrap();
+        assert_eq!(evo.preprocessing_report().leakage.len(), 2);
+    }
+
+    #[test]
+    fn strict_leakage_fails_validation() {
+        let data = leaky_data();
+        let Err(error) = Evolution::builder("y", &data).num_creatures(200).num_cycles(1).strict_leakage(0.99).build() else {
+            panic!("Leaky data was accepted")
+        };
+        let ConfigError::Multiple(errors) = &error else { panic!("Expected three problems, got {}", error) };
+        let flagged: Vec<&str> = errors.iter().map(|error| match error {
+            ConfigError::Leakage(warning) => warning.feature.as_str(),
+            other => panic!("Unexpected problem: {}", other),
+        }).collect();
+        assert_eq!(flagged, vec!["affine", "copy", "strong"]);
+        assert!(error.to_string().contains("possible target leakage: \"copy\" is an affine copy of the target"));
+
+        // Only reported (not an error) without the strict flag
+        let relaxed = EvolutionConfig { leakage_threshold: 0.99, ..Default::default() };
+        assert_eq!(relaxed.validate("y", &data), Ok(()));
+    }
+}
@@ -1,4 +1,83 @@
+// Unused code is an error so half-finished features cannot linger unnoticed
+#![deny(unused)]
+
 mod creature;
 mod standardize;
 mod util;
 mod evolution;
+mod config;
+mod builder;
+mod link;
+mod formula;
+mod loss;
+mod leakage;
+mod refit;
+pub mod data;
+pub use data::synthetic;
+pub mod prelude;
+mod predictor;
+mod scaling;
+mod selection;
+mod metrics;
+mod robustness;
+mod time_series;
+mod mutator;
+mod lookup;
+mod linear;
+mod seeding;
+mod quantize;
+mod encoding;
+mod events;
+mod ranges;
+mod budget;
+mod stability;
+mod feature_search;
+mod trace;
+mod template;
+mod insights;
+mod features;
+mod samplers;
+mod pin;
+#[cfg(feature = "polars")]
+mod dataframe;
+#[cfg(feature = "serde")]
+mod bundle;
+#[cfg(feature = "serde")]
+mod ensemble;
+
+pub use creature::{CreationOptions, Creature, MutateSpeed, MutationOptions};
+pub use evolution::{CycleStats, DetailedPrediction, DeterminismReport, Evolution, EvolutionError, MinImprovement, OptimizeChoice, OptimizeProgress, Outcome,
+                    PredictError, PreprocessFn};
+pub use config::{ConfigError, EvolutionConfig, PopulationLimit, Verbosity};
+pub use encoding::{TargetEncoder, TargetEncoding};
+pub use features::{DatetimeExpansion, DatetimePart, FeaturePipeline, FeatureStep, OneHotEncoder};
+pub use events::{CycleCallback, CycleReport, EventReceiver, EvolutionEvent, EVENT_CAPACITY};
+pub use builder::{ConfigBuilder, EvolutionBuilder};
+pub use link::{ErrorScale, Link};
+pub use formula::{ExportError, FormulaFormat, Precision};
+pub use loss::{CustomLoss, DatasetAggregation, Loss, LossFunction};
+pub use mutator::{GaussianMutator, Mutator};
+pub use samplers::{CoefficientPriors, TriangularPrior};
+pub use pin::{Pin, PinSpec};
+pub use refit::RefitConfig;
+pub use leakage::{check_target_leakage, LeakageWarning, PreprocessingReport};
+pub use predictor::Predictor;
+pub use lookup::{LookupError, LookupTable, MAX_LOOKUP_ENTRIES, MAX_LOOKUP_FEATURES};
+pub use linear::{CoefficientStats, LinearInferenceError, RidgeFit, RidgePenalty, DEFAULT_RIDGE_LAMBDA, INTERCEPT, PREVIOUS_LAYER};
+pub use scaling::{ParallelEfficiency, ThreadScaling};
+pub use selection::SelectionCriterion;
+pub use stability::StabilityReport;
+pub use feature_search::{FeatureSearch, SearchStep};
+pub use trace::{CalculationTrace, Divergence, TraceStep};
+pub use template::{Template, TemplateError};
+pub use insights::{FeatureUsage, PopulationInsights};
+pub use metrics::{Metric, MetricReport, ModelStats};
+pub use robustness::{RobustnessReport, Sensitivity};
+pub use time_series::{RollingCvConfig, Window};
+#[cfg(feature = "polars")]
+pub use dataframe::DataFrameError;
+#[cfg(feature = "serde")]
+pub use bundle::{BundleError, LoadError};
+#[cfg(feature = "serde")]
+pub use ensemble::{Aggregation, EnsembleError, EnsembleModel};
+pub use standardize::{RoundtripError, Standardizer};
@@ -0,0 +1,483 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use crate::creature::Creature;
+use crate::evolution::{Evolution, PredictError};
+
+
+/// Smallest fraction of a column's sum of squares which must be left after
+/// projecting it onto the earlier columns for the design matrix to count as full rank.
+const RANK_TOLERANCE: f64 = 1e-10;
+
+/// Name given to the intercept (the best creature's last layer bias) in `Evolution::linear_inference`.
+pub const INTERCEPT: &str = "intercept";
+
+/// Name given to the term of the previous layer's total in `Evolution::linear_inference`.
+pub const PREVIOUS_LAYER: &str = "previous layer";
+
+/// Default L2 penalty of `RidgePenalty::Fixed`, just enough for numerical stability.
+pub const DEFAULT_RIDGE_LAMBDA: f64 = 1e-6;
+
+/// Penalties tried by `RidgePenalty::GeneralizedCrossValidation`: 10^-8 to 10^2 in steps of 10^0.25.
+const GCV_LAMBDAS: std::ops::RangeInclusive<i32> = -32..=8;
+
+
+/// Estimate and inference statistics of one coefficient of a least squares fit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoefficientStats {
+    pub name: String,
+    pub estimate: f64,
+    /// Standard error of the estimate.
+    pub std_err: f64,
+    /// "estimate / std_err"
+    pub t_stat: f64,
+}
+
+/// How `Evolution::ridge_refit` chooses its L2 penalty "lambda" on the
+/// multipliers of the terms (the intercept is not penalized).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RidgePenalty {
+    Fixed(f64),
+    /// The penalty with the lowest generalized cross-validation score
+    /// n * RSS / (n - df)² (with "df" the effective number of coefficients).
+    GeneralizedCrossValidation,
+}
+
+impl Default for RidgePenalty {
+    fn default() -> RidgePenalty {
+        RidgePenalty::Fixed(DEFAULT_RIDGE_LAMBDA)
+    }
+}
+
+/// Result of `Evolution::ridge_refit`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RidgeFit {
+    /// The penalty used (the chosen one with `RidgePenalty::GeneralizedCrossValidation`).
+    pub lambda: f64,
+    /// Effective number of coefficients, which shrinks as the penalty grows.
+    pub effective_coefficients: f64,
+    pub coefficients: Vec<CoefficientStats>,
+}
+
+/// Why `Evolution::linear_inference` could not be calculated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LinearInferenceError {
+    /// Too few rows to estimate the coefficients and the residual variance.
+    TooFewRows { rows: usize, coefficients: usize },
+    /// A column is (nearly) a linear combination of the intercept and the columns before it.
+    RankDeficient { column: String },
+    /// The row at index "row" of the data is missing the target or an input parameter
+    /// of the model (which `EvolutionConfig::missing_params` does not fill in).
+    MissingColumn { row: usize, column: String },
+    /// Column names of the row at index "row" of the data which are the same once
+    /// normalized (see `ColumnNames::Normalized`).
+    ColumnCollision { row: usize, normalized: String, columns: Vec<String> },
+    /// A `RidgePenalty::Fixed` penalty which is negative or not finite.
+    InvalidPenalty(f64),
+}
+
+impl fmt::Display for LinearInferenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinearInferenceError::TooFewRows { rows, coefficients } => {
+                write!(f, "{} rows are too few for {} coefficients (at least {} are needed)", rows, coefficients, coefficients + 1)
+            },
+            LinearInferenceError::RankDeficient { column } => {
+                write!(f, "The design matrix is rank-deficient: \"{}\" is a linear combination of the columns before it", column)
+            },
+            LinearInferenceError::MissingColumn { row, column } => write!(f, "Row {} of the data has no \"{}\"", row, column),
+            LinearInferenceError::ColumnCollision { row, normalized, columns } => {
+                let columns: Vec<String> = columns.iter().map(|column| format!("\"{}\"", column)).collect();
+                write!(f, "Row {} of the data has columns {} all named \"{}\" once normalized", row, columns.join(", "), normalized)
+            },
+            LinearInferenceError::InvalidPenalty(lambda) => write!(f, "The ridge penalty must be a finite number of at least 0, not {}", lambda),
+        }
+    }
+}
+
+impl Error for LinearInferenceError {}
+
+
+impl Evolution {
+    /// Least squares refit over "data" of the coefficients of the best creature which
+    /// enter its output linearly: the multiplier "c" of each term of its last layer
+    /// and that layer's bias, with the rest of the creature held fixed.  Gives the
+    /// refit estimates with the standard error and t-statistic of each from the
+    /// residual variance and (XᵀX)⁻¹.  The fit is to the standardized (and linked)
+    /// target the creature outputs, so the estimates are on the scale of the
+    /// creature's own coefficients.  The intercept is named INTERCEPT, the previous
+    /// layer term PREVIOUS_LAYER, gated terms "{param} if {gate_param}" and the other
+    /// terms by their parameter.  The model itself is left unchanged.
+    pub fn linear_inference(&self, data: &[HashMap<String, f32>]) -> Result<Vec<CoefficientStats>, LinearInferenceError> {
+        let problem = self.linear_problem(data)?;
+        let solution = problem.solve(0.0)?;
+        Ok(problem.coefficient_stats(solution))
+    }
+
+    /// Refit the best creature's coefficients as in "linear_inference" but with an
+    /// L2 (ridge) penalty on the multipliers of the terms, which keeps them moderate
+    /// when terms are (nearly) collinear, and keep the refit coefficients in the
+    /// model.  The penalty scales with the spread of each term's outputs, so its
+    /// strength does not depend on their units, and standard errors come from the
+    /// covariance σ² A⁻¹XᵀXA⁻¹ of the penalized estimates (with A = XᵀX + penalty).
+    /// The penalty used is kept as `Evolution::ridge_lambda`, while the metrics
+    /// and history of training are left as they were.
+    pub fn ridge_refit(&mut self, data: &[HashMap<String, f32>], penalty: RidgePenalty) -> Result<RidgeFit, LinearInferenceError> {
+        let problem = self.linear_problem(data)?;
+        let solution = match penalty {
+            RidgePenalty::Fixed(lambda) if !(lambda >= 0.0 && lambda.is_finite()) => {
+                return Err(LinearInferenceError::InvalidPenalty(lambda));
+            },
+            RidgePenalty::Fixed(lambda) => problem.solve(lambda)?,
+            RidgePenalty::GeneralizedCrossValidation => {
+                let n = problem.rows.len() as f64;
+                let mut best: Option<(f64, Solution)> = None;
+                for exponent in GCV_LAMBDAS {
+                    let solution = problem.solve(10f64.powf(exponent as f64 / 4.0))?;
+                    let score = n * solution.residual_sum_of_squares / (n - solution.effective_coefficients).powi(2);
+                    if best.as_ref().is_none_or(|(best_score, _)| score < *best_score) {
+                        best = Some((score, solution));
+                    }
+                }
+                best.unwrap().1
+            },
+        };
+        let multipliers: Vec<f32> = solution.estimates[1..].iter().map(|estimate| *estimate as f32).collect();
+        self.best_creature = self.best_creature.with_linear_coefficients(solution.estimates[0] as f32, &multipliers);
+        self.ridge_lambda = Some(solution.lambda);
+        Ok(RidgeFit {
+            lambda: solution.lambda,
+            effective_coefficients: solution.effective_coefficients,
+            coefficients: problem.coefficient_stats(solution),
+        })
+    }
+
+    /// The least squares problem of "linear_inference", whose design matrix columns
+    /// are the outputs of each last layer term with a multiplier of 1 and whose
+    /// targets have the output of the rest of the creature taken off.
+    fn linear_problem(&self, data: &[HashMap<String, f32>]) -> Result<LinearProblem, LinearInferenceError> {
+        let creature = &self.best_creature;
+        let terms = linear_terms(creature);
+        let offset_creature = creature.with_linear_coefficients(0.0, &vec![0.0; terms.len()]);
+        let term_creatures: Vec<Creature> = (0..terms.len()).map(|term| {
+            let mut multipliers = vec![0.0; terms.len()];
+            multipliers[term] = 1.0;
+            creature.with_linear_coefficients(0.0, &multipliers)
+        }).collect();
+        let parameters = creature.parameters();
+        let target_standardizer = self.standardizer.param_standardizer(&self.target)
+            .expect("The standardizer is fit to the target");
+
+        let mut rows = Vec::with_capacity(data.len());
+        let mut targets = Vec::with_capacity(data.len());
+        for (row, point) in data.iter().enumerate() {
+            let missing = |column: &str| LinearInferenceError::MissingColumn { row, column: column.to_string() };
+            let point = self.prepared_point(point).map_err(|error| match error {
+                PredictError::ColumnCollision { normalized, columns } => LinearInferenceError::ColumnCollision { row, normalized, columns },
+                error => unreachable!("preparing a point only fails on a column collision, not {:?}", error),
+            })?;
+            let target = *point.get(&self.target).ok_or_else(|| missing(&self.target))?;
+            let mut standardized_point = self.standardizer.standardized_value(&point);
+            self.standardizer.fill_missing(&mut standardized_point, &self.target, self.config.missing_params);
+            if let Some(parameter) = parameters.iter().find(|parameter| !standardized_point.contains_key(**parameter)) {
+                return Err(missing(parameter));
+            }
+            let output = |creature: &Creature| creature.calculate_with(&standardized_point, self.config.strict_fp) as f64;
+            let offset = output(&offset_creature);
+            rows.push(std::iter::once(1.0).chain(term_creatures.iter().map(|term| output(term) - offset)).collect());
+            targets.push(target_standardizer.standardize(&self.config.link.apply(target)) as f64 - offset);
+        }
+        let names = std::iter::once(INTERCEPT.to_string()).chain(terms).collect();
+        Ok(LinearProblem { names, rows, targets })
+    }
+}
+
+/// Names of the terms of the last layer of "creature", in the order
+/// of the multipliers of `Creature::with_linear_coefficients`.
+fn linear_terms(creature: &Creature) -> Vec<String> {
+    creature.layers().last().into_iter().flat_map(|layer| {
+        layer.modifiers.sorted().into_iter().map(|(param, _)| param.clone())
+            .chain(layer.previous_layer_coefficients.iter().map(|_| PREVIOUS_LAYER.to_string()))
+            .chain(layer.gated_modifiers.iter().map(|gated| format!("{} if {}", gated.param, gated.gate_param)))
+    }).collect()
+}
+
+
+/// Design matrix "rows" (with an intercept column first) and "targets" of a least squares fit.
+struct LinearProblem {
+    names: Vec<String>,
+    rows: Vec<Vec<f64>>,
+    targets: Vec<f64>,
+}
+
+struct Solution {
+    lambda: f64,
+    estimates: Vec<f64>,
+    std_errs: Vec<f64>,
+    residual_sum_of_squares: f64,
+    effective_coefficients: f64,
+}
+
+impl LinearProblem {
+    /// Solve with a penalty of "lambda" times each (non-intercept) column's
+    /// sum of squared deviations from its mean.
+    fn solve(&self, lambda: f64) -> Result<Solution, LinearInferenceError> {
+        let n = self.rows.len() as f64;
+        let penalty: Vec<f64> = (0..self.names.len()).map(|column| match column {
+            0 => 0.0,
+            _ => {
+                let mean = self.rows.iter().map(|row| row[column]).sum::<f64>() / n;
+                lambda * self.rows.iter().map(|row| (row[column] - mean).powi(2)).sum::<f64>()
+            },
+        }).collect();
+        least_squares(&self.rows, &self.targets, &penalty)
+            .map(|(estimates, std_errs, residual_sum_of_squares, effective_coefficients)| Solution {
+                lambda, estimates, std_errs, residual_sum_of_squares, effective_coefficients
+            })
+            .map_err(|error| match error {
+                LeastSquaresError::TooFewRows => LinearInferenceError::TooFewRows { rows: self.rows.len(), coefficients: self.names.len() },
+                LeastSquaresError::RankDeficient(column) => LinearInferenceError::RankDeficient { column: self.names[column].clone() },
+            })
+    }
+
+    fn coefficient_stats(&self, solution: Solution) -> Vec<CoefficientStats> {
+        self.names.iter().zip(solution.estimates).zip(solution.std_errs).map(|((name, estimate), std_err)| CoefficientStats {
+            name: name.clone(),
+            estimate,
+            std_err,
+            t_stat: estimate / std_err,
+        }).collect()
+    }
+}
+
+
+enum LeastSquaresError {
+    TooFewRows,
+    /// Index of the first column which is a linear combination of those before it.
+    RankDeficient(usize),
+}
+
+/// (estimates, standard errors, residual sum of squares, effective number of
+/// coefficients) of the least squares solution of "rows" (the design matrix) times
+/// the coefficients = "targets", with "penalty" added to the diagonal of XᵀX.
+#[allow(clippy::type_complexity)]
+fn least_squares(rows: &[Vec<f64>], targets: &[f64], penalty: &[f64]) -> Result<(Vec<f64>, Vec<f64>, f64, f64), LeastSquaresError> {
+    let n_coefficients = rows.first().map_or(0, |row| row.len());
+    if rows.len() <= n_coefficients {
+        return Err(LeastSquaresError::TooFewRows);
+    }
+    let mut xtx = vec![vec![0.0; n_coefficients]; n_coefficients];
+    let mut xty = vec![0.0; n_coefficients];
+    for (row, target) in rows.iter().zip(targets) {
+        for i in 0..n_coefficients {
+            xty[i] += row[i] * target;
+            for j in 0..n_coefficients {
+                xtx[i][j] += row[i] * row[j];
+            }
+        }
+    }
+    let mut penalized = xtx.clone();
+    for (i, penalty) in penalty.iter().enumerate() {
+        penalized[i][i] += penalty;
+    }
+    let inverse = symmetric_inverse(penalized).map_err(LeastSquaresError::RankDeficient)?;
+
+    let estimates: Vec<f64> = inverse.iter()
+        .map(|inverse_row| inverse_row.iter().zip(&xty).map(|(a, b)| a * b).sum())
+        .collect();
+    let residual_sum_of_squares: f64 = rows.iter().zip(targets).map(|(row, target)| {
+        let fitted: f64 = row.iter().zip(&estimates).map(|(x, estimate)| x * estimate).sum();
+        (target - fitted).powi(2)
+    }).sum();
+
+    // Covariance of the estimates is σ² A⁻¹XᵀXA⁻¹, which is σ² A⁻¹ without a penalty,
+    // and the effective number of coefficients is the trace of A⁻¹XᵀX
+    let product = matrix_product(&inverse, &xtx);
+    let effective_coefficients: f64 = (0..n_coefficients).map(|i| product[i][i]).sum();
+    let residual_variance = residual_sum_of_squares / (rows.len() as f64 - effective_coefficients);
+    let covariance = matrix_product(&product, &inverse);
+    let std_errs = (0..n_coefficients).map(|i| (residual_variance * covariance[i][i]).sqrt()).collect();
+    Ok((estimates, std_errs, residual_sum_of_squares, effective_coefficients))
+}
+
+fn matrix_product(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    a.iter().map(|a_row| {
+        (0..b[0].len()).map(|j| a_row.iter().zip(b).map(|(a, b_row)| a * b_row[j]).sum()).collect()
+    }).collect()
+}
+
+/// Inverse of the symmetric positive semi-definite "matrix" by Gauss-Jordan
+/// elimination in column order, or the index of the first column which
+/// leaves (nearly) nothing once the columns before it are eliminated.
+fn symmetric_inverse(mut matrix: Vec<Vec<f64>>) -> Result<Vec<Vec<f64>>, usize> {
+    let size = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..size).map(|i| (0..size).map(|j| (i == j) as u8 as f64).collect()).collect();
+    let diagonal: Vec<f64> = (0..size).map(|i| matrix[i][i]).collect();
+    for column in 0..size {
+        let pivot = matrix[column][column];
+        if !(diagonal[column] > 0.0 && pivot > RANK_TOLERANCE * diagonal[column]) {
+            return Err(column);
+        }
+        for j in 0..size {
+            matrix[column][j] /= pivot;
+            inverse[column][j] /= pivot;
+        }
+        for row in 0..size {
+            let factor = matrix[row][column];
+            if row != column && factor != 0.0 {
+                for j in 0..size {
+                    matrix[row][j] -= factor * matrix[column][j];
+                    inverse[row][j] -= factor * inverse[column][j];
+                }
+            }
+        }
+    }
+    Ok(inverse)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EvolutionConfig;
+    use crate::creature::Coefficients;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn points(columns: &[(&str, &[f32])]) -> Vec<HashMap<String, f32>> {
+        (0..columns[0].1.len())
+            .map(|row| columns.iter().map(|(name, values)| (name.to_string(), values[row])).collect())
+            .collect()
+    }
+
+    /// A model trained on "data" with "creature" as its best creature.
+    fn trained(data: &[HashMap<String, f32>], creature: Creature) -> Evolution {
+        let config = EvolutionConfig { num_creatures: 100, num_cycles: 1, ..Default::default() };
+        let mut evo = Evolution::with_config("y".into(), data, &config).unwrap();
+        evo.best_creature = creature;
+        evo
+    }
+
+    /// Single layer creature with a linear term of each of "params".
+    fn linear_creature(params: &[&str]) -> Creature {
+        let terms = params.iter().map(|param| (param.to_string(), Coefficients { c: 3.0, b: 1.0, z: 0.0, x: 1 })).collect();
+        Creature::stacked(vec![terms], 0.5)
+    }
+
+    #[test]
+    fn matches_hand_computed_values() {
+        // y = 2.2 + 0.6 x with residuals (-0.8, 0.6, 1.0, -0.6, -0.2), so the residual
+        // variance is 2.4 / 3 = 0.8 and with Sxx = 10 and a mean x of 3:
+        //   se(slope) = sqrt(0.8 / 10)   se(intercept) = sqrt(0.8 * (1 / 5 + 9 / 10))
+        // On the standardized scale of the creature (with sample variances of 2.5 for x
+        // and 1.5 for y) the intercept is 0 and the slope and its standard error are
+        // scaled by sqrt(2.5 / 1.5), leaving the t-statistic of the slope unchanged.
+        let data = points(&[("x", &[1.0, 2.0, 3.0, 4.0, 5.0]), ("y", &[2.0, 4.0, 5.0, 4.0, 5.0])]);
+        let stats = trained(&data, linear_creature(&["x"])).linear_inference(&data).unwrap();
+        let scale = (2.5f64 / 1.5).sqrt();
+        let expected = [(INTERCEPT, 0.0, (0.8 / 1.5 / 5.0f64).sqrt()), ("x", 0.6 * scale, 0.08f64.sqrt() * scale)];
+        assert_eq!(stats.len(), 2);
+        for (stats, (name, estimate, std_err)) in stats.iter().zip(expected) {
+            assert_eq!(stats.name, name);
+            assert!((stats.estimate - estimate).abs() < 1e-5);
+            assert!((stats.std_err - std_err).abs() < 1e-5);
+        }
+        assert!((stats[1].t_stat - 0.6 / 0.08f64.sqrt()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn refits_the_last_layer() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let x: Vec<f32> = (0..80).map(|i| i as f32 * 0.05).collect();
+        let y: Vec<f32> = x.iter().map(|x| 1.0 + x * x - 2.0 * x + rng.gen_range(-0.2..0.2)).collect();
+        let data = points(&[("x", &x), ("y", &y)]);
+        // The squared term of the first layer is held fixed while the badly
+        // chosen linear term and bias of the last layer are refit
+        let creature = Creature::stacked(vec![
+            vec![("x".to_string(), Coefficients { c: 0.5, b: 1.0, z: 0.0, x: 2 })],
+            vec![("x".to_string(), Coefficients { c: 4.0, b: 1.0, z: 0.0, x: 1 })],
+        ], 2.0);
+        let mut evo = trained(&data, creature);
+        let stats = evo.linear_inference(&data).unwrap();
+        assert_eq!(stats.iter().map(|stats| stats.name.as_str()).collect::<Vec<_>>(), vec![INTERCEPT, "x"]);
+
+        let squared_error = |evo: &Evolution| evo.predict(&data).iter().zip(&y).map(|(predicted, y)| (predicted - y).powi(2)).sum::<f32>();
+        let before = squared_error(&evo);
+        evo.best_creature = evo.best_creature.with_linear_coefficients(stats[0].estimate as f32, &[stats[1].estimate as f32]);
+        assert!(squared_error(&evo) < before);
+        // Refitting the refit creature changes nothing
+        for (refit, stats) in evo.linear_inference(&data).unwrap().iter().zip(&stats) {
+            assert!((refit.estimate - stats.estimate).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn rank_deficient_design() {
+        let data = points(&[
+            ("x", &[1.0, 2.0, 3.0, 4.0, 5.0]),
+            ("z", &[2.0, 4.0, 6.0, 8.0, 10.0]),
+            ("y", &[2.0, 4.0, 5.0, 4.0, 5.0]),
+        ]);
+        let evo = trained(&data, linear_creature(&["x", "z"]));
+        let error = evo.linear_inference(&data).unwrap_err();
+        assert_eq!(error, LinearInferenceError::RankDeficient { column: "z".to_string() });
+        assert!(error.to_string().contains("rank-deficient"));
+        assert_eq!(evo.linear_inference(&data[..3]), Err(LinearInferenceError::TooFewRows { rows: 3, coefficients: 3 }));
+
+        let mut sparse = data.clone();
+        sparse[2].remove("z");
+        assert_eq!(evo.linear_inference(&sparse), Err(LinearInferenceError::MissingColumn { row: 2, column: "z".to_string() }));
+        sparse[1].remove("y");
+        let error = evo.linear_inference(&sparse).unwrap_err();
+        assert_eq!(error, LinearInferenceError::MissingColumn { row: 1, column: "y".to_string() });
+        assert_eq!(error.to_string(), "Row 1 of the data has no \"y\"");
+    }
+
+    #[test]
+    fn ridge_on_collinear_features() {
+        // y = x + w with w a copy of x plus tiny noise
+        let mut rng = StdRng::seed_from_u64(5);
+        let x: Vec<f32> = (0..60).map(|i| i as f32 * 0.1).collect();
+        let w: Vec<f32> = x.iter().map(|x| x + rng.gen_range(-1e-3..1e-3)).collect();
+        let y: Vec<f32> = x.iter().zip(&w).map(|(x, w)| x + w + rng.gen_range(-0.5..0.5)).collect();
+        let data = points(&[("w", &w), ("x", &x), ("y", &y)]);
+        let mut evo = trained(&data, linear_creature(&["w", "x"]));
+        assert_eq!(evo.ridge_lambda(), None);
+
+        let plain = evo.linear_inference(&data).unwrap();
+        let unbalanced = (plain[1].estimate - plain[2].estimate).abs();
+        println!("Least squares: w = {} +/- {}, x = {} +/- {}", plain[1].estimate, plain[1].std_err, plain[2].estimate, plain[2].std_err);
+        assert!(unbalanced > 1.0);
+        assert!(plain[1].std_err > 10.0);
+
+        for penalty in [RidgePenalty::Fixed(1e-3), RidgePenalty::GeneralizedCrossValidation] {
+            let ridge = evo.ridge_refit(&data, penalty).unwrap();
+            let (w, x) = (&ridge.coefficients[1], &ridge.coefficients[2]);
+            println!("Ridge (lambda {}): w = {} +/- {}, x = {} +/- {}", ridge.lambda, w.estimate, w.std_err, x.estimate, x.std_err);
+            assert!(ridge.coefficients.iter().all(|stats| stats.estimate.is_finite() && stats.std_err.is_finite()));
+            assert!((w.estimate - x.estimate).abs() < 0.1);
+            // With y nearly 2 x the standardized multipliers add up to nearly 1
+            assert!((w.estimate + x.estimate - 1.0).abs() < 0.1);
+            assert!(w.std_err < plain[1].std_err);
+            assert!(ridge.effective_coefficients < 3.0);
+
+            // The model keeps the refit coefficients and the penalty
+            let refit: Vec<f32> = evo.best_creature().coefficient_values().iter().map(|(c, ..)| *c).collect();
+            assert_eq!(refit, vec![w.estimate as f32, x.estimate as f32]);
+            assert_eq!(evo.ridge_lambda(), Some(ridge.lambda));
+            let predicted = evo.predict(&data);
+            let mse = predicted.iter().zip(&y).map(|(predicted, y)| (predicted - y).powi(2)).sum::<f32>() / y.len() as f32;
+            assert!(mse < 0.1, "Mean squared error of {} after the ridge refit", mse);
+        }
+        assert_eq!(evo.ridge_refit(&data, RidgePenalty::Fixed(1e-3)).unwrap().lambda, 1e-3);
+        assert_eq!(evo.ridge_refit(&data, RidgePenalty::Fixed(-1.0)), Err(LinearInferenceError::InvalidPenalty(-1.0)));
+        assert_eq!(evo.ridge_lambda(), Some(1e-3));
+
+        // Without a penalty it matches least squares
+        let unpenalized = evo.ridge_refit(&data, RidgePenalty::Fixed(0.0)).unwrap();
+        assert!((unpenalized.effective_coefficients - 3.0).abs() < 1e-6);
+        for (ridge, plain) in unpenalized.coefficients.iter().zip(&plain) {
+            assert!((ridge.estimate - plain.estimate).abs() <= 1e-6 * plain.estimate.abs().max(1.0));
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use crate::config::ConfigError;
+
+
+/// Smallest distance kept between a Log/Logit linked target and the edge of its domain.
+/// Targets of exactly 0 (Log), or exactly 0 or 1 (Logit), are squeezed by this amount.
+/// BoundedLogit targets at "min" or "max" are squeezed by this fraction of "max - min".
+const LINK_EPSILON: f32 = 1e-6;
+
+
+/// A "Link" relates a creature's raw output to the target, as in a GLM.
+/// The target is mapped onto the linked scale before standardization and
+/// creature output is mapped back through the inverse link after unstandardization.
+///   Identity:  output unchanged
+///   Log:       predictions are always positive (target must be >= 0)
+///   Logit:     predictions are always within (0, 1) (target must be within [0, 1])
+///   BoundedLogit:  Logit scaled to a target with hard limits, such as a percentage,
+///                  so predictions are always within [min, max] (target must be too)
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Link {
+    Identity,
+    Log,
+    Logit,
+    BoundedLogit { min: f32, max: f32 },
+}
+
+/// Which scale creature errors are calculated on when using a non-identity Link.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorScale {
+    /// Compare the standardized linked target with raw creature output.
+    Linked,
+    /// Compare the original target with the prediction after the inverse link.
+    Response,
+}
+
+impl Link {
+    /// Map a target value onto the linked scale.
+    pub fn apply(&self, value: f32) -> f32 {
+        match self {
+            Link::Identity => value,
+            Link::Log => {
+                assert!(value >= 0.0, "Log link requires a non-negative target but found {}", value);
+                value.max(LINK_EPSILON).ln()
+            },
+            Link::Logit => {
+                assert!((0.0..=1.0).contains(&value), "Logit link requires a target within [0, 1] but found {}", value);
+                let value = value.clamp(LINK_EPSILON, 1.0 - LINK_EPSILON);
+                (value / (1.0 - value)).ln()
+            },
+            Link::BoundedLogit { min, max } => {
+                assert!((*min..=*max).contains(&value), "BoundedLogit link requires a target within [{}, {}] but found {}", min, max, value);
+                Link::Logit.apply((value - min) / (max - min))
+            },
+        }
+    }
+
+    /// Map a linked value back onto the scale of the original target.
+    pub fn inverse(&self, value: f32) -> f32 {
+        match self {
+            Link::Identity => value,
+            Link::Log => value.exp(),
+            Link::Logit => 1.0 / (1.0 + (-value).exp()),
+            // Clamped as rounding could otherwise land just past a bound
+            Link::BoundedLogit { min, max } => (min + (max - min) * Link::Logit.inverse(value)).clamp(*min, *max),
+        }
+    }
+
+    /// Why this link cannot be used, if its parameters are invalid.
+    pub(crate) fn problem(&self) -> Option<String> {
+        match self {
+            Link::BoundedLogit { min, max } if !(min.is_finite() && max.is_finite() && min < max) => {
+                Some(format!("Link::BoundedLogit needs finite bounds with min < max but has [{}, {}]", min, max))
+            },
+            _ => None,
+        }
+    }
+
+    /// Inclusive range of the targets this link can map, or None for any target.
+    fn domain(&self) -> Option<(f32, f32)> {
+        match self {
+            Link::Identity => None,
+            Link::Log => Some((0.0, f32::INFINITY)),
+            Link::Logit => Some((0.0, 1.0)),
+            Link::BoundedLogit { min, max } => Some((*min, *max)),
+        }
+    }
+
+    /// A `ConfigError` counting the rows of "rows" (described by "source") whose
+    /// "target" is outside of this link's domain, if there are any.
+    pub(crate) fn target_problem(&self, target: &str, rows: &[HashMap<String, f32>], source: &str) -> Option<ConfigError> {
+        let (min, max) = self.domain().filter(|_| self.problem().is_none())?;
+        let outside = rows.iter()
+            .filter_map(|point| point.get(target))
+            .filter(|value| !(min..=max).contains(*value))
+            .count();
+        (outside > 0).then(|| ConfigError::Conflict(format!(
+            "{} rows of {} have a target \"{}\" outside of [{}, {}], which Link::{:?} requires", outside, source, target, min, max, self
+        )))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EvolutionConfig;
+    use crate::evolution::Evolution;
+
+    #[test]
+    fn link_round_trips() {
+        for value in [0.05, 0.3, 0.5, 0.9] {
+            for link in [Link::Identity, Link::Log, Link::Logit] {
+                assert!((link.inverse(link.apply(value)) - value).abs() < 1e-5);
+            }
+        }
+        assert!(Link::Log.inverse(-50.0) > 0.0);
+        assert!(Link::Logit.inverse(50.0) <= 1.0 && Link::Logit.inverse(-50.0) >= 0.0);
+        assert!(Link::Log.apply(0.0).is_finite());
+        assert!(Link::Logit.apply(1.0).is_finite());
+    }
+
+    #[test]
+    fn bounded_logit() {
+        let link = Link::BoundedLogit { min: 0.0, max: 100.0 };
+        for value in [1.0, 37.5, 50.0, 99.0] {
+            assert!((link.inverse(link.apply(value)) - value).abs() < 1e-3);
+        }
+        // Targets at the bounds are squeezed inside them
+        assert!(link.apply(0.0).is_finite() && link.apply(100.0).is_finite());
+        assert!(link.inverse(link.apply(100.0)) < 100.0);
+        for value in [-1e30, -80.0, 0.0, 80.0, 1e30, f32::INFINITY] {
+            assert!((0.0..=100.0).contains(&link.inverse(value)));
+        }
+        assert!(link.problem().is_none());
+        assert!(Link::BoundedLogit { min: 1.0, max: 1.0 }.problem().is_some());
+    }
+
+    #[test]
+    #[should_panic]
+    fn log_link_rejects_negative_target() {
+        Link::Log.apply(-1.0);
+    }
+
+    #[test]
+    fn targets_outside_of_the_domain() {
+        let data: Vec<HashMap<String, f32>> = (0..30)
+            .map(|i| HashMap::from([("x".to_string(), i as f32), ("y".to_string(), i as f32 / 10.0 - 0.5)]))
+            .collect();
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 2, link: Link::Log, ..Default::default() };
+        let Err(error) = Evolution::builder("y", &data).config(config.clone()).build() else { panic!("Negative targets were accepted") };
+        assert_eq!(error.to_string(), "5 rows of the training data have a target \"y\" outside of [0, inf], which Link::Log requires");
+        let logit = EvolutionConfig { link: Link::Logit, validation_data: Some(data.clone()), ..config.clone() };
+        let Err(ConfigError::Multiple(errors)) = logit.validate("y", &data) else { panic!("Targets outside of [0, 1] were accepted") };
+        assert_eq!(errors.len(), 2);
+
+        // Targets moved outside of the domain by preprocessing are found too
+        let positive: Vec<HashMap<String, f32>> = data.iter().filter(|point| point["y"] >= 0.0).cloned().collect();
+        assert!(Evolution::builder("y", &positive).config(config.clone()).build().is_ok());
+        let shifted = Evolution::builder("y", &positive)
+            .config(config)
+            .preprocess(Box::new(|point| if let Some(y) = point.get_mut("y") { *y -= 1.0 }))
+            .build();
+        assert!(matches!(shifted, Err(ConfigError::Conflict(_))));
+    }
+}
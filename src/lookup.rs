@@ -0,0 +1,199 @@
+use std::error::Error;
+use std::fmt;
+use crate::evolution::Evolution;
+
+
+/// Most input parameters a model can have to be baked into a LookupTable.
+pub const MAX_LOOKUP_FEATURES: usize = 4;
+
+/// Most entries a LookupTable can hold (64 MiB of f32 values).
+pub const MAX_LOOKUP_ENTRIES: usize = 1 << 24;
+
+
+/// Why `Evolution::to_lookup_table` cannot build a table.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LookupError {
+    /// The resolution is below 2.
+    ResolutionTooLow(usize),
+    /// The model has more than MAX_LOOKUP_FEATURES input parameters.
+    TooManyFeatures(usize),
+    /// The table would have more than MAX_LOOKUP_ENTRIES entries.
+    TooManyEntries { features: usize, resolution: usize },
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LookupError::ResolutionTooLow(resolution) => {
+                write!(f, "a lookup table needs a resolution of at least 2 but was given {}", resolution)
+            },
+            LookupError::TooManyFeatures(features) => write!(
+                f, "a lookup table supports at most {} input parameters but the model has {}", MAX_LOOKUP_FEATURES, features
+            ),
+            LookupError::TooManyEntries { features, resolution } => write!(
+                f, "a lookup table of {} parameters at a resolution of {} exceeds {} entries", features, resolution, MAX_LOOKUP_ENTRIES
+            ),
+        }
+    }
+}
+
+impl Error for LookupError {}
+
+
+/// A model's predictions precomputed over an evenly spaced grid of its inputs,
+/// for deployment where the equation cannot be evaluated at runtime.
+/// Each input is gridded from its training minimum to its training maximum
+/// and inputs outside of that range are clamped to it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LookupTable {
+    /// Input parameter names in sorted order.
+    params: Vec<String>,
+    /// (min, max) of each parameter's grid.
+    ranges: Vec<(f32, f32)>,
+    resolution: usize,
+    /// Row-major predictions with the last parameter changing fastest.
+    values: Vec<f32>,
+}
+
+impl LookupTable {
+    /// Input parameter names in the order "nearest" and "interpolated" expect their values.
+    pub fn parameters(&self) -> &[String] {
+        &self.params
+    }
+
+    /// Number of grid points along each input.
+    pub fn resolution(&self) -> usize {
+        self.resolution
+    }
+
+    /// Every prediction of the table, with the last parameter changing fastest.
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    /// Prediction at the grid point nearest to "inputs" (in "parameters" order).
+    pub fn nearest(&self, inputs: &[f32]) -> f32 {
+        let index = self.positions(inputs)
+            .fold(0, |index, position| index * self.resolution + position.round() as usize);
+        self.values[index]
+    }
+
+    /// Multilinear interpolation of the predictions at the grid points
+    /// surrounding "inputs" (in "parameters" order).
+    pub fn interpolated(&self, inputs: &[f32]) -> f32 {
+        let cells: Vec<(usize, f32)> = self.positions(inputs).map(|position| {
+            let lower = (position.floor() as usize).min(self.resolution - 2);
+            (lower, position - lower as f32)
+        }).collect();
+
+        // Each corner of the surrounding cell is a bit pattern of which parameters take their upper grid point
+        (0..1usize << cells.len()).map(|corner| {
+            let mut index = 0;
+            let mut weight = 1.0;
+            for (dimension, (lower, fraction)) in cells.iter().enumerate() {
+                let upper = (corner >> (cells.len() - 1 - dimension)) & 1 == 1;
+                index = index * self.resolution + lower + upper as usize;
+                weight *= if upper { *fraction } else { 1.0 - fraction };
+            }
+            weight * self.values[index]
+        }).sum()
+    }
+
+    /// Fractional grid position (0.0 to resolution - 1) of each input.
+    fn positions<'a>(&'a self, inputs: &'a [f32]) -> impl Iterator<Item = f32> + 'a {
+        assert_eq!(inputs.len(), self.params.len(), "LookupTable expects a value for each of {:?}", self.params);
+        inputs.iter().zip(&self.ranges).map(|(value, (min, max))| match max > min {
+            true => ((value.clamp(*min, *max) - min) / (max - min)) * (self.resolution - 1) as f32,
+            false => 0.0,
+        })
+    }
+}
+
+
+impl Evolution {
+    /// Precompute the model's predictions over a grid of "resolution" evenly spaced
+    /// values of each input (see `LookupTable`).  Only viable for models with at most
+    /// MAX_LOOKUP_FEATURES inputs, and fails if the table would have more than
+    /// MAX_LOOKUP_ENTRIES entries.
+    pub fn to_lookup_table(&self, resolution: usize) -> Result<LookupTable, LookupError> {
+        if resolution < 2 {
+            return Err(LookupError::ResolutionTooLow(resolution));
+        }
+        let params: Vec<String> = self.standardizer.parameters().into_iter()
+            .filter(|param| *param != self.target)
+            .map(|param| param.to_string())
+            .collect();
+        if params.len() > MAX_LOOKUP_FEATURES {
+            return Err(LookupError::TooManyFeatures(params.len()));
+        }
+        if resolution.checked_pow(params.len() as u32).is_none_or(|entries| entries > MAX_LOOKUP_ENTRIES) {
+            return Err(LookupError::TooManyEntries { features: params.len(), resolution });
+        }
+
+        let ranges = params.iter().map(|param| {
+            let stats = self.standardizer.param_standardizer(param).unwrap();
+            (stats.min(), stats.max())
+        }).collect();
+        let values = self.predict_grid(&params, resolution, false).into_iter()
+            .map(|(_, prediction)| prediction)
+            .collect();
+        Ok(LookupTable { params, ranges, resolution, values })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EvolutionConfig;
+    use std::collections::HashMap;
+
+    fn trained(n_features: usize) -> Evolution {
+        let data = crate::data::synthetic::linear(40, n_features, 0.1, 4);
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 2, ..Default::default() };
+        Evolution::with_config("y".into(), &data, &config).unwrap()
+    }
+
+    #[test]
+    fn lookup_matches_model_on_grid() {
+        let evo = trained(2);
+        let table = evo.to_lookup_table(9).unwrap();
+        assert_eq!(table.values().len(), 81);
+        let params = table.parameters().to_vec();
+        let point = |inputs: &[f32]| -> HashMap<String, f32> {
+            params.iter().cloned().zip(inputs.iter().cloned()).collect()
+        };
+
+        for (inputs, _) in evo.predict_grid(&params, 9, false) {
+            let prediction = evo.predict_point(&point(&inputs)).unwrap();
+            assert!((table.nearest(&inputs) - prediction).abs() <= 1e-4 * prediction.abs().max(1.0));
+            assert!((table.interpolated(&inputs) - prediction).abs() <= 1e-4 * prediction.abs().max(1.0));
+        }
+
+        // At the center of a grid cell the interpolation is the mean of its corners
+        let corners = evo.predict_grid(&params, 9, false);
+        let (low, high) = (&corners[0].0, &corners[10].0);
+        let middle: Vec<f32> = low.iter().zip(high).map(|(low, high)| (low + high) / 2.0).collect();
+        let values = [corners[0].1, corners[1].1, corners[9].1, corners[10].1];
+        let interpolated = table.interpolated(&middle);
+        assert!((interpolated - values.iter().sum::<f32>() / 4.0).abs() <= 1e-4 * interpolated.abs().max(1.0));
+
+        // Inputs outside of the training range are clamped
+        let beyond: Vec<f32> = high.iter().map(|value| value + 1000.0).collect();
+        assert_eq!(table.nearest(&beyond), *table.values().last().unwrap());
+    }
+
+    #[test]
+    fn too_many_features() {
+        assert_eq!(trained(5).to_lookup_table(2), Err(LookupError::TooManyFeatures(5)));
+    }
+
+    #[test]
+    fn table_too_large() {
+        let evo = trained(3);
+        assert_eq!(evo.to_lookup_table(300), Err(LookupError::TooManyEntries { features: 3, resolution: 300 }));
+        assert_eq!(evo.to_lookup_table(1), Err(LookupError::ResolutionTooLow(1)));
+        assert!(evo.to_lookup_table(2).is_ok());
+    }
+}
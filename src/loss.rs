@@ -0,0 +1,195 @@
+use std::fmt;
+use std::sync::Arc;
+
+
+/// Huber delta used by HuberAuto as a multiple of the robust residual scale,
+/// giving 95% efficiency relative to squared error for normal residuals.
+const HUBER_AUTO_SCALE: f32 = 1.345;
+
+/// Converts a median absolute deviation into a normal-consistent scale estimate.
+const MAD_TO_SCALE: f32 = 1.0 / 0.6745;
+
+
+/// Loss applied to each residual when calculating a creature's error.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LossFunction {
+    SquaredError,
+    /// Absolute residual, so errors are a (weighted) mean absolute error
+    /// and outliers count in proportion to their size.
+    AbsoluteError,
+    /// Squared error for residuals within "delta" and linear beyond,
+    /// which limits the influence of outliers.
+    Huber { delta: f32 },
+    /// Huber loss with delta set each cycle to 1.345 times a robust scale
+    /// estimate (MAD / 0.6745) of the current best creature's residuals.
+    /// Scored directly, it behaves like Huber with a delta of 1.345
+    /// (one standard deviation of the standardized target).
+    HuberAuto,
+}
+
+impl LossFunction {
+    /// Name used to label the loss in the history and summary.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LossFunction::SquaredError => "SquaredError",
+            LossFunction::AbsoluteError => "AbsoluteError",
+            LossFunction::Huber { .. } => "Huber",
+            LossFunction::HuberAuto => "HuberAuto",
+        }
+    }
+
+    pub fn point_loss(&self, residual: f32) -> f32 {
+        match self {
+            LossFunction::SquaredError => residual.powi(2),
+            LossFunction::AbsoluteError => residual.abs(),
+            LossFunction::Huber { delta } => huber(residual, *delta),
+            LossFunction::HuberAuto => huber(residual, HUBER_AUTO_SCALE),
+        }
+    }
+}
+
+/// A user defined loss for losses not covered by `LossFunction`, such as ones
+/// penalizing under-prediction more than over-prediction.  Predicted and actual
+/// values are on the scale creature errors are calculated on (see `ErrorScale`),
+/// which is the standardized (linked) target by default.
+pub trait Loss {
+    fn point_loss(&self, predicted: f32, actual: f32) -> f32;
+
+    /// Weighted mean loss over all of the data points.  Override to
+    /// calculate the losses of a whole dataset at once.
+    fn mean_loss(&self, predicted: &[f32], actual: &[f32], weights: Option<&[f32]>) -> f32 {
+        let mut total = 0.0;
+        let mut total_weight = 0.0;
+        for (index, (predicted, actual)) in predicted.iter().zip(actual).enumerate() {
+            let weight = weights.map_or(1.0, |weights| weights[index]);
+            total += weight * self.point_loss(*predicted, *actual);
+            total_weight += weight;
+        }
+        total / total_weight
+    }
+}
+
+/// A `Loss` along with the name it is reported under.
+#[derive(Clone)]
+pub struct CustomLoss {
+    pub name: String,
+    pub loss: Arc<dyn Loss + Send + Sync>,
+}
+
+impl CustomLoss {
+    pub fn new(name: &str, loss: Arc<dyn Loss + Send + Sync>) -> CustomLoss {
+        CustomLoss { name: name.to_string(), loss }
+    }
+}
+
+impl fmt::Debug for CustomLoss {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CustomLoss").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+/// How the errors on each dataset combine into a creature's error
+/// when training on several (see `EvolutionConfig::datasets`).
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DatasetAggregation {
+    /// Mean of the dataset errors, so each dataset counts equally whatever its size.
+    #[default]
+    Mean,
+    /// The worst dataset error.
+    Max,
+    /// Weighted mean of the dataset errors, with one weight per dataset.
+    Weighted(Vec<f32>),
+}
+
+impl DatasetAggregation {
+    pub fn combine(&self, errors: &[f32]) -> f32 {
+        match self {
+            DatasetAggregation::Mean => errors.iter().sum::<f32>() / errors.len() as f32,
+            DatasetAggregation::Max => errors.iter().cloned()
+                .fold(f32::NEG_INFINITY, |worst, error| if error.is_nan() || error > worst { error } else { worst }),
+            DatasetAggregation::Weighted(weights) => {
+                errors.iter().zip(weights).map(|(error, weight)| error * weight).sum::<f32>() / weights.iter().sum::<f32>()
+            },
+        }
+    }
+}
+
+fn huber(residual: f32, delta: f32) -> f32 {
+    let abs_residual = residual.abs();
+    if abs_residual <= delta {
+        0.5 * residual.powi(2)
+    } else {
+        delta * (abs_residual - 0.5 * delta)
+    }
+}
+
+/// Huber delta for HuberAuto given residuals and optional per-residual weights.
+pub(crate) fn auto_huber_delta(residuals: &[f32], weights: Option<&[f32]>) -> f32 {
+    let center = weighted_median(residuals, weights);
+    let deviations: Vec<f32> = residuals.iter().map(|residual| (residual - center).abs()).collect();
+    let mad = weighted_median(&deviations, weights);
+    (HUBER_AUTO_SCALE * mad * MAD_TO_SCALE).max(f32::EPSILON)
+}
+
+/// Smallest value at which the cumulative weight reaches half of the total weight.
+fn weighted_median(values: &[f32], weights: Option<&[f32]>) -> f32 {
+    let mut pairs: Vec<(f32, f32)> = match weights {
+        Some(weights) => values.iter().copied().zip(weights.iter().copied()).collect(),
+        None => values.iter().map(|&value| (value, 1.0)).collect(),
+    };
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let half_weight = pairs.iter().map(|(_, weight)| weight).sum::<f32>() / 2.0;
+    let mut cumulative = 0.0;
+    for (value, weight) in &pairs {
+        cumulative += weight;
+        if cumulative >= half_weight {
+            return *value;
+        }
+    }
+    pairs.last().map(|(value, _)| *value).unwrap_or(0.0)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huber_loss() {
+        let loss = LossFunction::Huber { delta: 1.0 };
+        assert_eq!(loss.point_loss(0.5), 0.125);
+        assert_eq!(loss.point_loss(-3.0), 2.5);
+        assert_eq!(LossFunction::SquaredError.point_loss(-3.0), 9.0);
+        assert_eq!(LossFunction::AbsoluteError.point_loss(-3.0), 3.0);
+    }
+
+    #[test]
+    fn custom_mean_loss() {
+        struct Absolute;
+        impl Loss for Absolute {
+            fn point_loss(&self, predicted: f32, actual: f32) -> f32 {
+                (predicted - actual).abs()
+            }
+        }
+        assert_eq!(Absolute.mean_loss(&[1.0, 2.0], &[2.0, 5.0], None), 2.0);
+        assert_eq!(Absolute.mean_loss(&[1.0, 2.0], &[2.0, 5.0], Some(&[3.0, 1.0])), 1.5);
+        let custom = CustomLoss::new("absolute", Arc::new(Absolute));
+        assert_eq!(format!("{:?}", custom), "CustomLoss { name: \"absolute\", .. }");
+    }
+
+    #[test]
+    fn auto_delta() {
+        assert_eq!(weighted_median(&[5.0, 1.0, 3.0], None), 3.0);
+        assert_eq!(weighted_median(&[5.0, 1.0, 3.0], Some(&[1.0, 1.0, 5.0])), 3.0);
+        assert_eq!(weighted_median(&[5.0, 1.0, 3.0], Some(&[5.0, 1.0, 1.0])), 5.0);
+
+        // An outlier barely moves the robust scale estimate
+        let residuals = [-1.0, -0.5, 0.0, 0.5, 1.0];
+        let with_outlier = [-1.0, -0.5, 0.0, 0.5, 100.0];
+        assert_eq!(auto_huber_delta(&residuals, None), auto_huber_delta(&with_outlier, None));
+        assert!((auto_huber_delta(&residuals, None) - 1.345 * 0.5 / 0.6745).abs() < 1e-5);
+        assert!(auto_huber_delta(&[2.0, 2.0, 2.0], None) > 0.0);
+    }
+}
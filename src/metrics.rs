@@ -0,0 +1,146 @@
+use std::fmt;
+
+
+/// Goodness of fit statistic reported alongside the training loss.
+/// Metrics are calculated on the original scale of the target
+/// (unstandardized and with the inverse link applied) and ignore sample weights.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Metric {
+    MeanSquaredError,
+    RootMeanSquaredError,
+    MeanAbsoluteError,
+    /// Coefficient of determination (1 - residual sum of squares / total sum of squares).
+    RSquared,
+}
+
+impl Metric {
+    /// Short name used in summaries and history column headers.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::MeanSquaredError => "MSE",
+            Metric::RootMeanSquaredError => "RMSE",
+            Metric::MeanAbsoluteError => "MAE",
+            Metric::RSquared => "R2",
+        }
+    }
+
+    pub fn compute(&self, predictions: &[f32], actuals: &[f32]) -> f32 {
+        assert_eq!(predictions.len(), actuals.len(), "Need one prediction per actual value");
+        let count = actuals.len() as f32;
+        let squared_error_sum = || predictions.iter().zip(actuals).map(|(p, a)| (p - a).powi(2)).sum::<f32>();
+        match self {
+            Metric::MeanSquaredError => squared_error_sum() / count,
+            Metric::RootMeanSquaredError => (squared_error_sum() / count).sqrt(),
+            Metric::MeanAbsoluteError => predictions.iter().zip(actuals).map(|(p, a)| (p - a).abs()).sum::<f32>() / count,
+            Metric::RSquared => {
+                let mean = actuals.iter().sum::<f32>() / count;
+                let total_sum = actuals.iter().map(|a| (a - mean).powi(2)).sum::<f32>();
+                1.0 - squared_error_sum() / total_sum
+            },
+        }
+    }
+}
+
+/// Values of the requested `EvolutionConfig::report_metrics` for a single creature.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetricReport {
+    pub train: Vec<(Metric, f32)>,
+    /// Empty when there is no validation data.
+    pub validation: Vec<(Metric, f32)>,
+}
+
+impl MetricReport {
+    pub(crate) fn new(metrics: &[Metric],
+        train: (&[f32], &[f32]),
+        validation: Option<(&[f32], &[f32])>) -> MetricReport {
+        let evaluate = |(predictions, actuals): (&[f32], &[f32])| -> Vec<(Metric, f32)> {
+            metrics.iter().map(|metric| (*metric, metric.compute(predictions, actuals))).collect()
+        };
+        MetricReport {
+            train: evaluate(train),
+            validation: validation.map(evaluate).unwrap_or_default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.train.is_empty() && self.validation.is_empty()
+    }
+
+    pub fn train_value(&self, metric: Metric) -> Option<f32> {
+        find(&self.train, metric)
+    }
+
+    pub fn validation_value(&self, metric: Metric) -> Option<f32> {
+        find(&self.validation, metric)
+    }
+}
+
+fn find(values: &[(Metric, f32)], metric: Metric) -> Option<f32> {
+    values.iter().find(|(m, _)| *m == metric).map(|(_, value)| *value)
+}
+
+/// Table with a row per metric and columns for train and validation.
+impl fmt::Display for MetricReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<8}{:>14}{:>14}", "Metric", "Train", "Validation")?;
+        for (metric, train) in &self.train {
+            let validation = self.validation_value(*metric).map(|value| value.to_string()).unwrap_or_default();
+            writeln!(f, "{:<8}{:>14}{:>14}", metric.name(), train, validation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Accuracy of a model's predictions of some data in the original units of the
+/// target (see `Evolution::stats`), for comparing models.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModelStats {
+    /// Coefficient of determination (see `Metric::RSquared`).
+    pub r_squared: f32,
+    pub mean_absolute_error: f32,
+    pub root_mean_squared_error: f32,
+    /// Number of data points evaluated (those with a value for the target).
+    pub count: usize,
+}
+
+impl ModelStats {
+    pub(crate) fn new(predictions: &[f32], actuals: &[f32]) -> ModelStats {
+        ModelStats {
+            r_squared: Metric::RSquared.compute(predictions, actuals),
+            mean_absolute_error: Metric::MeanAbsoluteError.compute(predictions, actuals),
+            root_mean_squared_error: Metric::RootMeanSquaredError.compute(predictions, actuals),
+            count: actuals.len(),
+        }
+    }
+}
+
+impl fmt::Display for ModelStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "R2 {}, MAE {}, RMSE {} over {} points",
+               self.r_squared, self.mean_absolute_error, self.root_mean_squared_error, self.count)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metric_values() {
+        let actuals = [1.0, 2.0, 3.0, 4.0];
+        let predictions = [1.0, 3.0, 3.0, 2.0];
+        assert_eq!(Metric::MeanSquaredError.compute(&predictions, &actuals), 1.25);
+        assert_eq!(Metric::RootMeanSquaredError.compute(&predictions, &actuals), 1.25f32.sqrt());
+        assert_eq!(Metric::MeanAbsoluteError.compute(&predictions, &actuals), 0.75);
+        assert_eq!(Metric::RSquared.compute(&predictions, &actuals), 0.0);
+        assert_eq!(Metric::RSquared.compute(&actuals, &actuals), 1.0);
+
+        let stats = ModelStats::new(&predictions, &actuals);
+        assert_eq!((stats.r_squared, stats.mean_absolute_error, stats.count), (0.0, 0.75, 4));
+        assert_eq!(stats.to_string(), format!("R2 0, MAE 0.75, RMSE {} over 4 points", 1.25f32.sqrt()));
+    }
+}
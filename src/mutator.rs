@@ -0,0 +1,72 @@
+use std::fmt;
+use rand::RngCore;
+use crate::creature::{Creature, MutationOptions};
+use crate::samplers::Samplers;
+
+
+/// Produces mutants of creatures during evolution cycles and the final
+/// optimization, replacing the built-in Gaussian mutation when set as
+/// `EvolutionConfig::mutator`.  `Creature::constants` and
+/// `Creature::with_constants` give access to everything that can be perturbed.
+pub trait Mutator {
+    /// A new creature with perturbed constants.
+    fn mutate(&self, creature: &Creature, rng: &mut dyn RngCore) -> Creature;
+
+    /// Structural change (such as adding or removing terms) applied to each
+    /// result of "mutate", except when `MutationOptions::freeze_structure` is set.
+    /// Does nothing by default.
+    fn mutate_structure(&self, creature: Creature, _rng: &mut dyn RngCore) -> Creature {
+        creature
+    }
+}
+
+impl fmt::Debug for dyn Mutator + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Mutator")
+    }
+}
+
+/// The built-in mutation: Gaussian noise with a standard deviation of "sigma"
+/// added to every constant, with exponents stepped up or down at random
+/// (within the limits of "options").  Evolution cycles use a sigma of
+/// `MutateSpeed::Fast.sigma()` and the final optimization switches
+/// to `MutateSpeed::Fine.sigma()` once it stops improving.
+#[derive(Clone, Debug)]
+pub struct GaussianMutator {
+    pub sigma: f32,
+    pub options: MutationOptions,
+}
+
+impl GaussianMutator {
+    pub fn new(sigma: f32) -> GaussianMutator {
+        GaussianMutator { sigma, options: MutationOptions::default() }
+    }
+}
+
+impl Mutator for GaussianMutator {
+    fn mutate(&self, creature: &Creature, rng: &mut dyn RngCore) -> Creature {
+        creature.perturbed(self.sigma, &self.options, Samplers::standard(), rng)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn gaussian_mutator() {
+        let creature = Creature::single_term("x", 2.0, 1);
+        let frozen = GaussianMutator { sigma: 0.1, options: MutationOptions { freeze_structure: true, ..Default::default() } };
+        let mutant = frozen.mutate(&creature, &mut StdRng::seed_from_u64(3));
+        assert_eq!(mutant.generation, creature.generation + 1);
+        assert_eq!(mutant.constants().len(), creature.constants().len());
+        assert_ne!(mutant.constants(), creature.constants());
+        assert_eq!(mutant.constants(), frozen.mutate(&creature, &mut StdRng::seed_from_u64(3)).constants());
+
+        let silent = GaussianMutator::new(0.0).mutate(&creature, &mut StdRng::seed_from_u64(3));
+        assert_eq!(silent.constants(), creature.constants());
+    }
+}
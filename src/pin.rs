@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use crate::config::ConfigError;
+use crate::creature::Coefficients;
+use crate::data::ColumnNames;
+
+
+/// Constraint on one constant ("c", "b" or "z") of a term (see `PinSpec`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Pin {
+    #[default]
+    Free,
+    Fixed(f32),
+    /// Kept within "min" to "max", either of which may be infinite.
+    Bounded { min: f32, max: f32 },
+}
+
+impl Pin {
+    pub fn at_least(min: f32) -> Pin {
+        Pin::Bounded { min, max: f32::INFINITY }
+    }
+
+    pub fn at_most(max: f32) -> Pin {
+        Pin::Bounded { min: f32::NEG_INFINITY, max }
+    }
+
+    fn applied(&self, value: f32) -> f32 {
+        match self {
+            Pin::Free => value,
+            Pin::Fixed(fixed) => *fixed,
+            Pin::Bounded { min, max } => value.max(*min).min(*max),
+        }
+    }
+
+    fn problem(&self) -> Option<String> {
+        match self {
+            Pin::Free => None,
+            Pin::Fixed(value) if !value.is_finite() => Some(format!("fixed value of {} is not finite", value)),
+            Pin::Bounded { min, max } if min.is_nan() || max.is_nan() || min > max || *min == f32::INFINITY || *max == f32::NEG_INFINITY => {
+                Some(format!("bounds [{}, {}] hold no finite value", min, max))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// How every term of one input parameter is constrained (see `EvolutionConfig::pin`),
+/// for when the form of its effect is known in advance.  For example, a squared
+/// effect with a positive multiplier:
+///
+/// ```
+/// # use revogression::{Pin, PinSpec};
+/// let spec = PinSpec { exponent: Some(2), c: Pin::at_least(0.0), ..Default::default() };
+/// ```
+///
+/// A term is "c * (b * param + z) ^ exponent" with "param" standardized
+/// (mean 0 and standard deviation 1), so "b" and "z" are on that scale.
+/// Constraints are enforced on every creature generated or mutated.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PinSpec {
+    /// Exponent every term must have, from 1 to `MutationOptions::max_exponent`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub exponent: Option<u8>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub c: Pin,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub b: Pin,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub z: Pin,
+}
+
+impl PinSpec {
+    /// Apply the constraints to "coefficients", returning whether any of them changed.
+    pub(crate) fn apply(&self, coefficients: &mut Coefficients) -> bool {
+        let pinned = Coefficients {
+            c: self.c.applied(coefficients.c),
+            b: self.b.applied(coefficients.b),
+            z: self.z.applied(coefficients.z),
+            x: self.exponent.unwrap_or(coefficients.x),
+        };
+        let changed = pinned != *coefficients;
+        *coefficients = pinned;
+        changed
+    }
+
+    /// Problems with the spec for "param", given the largest exponent mutations allow.
+    pub(crate) fn problems(&self, param: &str, max_exponent: u8) -> Vec<ConfigError> {
+        let mut problems = Vec::new();
+        if let Some(exponent) = self.exponent.filter(|exponent| !(1..=max_exponent).contains(exponent)) {
+            problems.push(ConfigError::Conflict(format!(
+                "pin for \"{}\" has an exponent of {} outside of 1 to mutation.max_exponent ({})", param, exponent, max_exponent
+            )));
+        }
+        for (name, pin) in [("c", self.c), ("b", self.b), ("z", self.z)] {
+            if let Some(problem) = pin.problem() {
+                problems.push(ConfigError::Conflict(format!("pin for \"{}\" on {}: {}", param, name, problem)));
+            }
+        }
+        problems
+    }
+}
+
+/// Copy of "pins" with the column names as the model uses them.
+pub(crate) fn normalized(pins: &HashMap<String, PinSpec>, column_names: ColumnNames) -> HashMap<String, PinSpec> {
+    pins.iter().map(|(param, spec)| (column_names.normalize(param).into_owned(), spec.clone())).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::config::EvolutionConfig;
+    use crate::creature::{CreationOptions, Creature, MutationOptions};
+    use crate::evolution::Evolution;
+    use crate::formula::FormulaFormat;
+    use crate::samplers::Samplers;
+
+    fn spec() -> PinSpec {
+        PinSpec { exponent: Some(2), c: Pin::at_least(0.0), z: Pin::Fixed(0.5), ..Default::default() }
+    }
+
+    /// Whether every term of "param" in "creature" meets "spec", and how many there are.
+    fn honored(creature: &Creature, param: &str, spec: &PinSpec) -> (bool, usize) {
+        let mut terms = 0;
+        let mut honored = true;
+        creature.clone().for_each_term(|term_param, coefficients| {
+            if term_param == param {
+                terms += 1;
+                honored &= !spec.apply(&mut coefficients.clone());
+            }
+        });
+        (honored, terms)
+    }
+
+    #[test]
+    fn pinned_generation_and_mutation() {
+        let pins = HashMap::from([("x1".to_string(), spec())]);
+        let samplers = Samplers::expect(&Default::default()).with_pins(&pins);
+        let params = vec!["x1", "x2"];
+        let options = CreationOptions { gated_probability: 0.5, ..Default::default() };
+        let mutation = MutationOptions::default();
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut pinned_terms = 0;
+        for _ in 0..300 {
+            let mut creature = Creature::generate(&params, 3, &options, &samplers, &mut rng);
+            for _ in 0..5 {
+                let (honored, terms) = honored(&creature, "x1", &spec());
+                assert!(honored, "{}", creature);
+                pinned_terms += terms;
+                creature = creature.perturbed(0.5, &mutation, &samplers, &mut rng);
+            }
+        }
+        assert!(pinned_terms > 100);
+    }
+
+    #[test]
+    fn pinned_evolution() {
+        let data = crate::evolution::tests::parabola_data();
+        let pins = HashMap::from([("x1".to_string(), PinSpec { exponent: Some(2), c: Pin::at_least(0.0), ..Default::default() })]);
+        let config = EvolutionConfig { num_creatures: 2000, num_cycles: 5, pin: pins.clone(), seed: Some(2), ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let spec = &pins["x1"];
+        let creatures = evo.best_creatures.iter().chain(&evo.hall_of_fame).chain(std::iter::once(&evo.best_creature));
+        for creature in creatures {
+            assert!(honored(creature, "x1", spec).0, "{}", creature);
+        }
+        assert!(honored(&evo.best_creature, "x1", spec).1 > 0);
+        // Pinned terms render like any other
+        assert!(evo.formula(FormulaFormat::Text).unwrap().contains("^2"));
+
+        let invalid = EvolutionConfig { pin: HashMap::from([
+            ("x1".to_string(), PinSpec { exponent: Some(9), ..Default::default() }),
+            ("y".to_string(), PinSpec::default()),
+            ("x9".to_string(), PinSpec { c: Pin::Bounded { min: 1.0, max: 0.0 }, ..Default::default() }),
+        ]), ..Default::default() };
+        let Err(ConfigError::Multiple(errors)) = invalid.validate("y", &data) else { panic!("Invalid pins were accepted") };
+        assert_eq!(errors.len(), 4);
+    }
+}
@@ -0,0 +1,588 @@
+use std::collections::HashMap;
+use rayon::prelude::*;
+use crate::config::EvolutionConfig;
+use crate::creature::{strict_powi, Coefficients, Creature, Gate};
+use crate::data::{ColumnNames, MissingParams};
+use crate::evolution::{Evolution, Preprocess};
+use crate::link::Link;
+use crate::standardize::{ParamStandardizer, Standardizer};
+
+
+/// A trained model compiled for fast repeated predictions.
+/// Parameters are resolved to indices once and the creature's layers are
+/// flattened into a single list of Ops, so predicting is one loop over
+/// contiguous memory which never looks up a term by name.  Terms within each
+/// layer are evaluated in sorted parameter order, so results are reproducible
+/// between calls.
+#[derive(Clone)]
+pub struct Predictor {
+    target: String,
+    link: Link,
+    /// Input parameter names in sorted order (excludes the target).
+    params: Vec<String>,
+    inputs: Vec<ParamStandardizer>,
+    output: ParamStandardizer,
+    /// The creature's calculation layer by layer, in evaluation order.
+    ops: Vec<Op>,
+    column_names: ColumnNames,
+    preprocess: Option<Preprocess>,
+    missing_params: MissingParams,
+}
+
+/// One step of a compiled creature, adding "c * (b * value + z) ^ x" to the running
+/// sum of terms (which carries over from layer to layer) for the value given by "kind".
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Op {
+    kind: OpKind,
+    /// Index of the input parameter of a Term or Gated op.
+    param: usize,
+    c: f32,
+    b: f32,
+    z: f32,
+    x: u8,
+    /// Multiply out the power as `Coefficients::calculate_strict` does.
+    strict: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OpKind {
+    /// Term of the input parameter (contributes nothing when it is missing).
+    Term,
+    /// Term of the previous layer's total.
+    PreviousLayer,
+    /// Term of the input parameter while the input "gate_param" is past "threshold"
+    /// (contributes nothing when either is missing).
+    Gated { gate_param: usize, above: bool, threshold: f32 },
+    /// A term whose value is known when compiling (such as a previous layer term
+    /// of the first layer, where the previous total is always 0), added as "c".
+    Constant,
+    /// Ends a layer: its total is the running sum of terms plus "c" (the layer bias).
+    EndLayer,
+}
+
+impl Op {
+    fn new(kind: OpKind, param: usize, coefficients: &Coefficients, strict: bool) -> Op {
+        Op { kind, param, c: coefficients.c, b: coefficients.b, z: coefficients.z, x: coefficients.x, strict }
+    }
+
+    /// Op with only a value "c" (a Constant or EndLayer).
+    fn value(kind: OpKind, c: f32) -> Op {
+        Op { kind, param: 0, c, b: 0.0, z: 0.0, x: 0, strict: false }
+    }
+
+    /// Same as `Coefficients::calculate` (or `Coefficients::calculate_strict`).
+    fn apply(&self, value: f32) -> f32 {
+        let base = self.b * value + self.z;
+        match self.strict {
+            true => self.c * strict_powi(base, self.x),
+            false => self.c * base.powi(self.x as i32),
+        }
+    }
+
+    /// Value of a Term or Gated op given its inputs.
+    fn term(&self, param_value: Option<f32>, gate_value: Option<f32>) -> f32 {
+        match (self.kind, param_value) {
+            (OpKind::Term, Some(value)) => self.apply(value),
+            (OpKind::Gated { above, threshold, .. }, Some(value)) => match gate_value {
+                // Like `GatedCoefficients::is_open`, a NaN gate value is never past the threshold
+                Some(gate_value) if above && gate_value >= threshold => self.apply(value),
+                Some(gate_value) if !above && gate_value < threshold => self.apply(value),
+                _ => 0.0,
+            },
+            _ => 0.0,
+        }
+    }
+
+    /// Whether a Term or Gated op uses input "index".
+    fn uses(&self, index: usize) -> bool {
+        match self.kind {
+            OpKind::Term => self.param == index,
+            OpKind::Gated { gate_param, .. } => self.param == index || gate_param == index,
+            _ => false,
+        }
+    }
+}
+
+/// Flatten the layers of "creature" into Ops, with "param_index" giving the
+/// input index of each parameter.  Previous layer terms are folded into
+/// Constants while the previous total does not depend on any input.
+/// Terms are evaluated as `Creature::calculate_strict` does when "strict".
+fn compile(creature: &Creature, param_index: impl Fn(&str) -> usize, strict: bool) -> Vec<Op> {
+    let mut ops = Vec::new();
+    // The running sum of terms and previous total while they are still constants
+    let mut constant_inner_total = Some(0.0);
+    let mut constant_total = Some(0.0);
+    for layer_modifiers in creature.layers() {
+        let mut terms: Vec<Op> = layer_modifiers.modifiers.iter()
+            .map(|(param, coefficients)| Op::new(OpKind::Term, param_index(param), coefficients, strict))
+            .collect();
+        terms.sort_by_key(|op| op.param);
+        if !terms.is_empty() {
+            constant_inner_total = None;
+        }
+        ops.extend(terms);
+
+        if let Some(t_coefficients) = &layer_modifiers.previous_layer_coefficients {
+            match constant_total {
+                Some(total) => {
+                    let term = t_coefficients.calculate_with(&total, strict);
+                    constant_inner_total = constant_inner_total.map(|inner_total: f32| inner_total + term);
+                    ops.push(Op::value(OpKind::Constant, term));
+                },
+                None => ops.push(Op::new(OpKind::PreviousLayer, 0, t_coefficients, strict)),
+            }
+        }
+
+        for gated in &layer_modifiers.gated_modifiers {
+            let kind = OpKind::Gated {
+                gate_param: param_index(&gated.gate_param),
+                above: matches!(gated.gate, Gate::Above),
+                threshold: gated.threshold,
+            };
+            constant_inner_total = None;
+            ops.push(Op::new(kind, param_index(&gated.param), &gated.coefficients, strict));
+        }
+
+        constant_total = constant_inner_total.map(|inner_total| inner_total + layer_modifiers.layer_bias);
+        ops.push(Op::value(OpKind::EndLayer, layer_modifiers.layer_bias));
+    }
+    ops
+}
+
+/// A step of a sweep which is only evaluated once the swept parameter
+/// (or a layer total depending on it) is involved.
+enum SweepOp<'a> {
+    Fixed(f32),
+    Swept(&'a Op),
+    PreviousLayer(&'a Op),
+    EndLayer(f32),
+}
+
+
+impl Predictor {
+    /// Compile "creature" to predict as a model trained with "config" does.
+    pub(crate) fn new(target: &str,
+        standardizer: &Standardizer,
+        creature: &Creature,
+        config: &EvolutionConfig,
+        preprocess: Option<Preprocess>) -> Predictor {
+        let params: Vec<String> = standardizer.parameters().into_iter()
+            .filter(|param| *param != target)
+            .map(|param| param.to_string())
+            .collect();
+        let param_index = |name: &str| params.iter().position(|param| param == name)
+            .unwrap_or_else(|| panic!("Unable to find ParamStandardizer for {}", name));
+        let standardizer_for = |name: &str| standardizer.param_standardizer(name)
+            .unwrap_or_else(|| panic!("Unable to find ParamStandardizer for {}", name))
+            .clone();
+
+        let ops = compile(creature, param_index, config.strict_fp);
+
+        Predictor {
+            target: target.to_string(),
+            link: config.link,
+            inputs: params.iter().map(|param| standardizer_for(param)).collect(),
+            output: standardizer_for(target),
+            params,
+            ops,
+            column_names: config.column_names,
+            preprocess,
+            missing_params: config.missing_params,
+        }
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Input parameter names in sorted order.
+    pub fn parameters(&self) -> &[String] {
+        &self.params
+    }
+
+    /// Standardized value of each input parameter, with those missing from "data_point"
+    /// filled as `MissingParams` says (or None when they are left out).
+    /// Keys which are not input parameters (such as the target) are ignored.
+    fn standardized_inputs(&self, data_point: &HashMap<String, f32>) -> Vec<Option<f32>> {
+        self.params.iter().zip(&self.inputs)
+            .map(|(param, stats)| match (data_point.get(param), self.missing_params) {
+                (Some(value), _) => Some(stats.standardize(value)),
+                (None, MissingParams::Mean) => Some(0.0),
+                (None, MissingParams::Constant(value)) => Some(stats.standardize(&value)),
+                (None, MissingParams::Skip | MissingParams::Penalty(_)) => None,
+            })
+            .collect()
+    }
+
+    /// Whether `MissingParams::Penalty` makes the prediction NaN as an op uses
+    /// a missing input (other than "except").
+    fn penalized(&self, inputs: &[Option<f32>], except: Option<usize>) -> bool {
+        matches!(self.missing_params, MissingParams::Penalty(_))
+            && inputs.iter().enumerate()
+                .any(|(index, input)| input.is_none() && Some(index) != except && self.ops.iter().any(|op| op.uses(index)))
+    }
+
+    fn unstandardized_output(&self, total: f32) -> f32 {
+        self.link.inverse(self.output.unstandardize(&total))
+    }
+
+    /// Like "Evolution::predict_point" without its checks: parameters missing
+    /// from "data_point" are handled by the model's `MissingParams`.
+    /// NaN when column names of "data_point" collide once normalized.
+    pub fn predict_point(&self, data_point: &HashMap<String, f32>) -> f32 {
+        let Ok(data_point) = self.column_names.normalized_point(data_point) else {
+            return f32::NAN;
+        };
+        let inputs = match &self.preprocess {
+            Some(preprocess) => {
+                let mut data_point = data_point.into_owned();
+                preprocess(&mut data_point);
+                self.standardized_inputs(&data_point)
+            },
+            None => self.standardized_inputs(&data_point),
+        };
+        if self.penalized(&inputs, None) {
+            return f32::NAN;
+        }
+        self.unstandardized_output(self.calculate(&inputs))
+    }
+
+    /// The creature's (standardized) output given its standardized "inputs".
+    fn calculate(&self, inputs: &[Option<f32>]) -> f32 {
+        let mut total = 0.0;
+        let mut inner_total = 0.0;
+        for op in &self.ops {
+            match op.kind {
+                OpKind::Term => if let Some(value) = inputs[op.param] {
+                    inner_total += op.apply(value);
+                },
+                OpKind::PreviousLayer => inner_total += op.apply(total),
+                OpKind::Gated { gate_param, .. } => inner_total += op.term(inputs[op.param], inputs[gate_param]),
+                OpKind::Constant => inner_total += op.c,
+                OpKind::EndLayer => total = inner_total + op.c,
+            }
+        }
+        total
+    }
+
+    /// Predict with "param" set to each of "values" and all other parameters
+    /// taken from "fixed".  Everything up to the first term involving "param"
+    /// is calculated once and later fixed terms are precomputed, so only terms
+    /// using "param" (or a layer total depending on it) are re-evaluated per value.
+    /// Results are identical to calling "predict_point" for each value.
+    /// A preprocessing closure may change any parameter based on the swept value,
+    /// so with one every value is predicted as a separate point.  Every prediction
+    /// is NaN when column names of "fixed" collide once normalized.
+    pub fn sweep(&self, param: &str, values: &[f32], fixed: &HashMap<String, f32>) -> Vec<f32> {
+        let param = &*self.column_names.normalize(param);
+        let swept = self.params.iter().position(|name| name == param)
+            .unwrap_or_else(|| panic!("\"{}\" is not an input parameter of this model", param));
+        let Ok(fixed) = self.column_names.normalized_point(fixed) else {
+            return vec![f32::NAN; values.len()];
+        };
+        let fixed = &*fixed;
+        if self.preprocess.is_some() {
+            return values.iter().map(|value| {
+                let mut data_point = fixed.clone();
+                data_point.insert(param.to_string(), *value);
+                self.predict_point(&data_point)
+            }).collect();
+        }
+        let mut inputs = self.standardized_inputs(fixed);
+        if self.penalized(&inputs, Some(swept)) {
+            return vec![f32::NAN; values.len()];
+        }
+        inputs[swept] = None;
+
+        // Running totals of the calculation before the swept parameter first appears
+        let mut total = 0.0;
+        let mut inner_total = 0.0;
+        let mut depends = false;
+        let mut ops = Vec::new();
+        for op in &self.ops {
+            let term = match op.kind {
+                OpKind::Term | OpKind::Gated { .. } if op.uses(swept) => {
+                    depends = true;
+                    ops.push(SweepOp::Swept(op));
+                    continue;
+                },
+                OpKind::Term => match inputs[op.param] {
+                    Some(value) => op.apply(value),
+                    None => continue,
+                },
+                OpKind::Gated { gate_param, .. } => op.term(inputs[op.param], inputs[gate_param]),
+                OpKind::PreviousLayer if depends => {
+                    ops.push(SweepOp::PreviousLayer(op));
+                    continue;
+                },
+                OpKind::PreviousLayer => op.apply(total),
+                OpKind::Constant => op.c,
+                OpKind::EndLayer => {
+                    if depends { ops.push(SweepOp::EndLayer(op.c)) } else { total = inner_total + op.c }
+                    continue;
+                },
+            };
+            if depends { ops.push(SweepOp::Fixed(term)) } else { inner_total += term }
+        }
+
+        let stats = &self.inputs[swept];
+        values.iter().map(|value| {
+            let value = stats.standardize(value);
+            let mut sweep_total = total;
+            let mut sweep_inner_total = inner_total;
+            for op in &ops {
+                match op {
+                    SweepOp::Fixed(term) => sweep_inner_total += term,
+                    SweepOp::Swept(op) => {
+                        let input = |index: usize| if index == swept { Some(value) } else { inputs[index] };
+                        let gate_value = match op.kind {
+                            OpKind::Gated { gate_param, .. } => input(gate_param),
+                            _ => None,
+                        };
+                        sweep_inner_total += op.term(input(op.param), gate_value);
+                    },
+                    SweepOp::PreviousLayer(op) => sweep_inner_total += op.apply(sweep_total),
+                    SweepOp::EndLayer(layer_bias) => sweep_total = sweep_inner_total + layer_bias,
+                }
+            }
+            self.unstandardized_output(sweep_total)
+        }).collect()
+    }
+}
+
+
+impl Evolution {
+    /// Compile the best creature into a Predictor for fast repeated predictions.
+    pub fn predictor(&self) -> Predictor {
+        Predictor::new(&self.target, &self.standardizer, &self.best_creature, &self.config, self.preprocess.clone())
+    }
+
+    /// Consume the model, keeping only what is needed to predict, as a plain function
+    /// (the equivalent of `Predictor::predict_point`) which can be stored or passed
+    /// around without the rest of the Evolution.
+    pub fn into_predictor(self) -> impl Fn(&HashMap<String, f32>) -> f32 + Send + Sync {
+        let predictor = self.predictor();
+        move |data_point| predictor.predict_point(data_point)
+    }
+
+    /// Predictions over a grid of two input parameters with every other
+    /// parameter held at its mean in the training data.
+    /// The result is row-major with one row per "y_grid" value:
+    ///   surface[i][j]  is the prediction at  y = y_grid[i],  x = x_grid[j]
+    /// Column names are normalized like the data (see `EvolutionConfig::column_names`).
+    /// Panics if "x" and "y" are the same or either is not an input parameter (including the target).
+    pub fn response_surface(&self, x: &str, y: &str, x_grid: &[f32], y_grid: &[f32]) -> Vec<Vec<f32>> {
+        let predictor = self.predictor();
+        let x = &*self.config.column_names.normalize(x);
+        let y = &*self.config.column_names.normalize(y);
+        assert!(x != y, "Cannot build a response surface over \"{}\" against itself", x);
+        for param in [x, y] {
+            assert!(param != self.target, "Cannot build a response surface over the target \"{}\"", param);
+            assert!(predictor.parameters().iter().any(|name| name == param),
+                    "\"{}\" is not an input parameter of this model", param);
+        }
+        let means: HashMap<String, f32> = predictor.parameters().iter()
+            .map(|param| (param.clone(), self.standardizer.param_standardizer(param).unwrap().mean()))
+            .collect();
+
+        y_grid.par_iter().map(|y_value| {
+            let mut fixed = means.clone();
+            fixed.insert(y.to_string(), *y_value);
+            predictor.sweep(x, x_grid, &fixed)
+        }).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::creature::CreationOptions;
+    use std::time::Instant;
+
+    fn sample_data() -> Vec<HashMap<String, f32>> {
+        (0..30).map(|i| HashMap::from([
+            ("a".to_string(), i as f32 * 0.5),
+            ("b".to_string(), (i % 7) as f32),
+            ("c".to_string(), (i % 4) as f32 - 1.5),
+            ("y".to_string(), i as f32 * 2.0 - (i % 7) as f32),
+        ])).collect()
+    }
+
+    #[test]
+    fn predict_point_matches_creature() {
+        let data = sample_data();
+        let evo = Evolution::with_config("y".into(), &data, &crate::config::EvolutionConfig {
+            num_creatures: 300, num_cycles: 2, ..Default::default()
+        }).unwrap();
+        let predictor = evo.predictor();
+        assert_eq!(predictor.parameters(), ["a", "b", "c"]);
+        for point in &data {
+            let expected = evo.predict_point(point).unwrap();
+            assert!((predictor.predict_point(point) - expected).abs() <= 1e-4 * expected.abs().max(1.0));
+        }
+    }
+
+    #[test]
+    fn into_predictor() {
+        let data = sample_data();
+        let config = crate::config::EvolutionConfig { num_creatures: 200, num_cycles: 1, ..Default::default() };
+        let models: Vec<Evolution> = (0..2).map(|_| Evolution::with_config("y".into(), &data, &config).unwrap()).collect();
+        let expected: Vec<Vec<f32>> = models.iter()
+            .map(|evo| data.iter().map(|point| evo.predictor().predict_point(point)).collect())
+            .collect();
+        type Predict = Box<dyn Fn(&HashMap<String, f32>) -> f32>;
+        let functions: Vec<Predict> = models.into_iter().map(|evo| Box::new(evo.into_predictor()) as Predict).collect();
+        for (function, expected) in functions.iter().zip(&expected) {
+            assert_eq!(&data.iter().map(function).collect::<Vec<f32>>(), expected);
+        }
+    }
+
+    #[test]
+    fn response_surface() {
+        let data: Vec<HashMap<String, f32>> = (0..64).map(|i| {
+            let (x, y) = ((i % 8) as f32, (i / 8) as f32);
+            HashMap::from([
+                ("x".to_string(), x),
+                ("y".to_string(), y),
+                ("w".to_string(), (i % 5) as f32),
+                ("z".to_string(), x * y),
+            ])
+        }).collect();
+        let evo = Evolution::with_config("z".into(), &data, &crate::config::EvolutionConfig {
+            num_creatures: 500, num_cycles: 3, ..Default::default()
+        }).unwrap();
+        let x_grid = [0.0, 2.5, 5.0, 7.0];
+        let y_grid = [1.0, 6.0, 7.0];
+        let surface = evo.response_surface("x", "y", &x_grid, &y_grid);
+        assert_eq!(surface.len(), 3);
+        assert!(surface.iter().all(|row| row.len() == 4));
+
+        let predictor = evo.predictor();
+        let w_mean = data.iter().map(|point| point["w"]).sum::<f32>() / data.len() as f32;
+        for (i, j) in [(0, 0), (0, 3), (2, 0), (2, 3)] {
+            let point = HashMap::from([
+                ("x".to_string(), x_grid[j]),
+                ("y".to_string(), y_grid[i]),
+                ("w".to_string(), w_mean),
+            ]);
+            assert!((surface[i][j] - predictor.predict_point(&point)).abs() <= 1e-4 * surface[i][j].abs().max(1.0));
+        }
+
+        for (x, y) in [("x", "z"), ("q", "y"), ("x", "x")] {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| evo.response_surface(x, y, &x_grid, &y_grid)));
+            assert!(result.is_err());
+        }
+
+        // With normalized column names "x" and "y" are looked up by their normalized names
+        let normalized = Evolution::with_config("Z".into(), &data, &crate::config::EvolutionConfig {
+            num_creatures: 300, num_cycles: 1, column_names: ColumnNames::Normalized, ..Default::default()
+        }).unwrap();
+        let parameters = normalized.predictor().parameters().to_vec();
+        if ["x", "y"].iter().all(|param| parameters.iter().any(|name| name == param)) {
+            assert_eq!(normalized.response_surface(" X", "Y ", &x_grid, &y_grid), normalized.response_surface("x", "y", &x_grid, &y_grid));
+        }
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| normalized.response_surface("x", " X ", &x_grid, &y_grid)));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn compiled_ops() {
+        use crate::creature::{GatedCoefficients, LayerModifiers, Modifiers};
+        let coefficients = |c: f32, x: u8| Coefficients { c, b: 0.5, z: 0.25, x };
+        let layer = |terms: Vec<(&str, Coefficients)>, previous: Option<Coefficients>, gated: Vec<GatedCoefficients>, layer_bias: f32| {
+            LayerModifiers {
+                modifiers: Modifiers::from_terms(terms.into_iter().map(|(param, coefficients)| (param.to_string(), coefficients)).collect()),
+                previous_layer_coefficients: previous,
+                gated_modifiers: gated,
+                layer_bias,
+            }
+        };
+        let gated = GatedCoefficients {
+            param: "a".to_string(),
+            gate_param: "b".to_string(),
+            gate: Gate::Above,
+            threshold: 0.1,
+            coefficients: coefficients(-1.5, 1),
+        };
+        let creature = Creature::from_layers(vec![
+            // A bias only layer makes the next layer's previous layer term a constant
+            layer(Vec::new(), None, Vec::new(), 0.75),
+            layer(vec![("c", coefficients(2.0, 2)), ("a", coefficients(1.0, 1))], Some(coefficients(3.0, 2)), Vec::new(), 0.5),
+            layer(vec![("b", coefficients(-1.0, 3))], Some(coefficients(0.5, 1)), vec![gated], -0.25),
+        ]);
+
+        let data = sample_data();
+        let standardizer = Standardizer::new(&data);
+        let predictor = Predictor::new("y", &standardizer, &creature, &EvolutionConfig::default(), None);
+        let kinds: Vec<(OpKind, usize)> = predictor.ops.iter().map(|op| (op.kind, op.param)).collect();
+        assert_eq!(kinds, vec![
+            (OpKind::EndLayer, 0),
+            (OpKind::Term, 0),
+            (OpKind::Term, 2),
+            (OpKind::Constant, 0),
+            (OpKind::EndLayer, 0),
+            (OpKind::Term, 1),
+            (OpKind::PreviousLayer, 0),
+            (OpKind::Gated { gate_param: 1, above: true, threshold: 0.1 }, 0),
+            (OpKind::EndLayer, 0),
+        ]);
+        assert_eq!(predictor.ops[3].c, 3.0 * (0.5f32 * 0.75 + 0.25).powi(2));
+
+        // Folding the constant does not change the outputs
+        for point in &data {
+            let standardized = standardizer.standardized_value(point);
+            let expected = standardizer.unstandardize_value("y", creature.calculate(&standardized));
+            assert!((predictor.predict_point(point) - expected).abs() <= 1e-5 * expected.abs().max(1.0));
+        }
+    }
+
+    #[test]
+    fn sweep_matches_predict_point() {
+        let data = sample_data();
+        let standardizer = Standardizer::new(&data);
+        let params = vec!["a", "b", "c"];
+        let options = CreationOptions { gated_probability: 0.5, ..Default::default() };
+        let fixed = HashMap::from([("a".to_string(), 3.0), ("b".to_string(), 2.0), ("c".to_string(), -0.5)]);
+        let values: Vec<f32> = (0..10_000).map(|i| i as f32 * 0.002 - 5.0).collect();
+
+        let mut creatures_checked = 0;
+        while creatures_checked < 20 {
+            let creature = Creature::new_with(&params, 3, &options);
+            if creature.num_layers() != 3 {
+                continue;
+            }
+            creatures_checked += 1;
+            let predictor = Predictor::new("y", &standardizer, &creature, &EvolutionConfig::default(), None);
+            for param in &params {
+                let swept = predictor.sweep(param, &values[..200], &fixed);
+                for (value, result) in values.iter().zip(&swept) {
+                    let mut point = fixed.clone();
+                    point.insert(param.to_string(), *value);
+                    assert_eq!(result.to_bits(), predictor.predict_point(&point).to_bits());
+                }
+            }
+        }
+
+        let creature = loop {
+            let creature = Creature::new_with(&params, 3, &options);
+            if creature.num_layers() == 3 {
+                break creature;
+            }
+        };
+        let predictor = Predictor::new("y", &standardizer, &creature, &EvolutionConfig::default(), None);
+        let start = Instant::now();
+        let naive: Vec<f32> = values.iter().map(|value| {
+            let mut point = fixed.clone();
+            point.insert("b".to_string(), *value);
+            predictor.predict_point(&point)
+        }).collect();
+        let naive_time = start.elapsed();
+        let start = Instant::now();
+        let swept = predictor.sweep("b", &values, &fixed);
+        let sweep_time = start.elapsed();
+        assert!(naive.iter().zip(&swept).all(|(a, b)| a.to_bits() == b.to_bits()));
+        println!("Sweep of {} values: {:?} vs {:?} per point", values.len(), sweep_time, naive_time);
+        assert!(sweep_time < naive_time);
+    }
+}
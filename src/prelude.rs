@@ -0,0 +1,29 @@
+//! The types needed for typical use, for importing all at once:
+//!
+//! ```
+//! use revogression::prelude::*;
+//!
+//! let data = synthetic::linear(40, 1, 0.0, 7);
+//! let evo = Evolution::builder(synthetic::TARGET, &data)
+//!     .num_creatures(300)
+//!     .num_cycles(2)
+//!     .build()
+//!     .unwrap();
+//! let predictor: Predictor = evo.predictor();
+//! assert!(predictor.predict_point(&data[0]).is_finite());
+//!
+//! let prediction: Result<f32, PredictError> = evo.predict_point(&data[0]);
+//! assert!(prediction.is_ok());
+//! let invalid: Result<Evolution, ConfigError> = Evolution::builder(synthetic::TARGET, &data).num_creatures(0).build();
+//! assert!(invalid.is_err());
+//! ```
+
+pub use crate::{ConfigBuilder, Evolution, EvolutionBuilder, EvolutionConfig, Predictor};
+pub use crate::{CreationOptions, Creature, GaussianMutator, MutateSpeed, MutationOptions, Mutator};
+pub use crate::{CustomLoss, ErrorScale, Link, Loss, LossFunction, Metric, SelectionCriterion, Standardizer};
+pub use crate::{ConfigError, EvolutionError, ExportError, LinearInferenceError, LookupError, PredictError, RoundtripError, TemplateError};
+#[cfg(feature = "polars")]
+pub use crate::DataFrameError;
+#[cfg(feature = "serde")]
+pub use crate::{BundleError, EnsembleError, EnsembleModel, LoadError};
+pub use crate::data::synthetic;
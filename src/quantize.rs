@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use crate::creature::Creature;
+
+
+/// Level marking a value missing from its data point.
+const MISSING: u16 = u16::MAX;
+
+/// Largest quantized value, which maps onto a column's maximum.
+const LEVELS: f32 = (MISSING - 1) as f32;
+
+/// Input columns of a set of data points stored as u16 values with a per-column
+/// offset and scale, taking half the memory of the f32 values.  Each value is
+/// within half a quantization step ((max - min) / 65534 / 2) of the original.
+/// Used for the approximate errors of `EvolutionConfig::quantized_screening`.
+pub(crate) struct QuantizedData {
+    columns: Vec<String>,
+    offsets: Vec<f32>,
+    scales: Vec<f32>,
+    rows: usize,
+    /// Column-major values, one column of "rows" levels after another.
+    values: Vec<u16>,
+}
+
+impl QuantizedData {
+    /// Quantize the given "columns" of every point, recording
+    /// the values missing from a point as missing.
+    pub(crate) fn new(points: &[HashMap<String, f32>], columns: &[&str]) -> QuantizedData {
+        let (offsets, scales): (Vec<f32>, Vec<f32>) = columns.iter().map(|column| {
+            let (min, max) = points.iter()
+                .filter_map(|point| point.get(*column))
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| (min.min(*value), max.max(*value)));
+            match max > min {
+                true => (min, (max - min) / LEVELS),
+                // A constant (or empty) column needs no levels
+                false => (if min.is_finite() { min } else { 0.0 }, 0.0),
+            }
+        }).unzip();
+
+        let mut values = Vec::with_capacity(points.len() * columns.len());
+        for (column, (offset, scale)) in columns.iter().zip(offsets.iter().zip(&scales)) {
+            values.extend(points.iter().map(|point| match (point.get(*column), *scale > 0.0) {
+                (None, _) => MISSING,
+                (Some(value), true) => ((value - offset) / scale).round().clamp(0.0, LEVELS) as u16,
+                (Some(_), false) => 0,
+            }));
+        }
+        QuantizedData {
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+            offsets,
+            scales,
+            rows: points.len(),
+            values,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.rows
+    }
+
+    /// Reader of the approximate value of "param" in a row, which is None where
+    /// it is missing and 0 (the standardized mean) when "param" is one of "dropped".
+    fn reader(&self, param: &str, dropped: &[&str]) -> impl Fn(usize) -> Option<f32> + '_ {
+        let column = self.columns.iter().position(|column| column == param);
+        let levels = column.map_or(&[][..], |column| &self.values[column * self.rows..(column + 1) * self.rows]);
+        let (offset, scale) = column.map_or((0.0, 0.0), |column| (self.offsets[column], self.scales[column]));
+        let dropped = dropped.contains(&param);
+        move |row| match *levels.get(row)? {
+            MISSING => None,
+            _ if dropped => Some(0.0),
+            level => Some(offset + scale * level as f32),
+        }
+    }
+
+    /// Output of "creature" for each row, calculated a term at a time over whole
+    /// columns of levels.  As in `Creature::calculate`, a term whose input is missing
+    /// from a row adds nothing, though when "penalize_missing" such rows have no output
+    /// (see `MissingParams::Penalty`).  Columns in "dropped" read as 0.
+    pub(crate) fn outputs(&self, creature: &Creature, dropped: &[&str], strict: bool, penalize_missing: bool) -> Vec<Option<f32>> {
+        let mut totals = vec![0.0; self.rows];
+        let mut inner_totals = vec![0.0; self.rows];
+        let mut missing = vec![false; self.rows];
+        for layer in creature.layers() {
+            let terms = match strict {
+                true => layer.modifiers.sorted(),
+                false => layer.modifiers.iter().collect(),
+            };
+            for (param, coefficients) in terms {
+                let value = self.reader(param, dropped);
+                for row in 0..self.rows {
+                    match value(row) {
+                        Some(value) => inner_totals[row] += coefficients.calculate_with(&value, strict),
+                        None => missing[row] = true,
+                    }
+                }
+            }
+            if let Some(t_coefficients) = &layer.previous_layer_coefficients {
+                for (inner_total, total) in inner_totals.iter_mut().zip(&totals) {
+                    *inner_total += t_coefficients.calculate_with(total, strict);
+                }
+            }
+            for gated in &layer.gated_modifiers {
+                let (value, gate_value) = (self.reader(&gated.param, dropped), self.reader(&gated.gate_param, dropped));
+                for row in 0..self.rows {
+                    match (value(row), gate_value(row)) {
+                        (Some(value), Some(gate_value)) if gated.is_open(gate_value) => {
+                            inner_totals[row] += gated.coefficients.calculate_with(&value, strict);
+                        },
+                        (Some(_), Some(_)) => {},
+                        _ => missing[row] = true,
+                    }
+                }
+            }
+            for (total, inner_total) in totals.iter_mut().zip(&inner_totals) {
+                *total = inner_total + layer.layer_bias;
+            }
+        }
+        totals.into_iter().zip(missing)
+            .map(|(total, missing)| (!(penalize_missing && missing)).then_some(total))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_within_half_step() {
+        let points: Vec<HashMap<String, f32>> = (0..200)
+            .map(|i| HashMap::from([
+                ("a".to_string(), (i as f32 * 0.37).sin() * 50.0),
+                ("b".to_string(), 3.0),
+                ("y".to_string(), i as f32),
+            ]))
+            .collect();
+        let quantized = QuantizedData::new(&points, &["a", "b"]);
+        assert_eq!(quantized.len(), 200);
+
+        let step = 100.0 / LEVELS;
+        let (a, b, y) = (quantized.reader("a", &[]), quantized.reader("b", &[]), quantized.reader("y", &[]));
+        for (index, original) in points.iter().enumerate() {
+            assert!((a(index).unwrap() - original["a"]).abs() <= 0.5 * step + 1e-4);
+            assert_eq!(b(index), Some(3.0));
+            assert_eq!(y(index), None);
+        }
+        assert_eq!(quantized.reader("a", &["a"])(7), Some(0.0));
+    }
+
+    #[test]
+    fn outputs_of_sparse_rows() {
+        let points: Vec<HashMap<String, f32>> = (0..300)
+            .map(|i| {
+                let mut point = HashMap::from([("a".to_string(), (i as f32 * 0.37).sin()), ("b".to_string(), (i % 7) as f32 - 3.0)]);
+                if i % 5 == 0 {
+                    point.remove(["a", "b"][i % 2]);
+                }
+                point
+            })
+            .collect();
+        let quantized = QuantizedData::new(&points, &["a", "b"]);
+        let dequantized: Vec<HashMap<String, f32>> = (0..points.len())
+            .map(|row| ["a", "b"].into_iter()
+                .filter_map(|param| quantized.reader(param, &[])(row).map(|value| (param.to_string(), value)))
+                .collect())
+            .collect();
+        for _ in 0..50 {
+            let creature = Creature::new(&vec!["a", "b"], 3);
+            let outputs = quantized.outputs(&creature, &[], true, false);
+            let penalized = quantized.outputs(&creature, &[], true, true);
+            for (row, point) in dequantized.iter().enumerate() {
+                let expected = creature.calculate_strict(point);
+                assert!(outputs[row] == Some(expected) || (expected.is_nan() && outputs[row].unwrap().is_nan()));
+                assert_eq!(penalized[row].is_none(), creature.uses_missing(point));
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::config::ConfigError;
+use crate::data::ColumnNames;
+
+
+/// Which rows of "data" have every column of "ranges" within its inclusive
+/// (min, max) range (missing columns are not checked, and NaN is never in range),
+/// along with the number of rows outside of each violated column's range, by column.
+pub(crate) fn check_rows(data: &[HashMap<String, f32>], ranges: &BTreeMap<String, (f32, f32)>) -> (Vec<bool>, Vec<(String, usize)>) {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    let keep = data.iter().map(|point| {
+        let violated = out_of_range(point, ranges);
+        for column in &violated {
+            *counts.entry(column).or_default() += 1;
+        }
+        violated.is_empty()
+    }).collect();
+    (keep, counts.into_iter().map(|(column, count)| (column.to_string(), count)).collect())
+}
+
+/// A `ConfigError` for each violated column counted by "check_rows".
+pub(crate) fn violation_errors(violations: &[(String, usize)], ranges: &BTreeMap<String, (f32, f32)>) -> Vec<ConfigError> {
+    violations.iter().map(|(column, count)| {
+        let (min, max) = ranges[column];
+        ConfigError::Conflict(format!("{} rows have \"{}\" outside of its valid range [{}, {}]", count, column, min, max))
+    }).collect()
+}
+
+/// Columns of "point" (in sorted order) with values outside of their range.
+pub(crate) fn out_of_range<'a>(point: &HashMap<String, f32>, ranges: &'a BTreeMap<String, (f32, f32)>) -> Vec<&'a str> {
+    ranges.iter()
+        .filter(|(column, (min, max))| point.get(*column).is_some_and(|value| !(*min..=*max).contains(value)))
+        .map(|(column, _)| column.as_str())
+        .collect()
+}
+
+/// "ranges" with the column names as the model uses them.
+pub(crate) fn normalized(ranges: &BTreeMap<String, (f32, f32)>, column_names: ColumnNames) -> BTreeMap<String, (f32, f32)> {
+    ranges.iter().map(|(column, range)| (column_names.normalize(column).into_owned(), *range)).collect()
+}
+
+/// The values of "items" (one per row) for the rows being kept.
+pub(crate) fn kept<T: Clone>(items: &[T], keep: &[bool]) -> Vec<T> {
+    items.iter().zip(keep).filter(|(_, kept)| **kept).map(|(item, _)| item.clone()).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EvolutionConfig;
+    use crate::evolution::Evolution;
+
+    fn sensor_data() -> Vec<HashMap<String, f32>> {
+        let mut data: Vec<HashMap<String, f32>> = (0..30)
+            .map(|i| HashMap::from([
+                ("mass".to_string(), 1.0 + i as f32),
+                ("speed".to_string(), (i % 5) as f32),
+                ("y".to_string(), 2.0 * (1.0 + i as f32)),
+            ]))
+            .collect();
+        data[4].insert("mass".to_string(), -3.0);
+        data[9].insert("mass".to_string(), f32::NAN);
+        data[9].insert("speed".to_string(), 400.0);
+        data
+    }
+
+    #[test]
+    fn violating_rows_dropped() {
+        let data = sensor_data();
+        let ranges = BTreeMap::from([("mass".to_string(), (0.0, f32::MAX)), ("speed".to_string(), (0.0, 100.0))]);
+        let (keep, counts) = check_rows(&data, &ranges);
+        assert_eq!(keep.iter().filter(|kept| !**kept).count(), 2);
+        assert_eq!(counts, vec![("mass".to_string(), 2), ("speed".to_string(), 1)]);
+
+        let config = EvolutionConfig {
+            num_creatures: 300,
+            num_cycles: 2,
+            valid_ranges: ranges,
+            sample_weights: Some(vec![1.0; 30]),
+            ..Default::default()
+        };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let report = evo.preprocessing_report();
+        assert_eq!(report.rows_dropped, 2);
+        assert_eq!(report.range_violations, counts);
+        assert_eq!(evo.config().sample_weights.as_ref().map(|weights| weights.len()), Some(28));
+
+        let detailed = evo.predict_detailed(&HashMap::from([("mass".to_string(), -1.0), ("speed".to_string(), 2.0)]));
+        assert_eq!(detailed.out_of_range, vec!["mass".to_string()]);
+        assert_eq!(detailed.prediction, evo.predict_point(&HashMap::from([("mass".to_string(), -1.0), ("speed".to_string(), 2.0)])).unwrap());
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(evo.config()).unwrap();
+            assert_eq!(serde_json::from_str::<EvolutionConfig>(&json).unwrap().valid_ranges, config.valid_ranges);
+        }
+        let strict = EvolutionConfig { strict_ranges: true, ..config };
+        assert!(strict.validate("y", &data).is_err());
+
+        // Preprocessed rows are checked, so clipping makes the data valid and shifting it does not
+        let clipped = Evolution::builder("y", &data)
+            .config(strict.clone())
+            .preprocess(Box::new(|point| for (column, (min, max)) in [("mass", (0.0, 1000.0)), ("speed", (0.0, 100.0))] {
+                if let Some(value) = point.get_mut(column) {
+                    *value = if value.is_nan() { min } else { value.clamp(min, max) };
+                }
+            }))
+            .build()
+            .unwrap();
+        assert_eq!(clipped.preprocessing_report().rows_dropped, 0);
+        let shifted = data.iter().map(|point| {
+            point.iter().map(|(column, value)| (column.clone(), if column == "speed" { 10.0 } else { *value })).collect()
+        }).collect::<Vec<HashMap<String, f32>>>();
+        let Err(error) = Evolution::builder("y", &shifted)
+            .config(strict)
+            .preprocess(Box::new(|point| if let Some(speed) = point.get_mut("speed") { *speed *= 20.0 }))
+            .build() else { panic!("Preprocessed values outside of the valid ranges were accepted") };
+        let ConfigError::Multiple(errors) = error else { panic!("Expected a problem for each column, got {}", error) };
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[1].to_string(), "30 rows have \"speed\" outside of its valid range [0, 100]");
+    }
+}
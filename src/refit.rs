@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use crate::creature::MutationOptions;
+use crate::config::{ConfigError, EvolutionConfig, PopulationLimit};
+use crate::evolution::{calc_error_sum, creature_metrics, optimize_creature, original_rmse, prepare_training_data, preprocessed, scoring_data,
+                       target_values, Evolution, EvolutionError};
+use crate::leakage::{self, PreprocessingReport};
+use crate::ranges;
+use crate::samplers::Samplers;
+use crate::seeding::SeedStreams;
+use crate::selection::SelectionCriterion;
+use rand::Rng;
+use crate::standardize::Standardizer;
+
+
+/// Options for `Evolution::refit`.
+#[derive(Clone, Debug)]
+pub struct RefitConfig {
+    /// Fit a new Standardizer to the new data rather than reusing the current one.
+    pub refit_standardizer: bool,
+    /// Standardizer to use instead of either of those, such as the current one
+    /// updated with the new rows (see `Standardizer::update`).
+    pub standardizer: Option<Standardizer>,
+    /// Rounds of mutation used to refit the equation's constants.
+    pub iterations: u16,
+    /// Options for the mutations (exponents always stay fixed).
+    pub mutation: MutationOptions,
+}
+
+impl Default for RefitConfig {
+    fn default() -> RefitConfig {
+        RefitConfig { refit_standardizer: true, standardizer: None, iterations: 30, mutation: MutationOptions::default() }
+    }
+}
+
+/// Rounds of mutation used by each refit of `Evolution::jackknife_uncertainty`.
+const JACKKNIFE_ITERATIONS: u16 = 10;
+
+
+impl Evolution {
+    /// Refit this model to "new_data" from the same process while keeping the
+    /// structure of its equation (layers, parameters and exponents) fixed.
+    /// Only the constants get optimized, which takes "config.iterations" rounds
+    /// of 500 mutants rather than a full evolution.
+    /// The link, loss, column names, valid ranges and preprocessing of this model are reused;
+    /// sample weights, validation data and other datasets from the original
+    /// training config are not.  Fails without refitting for the problems
+    /// `EvolutionConfig::validate` finds with "new_data" or if every row is dropped
+    /// for values outside of the valid ranges.
+    pub fn refit(&self, new_data: &[HashMap<String, f32>], config: &RefitConfig) -> Result<Evolution, EvolutionError> {
+        let evolution_config = EvolutionConfig {
+            sample_weights: None,
+            validation_data: None,
+            datasets: Vec::new(),
+            ..self.config.clone()
+        };
+        // Options of the original training which a refit does not use
+        EvolutionConfig {
+            population_limit: PopulationLimit::Warn,
+            selection: SelectionCriterion::MinTrainError,
+            target_r2: None,
+            ..evolution_config.clone()
+        }.validate(&self.target, new_data)?;
+
+        let new_data = &*self.config.column_names.normalized_rows(new_data)?;
+        let preprocessed_data;
+        let data = match &self.preprocess {
+            Some(preprocess) => {
+                preprocessed_data = preprocessed(preprocess, new_data);
+                &preprocessed_data
+            },
+            None => new_data,
+        };
+        let (keep, range_violations) = ranges::check_rows(data, &self.config.valid_ranges);
+        let rows_dropped = keep.iter().filter(|kept| !**kept).count();
+        let in_range_data;
+        let data = match rows_dropped {
+            0 => data,
+            _ if rows_dropped == data.len() => return Err(EvolutionError::EmptyData),
+            _ => {
+                in_range_data = ranges::kept(data, &keep);
+                &in_range_data[..]
+            },
+        };
+
+        let reused = |standardizer: &Standardizer| {
+            let mut training_data = scoring_data(standardizer, &self.target, data, &evolution_config);
+            training_data.initialize_huber_auto();
+            (standardizer.clone(), training_data)
+        };
+        let (standardizer, training_data) = match (&config.standardizer, config.refit_standardizer) {
+            (Some(standardizer), _) => reused(standardizer),
+            (None, true) => prepare_training_data(&self.target, data, &evolution_config),
+            (None, false) => reused(&self.standardizer),
+        };
+
+        let mut creature = self.best_creature.clone();
+        creature.set_cached_error(calc_error_sum(&creature, &training_data));
+        let mutation = MutationOptions { freeze_structure: true, ..config.mutation.clone() };
+        let seed = self.config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let (refit_creature, optimize_choice, _) = optimize_creature(
+            &creature, &training_data, None, config.iterations, &mutation, evolution_config.mutator.as_ref(),
+            &Samplers::new(&evolution_config.creation.priors)?.with_pins(&evolution_config.pin), evolution_config.min_improvement, SeedStreams::new(seed).next(), None, None
+        );
+
+        let actuals = target_values(&self.target, data);
+        let metrics = creature_metrics(&refit_creature, &evolution_config, &standardizer, (&training_data, &actuals), None);
+        let train_rmse = original_rmse(&refit_creature, &evolution_config, &standardizer, &training_data, &actuals);
+        Ok(Evolution {
+            target: self.target.clone(),
+            config: evolution_config,
+            standardizer,
+            best_creatures: vec![creature],
+            best_creature: refit_creature,
+            pareto_front: Vec::new(),
+            hall_of_fame: Vec::new(),
+            history: Vec::new(),
+            cycles_run: 0,
+            metrics,
+            optimize_choice: Some(optimize_choice),
+            target_r2_reached: None,
+            outcome: None,
+            preprocess: self.preprocess.clone(),
+            target_encoder: self.target_encoder.clone(),
+            pipeline: self.pipeline.clone(),
+            ridge_lambda: None,
+            train_rmse: Some(train_rmse),
+            preprocessing_report: PreprocessingReport {
+                leakage: leakage::check_target_leakage(&self.target, data, self.config.leakage_threshold),
+                rows_folded: 0,
+                rows_dropped,
+                range_violations,
+            },
+            determinism: None,
+        })
+    }
+
+    /// Jackknife (mean, standard deviation) of the prediction for "point": the
+    /// training "data" is split into "blocks" consecutive blocks and the model is
+    /// refit (see `refit`) once without each of them, keeping its structure.
+    /// The standard deviation is the jackknife estimate sqrt((n - 1) / n * Σ(pᵢ - p̄)²)
+    /// over the n leave-one-block-out predictions pᵢ, which is much cheaper than
+    /// training a bootstrapped ensemble.  The refits draw their mutations from
+    /// `EvolutionConfig::seed` of this model, so the results only repeat when it is set
+    /// (otherwise each call draws a new seed).  Fails with fewer than 2 blocks, more
+    /// blocks than data points or if any of the refits fails.
+    pub fn jackknife_uncertainty(&self,
+        data: &[HashMap<String, f32>],
+        point: &HashMap<String, f32>,
+        blocks: usize) -> Result<(f32, f32), EvolutionError> {
+        if blocks < 2 {
+            return Err(ConfigError::TooSmall { option: "blocks", minimum: 2 }.into());
+        }
+        if blocks > data.len() {
+            return Err(ConfigError::TooLarge { option: "blocks", maximum: data.len() }.into());
+        }
+        let config = RefitConfig { iterations: JACKKNIFE_ITERATIONS, ..Default::default() };
+        let predictions = (0..blocks).map(|block| {
+            let (start, end) = (block * data.len() / blocks, (block + 1) * data.len() / blocks);
+            let kept: Vec<HashMap<String, f32>> = data[..start].iter().chain(&data[end..]).cloned().collect();
+            Ok(self.refit(&kept, &config)?.predict_unchecked(point) as f64)
+        }).collect::<Result<Vec<f64>, EvolutionError>>()?;
+
+        let n = blocks as f64;
+        let mean = predictions.iter().sum::<f64>() / n;
+        let sum_of_squares: f64 = predictions.iter().map(|prediction| (prediction - mean).powi(2)).sum();
+        Ok((mean as f32, ((n - 1.0) / n * sum_of_squares).sqrt() as f32))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn line(slope: f32, intercept: f32) -> Vec<HashMap<String, f32>> {
+        (0..30).map(|i| {
+            let x = i as f32 * 0.5;
+            HashMap::from([("x".to_string(), x), ("y".to_string(), slope * x + intercept)])
+        }).collect()
+    }
+
+    fn noisy_line(rows: usize, noise: f32, seed: u64) -> Vec<HashMap<String, f32>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..rows).map(|i| {
+            let x = i as f32 * 15.0 / rows as f32;
+            HashMap::from([("x".to_string(), x), ("y".to_string(), 2.0 * x + 1.0 + noise * rng.gen_range(-1.0..1.0))])
+        }).collect()
+    }
+
+    fn mean_abs_error(evo: &Evolution, data: &[HashMap<String, f32>]) -> f32 {
+        data.iter().map(|point| (evo.predict_point(point).unwrap() - point["y"]).abs()).sum::<f32>() / data.len() as f32
+    }
+
+    #[test]
+    fn refit_on_shifted_data() {
+        // Full training scores 2000 creatures for 5 cycles plus 30 rounds of optimization,
+        // while each refit only runs 10 rounds of optimization
+        let config = EvolutionConfig { num_creatures: 2000, num_cycles: 5, ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &line(2.0, 1.0), &config).unwrap();
+        let shifted = line(2.0, 4.0);
+        let before = mean_abs_error(&evo, &shifted);
+
+        for refit_standardizer in [true, false] {
+            let refit_config = RefitConfig { refit_standardizer, iterations: 10, ..Default::default() };
+            let refit = evo.refit(&shifted, &refit_config).unwrap();
+            let after = mean_abs_error(&refit, &shifted);
+            println!("Refit (new standardizer: {}): {} -> {}", refit_standardizer, before, after);
+            assert!(after < before / 2.0);
+            assert_eq!(refit.best_creature.num_layers(), evo.best_creature.num_layers());
+            assert_eq!(refit.best_creature.complexity(), evo.best_creature.complexity());
+        }
+
+        // Warm start from the original standardizer updated with the new rows
+        let mut updated = evo.standardizer.clone();
+        updated.update(&shifted);
+        let refit_config = RefitConfig { standardizer: Some(updated.clone()), iterations: 10, ..Default::default() };
+        let refit = evo.refit(&shifted, &refit_config).unwrap();
+        assert!(mean_abs_error(&refit, &shifted) < before / 2.0);
+        assert_eq!(refit.standardizer.unstandardize_value("y", 1.0), updated.unstandardize_value("y", 1.0));
+
+        assert!(matches!(evo.refit(&[], &refit_config), Err(EvolutionError::Config(_))));
+    }
+
+    #[test]
+    fn jackknife() {
+        let config = EvolutionConfig { num_creatures: 1000, num_cycles: 4, no_signal_cycles: None, seed: Some(4), ..Default::default() };
+        let point = HashMap::from([("x".to_string(), 7.0)]);
+
+        let clean = noisy_line(300, 0.0, 0);
+        let evo = Evolution::with_config("y".into(), &clean, &config).unwrap();
+        let (clean_mean, clean_std) = evo.jackknife_uncertainty(&clean, &point, 5).unwrap();
+        assert_eq!(evo.jackknife_uncertainty(&clean, &point, 5).unwrap(), (clean_mean, clean_std));
+        println!("Clean data: {} +/- {}", clean_mean, clean_std);
+        assert!((clean_mean - evo.predict_point(&point).unwrap()).abs() < 0.5);
+        assert!(clean_std < 0.1);
+
+        let noisy = noisy_line(20, 8.0, 3);
+        let evo = Evolution::with_config("y".into(), &noisy, &config).unwrap();
+        let (noisy_mean, noisy_std) = evo.jackknife_uncertainty(&noisy, &point, 5).unwrap();
+        println!("Noisy data: {} +/- {}", noisy_mean, noisy_std);
+        assert!(noisy_std > 5.0 * clean_std.max(0.01));
+        assert!(evo.jackknife_uncertainty(&noisy, &point, 1).is_err());
+        assert!(evo.jackknife_uncertainty(&noisy, &point, 21).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::Normal;
+use crate::evolution::Evolution;
+
+
+/// Absolute changes in prediction caused by perturbing the inputs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sensitivity {
+    pub mean_change: f32,
+    /// 95th percentile (nearest rank) of the absolute changes.
+    pub p95_change: f32,
+}
+
+impl Sensitivity {
+    fn new(mut changes: Vec<f32>) -> Sensitivity {
+        if changes.is_empty() {
+            return Sensitivity { mean_change: 0.0, p95_change: 0.0 };
+        }
+        changes.sort_by(|a, b| a.total_cmp(b));
+        let rank = (0.95 * changes.len() as f32).ceil() as usize;
+        Sensitivity {
+            mean_change: changes.iter().sum::<f32>() / changes.len() as f32,
+            p95_change: changes[rank.max(1) - 1],
+        }
+    }
+}
+
+/// Results of "Evolution::robustness".
+#[derive(Clone, Debug)]
+pub struct RobustnessReport {
+    pub noise_fraction: f32,
+    /// Sensitivity to perturbing each input parameter alone, in sorted order.
+    pub features: Vec<(String, Sensitivity)>,
+    /// Sensitivity to perturbing every input parameter at once.
+    pub overall: Sensitivity,
+}
+
+impl fmt::Display for RobustnessReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Prediction changes with noise of {} stdev", self.noise_fraction)?;
+        writeln!(f, "{:<16}{:>14}{:>14}", "Feature", "Mean", "P95")?;
+        for (feature, sensitivity) in &self.features {
+            writeln!(f, "{:<16}{:>14.6}{:>14.6}", feature, sensitivity.mean_change, sensitivity.p95_change)?;
+        }
+        writeln!(f, "{:<16}{:>14.6}{:>14.6}", "(all)", self.overall.mean_change, self.overall.p95_change)
+    }
+}
+
+
+impl Evolution {
+    /// How much predictions for "data" move when inputs get Gaussian noise with
+    /// a standard deviation of "noise_fraction" times each parameter's training stdev.
+    /// Each point is perturbed "n_repeats" times for each parameter on its own
+    /// and for all parameters together.  Models relying on large exponents
+    /// show up with large changes even for small amounts of noise.
+    /// The same "seed" always gives the same report.  Points whose column names
+    /// collide once normalized are left out.
+    pub fn robustness(&self,
+        data: &[HashMap<String, f32>],
+        noise_fraction: f32,
+        n_repeats: usize,
+        seed: u64) -> RobustnessReport {
+        let data: Vec<HashMap<String, f32>> = data.iter()
+            .filter_map(|point| self.config.column_names.normalized_point(point).ok().map(Cow::into_owned))
+            .collect();
+        let predictor = self.predictor();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let noise: Vec<(&str, Normal<f32>)> = predictor.parameters().iter().map(|param| {
+            let stdev = self.standardizer.param_standardizer(param).expect("Parameter missing from standardizer").stdev();
+            let distribution = Normal::new(0.0, noise_fraction.abs() * stdev).expect("Noise must be finite");
+            (param.as_str(), distribution)
+        }).collect();
+        let baselines: Vec<f32> = data.iter().map(|point| predictor.predict_point(point)).collect();
+
+        let mut changes = |perturbed: &[(&str, Normal<f32>)]| -> Vec<f32> {
+            let mut changes = Vec::with_capacity(data.len() * n_repeats);
+            for (point, baseline) in data.iter().zip(&baselines) {
+                for _ in 0..n_repeats {
+                    let mut point = point.clone();
+                    for (param, distribution) in perturbed {
+                        if let Some(value) = point.get_mut(*param) {
+                            *value += rng.sample(distribution);
+                        }
+                    }
+                    changes.push((predictor.predict_point(&point) - baseline).abs());
+                }
+            }
+            changes
+        };
+
+        let features = noise.iter()
+            .map(|param_noise| (param_noise.0.to_string(), Sensitivity::new(changes(std::slice::from_ref(param_noise)))))
+            .collect();
+        let overall = Sensitivity::new(changes(&noise));
+        RobustnessReport { noise_fraction, features, overall }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EvolutionConfig;
+    use crate::creature::Creature;
+
+    fn sample_data() -> Vec<HashMap<String, f32>> {
+        (0..30).map(|i| {
+            let x = i as f32 * 0.2 - 3.0;
+            HashMap::from([("x".to_string(), x), ("w".to_string(), (i % 4) as f32), ("y".to_string(), 2.0 * x + 1.0)])
+        }).collect()
+    }
+
+    #[test]
+    fn zero_noise_has_no_effect() {
+        let data = sample_data();
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 2, ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let report = evo.robustness(&data, 0.0, 3, 7);
+        assert_eq!(report.features.iter().map(|(feature, _)| feature.as_str()).collect::<Vec<_>>(), vec!["w", "x"]);
+        assert!(report.features.iter().all(|(_, sensitivity)| sensitivity.p95_change == 0.0));
+        assert_eq!(report.overall, Sensitivity { mean_change: 0.0, p95_change: 0.0 });
+
+        let noisy = evo.robustness(&data, 0.1, 3, 7);
+        assert_eq!(noisy.overall, evo.robustness(&data, 0.1, 3, 7).overall);
+        println!("{}", noisy);
+    }
+
+    #[test]
+    fn steep_model_is_more_sensitive() {
+        let data = sample_data();
+        let config = EvolutionConfig { num_creatures: 100, num_cycles: 1, ..Default::default() };
+        let mut flat = Evolution::with_config("y".into(), &data, &config).unwrap();
+        flat.best_creature = Creature::single_term("x", 0.01, 1);
+        let mut steep = Evolution::with_config("y".into(), &data, &config).unwrap();
+        steep.best_creature = Creature::single_term("x", 5.0, 4);
+
+        let flat_report = flat.robustness(&data, 0.05, 5, 11);
+        let steep_report = steep.robustness(&data, 0.05, 5, 11);
+        assert!(steep_report.overall.mean_change > 100.0 * flat_report.overall.mean_change);
+        assert!(steep_report.features[1].1.p95_change > 100.0 * flat_report.features[1].1.p95_change);
+        // Neither creature uses "w"
+        assert_eq!(steep_report.features[0].1.mean_change, 0.0);
+    }
+}
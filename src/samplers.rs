@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use rand::{Rng, RngCore};
+use rand_distr::{Normal, StandardNormal, Triangular};
+use crate::config::ConfigError;
+use crate::creature::Creature;
+use crate::pin::PinSpec;
+
+
+/// Triangular distribution from "min" to "max", most likely at "mode".
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TriangularPrior {
+    pub min: f32,
+    pub max: f32,
+    pub mode: f32,
+}
+
+impl TriangularPrior {
+    pub fn new(min: f32, max: f32, mode: f32) -> TriangularPrior {
+        TriangularPrior { min, max, mode }
+    }
+
+    fn distribution(&self, prior: &'static str) -> Result<Triangular<f32>, ConfigError> {
+        let invalid = |reason: String| Err(ConfigError::InvalidPrior { prior, reason });
+        if !(self.min.is_finite() && self.max.is_finite() && self.mode.is_finite()) {
+            return invalid(format!("{:?} is not finite", self));
+        }
+        if self.min >= self.max {
+            return invalid(format!("min of {} is not below max of {}", self.min, self.max));
+        }
+        if !(self.min..=self.max).contains(&self.mode) {
+            return invalid(format!("mode of {} is outside of {} to {}", self.mode, self.min, self.max));
+        }
+        Triangular::new(self.min, self.max, self.mode).or_else(|error| invalid(error.to_string()))
+    }
+}
+
+/// Distributions the constants of new creatures are drawn from
+/// (see `CreationOptions::priors`).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoefficientPriors {
+    /// Size of each "c" and "b" which is not simply 1, before a random sign.
+    pub magnitude: TriangularPrior,
+    /// Each "z" offset which is not simply 0.
+    pub offset: TriangularPrior,
+    /// Standard deviation of the Gaussian each nonzero layer bias is drawn from.
+    pub layer_bias_sigma: f32,
+    /// Standard deviation of the Gaussian each gate threshold (on the
+    /// standardized parameter) is drawn from.
+    pub threshold_sigma: f32,
+}
+
+impl Default for CoefficientPriors {
+    fn default() -> CoefficientPriors {
+        CoefficientPriors {
+            magnitude: TriangularPrior::new(0.0, 2.0, 1.0),
+            offset: TriangularPrior::new(-2.0, 2.0, 0.0),
+            layer_bias_sigma: 0.1,
+            threshold_sigma: 1.0,
+        }
+    }
+}
+
+
+/// The distributions of "CoefficientPriors", constructed once so creating and
+/// mutating creatures only draws from them.  Invalid priors are rejected here
+/// (and so by `EvolutionConfig::validate`) rather than panicking while sampling.
+/// Any "pins" (see `EvolutionConfig::pin`) constrain every creature created or mutated.
+#[derive(Clone, Debug)]
+pub(crate) struct Samplers {
+    magnitude: Triangular<f32>,
+    offset: Triangular<f32>,
+    layer_bias: Normal<f32>,
+    threshold: Normal<f32>,
+    pins: HashMap<String, PinSpec>,
+}
+
+impl Samplers {
+    pub(crate) fn new(priors: &CoefficientPriors) -> Result<Samplers, ConfigError> {
+        let normal = |sigma: f32, prior: &'static str| Normal::new(0.0, sigma)
+            .ok()
+            .filter(|_| sigma.is_finite() && sigma >= 0.0)
+            .ok_or_else(|| ConfigError::InvalidPrior { prior, reason: format!("sigma of {} is not finite and non-negative", sigma) });
+        Ok(Samplers {
+            magnitude: priors.magnitude.distribution("magnitude")?,
+            offset: priors.offset.distribution("offset")?,
+            layer_bias: normal(priors.layer_bias_sigma, "layer_bias_sigma")?,
+            threshold: normal(priors.threshold_sigma, "threshold_sigma")?,
+            pins: HashMap::new(),
+        })
+    }
+
+    pub(crate) fn with_pins(self, pins: &HashMap<String, PinSpec>) -> Samplers {
+        Samplers { pins: pins.clone(), ..self }
+    }
+
+    /// Apply the pins to the terms of "creature".
+    pub(crate) fn pin(&self, creature: &mut Creature) {
+        if self.pins.is_empty() {
+            return;
+        }
+        let mut changed = false;
+        creature.for_each_term(|param, coefficients| {
+            if let Some(spec) = self.pins.get(param) {
+                changed |= spec.apply(coefficients);
+            }
+        });
+        if changed {
+            creature.invalidate_cache();
+        }
+    }
+
+    /// Samplers of the default priors.
+    pub(crate) fn standard() -> &'static Samplers {
+        static STANDARD: OnceLock<Samplers> = OnceLock::new();
+        STANDARD.get_or_init(|| Samplers::new(&CoefficientPriors::default()).unwrap())
+    }
+
+    /// Samplers of "priors", panicking if they are invalid, for callers
+    /// which have no way to return an error.
+    pub(crate) fn expect(priors: &CoefficientPriors) -> Samplers {
+        Samplers::new(priors).unwrap_or_else(|error| panic!("Invalid CreationOptions::priors: {}", error))
+    }
+
+    pub(crate) fn magnitude(&self, rng: &mut dyn RngCore) -> f32 {
+        rng.sample(self.magnitude)
+    }
+
+    pub(crate) fn offset(&self, rng: &mut dyn RngCore) -> f32 {
+        rng.sample(self.offset)
+    }
+
+    pub(crate) fn layer_bias(&self, rng: &mut dyn RngCore) -> f32 {
+        rng.sample(self.layer_bias)
+    }
+
+    pub(crate) fn threshold(&self, rng: &mut dyn RngCore) -> f32 {
+        rng.sample(self.threshold)
+    }
+}
+
+/// Gaussian noise with standard deviation "sigma", drawing the same value
+/// as sampling `Normal::new(0.0, sigma)` without constructing it.
+pub(crate) fn gaussian(sigma: f32, rng: &mut dyn RngCore) -> f32 {
+    sigma * rng.sample::<f32, _>(StandardNormal)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::config::EvolutionConfig;
+    use crate::creature::{CreationOptions, Creature};
+    use crate::evolution::Evolution;
+
+    #[test]
+    fn invalid_priors() {
+        assert!(Samplers::new(&CoefficientPriors::default()).is_ok());
+        let degenerate = CoefficientPriors { magnitude: TriangularPrior::new(1.0, 1.0, 1.0), ..Default::default() };
+        assert!(matches!(Samplers::new(&degenerate), Err(ConfigError::InvalidPrior { prior: "magnitude", .. })));
+        let outside = CoefficientPriors { offset: TriangularPrior::new(-1.0, 1.0, 2.0), ..Default::default() };
+        assert!(matches!(Samplers::new(&outside), Err(ConfigError::InvalidPrior { prior: "offset", .. })));
+        for sigma in [-0.1, f32::NAN, f32::INFINITY] {
+            let priors = CoefficientPriors { threshold_sigma: sigma, ..Default::default() };
+            assert!(matches!(Samplers::new(&priors), Err(ConfigError::InvalidPrior { prior: "threshold_sigma", .. })));
+        }
+
+        let data = crate::data::synthetic::linear(20, 1, 0.1, 1);
+        let creation = CreationOptions { priors: degenerate, ..Default::default() };
+        let config = EvolutionConfig { creation, ..Default::default() };
+        let Err(error) = Evolution::builder("y", &data).config(config).build() else { panic!("Invalid priors were accepted") };
+        assert_eq!(error.to_string(), "creation.priors.magnitude is invalid: min of 1 is not below max of 1");
+    }
+
+    #[test]
+    fn shared_samplers_match_constructed() {
+        // Draws are the same as constructing the distributions for every draw, as creatures used to
+        let samplers = Samplers::standard();
+        let constructed: &mut dyn RngCore = &mut StdRng::seed_from_u64(1);
+        let shared: &mut dyn RngCore = &mut StdRng::seed_from_u64(1);
+        for _ in 0..1000 {
+            assert_eq!(constructed.sample(Triangular::new(0.0, 2.0, 1.0).unwrap()), samplers.magnitude(shared));
+            assert_eq!(constructed.sample(Triangular::new(-2.0, 2.0, 0.0).unwrap()), samplers.offset(shared));
+            assert_eq!(constructed.sample(Normal::new(0.0, 0.1).unwrap()), samplers.layer_bias(shared));
+            assert_eq!(constructed.sample(Normal::new(0.0, 1.0).unwrap()), samplers.threshold(shared));
+        }
+
+        // Creatures draw their constants from the priors
+        let params = vec!["x1", "x2", "x3"];
+        let largest = |options: &CreationOptions| Creature::create_many_parallel_with(200, &params, 3, options).iter()
+            .flat_map(|creature| creature.constants())
+            .fold(0.0f32, |largest, constant| largest.max(constant.abs()));
+        let large = CoefficientPriors { magnitude: TriangularPrior::new(10.0, 11.0, 10.5), ..Default::default() };
+        assert!(largest(&CreationOptions::default()) < 10.0);
+        assert!(largest(&CreationOptions { priors: large, ..Default::default() }) >= 10.0);
+    }
+
+    #[test]
+    fn gaussian_matches_normal() {
+        let mut a = StdRng::seed_from_u64(3);
+        let mut b = StdRng::seed_from_u64(3);
+        let normal = Normal::new(0.0, 0.3).unwrap();
+        for _ in 0..100 {
+            assert_eq!(gaussian(0.3, &mut a), b.sample(normal));
+        }
+    }
+}
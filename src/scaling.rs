@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+use rayon::prelude::*;
+use crate::config::EvolutionConfig;
+use crate::creature::Creature;
+use crate::data;
+use crate::evolution::{calc_error_sum, prepare_training_data, Evolution};
+
+
+/// Throughput of scoring one population with a given number of threads.
+#[derive(Clone, Debug)]
+pub struct ThreadScaling {
+    pub threads: usize,
+    pub elapsed: Duration,
+    pub creatures_per_second: f64,
+    /// Throughput relative to a single thread.
+    pub speedup: f64,
+    /// Speedup divided by the number of threads (1.0 is perfect scaling).
+    pub efficiency: f64,
+}
+
+/// Results of "Evolution::parallel_efficiency_report", one row per thread count.
+#[derive(Clone, Debug)]
+pub struct ParallelEfficiency {
+    pub rows: Vec<ThreadScaling>,
+}
+
+impl fmt::Display for ParallelEfficiency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>7}  {:>14}  {:>8}  {:>10}", "Threads", "Creatures/sec", "Speedup", "Efficiency")?;
+        for row in &self.rows {
+            writeln!(f, "{:>7}  {:>14.0}  {:>7.2}x  {:>9.1}%",
+                     row.threads, row.creatures_per_second, row.speedup, row.efficiency * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+
+impl Evolution {
+    /// Measure how the per-cycle scoring of a population of "config.num_creatures"
+    /// creatures against "data" scales from 1 up to "max_threads" threads.
+    /// Each thread count scores the same population in its own rayon thread pool.
+    /// Use the results to pick a thread budget (for example via RAYON_NUM_THREADS)
+    /// where additional threads still pay off.
+    pub fn parallel_efficiency_report(target: &str,
+        data: &[HashMap<String, f32>],
+        config: &EvolutionConfig,
+        max_threads: usize) -> ParallelEfficiency {
+        let (_, training_data) = prepare_training_data(target, data, config);
+        let param_options: Vec<&str> = data::input_columns(data, target);
+        let creatures = Creature::create_many_parallel_with(
+            config.num_creatures, &param_options, config.max_layers, &config.creation
+        );
+        let score = |creatures: &[Creature]| -> Vec<f32> {
+            creatures.par_iter().map(|creature| calc_error_sum(creature, &training_data)).collect()
+        };
+
+        // Warm up caches and the global pool before timing anything
+        score(&creatures);
+
+        let mut rows: Vec<ThreadScaling> = Vec::new();
+        for threads in 1..=max_threads.max(1) {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .expect("Unable to build thread pool");
+            let start = Instant::now();
+            pool.install(|| score(&creatures));
+            let elapsed = start.elapsed();
+
+            let creatures_per_second = creatures.len() as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+            let speedup = match rows.first() {
+                Some(single) => creatures_per_second / single.creatures_per_second,
+                None => 1.0,
+            };
+            rows.push(ThreadScaling { threads, elapsed, creatures_per_second, speedup, efficiency: speedup / threads as f64 });
+        }
+        ParallelEfficiency { rows }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn efficiency_report() {
+        let data: Vec<HashMap<String, f32>> = (0..50)
+            .map(|i| HashMap::from([("x".to_string(), i as f32), ("y".to_string(), (i * i) as f32)]))
+            .collect();
+        let config = EvolutionConfig { num_creatures: 2000, ..Default::default() };
+        let report = Evolution::parallel_efficiency_report("y", &data, &config, 2);
+        println!("{}", report);
+
+        assert_eq!(report.rows.iter().map(|row| row.threads).collect::<Vec<usize>>(), vec![1, 2]);
+        assert_eq!(report.rows[0].speedup, 1.0);
+        assert_eq!(report.rows[0].efficiency, 1.0);
+        assert!(report.rows.iter().all(|row| row.creatures_per_second > 0.0));
+        assert_eq!(report.to_string().lines().count(), 3);
+    }
+}
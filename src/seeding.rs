@@ -0,0 +1,70 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+
+/// Independent random number streams derived from the seed of a fit.
+/// Every parallel task gets its own generator keyed by (seed, stream, index),
+/// so results do not depend on the number of threads or how rayon splits
+/// the work between them.
+pub(crate) struct SeedStreams {
+    seed: u64,
+    next: u64,
+}
+
+impl SeedStreams {
+    pub(crate) fn new(seed: u64) -> SeedStreams {
+        SeedStreams { seed, next: 0 }
+    }
+
+    /// A stream which no other call has returned.  Streams must be taken
+    /// in the same order on every run for the results to repeat.
+    pub(crate) fn next(&mut self) -> Stream {
+        self.next += 1;
+        Stream { seed: self.seed, id: self.next }
+    }
+
+    /// A stream which "next" never returns, for optional checks (such as
+    /// `EvolutionConfig::sanity_check`) whose draws must not shift the streams
+    /// of the fit itself.
+    pub(crate) fn checks(&self) -> Stream {
+        Stream { seed: self.seed, id: 0 }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Stream {
+    seed: u64,
+    id: u64,
+}
+
+impl Stream {
+    /// Generator for the task at "index" of this stream.
+    pub(crate) fn rng(&self, index: usize) -> StdRng {
+        let mut key = [0; 32];
+        key[..8].copy_from_slice(&self.seed.to_le_bytes());
+        key[8..16].copy_from_slice(&self.id.to_le_bytes());
+        key[16..24].copy_from_slice(&(index as u64).to_le_bytes());
+        StdRng::from_seed(key)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn independent_streams() {
+        let mut streams = SeedStreams::new(7);
+        let (first, second) = (streams.next(), streams.next());
+        let draw = |stream: Stream, index: usize| stream.rng(index).gen::<u64>();
+        assert_eq!(draw(first, 3), draw(first, 3));
+        assert_ne!(draw(first, 3), draw(first, 4));
+        assert_ne!(draw(first, 3), draw(second, 3));
+        assert_eq!(draw(SeedStreams::new(7).next(), 3), draw(first, 3));
+        assert_ne!(draw(SeedStreams::new(8).next(), 3), draw(first, 3));
+        assert_ne!(draw(streams.checks(), 3), draw(first, 3));
+        assert_eq!(draw(streams.checks(), 3), draw(SeedStreams::new(7).checks(), 3));
+    }
+}
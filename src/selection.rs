@@ -0,0 +1,127 @@
+/// Which statistic decides the best creature of each cycle,
+/// and which of those cycle bests gets optimized into the final model.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SelectionCriterion {
+    /// Lowest error on the training data.
+    MinTrainError,
+    /// Lowest error on `EvolutionConfig::validation_data` among the
+    /// creatures with the lowest training errors.
+    MinValidationError,
+    /// Lowest "training error + lambda * complexity" among the creatures with
+    /// the lowest training errors, where complexity is `Creature::complexity`.
+    /// Training errors are on the standardized scale, so a lambda of 0.01
+    /// trades one extra term for a 0.01 reduction in standardized error.
+    BestComplexityTradeoff { lambda: f32 },
+}
+
+impl SelectionCriterion {
+    /// Score (lower is better) of a creature with the given errors and complexity.
+    pub(crate) fn score(&self, train_error: f32, validation_error: Option<f32>, complexity: usize) -> f32 {
+        match self {
+            SelectionCriterion::MinTrainError => train_error,
+            SelectionCriterion::MinValidationError => validation_error
+                .expect("SelectionCriterion::MinValidationError requires validation data"),
+            SelectionCriterion::BestComplexityTradeoff { lambda } => train_error + lambda * complexity as f32,
+        }
+    }
+}
+
+/// Indices of the (error, complexity) points in each successive non-dominated
+/// front when minimizing both, so the first front is the Pareto front.
+/// With only two objectives, the points can be placed in error order
+/// by comparing each to the least complex member of every front.
+pub(crate) fn nondominated_fronts(points: &[(f32, usize)]) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|&a, &b| points[a].0.total_cmp(&points[b].0).then(points[a].1.cmp(&points[b].1)));
+
+    let mut fronts: Vec<Vec<usize>> = Vec::new();
+    // Lowest complexity in each front and the lowest error with that complexity
+    let mut front_bests: Vec<(usize, f32)> = Vec::new();
+    for index in order {
+        let (error, complexity) = points[index];
+        let dominates = |&(best_complexity, best_error): &(usize, f32)| {
+            best_complexity < complexity || (best_complexity == complexity && best_error < error)
+        };
+        match front_bests.iter().position(|best| !dominates(best)) {
+            Some(front) => {
+                fronts[front].push(index);
+                if complexity < front_bests[front].0 {
+                    front_bests[front] = (complexity, error);
+                }
+            },
+            None => {
+                fronts.push(vec![index]);
+                front_bests.push((complexity, error));
+            },
+        }
+    }
+    fronts
+}
+
+/// NSGA-II crowding distance of each member of "front" (in the same order):
+/// the sum over both objectives of the normalized gap between its neighbors,
+/// which is infinite for the members at either end of an objective.
+pub(crate) fn crowding_distances(points: &[(f32, usize)], front: &[usize]) -> Vec<f32> {
+    let mut distances = vec![0.0; front.len()];
+    let objectives: [&dyn Fn(usize) -> f32; 2] = [&|index| points[index].0, &|index| points[index].1 as f32];
+    for objective in objectives {
+        let mut order: Vec<usize> = (0..front.len()).collect();
+        order.sort_by(|&a, &b| objective(front[a]).total_cmp(&objective(front[b])));
+        let (first, last) = (order[0], order[order.len() - 1]);
+        let range = objective(front[last]) - objective(front[first]);
+        distances[first] = f32::INFINITY;
+        distances[last] = f32::INFINITY;
+        if range > 0.0 {
+            for window in order.windows(3) {
+                distances[window[1]] += (objective(front[window[2]]) - objective(front[window[0]])) / range;
+            }
+        }
+    }
+    distances
+}
+
+/// Indices of the "count" points ranked best by front and then by crowding distance, best first.
+pub(crate) fn pareto_ranking(points: &[(f32, usize)], count: usize) -> Vec<usize> {
+    let mut ranked = Vec::with_capacity(count);
+    for front in nondominated_fronts(points) {
+        if ranked.len() >= count {
+            break;
+        }
+        let distances = crowding_distances(points, &front);
+        let mut members: Vec<(usize, f32)> = front.into_iter().zip(distances).collect();
+        members.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked.extend(members.into_iter().map(|(index, _)| index).take(count - ranked.len()));
+    }
+    ranked
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_scores() {
+        assert_eq!(SelectionCriterion::MinTrainError.score(0.5, Some(0.9), 7), 0.5);
+        assert_eq!(SelectionCriterion::MinValidationError.score(0.5, Some(0.9), 7), 0.9);
+        let tradeoff = SelectionCriterion::BestComplexityTradeoff { lambda: 0.25 };
+        assert_eq!(tradeoff.score(0.5, None, 4), 1.5);
+    }
+
+    #[test]
+    fn pareto_fronts() {
+        let points = [(1.0, 5), (2.0, 3), (3.0, 1), (2.5, 4), (4.0, 2), (1.0, 5), (5.0, 6)];
+        let fronts = nondominated_fronts(&points);
+        let mut first = fronts[0].clone();
+        first.sort();
+        assert_eq!(first, vec![0, 1, 2, 5]);
+        assert_eq!(fronts[1..], [vec![3, 4], vec![6]]);
+
+        let distances = crowding_distances(&points, &fronts[0]);
+        assert_eq!(distances.iter().filter(|distance| distance.is_infinite()).count(), 3);
+        assert_eq!(pareto_ranking(&points, 5)[..4].iter().filter(|index| fronts[0].contains(index)).count(), 4);
+        assert_eq!(pareto_ranking(&points, 5)[4], 3);
+        assert_eq!(pareto_ranking(&points, 100).len(), points.len());
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fmt;
+use rand::Rng;
+use crate::config::{ConfigError, EvolutionConfig};
+use crate::evolution::{Evolution, EvolutionError};
+use crate::metrics::Metric;
+
+
+/// Values each input is swept through (from its training minimum to maximum,
+/// with the other inputs at their means) to compare the models of a stability run.
+const PROBE_STEPS: usize = 20;
+
+/// Results of "Evolution::stability".
+#[derive(Clone, Debug)]
+pub struct StabilityReport {
+    /// Seed of each run, in order.
+    pub seeds: Vec<u64>,
+    /// Mean squared error (in the original units of the target) of each run's model
+    /// on `EvolutionConfig::validation_data`, or on the training data when there is none.
+    pub errors: Vec<f32>,
+    pub mean_error: f32,
+    pub stdev_error: f32,
+    pub min_error: f32,
+    pub max_error: f32,
+    /// Pearson correlation of the predictions of each pair of models (i, j)
+    /// with i < j over the probe grid.  Two constant models correlate with 1.0
+    /// and a constant model with a varying one with 0.0.
+    pub correlations: Vec<((usize, usize), f32)>,
+    pub mean_correlation: f32,
+}
+
+impl fmt::Display for StabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Stability over {} seeds", self.seeds.len())?;
+        writeln!(f, "  Error: mean {:.6}, stdev {:.6}, range [{:.6}, {:.6}]",
+                 self.mean_error, self.stdev_error, self.min_error, self.max_error)?;
+        writeln!(f, "  Mean pairwise prediction correlation: {:.4}", self.mean_correlation)
+    }
+}
+
+
+impl Evolution {
+    /// Train "n_seeds" models with "config" (whose budget should usually be kept
+    /// small) that differ only in their seed, and report how much their final errors
+    /// and their predictions vary.  The seeds follow on from `EvolutionConfig::seed`
+    /// (or a random seed when None).  A large spread of errors or low prediction
+    /// correlations suggest increasing `num_creatures` or `num_cycles`.
+    /// Fails without training for fewer than 2 seeds or any problem with the config.
+    pub fn stability(target: &str,
+        data: &[HashMap<String, f32>],
+        config: &EvolutionConfig,
+        n_seeds: usize) -> Result<StabilityReport, EvolutionError> {
+        if n_seeds < 2 {
+            return Err(ConfigError::TooSmall { option: "n_seeds", minimum: 2 }.into());
+        }
+        let first_seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let seeds: Vec<u64> = (0..n_seeds as u64).map(|offset| first_seed.wrapping_add(offset)).collect();
+        let models = seeds.iter()
+            .map(|seed| Evolution::with_config(target.to_string(), data, &EvolutionConfig { seed: Some(*seed), ..config.clone() }))
+            .collect::<Result<Vec<Evolution>, EvolutionError>>()?;
+
+        let scored = config.validation_data.as_deref().unwrap_or(data);
+        let actuals: Vec<f32> = scored.iter().map(|point| point[target]).collect();
+        let errors: Vec<f32> = models.iter().map(|model| {
+            let predictions: Vec<f32> = scored.iter().map(|point| model.predict_unchecked(point)).collect();
+            Metric::MeanSquaredError.compute(&predictions, &actuals)
+        }).collect();
+        let mean_error = errors.iter().sum::<f32>() / n_seeds as f32;
+        let stdev_error = (errors.iter().map(|error| (error - mean_error).powi(2)).sum::<f32>() / n_seeds as f32).sqrt();
+
+        let probes: Vec<Vec<f32>> = models.iter().map(probe_predictions).collect();
+        let mut correlations = Vec::new();
+        for i in 0..n_seeds {
+            for j in i + 1..n_seeds {
+                correlations.push(((i, j), correlation(&probes[i], &probes[j])));
+            }
+        }
+        let mean_correlation = correlations.iter().map(|(_, r)| r).sum::<f32>() / correlations.len() as f32;
+
+        Ok(StabilityReport {
+            seeds,
+            mean_error,
+            stdev_error,
+            min_error: errors.iter().copied().fold(f32::INFINITY, f32::min),
+            max_error: errors.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+            errors,
+            correlations,
+            mean_correlation,
+        })
+    }
+}
+
+
+/// Predictions of "model" sweeping each input (in sorted order) over its training range.
+fn probe_predictions(model: &Evolution) -> Vec<f32> {
+    let mut params: Vec<String> = model.standardizer.parameters().into_iter()
+        .filter(|param| *param != model.target)
+        .map(|param| param.to_string())
+        .collect();
+    params.sort();
+    params.into_iter()
+        .flat_map(|param| model.predict_grid(&[param], PROBE_STEPS, true))
+        .map(|(_, prediction)| prediction)
+        .collect()
+}
+
+fn correlation(a: &[f32], b: &[f32]) -> f32 {
+    let mean = |values: &[f32]| values.iter().map(|v| *v as f64).sum::<f64>() / values.len() as f64;
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        let (dx, dy) = (*x as f64 - mean_a, *y as f64 - mean_b);
+        covariance += dx * dy;
+        variance_a += dx * dx;
+        variance_b += dy * dy;
+    }
+    match (variance_a > 0.0, variance_b > 0.0) {
+        (true, true) => (covariance / (variance_a * variance_b).sqrt()).clamp(-1.0, 1.0) as f32,
+        (false, false) => 1.0,
+        _ => 0.0,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evolution::tests::parabola_data;
+
+    #[test]
+    fn stability_report() {
+        let data = parabola_data();
+        let config = EvolutionConfig { num_creatures: 500, num_cycles: 3, seed: Some(5), ..Default::default() };
+        let report = Evolution::stability("y", &data, &config, 3).unwrap();
+        println!("{}", report);
+
+        assert_eq!(report.seeds, vec![5, 6, 7]);
+        assert_eq!(report.errors.len(), 3);
+        assert!(report.errors.iter().all(|error| error.is_finite() && *error >= 0.0));
+        assert!(report.min_error <= report.mean_error && report.mean_error <= report.max_error);
+        assert!(report.stdev_error >= 0.0);
+        assert_eq!(report.correlations.iter().map(|(pair, _)| *pair).collect::<Vec<_>>(), vec![(0, 1), (0, 2), (1, 2)]);
+        assert!(report.correlations.iter().all(|(_, r)| (-1.0..=1.0).contains(r)));
+        assert!((-1.0..=1.0).contains(&report.mean_correlation));
+
+        assert!(Evolution::stability("y", &data, &config, 1).is_err());
+
+        assert_eq!(correlation(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]), 1.0);
+        assert_eq!(correlation(&[1.0, 1.0], &[2.0, 2.0]), 1.0);
+        assert_eq!(correlation(&[1.0, 1.0], &[2.0, 3.0]), 0.0);
+    }
+}
@@ -1,28 +1,42 @@
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use crate::data::MissingParams;
 
 
+/// Largest difference (relative to the value's magnitude, or the stdev when
+/// larger) allowed between a value and its standardize/unstandardize round trip.
+const ROUNDTRIP_TOLERANCE: f32 = 1e-4;
+
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Standardizer {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::util::serialize_sorted_map"))]
     standardizers: HashMap<String, ParamStandardizer>,
 }
 
 impl Standardizer {
+    /// Fit to every parameter of "data", each from the points which have it.
     pub fn new(data: &[HashMap<String, f32>]) -> Standardizer {
-        let mut standardizers = HashMap::new();
-        for key in data[0].keys() {
-            let values: Vec<&f32> = data.iter().map(|hash| hash.get(key).unwrap()).collect();
-            standardizers.insert(key.to_string(), ParamStandardizer::new(&values));
+        let mut values: HashMap<&str, Vec<&f32>> = HashMap::new();
+        for point in data {
+            for (key, value) in point {
+                values.entry(key).or_default().push(value);
+            }
         }
+        let standardizers = values.into_iter()
+            .map(|(key, values)| (key.to_string(), ParamStandardizer::new(&values)))
+            .collect();
         Standardizer { standardizers }
     }
 
+    /// Standardized copy of "data", leaving out keys which were not in the
+    /// data the Standardizer was fit to (and so cannot be model inputs).
     pub fn standardized_value(&self, data: &HashMap<String, f32>) -> HashMap<String, f32> {
-        let mut standardized = HashMap::new();
-        for (key, value) in data {
-            standardized.insert(key.to_string(), self.standardizers.get(key)
-                    .expect("Did not have a ParamStandardizer for a given key?")
-                    .standardize(&value));
-        }
-        standardized
+        data.iter()
+            .filter_map(|(key, value)| Some((key.to_string(), self.standardizers.get(key)?.standardize(value))))
+            .collect()
     }
 
     pub fn standardized_values(&self, data: &[HashMap<String, f32>]) -> Vec<HashMap<String, f32>> {
@@ -32,7 +46,7 @@ impl Standardizer {
             for (key, value) in row {
                 new_row.insert(key.to_string(), self.standardizers.get(key)
                         .expect("Did not have a ParamStandardizer for a given key?")
-                        .standardize(&value));
+                        .standardize(value));
             }
             compiled.push(new_row);
         }
@@ -44,6 +58,84 @@ impl Standardizer {
             .unstandardize(&value)
     }
 
+    pub(crate) fn param_standardizer(&self, param: &str) -> Option<&ParamStandardizer> {
+        self.standardizers.get(param)
+    }
+
+    /// Fill the parameters (other than "target") missing from the standardized "point"
+    /// as "missing" says, which leaves them out for `MissingParams::Skip` and `MissingParams::Penalty`.
+    pub(crate) fn fill_missing(&self, point: &mut HashMap<String, f32>, target: &str, missing: MissingParams) {
+        let fill = |stats: &ParamStandardizer| match missing {
+            MissingParams::Mean => Some(0.0),
+            MissingParams::Constant(value) => Some(stats.standardize(&value)),
+            MissingParams::Skip | MissingParams::Penalty(_) => None,
+        };
+        for (param, stats) in &self.standardizers {
+            if param != target && !point.contains_key(param) {
+                if let Some(value) = fill(stats) {
+                    point.insert(param.clone(), value);
+                }
+            }
+        }
+    }
+
+    /// Names of all standardized parameters in sorted order.
+    pub fn parameters(&self) -> Vec<&str> {
+        let mut parameters: Vec<&str> = self.standardizers.keys().map(|key| key.as_str()).collect();
+        parameters.sort();
+        parameters
+    }
+
+    /// Check that unstandardizing a standardized value gives back the original value
+    /// for each parameter's training mean, minimum and maximum.
+    /// Useful after loading a Standardizer, where corrupted statistics
+    /// (such as a zero or non-finite stdev) would silently distort predictions.
+    pub fn roundtrip_check(&self) -> Result<(), RoundtripError> {
+        for param in self.parameters() {
+            let stats = &self.standardizers[param];
+            self.check_roundtrip(param, &[stats.mean, stats.min, stats.max])?;
+        }
+        Ok(())
+    }
+
+    /// Round trip "values" through the standardization of "param".
+    pub(crate) fn check_roundtrip(&self, param: &str, values: &[f32]) -> Result<(), RoundtripError> {
+        let stats = self.standardizers.get(param)
+            .unwrap_or_else(|| panic!("Unable to find ParamStandardizer for {}", param));
+        for &value in values {
+            let roundtrip = self.unstandardize_value(param, stats.standardize(&value));
+            let tolerance = ROUNDTRIP_TOLERANCE * value.abs().max(stats.stdev).max(1.0);
+            if roundtrip.is_nan() || (roundtrip - value).abs() > tolerance {
+                return Err(RoundtripError { param: param.to_string(), value, roundtrip });
+            }
+        }
+        Ok(())
+    }
+
+    /// Combine the statistics of "other" (fit to different rows with the same columns)
+    /// into this Standardizer, giving the same result (within float tolerance) as fitting
+    /// on all of the rows together.  Columns only in "other" are added as they are.
+    /// Panics if a column is missing its row count, as in Standardizers saved before
+    /// counts were recorded.
+    pub fn merge(&mut self, other: &Standardizer) {
+        for (key, other_stats) in &other.standardizers {
+            match self.standardizers.get_mut(key) {
+                Some(stats) => stats.merge(other_stats, key),
+                None => {
+                    self.standardizers.insert(key.clone(), other_stats.clone());
+                },
+            }
+        }
+    }
+
+    /// Update the statistics with "new_rows" without the rows they were first fit to
+    /// (see "merge").  Does nothing when "new_rows" is empty.
+    pub fn update(&mut self, new_rows: &[HashMap<String, f32>]) {
+        if !new_rows.is_empty() {
+            self.merge(&Standardizer::new(new_rows));
+        }
+    }
+
     pub fn print_standardization(&self) {
         for (key, param_stand) in &self.standardizers {
             println!("Key: {}  ParamStand: {:?}", key, param_stand);
@@ -51,10 +143,46 @@ impl Standardizer {
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "serde")]
+impl Standardizer {
+    /// The fitted statistics of each parameter as JSON, so the same scaling can be
+    /// reused by other models or by preprocessing outside of Rust.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Load a Standardizer saved with `Standardizer::to_json`.
+    pub fn from_json(json: &str) -> serde_json::Result<Standardizer> {
+        serde_json::from_str(json)
+    }
+}
+
+/// A value which did not survive standardizing and unstandardizing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoundtripError {
+    pub param: String,
+    pub value: f32,
+    pub roundtrip: f32,
+}
+
+impl fmt::Display for RoundtripError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Standardizing \"{}\" is not reversible: {} came back as {}", self.param, self.value, self.roundtrip)
+    }
+}
+
+impl Error for RoundtripError {}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParamStandardizer {
     mean: f32,
     stdev: f32,
+    min: f32,
+    max: f32,
+    /// Number of values the statistics were calculated from (0 when unknown).
+    #[cfg_attr(feature = "serde", serde(default))]
+    count: u64,
 }
 
 impl ParamStandardizer {
@@ -62,12 +190,49 @@ impl ParamStandardizer {
         ParamStandardizer {
             mean: mean(values).expect("Cannot calculate mean for empty data"),
             stdev: std_deviation(&values[..]).expect("Cannot calculate std_deviation for empty data"),
+            min: values.iter().fold(f32::INFINITY, |min, value| min.min(**value)),
+            max: values.iter().fold(f32::NEG_INFINITY, |max, value| max.max(**value)),
+            count: values.len() as u64,
         }
     }
-    fn standardize(&self, value: &f32) -> f32 {
+
+    /// Combine with the statistics of other values using the pairwise
+    /// update of Chan et al. for the mean and sum of squared deviations.
+    fn merge(&mut self, other: &ParamStandardizer, param: &str) {
+        assert!(self.count > 0 && other.count > 0, "Cannot merge the statistics of \"{}\" without their row counts", param);
+        let (count_a, count_b) = (self.count as f64, other.count as f64);
+        let count = count_a + count_b;
+        let delta = other.mean as f64 - self.mean as f64;
+        // A single value has no deviation (but a NaN sample stdev)
+        let squared_deviations = |stats: &ParamStandardizer, count: f64| match count > 1.0 {
+            true => (stats.stdev as f64).powi(2) * (count - 1.0),
+            false => 0.0,
+        };
+        let total_squared_deviations = squared_deviations(self, count_a) + squared_deviations(other, count_b)
+            + delta.powi(2) * count_a * count_b / count;
+
+        self.mean = (self.mean as f64 + delta * count_b / count) as f32;
+        self.stdev = (total_squared_deviations / (count - 1.0)).sqrt() as f32;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.count += other.count;
+    }
+    pub(crate) fn mean(&self) -> f32 {
+        self.mean
+    }
+    pub(crate) fn stdev(&self) -> f32 {
+        self.stdev
+    }
+    pub(crate) fn min(&self) -> f32 {
+        self.min
+    }
+    pub(crate) fn max(&self) -> f32 {
+        self.max
+    }
+    pub(crate) fn standardize(&self, value: &f32) -> f32 {
         (value - self.mean) / self.stdev
     }
-    fn unstandardize(&self, value: &f32) -> f32 {
+    pub(crate) fn unstandardize(&self, value: &f32) -> f32 {
         value * self.stdev + self.mean
     }
 }
@@ -104,11 +269,11 @@ mod tests {
     #[test]
     fn mean_calcs() {
         let v1: Vec<&f32> = vec![&3.0, &5.8, &1.5, &-3.7];
-        assert_eq!((mean(&v1[..]).unwrap() - 1.65).abs() < 0.00001, true);
+        assert!((mean(&v1[..]).unwrap() - 1.65).abs() < 0.00001);
         let v2: Vec<&f32> = vec![&-87.3];
-        assert_eq!((mean(&v2[..]).unwrap() - (-87.3)).abs() < 0.00001, true);
-        let v3 = vec![];
-        assert_eq!(mean(&v3[..]) == None, true);
+        assert!((mean(&v2[..]).unwrap() - (-87.3)).abs() < 0.00001);
+        let v3 = [];
+        assert!(mean(&v3[..]).is_none());
     }
 
     #[test]
@@ -118,6 +283,99 @@ mod tests {
         println!("Std: {}", result);
         // checking against "sample" standard deviation method where divide by n-1
         // dividing by n for "population" would instead calculate 2.646
-        assert_eq!((result - 2.89856).abs() < 0.0001, true);
+        assert!((result - 2.89856).abs() < 0.0001);
+    }
+
+    #[test]
+    fn standardize_round_trips() {
+        // Parameters with very different scales so mixing up columns would show
+        let data: Vec<HashMap<String, f32>> = (0..50).map(|i| {
+            let i = i as f32;
+            HashMap::from([
+                ("small".to_string(), i * 0.001 - 0.02),
+                ("large".to_string(), 1.0e5 + i * 250.0),
+                ("negative".to_string(), -3.0 * i - 7.5),
+                ("target".to_string(), (i * 0.4).sin() * 12.0 + 30.0),
+            ])
+        }).collect();
+        let standardizer = Standardizer::new(&data);
+        assert_eq!(standardizer.roundtrip_check(), Ok(()));
+        for param in standardizer.parameters() {
+            let stats = standardizer.param_standardizer(param).unwrap();
+            for point in &data {
+                let value = point[param];
+                let roundtrip = standardizer.unstandardize_value(param, stats.standardize(&value));
+                assert!((roundtrip - value).abs() <= 1e-4 * value.abs().max(stats.stdev()).max(1.0),
+                        "{}: {} -> {}", param, value, roundtrip);
+                assert_eq!(standardizer.standardized_value(point)[param], stats.standardize(&value));
+            }
+            assert!(standardizer.check_roundtrip(param, &data.iter().map(|point| point[param]).collect::<Vec<_>>()).is_ok());
+        }
+        // Unstandardizing uses the stats of the requested column
+        assert!((standardizer.unstandardize_value("target", 0.0) - standardizer.param_standardizer("target").unwrap().mean()).abs() < 1e-4);
+        assert!(standardizer.unstandardize_value("large", 0.0) > 1.0e5);
+    }
+
+    #[test]
+    fn inconsistent_standardization_fails_check() {
+        let data = vec![HashMap::from([("x".to_string(), 1.0)]), HashMap::from([("x".to_string(), 3.0)])];
+        let mut standardizer = Standardizer::new(&data);
+        for stdev in [0.0, f32::NAN, f32::INFINITY] {
+            standardizer.standardizers.get_mut("x").unwrap().stdev = stdev;
+            let error = standardizer.roundtrip_check().unwrap_err();
+            assert_eq!(error.param, "x");
+            assert!(error.to_string().contains("not reversible"));
+        }
+    }
+
+    #[test]
+    fn merged_halves_match_whole() {
+        let data: Vec<HashMap<String, f32>> = (0..101).map(|i| {
+            let i = i as f32;
+            HashMap::from([("x".to_string(), (i * 0.7).sin() * 40.0 + 1.0e3), ("y".to_string(), i * i * 0.01 - 3.0)])
+        }).collect();
+        let whole = Standardizer::new(&data);
+        let mut merged = Standardizer::new(&data[..30]);
+        merged.merge(&Standardizer::new(&data[30..]));
+        let mut updated = Standardizer::new(&data[..1]);
+        updated.update(&data[1..60]);
+        updated.update(&data[60..]);
+        updated.update(&[]);
+
+        for standardizer in [&merged, &updated] {
+            for param in whole.parameters() {
+                let (expected, stats) = (&whole.standardizers[param], &standardizer.standardizers[param]);
+                assert!((stats.mean - expected.mean).abs() <= 1e-5 * expected.mean.abs().max(1.0), "{}: {:?}", param, stats);
+                assert!((stats.stdev - expected.stdev).abs() <= 1e-4 * expected.stdev, "{}: {:?}", param, stats);
+                assert_eq!((stats.min, stats.max, stats.count), (expected.min, expected.max, 101));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "without their row counts")]
+    fn merge_needs_counts() {
+        let data = vec![HashMap::from([("x".to_string(), 1.0)]), HashMap::from([("x".to_string(), 3.0)])];
+        let mut standardizer = Standardizer::new(&data);
+        standardizer.standardizers.get_mut("x").unwrap().count = 0;
+        standardizer.update(&data);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trip() {
+        let data: Vec<HashMap<String, f32>> = (0..10)
+            .map(|i| HashMap::from([("x".to_string(), i as f32 * 0.37 - 1.1), ("y".to_string(), (i * i) as f32 / 3.0)]))
+            .collect();
+        let standardizer = Standardizer::new(&data);
+        let json = standardizer.to_json().unwrap();
+        let loaded = Standardizer::from_json(&json).unwrap();
+        assert_eq!(loaded.parameters(), standardizer.parameters());
+        for point in &data {
+            assert_eq!(loaded.standardized_value(point), standardizer.standardized_value(point));
+        }
+        assert_eq!(loaded.unstandardize_value("y", 0.7), standardizer.unstandardize_value("y", 0.7));
+        assert_eq!(loaded.to_json().unwrap(), json);
+        assert!(Standardizer::from_json("{\"standardizers\": 3}").is_err());
     }
 }
@@ -0,0 +1,415 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use rand::RngCore;
+use crate::config::ConfigError;
+use crate::creature::{Coefficients, Creature};
+use crate::data::ColumnNames;
+use crate::samplers::Samplers;
+use crate::seeding::{SeedStreams, Stream};
+
+
+/// A functional form from domain knowledge, such as "a*x1^2 - b*x2 + c", used to
+/// seed the initial population (see `EvolutionConfig::templates`) with creatures of
+/// that form.  A template is a sum of terms, each one of:
+///  - "constant * parameter ^ exponent", where the "^ exponent" is optional,
+///  - "parameter ^ exponent", with an implied named constant,
+///  - a lone constant or parameter name, which is a parameter when the data
+///    has a column of that name and otherwise a constant added to the sum.
+///
+/// Constants are numbers (the constant's starting value) or names, which start at
+/// a random value from 0 to 2 (most likely 1) given the sign of their term, with
+/// each use of the same name starting at the same value.  Templates are expanded
+/// on the standardized data, so they fix which parameters and powers a creature
+/// starts with rather than exact values, and each instance is free to evolve away
+/// from the template.  Each use of a parameter beyond its first goes in a further
+/// layer, so the template needs as many `EvolutionConfig::max_layers` as the most
+/// uses of one parameter.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct Template {
+    source: String,
+    terms: Vec<Term>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Term {
+    /// "constant * param ^ exponent", where "bare" is a lone name which may be a constant instead.
+    Power { negative: bool, constant: Option<Constant>, param: String, exponent: u8, bare: bool },
+    Intercept { negative: bool, constant: Constant },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Constant {
+    Named(String),
+    Value(f32),
+}
+
+/// Why `Template::parse` rejected a template, pointing at the offending token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateError {
+    /// Character offset of the token in the template (its length at the end).
+    pub position: usize,
+    /// The offending token, or "end of template".
+    pub token: String,
+    pub message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} but found \"{}\" at position {}", self.message, self.token, self.position)
+    }
+}
+
+impl Error for TemplateError {}
+
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Name(String),
+    Number(f32),
+    Plus,
+    Minus,
+    Times,
+    Power,
+}
+
+/// Tokens of "source" with their character offsets and text.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize, String)>, TemplateError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while position < chars.len() {
+        let start = position;
+        let c = chars[position];
+        let token = match c {
+            _ if c.is_whitespace() => {
+                position += 1;
+                continue;
+            },
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Times,
+            '^' => Token::Power,
+            _ if c.is_alphabetic() || c == '_' => {
+                while position + 1 < chars.len() && (chars[position + 1].is_alphanumeric() || "_.".contains(chars[position + 1])) {
+                    position += 1;
+                }
+                Token::Name(chars[start..=position].iter().collect())
+            },
+            _ if c.is_ascii_digit() || c == '.' => {
+                while position + 1 < chars.len() && (chars[position + 1].is_ascii_digit() || chars[position + 1] == '.') {
+                    position += 1;
+                }
+                let text: String = chars[start..=position].iter().collect();
+                match text.parse() {
+                    Ok(value) => Token::Number(value),
+                    Err(_) => return Err(TemplateError { position: start, token: text, message: "expected a number".to_string() }),
+                }
+            },
+            _ => return Err(TemplateError {
+                position: start, token: c.to_string(), message: "expected a constant, parameter, \"+\", \"-\", \"*\" or \"^\"".to_string(),
+            }),
+        };
+        position += 1;
+        tokens.push((token, start, chars[start..position].iter().collect()));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize, String)>,
+    next: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(token, _, _)| token)
+    }
+
+    /// Error at the next token (or the end of the template).
+    fn error(&self, message: &str) -> TemplateError {
+        let (position, token) = match self.tokens.get(self.next) {
+            Some((_, position, text)) => (*position, text.clone()),
+            None => (self.end, "end of template".to_string()),
+        };
+        TemplateError { position, token, message: message.to_string() }
+    }
+
+    fn term(&mut self, negative: bool) -> Result<Term, TemplateError> {
+        let first = match self.peek() {
+            Some(Token::Name(name)) => Constant::Named(name.clone()),
+            Some(Token::Number(value)) => Constant::Value(*value),
+            _ => return Err(self.error("expected a constant or parameter")),
+        };
+        self.next += 1;
+        let (constant, param, bare) = match (self.peek(), first) {
+            (Some(Token::Times), constant) => {
+                self.next += 1;
+                let param = match self.peek() {
+                    Some(Token::Name(param)) => param.clone(),
+                    _ => return Err(self.error("expected a parameter")),
+                };
+                self.next += 1;
+                (Some(constant), param, false)
+            },
+            (Some(Token::Power), Constant::Named(param)) => (None, param, false),
+            (Some(Token::Power), Constant::Value(_)) => return Err(self.error("expected a parameter before \"^\"")),
+            (_, Constant::Named(name)) => (None, name, true),
+            (_, constant) => return Ok(Term::Intercept { negative, constant }),
+        };
+        let exponent = match self.peek() {
+            Some(Token::Power) => {
+                self.next += 1;
+                match self.tokens.get(self.next) {
+                    Some((Token::Number(_), _, text)) if text.parse::<u8>().is_ok_and(|exponent| exponent > 0) => {
+                        self.next += 1;
+                        text.parse().unwrap()
+                    },
+                    _ => return Err(self.error("expected a whole exponent from 1 to 255")),
+                }
+            },
+            _ => 1,
+        };
+        Ok(Term::Power { negative, constant, param, exponent, bare })
+    }
+}
+
+impl Template {
+    /// Parse a template such as "a*x1^2 - b*x2 + 0.5" (see `Template`).
+    pub fn parse(source: &str) -> Result<Template, TemplateError> {
+        let mut parser = Parser { tokens: tokenize(source)?, next: 0, end: source.chars().count() };
+        let mut terms = Vec::new();
+        while terms.is_empty() || parser.peek().is_some() {
+            let negative = match parser.peek() {
+                Some(Token::Plus) => false,
+                Some(Token::Minus) => true,
+                _ if terms.is_empty() => {
+                    terms.push(parser.term(false)?);
+                    continue;
+                },
+                _ => return Err(parser.error("expected \"+\" or \"-\"")),
+            };
+            parser.next += 1;
+            terms.push(parser.term(negative)?);
+        }
+        Ok(Template { source: source.to_string(), terms })
+    }
+
+    /// The template as written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// "count" creatures of this form over the input "parameters" (which must
+    /// include every parameter of the template), each with its own random
+    /// constants, repeating for the same "seed".
+    pub fn instances(&self, parameters: &[&str], count: usize, seed: u64) -> Vec<Creature> {
+        let stream = SeedStreams::new(seed).next();
+        (0..count).map(|index| self.creature(parameters, Samplers::standard(), &mut stream.rng(index))).collect()
+    }
+
+    /// Copy with the parameter names as the model uses them.
+    pub(crate) fn normalized(&self, column_names: ColumnNames) -> Template {
+        let mut template = self.clone();
+        for term in template.terms.iter_mut() {
+            if let Term::Power { param, .. } = term {
+                *param = column_names.normalize(param).into_owned();
+            }
+        }
+        template
+    }
+
+    /// The parameter of each term which is one (a lone name only when it is one of "parameters").
+    fn term_params<'a>(&'a self, parameters: &[&str]) -> Vec<Option<&'a str>> {
+        self.terms.iter().map(|term| match term {
+            Term::Power { param, bare, .. } if !bare || parameters.contains(&param.as_str()) => Some(param.as_str()),
+            _ => None,
+        }).collect()
+    }
+
+    /// Number of layers needed, the most uses of any one parameter (at least 1).
+    fn layers_needed(&self, parameters: &[&str]) -> usize {
+        let mut uses: HashMap<&str, usize> = HashMap::new();
+        for param in self.term_params(parameters).into_iter().flatten() {
+            *uses.entry(param).or_default() += 1;
+        }
+        uses.into_values().max().unwrap_or(0).max(1)
+    }
+
+    /// Problems using this template (`EvolutionConfig::templates[index]`) with the
+    /// input "parameters" of the data.
+    pub(crate) fn problems(&self, index: usize, parameters: &[&str], target: &str, max_layers: u8, max_exponent: u8) -> Vec<ConfigError> {
+        let mut problems = Vec::new();
+        for param in self.term_params(parameters).into_iter().flatten() {
+            if param == target {
+                problems.push(ConfigError::Conflict(format!("templates[{}] \"{}\" uses the target", index, self.source)));
+            } else if !parameters.contains(&param) {
+                problems.push(ConfigError::UnknownColumn { option: "templates", column: param.to_string() });
+            }
+        }
+        if let Some(exponent) = self.terms.iter().filter_map(|term| match term {
+            Term::Power { exponent, .. } => Some(*exponent),
+            Term::Intercept { .. } => None,
+        }).find(|exponent| *exponent > max_exponent) {
+            problems.push(ConfigError::Conflict(format!(
+                "templates[{}] \"{}\" has an exponent of {} above mutation.max_exponent", index, self.source, exponent
+            )));
+        }
+        let layers = self.layers_needed(parameters);
+        if layers > max_layers as usize {
+            problems.push(ConfigError::Conflict(format!(
+                "templates[{}] \"{}\" needs {} layers but max_layers is {}", index, self.source, layers, max_layers
+            )));
+        }
+        problems
+    }
+
+    /// A creature of this form with random values (of magnitudes from "samplers") for the named constants.
+    pub(crate) fn creature(&self, parameters: &[&str], samplers: &Samplers, rng: &mut dyn RngCore) -> Creature {
+        let mut named: HashMap<String, f32> = HashMap::new();
+        let mut value = |constant: Option<&Constant>, negative: bool, rng: &mut dyn RngCore| {
+            let value = match constant {
+                Some(Constant::Value(value)) => *value,
+                Some(Constant::Named(name)) => *named.entry(name.clone()).or_insert_with(|| samplers.magnitude(rng)),
+                None => samplers.magnitude(rng),
+            };
+            if negative { -value } else { value }
+        };
+
+        let mut layers: Vec<Vec<(String, Coefficients)>> = vec![Vec::new(); self.layers_needed(parameters)];
+        let mut bias = 0.0;
+        let mut used: Vec<HashSet<&str>> = vec![HashSet::new(); layers.len()];
+        for (term, param) in self.terms.iter().zip(self.term_params(parameters)) {
+            match (term, param) {
+                (Term::Power { negative, constant, exponent, .. }, Some(param)) => {
+                    let c = value(constant.as_ref(), *negative, rng);
+                    let layer = used.iter().position(|params| !params.contains(param)).unwrap();
+                    used[layer].insert(param);
+                    layers[layer].push((param.to_string(), Coefficients { c, b: 1.0, z: 0.0, x: *exponent }));
+                },
+                (Term::Power { negative, param, .. }, None) => bias += value(Some(&Constant::Named(param.clone())), *negative, rng),
+                (Term::Intercept { negative, constant }, _) => bias += value(Some(constant), *negative, rng),
+            }
+        }
+        let mut creature = Creature::stacked(layers, bias);
+        samplers.pin(&mut creature);
+        creature
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = TemplateError;
+
+    fn try_from(source: String) -> Result<Template, TemplateError> {
+        Template::parse(&source)
+    }
+}
+
+impl From<Template> for String {
+    fn from(template: Template) -> String {
+        template.source
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+
+/// "instances" creatures of each template (seeding the initial population).
+pub(crate) fn expanded(templates: &[Template], instances: u32, parameters: &[&str], samplers: &Samplers, stream: Stream) -> Vec<Creature> {
+    let instances = instances as usize;
+    templates.iter()
+        .enumerate()
+        .flat_map(|(index, template)| {
+            (0..instances).map(move |instance| template.creature(parameters, samplers, &mut stream.rng(index * instances + instance)))
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evolution::Evolution;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn point(x1: f32, x2: f32) -> HashMap<String, f32> {
+        HashMap::from([("x1".to_string(), x1), ("x2".to_string(), x2)])
+    }
+
+    #[test]
+    fn parse_templates() {
+        let template = Template::parse("2*x1^2 - 3 * x2 + 0.5").unwrap();
+        assert_eq!(template.terms, vec![
+            Term::Power { negative: false, constant: Some(Constant::Value(2.0)), param: "x1".to_string(), exponent: 2, bare: false },
+            Term::Power { negative: true, constant: Some(Constant::Value(3.0)), param: "x2".to_string(), exponent: 1, bare: false },
+            Term::Intercept { negative: false, constant: Constant::Value(0.5) },
+        ]);
+        let creature = &template.instances(&["x1", "x2"], 1, 0)[0];
+        assert_eq!(creature.calculate(&point(1.5, 2.0)), 2.0 * 1.5 * 1.5 - 3.0 * 2.0 + 0.5);
+
+        // Named constants are random, a lone "c" is a constant and x1 used twice needs a second layer
+        let template = Template::parse("-a*x1 + b*x1^3 + x2^2 + c").unwrap();
+        let parameters = ["x1", "x2"];
+        assert_eq!(template.layers_needed(&parameters), 2);
+        let creatures = template.instances(&parameters, 5, 3);
+        assert_ne!(creatures[0].constants(), creatures[1].constants());
+        for creature in &creatures {
+            assert_eq!(creature.num_layers(), 2);
+            let bias = creature.calculate(&point(0.0, 0.0));
+            let terms = creature.coefficient_values();
+            let (a, b) = (terms[0].0, terms[2].0);
+            assert!(a < 0.0 && b > 0.0 && terms[1].0 > 0.0 && terms[1].3 == 2 && terms[2].3 == 3);
+            let expected = a * 0.5 + b * 0.125 + terms[1].0 * 4.0 + bias;
+            assert!((creature.calculate(&point(0.5, -2.0)) - expected).abs() < 1e-5);
+        }
+        assert_eq!(template.term_params(&["x1"]), vec![Some("x1"), Some("x1"), Some("x2"), None]);
+
+        #[cfg(feature = "serde")]
+        assert_eq!(serde_json::from_str::<Template>(&serde_json::to_string(&template).unwrap()).unwrap(), template);
+    }
+
+    #[test]
+    fn parse_errors_point_at_token() {
+        let error = |source: &str| {
+            let error = Template::parse(source).unwrap_err();
+            (error.position, error.token)
+        };
+        assert_eq!(error("a*x1^^2"), (5, "^".to_string()));
+        assert_eq!(error("a*x1^0 + b"), (5, "0".to_string()));
+        assert_eq!(error("a*x1 b*x2"), (5, "b".to_string()));
+        assert_eq!(error("a*x1 + (x2)"), (7, "(".to_string()));
+        assert_eq!(error("a*2"), (2, "2".to_string()));
+        assert_eq!(error("a*x1 +"), (6, "end of template".to_string()));
+        assert_eq!(error(""), (0, "end of template".to_string()));
+        assert_eq!(Template::parse("a * x1 ^ 2.5").unwrap_err().to_string(),
+                   "expected a whole exponent from 1 to 255 but found \"2.5\" at position 9");
+    }
+
+    #[test]
+    fn templates_seed_population() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let data: Vec<HashMap<String, f32>> = (0..200).map(|_| {
+            let mut point: HashMap<String, f32> = (1..=6).map(|index| (format!("x{}", index), rng.gen_range(-1.0..1.0))).collect();
+            point.insert("y".to_string(), 3.0 * point["x1"].powi(2) - 2.0 * point["x2"] + point["x3"].powi(3));
+            point
+        }).collect();
+        let early_errors = |templates: Vec<Template>| {
+            let evo = Evolution::builder("y", &data).num_creatures(100).num_cycles(3).seed(11).templates(templates, 20).build().unwrap();
+            evo.history().iter().map(|stats| stats.best_error).collect::<Vec<f32>>()
+        };
+        let template = Template::parse("a*x1^2 - b*x2 + c*x3^3 + d").unwrap();
+        let (seeded, unseeded) = (early_errors(vec![template]), early_errors(Vec::new()));
+        assert!(seeded[1] < unseeded[1] && seeded[2] < unseeded[2]);
+
+        let unknown = Evolution::builder("y", &data).templates(vec![Template::parse("a*x9^2").unwrap()], 10).build();
+        assert_eq!(unknown.err(), Some(ConfigError::UnknownColumn { option: "templates", column: "x9".to_string() }));
+    }
+}
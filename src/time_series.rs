@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use crate::config::{ConfigError, EvolutionConfig};
+use crate::evolution::{Evolution, EvolutionError};
+use crate::metrics::Metric;
+
+
+/// Which earlier rows each split of `Evolution::rolling_cv` trains on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    /// Every row before the test rows.
+    Expanding,
+    /// Only the given number of rows immediately before the test rows.
+    Rolling(usize),
+}
+
+/// Options for `Evolution::rolling_cv`.
+#[derive(Clone, Debug)]
+pub struct RollingCvConfig {
+    /// Config used to train the model of each split.
+    pub evolution: EvolutionConfig,
+    pub window: Window,
+    /// Number of rows each split is tested on.  None divides the data into
+    /// "n_splits + 1" equal blocks, with the first block only ever used for training.
+    pub horizon: Option<usize>,
+}
+
+impl Default for RollingCvConfig {
+    fn default() -> RollingCvConfig {
+        RollingCvConfig { evolution: EvolutionConfig::default(), window: Window::Expanding, horizon: None }
+    }
+}
+
+
+impl Evolution {
+    /// Out-of-sample error for data in time order (oldest first).  Each of the
+    /// "n_splits" splits trains a new model on rows before its test rows and returns
+    /// the mean squared error (in the target's original units) of its predictions
+    /// for the next "horizon" rows.  The test rows move forward by "horizon" each
+    /// split, with the last split testing on the final rows of the data, so a model
+    /// is never trained on rows which come after the ones it is tested on.
+    /// Fails without training if the splits leave no rows to train on or for any
+    /// problem `EvolutionConfig::validate` finds with "data".
+    pub fn rolling_cv(data: &[HashMap<String, f32>],
+        target: &str,
+        n_splits: usize,
+        config: &RollingCvConfig) -> Result<Vec<f32>, EvolutionError> {
+        let splits = rolling_splits(data.len(), n_splits, config.window, config.horizon)?;
+        config.evolution.validate(target, data)?;
+        splits.into_iter().map(|(train, test)| {
+            let evo = Evolution::with_config(target.to_string(), &data[train], &config.evolution)?;
+            let predictor = evo.predictor();
+            let test_data = &data[test];
+            let predictions: Vec<f32> = test_data.iter().map(|point| predictor.predict_point(point)).collect();
+            let actuals: Vec<f32> = test_data.iter().map(|point| point[target]).collect();
+            Ok(Metric::MeanSquaredError.compute(&predictions, &actuals))
+        }).collect()
+    }
+}
+
+/// Train and test rows of one split.
+type Split = (Range<usize>, Range<usize>);
+
+/// Train and test row ranges for each split of "n_rows" rows.
+pub(crate) fn rolling_splits(n_rows: usize,
+    n_splits: usize,
+    window: Window,
+    horizon: Option<usize>) -> Result<Vec<Split>, ConfigError> {
+    if n_splits == 0 {
+        return Err(ConfigError::ZeroCount { option: "n_splits" });
+    }
+    if window == Window::Rolling(0) {
+        return Err(ConfigError::ZeroCount { option: "window" });
+    }
+    let horizon = horizon.unwrap_or(n_rows / (n_splits + 1));
+    if horizon == 0 {
+        return Err(ConfigError::Conflict(format!("{} rows are not enough for {} splits", n_rows, n_splits)));
+    }
+    if n_splits * horizon >= n_rows {
+        return Err(ConfigError::Conflict(format!("{} splits of {} rows leave no rows to train on", n_splits, horizon)));
+    }
+
+    Ok((0..n_splits).map(|split| {
+        let test_start = n_rows - (n_splits - split) * horizon;
+        let train_start = match window {
+            Window::Expanding => 0,
+            Window::Rolling(size) => test_start.saturating_sub(size),
+        };
+        (train_start..test_start, test_start..test_start + horizon)
+    }).collect())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_ranges() {
+        assert_eq!(rolling_splits(10, 4, Window::Expanding, None),
+                   Ok(vec![(0..2, 2..4), (0..4, 4..6), (0..6, 6..8), (0..8, 8..10)]));
+        assert_eq!(rolling_splits(11, 3, Window::Rolling(3), Some(2)),
+                   Ok(vec![(2..5, 5..7), (4..7, 7..9), (6..9, 9..11)]));
+        // Training rows always come before the test rows
+        for (train, test) in rolling_splits(100, 7, Window::Expanding, None).unwrap() {
+            assert!(!train.is_empty() && train.end == test.start && test.end <= 100);
+        }
+    }
+
+    #[test]
+    fn invalid_splits() {
+        let Err(error) = rolling_splits(10, 2, Window::Expanding, Some(5)) else { panic!("A horizon too long was accepted") };
+        assert_eq!(error.to_string(), "2 splits of 5 rows leave no rows to train on");
+        assert_eq!(rolling_splits(10, 0, Window::Expanding, None), Err(ConfigError::ZeroCount { option: "n_splits" }));
+        assert_eq!(rolling_splits(10, 2, Window::Rolling(0), None), Err(ConfigError::ZeroCount { option: "window" }));
+    }
+
+    #[test]
+    fn rolling_cv_errors() {
+        let data: Vec<HashMap<String, f32>> = (0..40)
+            .map(|i| HashMap::from([("t".to_string(), i as f32), ("y".to_string(), 0.5 * i as f32 + 3.0)]))
+            .collect();
+        let config = RollingCvConfig {
+            evolution: EvolutionConfig { num_creatures: 300, num_cycles: 2, ..Default::default() },
+            window: Window::Rolling(20),
+            horizon: Some(5),
+        };
+        let errors = Evolution::rolling_cv(&data, "y", 3, &config).unwrap();
+        assert_eq!(errors.len(), 3);
+        assert!(errors.iter().all(|error| error.is_finite() && *error >= 0.0));
+
+        let empty_window = RollingCvConfig { window: Window::Rolling(0), ..config };
+        assert!(Evolution::rolling_cv(&data, "y", 3, &empty_window).is_err());
+    }
+}
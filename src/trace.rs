@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::data::MissingParams;
+use crate::evolution::{Evolution, PredictError};
+
+
+/// One intermediate value of a prediction, named after what it is, such as
+/// "standardized x", "layer 1 term x" or "layer 2 total".
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceStep {
+    pub name: String,
+    pub value: f32,
+}
+
+/// Every intermediate value of a prediction in the order it is calculated
+/// (see `Evolution::trace`), for localizing differences between
+/// `Evolution::predict_point` and an external implementation such as the SQL formula.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CalculationTrace {
+    pub steps: Vec<TraceStep>,
+}
+
+/// The first step where two traces disagree, with the value of each (None when
+/// that trace has no step there or names it differently).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    pub index: usize,
+    pub name: String,
+    pub left: Option<f32>,
+    pub right: Option<f32>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = |value: Option<f32>| value.map_or("missing".to_string(), |value| format!("{:?}", value));
+        write!(f, "step {} (\"{}\") differs: {} vs {}", self.index, self.name, value(self.left), value(self.right))
+    }
+}
+
+impl CalculationTrace {
+    fn record(&mut self, name: String, value: f32) {
+        self.steps.push(TraceStep { name, value });
+    }
+
+    /// The final prediction (the last step), if any.
+    pub fn prediction(&self) -> Option<f32> {
+        self.steps.last().map(|step| step.value)
+    }
+
+    /// The first step at which "other" has a different name or a value more than
+    /// "tolerance" away (so 0.0 requires the same f32), or None when they agree
+    /// throughout.  NaN matches only NaN.
+    pub fn first_divergence(&self, other: &CalculationTrace, tolerance: f32) -> Option<Divergence> {
+        let same = |a: f32, b: f32| (a.is_nan() && b.is_nan()) || a == b || (a - b).abs() <= tolerance;
+        (0..self.steps.len().max(other.steps.len())).find_map(|index| {
+            match (self.steps.get(index), other.steps.get(index)) {
+                (Some(left), Some(right)) if left.name == right.name && same(left.value, right.value) => None,
+                (Some(left), Some(right)) if left.name == right.name => Some(Divergence {
+                    index, name: left.name.clone(), left: Some(left.value), right: Some(right.value),
+                }),
+                (left, right) => Some(Divergence {
+                    index,
+                    name: left.or(right).map(|step| step.name.clone()).unwrap_or_default(),
+                    left: left.map(|step| step.value),
+                    right: right.filter(|right| left.is_none_or(|left| left.name == right.name)).map(|step| step.value),
+                }),
+            }
+        })
+    }
+
+    /// The trace as JSON, with every value written so it reads back as the same f32
+    /// (except NaN and infinities, which JSON cannot hold and are written as null).
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("A trace always serializes")
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<CalculationTrace, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+
+impl Evolution {
+    /// Predict "data_point" like `Evolution::predict_point`, recording every
+    /// intermediate value: each input and its standardized value (after any
+    /// `MissingParams` fill), the output of each term and the total of each layer
+    /// (summing the terms in the listed order, as with `EvolutionConfig::strict_fp`),
+    /// and the output before unstandardizing, after it and after the inverse link.
+    /// Fails with `PredictError::ColumnCollision` if column names of "data_point"
+    /// are the same once normalized.
+    pub fn trace(&self, data_point: &HashMap<String, f32>) -> Result<CalculationTrace, PredictError> {
+        let data_point = self.prepared_point(data_point)?;
+        let mut trace = CalculationTrace::default();
+        let mut standardized = self.standardizer.standardized_value(&data_point);
+        self.standardizer.fill_missing(&mut standardized, &self.target, self.config.missing_params);
+        let inputs = self.standardizer.parameters().into_iter().filter(|param| *param != self.target);
+        for param in inputs {
+            if let Some(value) = data_point.get(param) {
+                trace.record(format!("input {}", param), *value);
+            }
+            if let Some(value) = standardized.get(param) {
+                trace.record(format!("standardized {}", param), *value);
+            }
+        }
+
+        let strict = self.config.strict_fp;
+        let mut total = 0.0;
+        let mut inner_total = 0.0;
+        for (index, layer) in self.best_creature.layers().iter().enumerate() {
+            let layer_number = index + 1;
+            for (param, coefficients) in layer.modifiers.sorted() {
+                if let Some(value) = standardized.get(param) {
+                    let term = coefficients.calculate_with(value, strict);
+                    trace.record(format!("layer {} term {}", layer_number, param), term);
+                    inner_total += term;
+                }
+            }
+            if let Some(coefficients) = &layer.previous_layer_coefficients {
+                let term = coefficients.calculate_with(&total, strict);
+                trace.record(format!("layer {} previous layer term", layer_number), term);
+                inner_total += term;
+            }
+            for gated in &layer.gated_modifiers {
+                let term = match strict {
+                    true => gated.calculate_strict(&standardized),
+                    false => gated.calculate(&standardized),
+                };
+                trace.record(format!("layer {} gated term {} by {}", layer_number, gated.param, gated.gate_param), term);
+                inner_total += term;
+            }
+            total = inner_total + layer.layer_bias;
+            trace.record(format!("layer {} total", layer_number), total);
+        }
+
+        if matches!(self.config.missing_params, MissingParams::Penalty(_)) && self.best_creature.uses_missing(&standardized) {
+            total = f32::NAN;
+        }
+        trace.record("standardized output".to_string(), total);
+        let unstandardized = self.standardizer.unstandardize_value(&self.target, total);
+        trace.record("unstandardized output".to_string(), unstandardized);
+        trace.record("prediction".to_string(), self.config.link.inverse(unstandardized));
+        Ok(trace)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EvolutionConfig;
+
+    #[test]
+    fn diff_traces() {
+        let data = crate::data::synthetic::polynomial(120, 2, 0.2, 3);
+        let config = EvolutionConfig { num_creatures: 300, num_cycles: 3, seed: Some(7), ..Default::default() };
+        let evo = Evolution::with_config("y".into(), &data, &config).unwrap();
+        let point = data[5].clone();
+        let trace = evo.trace(&point).unwrap();
+        assert_eq!(trace.prediction(), Some(evo.predict_point(&point).unwrap()));
+        assert_eq!(trace.first_divergence(&trace.clone(), 0.0), None);
+
+        let index = trace.steps.iter().position(|step| step.name == "layer 1 total").unwrap();
+        let mut perturbed = trace.clone();
+        perturbed.steps[index].value += 1e-3;
+        let divergence = trace.first_divergence(&perturbed, 0.0).unwrap();
+        assert_eq!((divergence.index, divergence.name.as_str()), (index, "layer 1 total"));
+        assert_eq!(trace.first_divergence(&perturbed, 1e-2), None);
+
+        let mut truncated = trace.clone();
+        truncated.steps.pop();
+        assert_eq!(trace.first_divergence(&truncated, 0.0).unwrap().right, None);
+
+        #[cfg(feature = "serde")]
+        assert_eq!(CalculationTrace::from_json(&trace.to_json()).unwrap(), trace);
+    }
+}
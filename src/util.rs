@@ -1,28 +1,34 @@
-
-
-/// CAUTION!  Not sure if even need many data checks...
-/// Needed them in Python, but Rust will ensure no
-/// improper types get used at runtime... TBD on this.
-
-
-fn fill_none_with_median<T>(data: &[T]) -> &[T] {
-    todo!();
+#[cfg(feature = "serde")]
+use std::collections::{BTreeMap, HashMap};
+
+
+/// Serialize a HashMap with its keys in sorted order so that
+/// serialized output is deterministic from run to run.
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_sorted_map<S, V>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    V: serde::Serialize,
+{
+    let sorted: BTreeMap<&String, &V> = map.iter().collect();
+    serde::Serialize::serialize(&sorted, serializer)
 }
 
-/// Check cleaned input data for potentail issues.
-/// At point when this is called on cleaned data,
-/// there should be no issues.
-/// If this method panics, need to add more data-
-/// cleaning checks/capabilities to handle the issue!
-fn data_checks<T>(data: &[T]) -> () {
-    println!("Data looks clean!");
+/// Like "serialize_sorted_map" for an optional HashMap.
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_sorted_option_map<S, V>(map: &Option<HashMap<String, V>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    V: serde::Serialize,
+{
+    let sorted: Option<BTreeMap<&String, &V>> = map.as_ref().map(|map| map.iter().collect());
+    serde::Serialize::serialize(&sorted, serializer)
 }
 
 
+
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     #[test]
     fn first_test() {
         assert_eq!(true, true);